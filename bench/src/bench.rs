@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 
-use biblatex::Bibliography;
+use biblatex::{Bibliography, RawBibliography};
 
 const GRAL: &str = include_str!("../../tests/gral.bib");
 const CROSS: &str = include_str!("../../tests/cross.bib");
@@ -22,6 +22,17 @@ fn benchmarks(c: &mut Criterion) {
     let bib = Bibliography::parse(CROSS).unwrap();
     let entry = bib.get("issue201").unwrap();
     bench!("get-date-cross": entry.date());
+
+    let abstracts: String = (0..64)
+        .map(|i| {
+            let text = "Wörter in {geschweiften} Klammern mit \\emph{Befehlen}. ";
+            format!("@article{{a{i}, abstract = {{{}}}}}\n", text.repeat(64))
+        })
+        .collect();
+    bench!("parse-raw-abstracts": RawBibliography::parse(&abstracts).unwrap());
+
+    let plain = format!("@misc{{a, abstract = {{{}}}}}", "Plain text. ".repeat(1 << 12));
+    bench!("parse-raw-plain": RawBibliography::parse(&plain).unwrap());
 }
 
 criterion_group!(benches, benchmarks);
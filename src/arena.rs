@@ -0,0 +1,188 @@
+//! Decoding field values into an arena.
+
+use std::borrow::Cow;
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+
+use crate::resolve::{parse_field_into, ChunkKind, ChunkSink};
+use crate::{Chunk, Pair, ParseError, RawBibliography, Span, Spanned};
+
+/// A part of a field value whose text is allocated in an arena.
+///
+/// The counterpart of a [`Chunk`] that is produced by
+/// [`RawBibliography::decode_in`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ArenaChunk<'b> {
+    /// Normal values within quotes or single braces subject to
+    /// capitalization formatting.
+    Normal(&'b str),
+    /// Values nested in braces that are to be printed like specified
+    /// in the file.
+    Verbatim(&'b str),
+    /// Values nested in dollar signs.
+    Math(&'b str),
+}
+
+impl<'b> ArenaChunk<'b> {
+    /// Get the string contained in the chunk.
+    pub fn get(&self) -> &'b str {
+        match self {
+            Self::Normal(s) | Self::Verbatim(s) | Self::Math(s) => s,
+        }
+    }
+
+    /// Copy the chunk out of the arena.
+    pub fn to_chunk(&self) -> Chunk {
+        match self {
+            Self::Normal(s) => Chunk::Normal(s.to_string()),
+            Self::Verbatim(s) => Chunk::Verbatim(s.to_string()),
+            Self::Math(s) => Chunk::Math(s.to_string()),
+        }
+    }
+}
+
+impl RawBibliography<'_> {
+    /// Decode a field of one of the entries like
+    /// [`Bibliography::from_raw`](crate::Bibliography::from_raw) does, but
+    /// allocate the chunks in an arena.
+    ///
+    /// Decoding a bibliography allocates many small vectors and strings. Tools
+    /// that process many fields in a batch can instead decode them into an
+    /// arena and reset it after each batch.
+    ///
+    /// ```
+    /// # use biblatex::{ArenaChunk, RawBibliography};
+    /// # use bumpalo::Bump;
+    /// let src = r"@string{tug = {TeX Users Group}}
+    ///     @book{knuth, title = {The {\TeX}book}, publisher = tug}";
+    /// let raw = RawBibliography::parse(src).unwrap();
+    ///
+    /// let mut bump = Bump::new();
+    /// for pair in &raw.entries[0].v.fields {
+    ///     let chunks = raw.decode_in(pair, &bump).unwrap();
+    ///     assert!(!chunks.is_empty());
+    /// }
+    ///
+    /// let publisher = raw.decode_in(&raw.entries[0].v.fields[1], &bump).unwrap();
+    /// assert_eq!(publisher[0].v, ArenaChunk::Normal("TeX Users Group"));
+    /// bump.reset();
+    /// ```
+    pub fn decode_in<'b>(
+        &self,
+        pair: &Pair,
+        bump: &'b Bump,
+    ) -> Result<&'b [Spanned<ArenaChunk<'b>>], ParseError> {
+        let key = if pair.key.v.bytes().any(|b| b.is_ascii_uppercase()) {
+            Cow::Owned(pair.key.v.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(&*pair.key.v)
+        };
+
+        let mut sink = ArenaSink {
+            bump,
+            chunks: BumpVec::new_in(bump),
+            open: None,
+            text: String::new(),
+        };
+        parse_field_into(&key, &pair.value.v, &self.abbreviations, &mut sink)?;
+        sink.close();
+        Ok(sink.chunks.into_bump_slice())
+    }
+}
+
+/// Collects decoded chunks in an arena.
+///
+/// Adjacent chunks are merged in a scratch buffer, so that each chunk is only
+/// allocated in the arena once it is complete.
+struct ArenaSink<'b> {
+    bump: &'b Bump,
+    chunks: BumpVec<'b, Spanned<ArenaChunk<'b>>>,
+    /// The kind and span of the chunk that is not yet complete.
+    open: Option<(ChunkKind, Span)>,
+    /// The text of the chunk that is not yet complete.
+    text: String,
+}
+
+impl ArenaSink<'_> {
+    /// Allocate the chunk that is not yet complete in the arena.
+    fn close(&mut self) {
+        let Some((kind, span)) = self.open.take() else { return };
+        let text = self.bump.alloc_str(&self.text);
+        self.text.clear();
+        let chunk = match kind {
+            ChunkKind::Normal => ArenaChunk::Normal(text),
+            ChunkKind::Verbatim => ArenaChunk::Verbatim(text),
+            ChunkKind::Math => ArenaChunk::Math(text),
+        };
+        self.chunks.push(Spanned::new(chunk, span));
+    }
+}
+
+impl ChunkSink for ArenaSink<'_> {
+    fn push(&mut self, kind: ChunkKind, text: &str, span: Span) {
+        match &mut self.open {
+            Some((open, open_span)) if kind.merges_with(*open) => {
+                open_span.end = span.end;
+            }
+            _ => {
+                self.close();
+                self.open = Some((kind, span));
+            }
+        }
+        self.text.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolve::parse_field;
+
+    #[test]
+    fn test_decode_in() {
+        let src = std::fs::read_to_string("tests/gral.bib").unwrap();
+        let raw = RawBibliography::parse(&src).unwrap();
+        let mut bump = Bump::new();
+
+        for entry in &raw.entries {
+            for pair in &entry.v.fields {
+                let key = pair.key.v.to_ascii_lowercase();
+                let expected = parse_field(&key, &pair.value.v, &raw.abbreviations);
+                let chunks = raw.decode_in(pair, &bump).map(|chunks| {
+                    chunks
+                        .iter()
+                        .map(|chunk| Spanned::new(chunk.v.to_chunk(), chunk.span.clone()))
+                        .collect::<Vec<_>>()
+                });
+                assert_eq!(chunks, expected);
+            }
+            bump.reset();
+        }
+
+        let src = "@misc{a, Note = {x} # undefined, Title = {a} # { {b} $c$}}";
+        let raw = RawBibliography::parse(src).unwrap();
+        let fields = &raw.entries[0].v.fields;
+        assert!(raw.decode_in(&fields[0], &bump).is_err());
+        let title = raw.decode_in(&fields[1], &bump).unwrap();
+        assert_eq!(
+            title.iter().map(|chunk| chunk.v).collect::<Vec<_>>(),
+            [
+                ArenaChunk::Normal("a "),
+                ArenaChunk::Verbatim("b"),
+                ArenaChunk::Normal(" "),
+                ArenaChunk::Math("c"),
+            ]
+        );
+
+        // Merged chunks are allocated once, not once per part.
+        let parts = vec!["{ab}"; 2000].join(" # ");
+        let src = format!("@misc{{a, note = {parts}}}");
+        let raw = RawBibliography::parse(&src).unwrap();
+        let bump = Bump::new();
+        let note = raw.decode_in(&raw.entries[0].v.fields[0], &bump).unwrap();
+        assert_eq!(note.len(), 1);
+        assert_eq!(note[0].v.get().len(), 4000);
+        assert!(bump.allocated_bytes() < 16 * 1024);
+    }
+}
@@ -0,0 +1,609 @@
+//! Reading and writing biber's BibLaTeXML format.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::names::NAME_FIELDS;
+use crate::types::Type;
+use crate::xml::{escape, Element};
+use crate::{Bibliography, Chunk, Chunks, ChunksExt, Entry, EntryType, Person, Spanned};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The namespace of BibLaTeXML elements.
+const NAMESPACE: &str = "http://biblatex-biber.sourceforge.net/biblatexml";
+
+/// The fields holding literal lists, which are separated by `and`.
+const LIST_FIELDS: [&str; 15] = [
+    "address",
+    "institution",
+    "language",
+    "lista",
+    "listb",
+    "listc",
+    "listd",
+    "liste",
+    "listf",
+    "location",
+    "organization",
+    "origlocation",
+    "origpublisher",
+    "publisher",
+    "school",
+];
+
+/// The fields holding comma-separated lists of cite keys.
+const KEY_FIELDS: [&str; 4] = ["entryset", "ids", "related", "xdata"];
+
+/// The alternative script forms a field can have besides its default form.
+const SCRIPT_FORMS: [&str; 3] = ["romanised", "translated", "uniform"];
+
+/// Errors that can occur when reading BibLaTeXML.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BltxmlError {
+    /// The input is not well-formed XML or nested too deeply. Contains the
+    /// byte offset of the error.
+    Syntax(usize),
+    /// The root element is not `bltx:entries`. Contains its name.
+    UnexpectedRoot(String),
+    /// An entry has no `id` attribute. Contains the index of the entry.
+    MissingId(usize),
+}
+
+impl Display for BltxmlError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Syntax(offset) => write!(f, "invalid XML at byte {}", offset),
+            Self::UnexpectedRoot(name) => write!(f, "unexpected root element {}", name),
+            Self::MissingId(index) => write!(f, "entry {} has no id", index),
+        }
+    }
+}
+
+impl std::error::Error for BltxmlError {}
+
+impl Bibliography {
+    /// Parse a bibliography from biber's BibLaTeXML format.
+    ///
+    /// Names, dates, lists, and keywords are converted into the
+    /// corresponding fields. Data annotations are stored in `+an` fields like
+    /// `author+an = {1:family="corresponding"}` and the alternative script
+    /// forms of a field (`msform` and `mslang`) in fields like
+    /// `title+translated:german`, which can be written back losslessly with
+    /// [`Bibliography::write_biblatexml`].
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, ChunksExt};
+    /// let src = r#"<bltx:entries xmlns:bltx="http://biblatex-biber.sourceforge.net/biblatexml">
+    ///   <bltx:entry id="doe" entrytype="book">
+    ///     <bltx:names type="author">
+    ///       <bltx:name>
+    ///         <bltx:namepart type="given">Jane</bltx:namepart>
+    ///         <bltx:namepart type="family">Doe</bltx:namepart>
+    ///       </bltx:name>
+    ///     </bltx:names>
+    ///     <bltx:title>Tracts &amp; Treatises</bltx:title>
+    ///     <bltx:date>2001-03</bltx:date>
+    ///   </bltx:entry>
+    /// </bltx:entries>"#;
+    ///
+    /// let bibliography = Bibliography::from_biblatexml(src).unwrap();
+    /// let entry = bibliography.get("doe").unwrap();
+    /// assert_eq!(entry.author().unwrap()[0].name, "Doe");
+    /// assert_eq!(entry.title().unwrap().format_verbatim(), "Tracts & Treatises");
+    /// ```
+    pub fn from_biblatexml(src: &str) -> Result<Self, BltxmlError> {
+        let root = Element::parse(src).map_err(BltxmlError::Syntax)?;
+        if root.local_name() != "entries" {
+            return Err(BltxmlError::UnexpectedRoot(root.name));
+        }
+
+        let mut bibliography = Self::new();
+        for (i, element) in
+            root.elements().filter(|e| e.local_name() == "entry").enumerate()
+        {
+            let key = element.attribute("id").ok_or(BltxmlError::MissingId(i))?;
+            let entry_type = element.attribute("entrytype").unwrap_or("misc");
+            let mut entry = Entry::new(key.to_string(), EntryType::new(entry_type));
+            let mut annotations: Vec<(String, Vec<String>)> = vec![];
+
+            for field in element.elements() {
+                if field.local_name() == "annotation" {
+                    read_annotation(field, &mut annotations);
+                } else {
+                    let (name, chunks) = read_field(field);
+                    entry.set(&name, chunks);
+                }
+            }
+
+            for (name, items) in annotations {
+                entry.set(&name, text_chunks(items.join("; ")));
+            }
+
+            bibliography.insert(entry);
+        }
+
+        Ok(bibliography)
+    }
+
+    /// Write the entries in biber's BibLaTeXML format.
+    ///
+    /// Name lists, literal lists, cite key lists, and keywords are split
+    /// into their items. Fields like `author+an` are written as
+    /// `bltx:annotation` elements and fields like `title+translated:german`
+    /// as alternative script forms.
+    pub fn write_biblatexml(&self, mut sink: impl Write) -> fmt::Result {
+        writeln!(sink, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(sink, "<bltx:entries xmlns:bltx=\"{}\">", NAMESPACE)?;
+        for entry in self.iter() {
+            write_entry(&mut sink, entry)?;
+        }
+        writeln!(sink, "</bltx:entries>")
+    }
+
+    /// Serialize the entries into a BibLaTeXML string. See
+    /// [`Bibliography::write_biblatexml`].
+    pub fn to_biblatexml_string(&self) -> String {
+        let mut xml = String::new();
+        self.write_biblatexml(&mut xml).unwrap();
+        xml
+    }
+}
+
+/// Read a field element into the name and value of a field.
+fn read_field(element: &Element) -> (String, Chunks) {
+    let (name, value) = match element.local_name() {
+        "names" => {
+            let name = element.attribute("type").unwrap_or("author").to_string();
+            let persons: Vec<Person> = element
+                .elements()
+                .filter(|e| e.local_name() == "name")
+                .map(read_name)
+                .collect();
+            let mut chunks = persons.to_chunks();
+            if element.attribute("morenames") == Some("1") {
+                chunks.push(Spanned::detached(Chunk::Normal(" and others".into())));
+            }
+            (name, chunks)
+        }
+        "date" => {
+            let name = format!("{}date", element.attribute("type").unwrap_or_default());
+            let text = match element.element("start") {
+                Some(start) => {
+                    let end =
+                        element.element("end").map(Element::text).unwrap_or_default();
+                    format!("{}/{}", or_open(start.text()), or_open(end))
+                }
+                None => element.text().trim().to_string(),
+            };
+            (name, text_chunks(text))
+        }
+        "keywords" => {
+            let keywords: Vec<String> = element
+                .elements()
+                .filter(|e| e.local_name() == "keyword")
+                .map(|e| e.text().trim().to_string())
+                .collect();
+            ("keywords".to_string(), text_chunks(keywords.join(", ")))
+        }
+        name => {
+            let name = name.to_string();
+            let value = match element.element("list") {
+                Some(list) => {
+                    let mut items: Vec<Chunks> = list
+                        .elements()
+                        .filter(|e| e.local_name() == "item")
+                        .map(|item| match item.element("start") {
+                            Some(start) => match item.element("end") {
+                                Some(end) => format!("{}--{}", start.text(), end.text()),
+                                None => start.text(),
+                            },
+                            None => item.text().trim().to_string(),
+                        })
+                        .map(|item| list_item(&name, item))
+                        .collect();
+
+                    if LIST_FIELDS.contains(&name.as_str()) {
+                        if list.attribute("morelist") == Some("1") {
+                            items.push(text_chunks("others".to_string()));
+                        }
+                        items.to_chunks()
+                    } else {
+                        let items: Vec<String> =
+                            items.iter().map(|item| item.format_verbatim()).collect();
+                        text_chunks(items.join(", "))
+                    }
+                }
+                None => text_chunks(element.text().trim().to_string()),
+            };
+            (name, value)
+        }
+    };
+
+    let name = match element.attribute("msform").filter(|form| *form != "default") {
+        Some(form) => match element.attribute("mslang") {
+            Some(lang) => format!("{}+{}:{}", name, form, lang),
+            None => format!("{}+{}", name, form),
+        },
+        None => name,
+    };
+
+    (name, value)
+}
+
+/// Read a `bltx:name` element. Name parts with multiple nested parts are
+/// joined with spaces.
+fn read_name(element: &Element) -> Person {
+    let mut person = Person {
+        name: String::new(),
+        given_name: String::new(),
+        prefix: String::new(),
+        suffix: String::new(),
+    };
+
+    for part in element.elements().filter(|e| e.local_name() == "namepart") {
+        let nested: Vec<String> =
+            part.elements().map(|e| e.text().trim().to_string()).collect();
+        let text = if nested.is_empty() {
+            part.text().trim().to_string()
+        } else {
+            nested.join(" ")
+        };
+        match part.attribute("type") {
+            Some("given") => person.given_name = text,
+            Some("prefix") => person.prefix = text,
+            Some("suffix") => person.suffix = text,
+            _ => person.name = text,
+        }
+    }
+
+    person
+}
+
+/// Read a `bltx:annotation` element into the items of its `+an` field.
+fn read_annotation(element: &Element, annotations: &mut Vec<(String, Vec<String>)>) {
+    let Some(field) = element.attribute("field") else { return };
+    let name = match element.attribute("name") {
+        Some(name) => format!("{}+an:{}", field, name),
+        None => format!("{}+an", field),
+    };
+
+    let mut target = element.attribute("item").unwrap_or_default().to_string();
+    if let Some(part) = element.attribute("part") {
+        target = format!("{}:{}", target, part);
+    }
+
+    let value = element.text().trim().to_string();
+    let value = if element.attribute("literal") == Some("1") {
+        format!("\"{}\"", value)
+    } else {
+        value
+    };
+
+    let item = format!("{}={}", target, value);
+    match annotations.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, items)) => items.push(item),
+        None => annotations.push((name, vec![item])),
+    }
+}
+
+/// Write an entry element.
+fn write_entry(sink: &mut impl Write, entry: &Entry) -> fmt::Result {
+    writeln!(
+        sink,
+        "  <bltx:entry id=\"{}\" entrytype=\"{}\">",
+        escape(&entry.key),
+        escape(&entry.entry_type.to_string())
+    )?;
+
+    let mut annotations = vec![];
+    for (key, chunks) in &entry.fields {
+        let (name, suffix) = key.split_once('+').unwrap_or((key, ""));
+        if suffix == "an" || suffix.starts_with("an:") {
+            annotations.push((name, suffix.strip_prefix("an:"), chunks));
+            continue;
+        }
+
+        let (form, lang) = suffix.split_once(':').unwrap_or((suffix, ""));
+        let (name, script) = if SCRIPT_FORMS.contains(&form) {
+            let mut script = format!(" msform=\"{}\"", form);
+            if !lang.is_empty() {
+                write!(script, " mslang=\"{}\"", escape(lang))?;
+            }
+            (name, script)
+        } else {
+            (key.as_str(), String::new())
+        };
+
+        write_field(sink, name, &script, chunks)?;
+    }
+
+    for (field, name, chunks) in annotations {
+        write_annotations(sink, field, name, &chunks.format_verbatim())?;
+    }
+
+    writeln!(sink, "  </bltx:entry>")
+}
+
+/// Write a field element with the given script form attributes.
+fn write_field(
+    sink: &mut impl Write,
+    name: &str,
+    script: &str,
+    chunks: &Chunks,
+) -> fmt::Result {
+    let name = escape(name);
+    let date_type = name.strip_suffix("date");
+
+    if NAME_FIELDS.contains(&name.as_str()) {
+        let mut persons: Vec<Person> = chunks.parse().unwrap_or_default();
+        let more = persons.last().is_some_and(is_others);
+        if more {
+            persons.pop();
+        }
+
+        let more = if more { " morenames=\"1\"" } else { "" };
+        writeln!(sink, "    <bltx:names type=\"{}\"{}{}>", name, script, more)?;
+        for person in persons {
+            writeln!(sink, "      <bltx:name>")?;
+            let parts = [
+                ("given", &person.given_name),
+                ("prefix", &person.prefix),
+                ("family", &person.name),
+                ("suffix", &person.suffix),
+            ];
+            for (kind, part) in parts.into_iter().filter(|(_, part)| !part.is_empty()) {
+                writeln!(
+                    sink,
+                    "        <bltx:namepart type=\"{}\">{}</bltx:namepart>",
+                    kind,
+                    escape(part)
+                )?;
+            }
+            writeln!(sink, "      </bltx:name>")?;
+        }
+        writeln!(sink, "    </bltx:names>")
+    } else if let Some(date_type) =
+        date_type.filter(|_| chunks.parse::<crate::Date>().is_ok())
+    {
+        let date_type = if date_type.is_empty() {
+            String::new()
+        } else {
+            format!(" type=\"{}\"", date_type)
+        };
+        let text = escape(&chunks.format_verbatim());
+        writeln!(sink, "    <bltx:date{}{}>{}</bltx:date>", date_type, script, text)
+    } else if name == "keywords" {
+        writeln!(sink, "    <bltx:keywords{}>", script)?;
+        for keyword in chunks.format_verbatim().split(',').map(str::trim) {
+            writeln!(sink, "      <bltx:keyword>{}</bltx:keyword>", escape(keyword))?;
+        }
+        writeln!(sink, "    </bltx:keywords>")
+    } else if LIST_FIELDS.contains(&name.as_str()) || KEY_FIELDS.contains(&name.as_str())
+    {
+        let items: Vec<String> = if KEY_FIELDS.contains(&name.as_str()) {
+            let text = chunks.format_verbatim();
+            text.split(',').map(|key| key.trim().to_string()).collect()
+        } else {
+            let items: Vec<Chunks> = chunks.parse().unwrap_or_default();
+            items.iter().map(|item| item.format_verbatim()).collect()
+        };
+
+        let more = items.last().is_some_and(|item| item == "others");
+        let items = if more { &items[..items.len() - 1] } else { &items[..] };
+
+        writeln!(sink, "    <bltx:{}{}>", name, script)?;
+        let more = if more { " morelist=\"1\"" } else { "" };
+        writeln!(sink, "      <bltx:list{}>", more)?;
+        for item in items {
+            writeln!(sink, "        <bltx:item>{}</bltx:item>", escape(item))?;
+        }
+        writeln!(sink, "      </bltx:list>")?;
+        writeln!(sink, "    </bltx:{}>", name)
+    } else {
+        let text = escape(&chunks.format_verbatim());
+        writeln!(sink, "    <bltx:{}{}>{}</bltx:{}>", name, script, text, name)
+    }
+}
+
+/// Write the items of a `+an` field as `bltx:annotation` elements.
+///
+/// The items are separated by semicolons and have the form `=value` for the
+/// whole field, `item=value` for a list item, or `item:part=value` for a
+/// name part. Quoted values are literal annotations.
+fn write_annotations(
+    sink: &mut impl Write,
+    field: &str,
+    name: Option<&str>,
+    value: &str,
+) -> fmt::Result {
+    for item in value.split(';').map(str::trim).filter(|item| !item.is_empty()) {
+        let (target, value) = item.split_once('=').unwrap_or(("", item));
+        let (index, part) = target.trim().split_once(':').unwrap_or((target.trim(), ""));
+
+        let mut attributes = format!(" field=\"{}\"", escape(field));
+        if let Some(name) = name {
+            write!(attributes, " name=\"{}\"", escape(name))?;
+        }
+        if !index.is_empty() {
+            write!(attributes, " item=\"{}\"", escape(index))?;
+        }
+        if !part.is_empty() {
+            write!(attributes, " part=\"{}\"", escape(part))?;
+        }
+
+        let value = value.trim();
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(literal) => {
+                attributes.push_str(" literal=\"1\"");
+                literal
+            }
+            None => value,
+        };
+
+        writeln!(
+            sink,
+            "    <bltx:annotation{}>{}</bltx:annotation>",
+            attributes,
+            escape(value)
+        )?;
+    }
+    Ok(())
+}
+
+/// Whether a person stands for `and others`.
+fn is_others(person: &Person) -> bool {
+    person.name == "others" && person.given_name.is_empty() && person.prefix.is_empty()
+}
+
+/// Replace an empty range boundary with the open boundary `..`.
+fn or_open(boundary: String) -> String {
+    let boundary = boundary.trim();
+    if boundary.is_empty() {
+        "..".to_string()
+    } else {
+        boundary.to_string()
+    }
+}
+
+/// The chunks of a list item, protected if it would be split at an `and`.
+fn list_item(field: &str, item: String) -> Chunks {
+    let protect =
+        LIST_FIELDS.contains(&field) && item.split_whitespace().any(|w| w == "and");
+    let chunk = if protect { Chunk::Verbatim(item) } else { Chunk::Normal(item) };
+    vec![Spanned::detached(chunk)]
+}
+
+fn text_chunks(text: String) -> Chunks {
+    vec![Spanned::detached(Chunk::Normal(text))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_biblatexml() {
+        let src = r#"@book{knuth,
+            author = {Knuth, Donald E. and van der Berg, Jr., Jan and others},
+            author+an = {1:family="corresponding"; =equal},
+            title = {The {TeX}book & More},
+            title+translated:german = {Das TeXbuch},
+            date = {1984-05/1986},
+            origdate = {1980},
+            publisher = {Addison-Wesley and {Barnes and Noble} and others},
+            ids = {tex, texbook},
+            keywords = {typesetting, tex},
+            pages = {1--10},
+        }"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let xml = bibliography.to_biblatexml_string();
+
+        assert!(xml.contains(
+            "    <bltx:names type=\"author\" morenames=\"1\">\n      <bltx:name>\n        \
+             <bltx:namepart type=\"given\">Donald E.</bltx:namepart>\n        \
+             <bltx:namepart type=\"family\">Knuth</bltx:namepart>\n      </bltx:name>\n"
+        ));
+        assert!(xml.contains(
+            "    <bltx:annotation field=\"author\" item=\"1\" part=\"family\" literal=\"1\">\
+             corresponding</bltx:annotation>\n    \
+             <bltx:annotation field=\"author\">equal</bltx:annotation>\n"
+        ));
+        assert!(xml.contains("<bltx:date>1984-05/1986</bltx:date>"));
+        assert!(xml.contains("<bltx:date type=\"orig\">1980</bltx:date>"));
+        assert!(xml.contains(
+            "<bltx:title msform=\"translated\" mslang=\"german\">Das TeXbuch</bltx:title>"
+        ));
+        assert!(xml.contains("<bltx:title>The TeXbook &amp; More</bltx:title>"));
+        assert!(xml.contains(
+            "      <bltx:list morelist=\"1\">\n        \
+             <bltx:item>Addison-Wesley</bltx:item>\n        \
+             <bltx:item>Barnes and Noble</bltx:item>\n      </bltx:list>\n"
+        ));
+        assert!(xml.contains("<bltx:item>texbook</bltx:item>"));
+        assert!(xml.contains("<bltx:keyword>tex</bltx:keyword>"));
+
+        let parsed = Bibliography::from_biblatexml(&xml).unwrap();
+        let entry = parsed.get("knuth").unwrap();
+        assert_eq!(
+            entry.author().unwrap(),
+            bibliography.get("knuth").unwrap().author().unwrap()
+        );
+        for field in [
+            "author+an",
+            "title+translated:german",
+            "date",
+            "origdate",
+            "ids",
+            "keywords",
+            "publisher",
+        ] {
+            assert_eq!(
+                entry.get(field).unwrap().format_verbatim(),
+                bibliography
+                    .get("knuth")
+                    .unwrap()
+                    .get(field)
+                    .unwrap()
+                    .format_verbatim(),
+                "{}",
+                field
+            );
+        }
+        assert_eq!(parsed.get("tex").unwrap().key, "knuth");
+        assert_eq!(entry.publisher().unwrap()[1].format_verbatim(), "Barnes and Noble");
+
+        let src = r#"<?xml version="1.0"?>
+            <bltx:entries xmlns:bltx="http://biblatex-biber.sourceforge.net/biblatexml">
+              <!-- A comment -->
+              <bltx:entry id="a" entrytype="article">
+                <bltx:names type="editor">
+                  <bltx:name>
+                    <bltx:namepart type="given">
+                      <bltx:namepart>Anna</bltx:namepart>
+                      <bltx:namepart>Maria</bltx:namepart>
+                    </bltx:namepart>
+                    <bltx:namepart type="family">Roe</bltx:namepart>
+                  </bltx:name>
+                </bltx:names>
+                <bltx:date type="event"><bltx:start>2020-01-02</bltx:start><bltx:end/></bltx:date>
+                <bltx:pages>
+                  <bltx:list>
+                    <bltx:item><bltx:start>10</bltx:start><bltx:end>15</bltx:end></bltx:item>
+                    <bltx:item>20</bltx:item>
+                  </bltx:list>
+                </bltx:pages>
+                <bltx:annotation field="title" name="alt" literal="1">x</bltx:annotation>
+              </bltx:entry>
+            </bltx:entries>"#;
+        let parsed = Bibliography::from_biblatexml(src).unwrap();
+        let entry = parsed.get("a").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Article);
+        assert_eq!(entry.editor().unwrap()[0].given_name, "Anna Maria");
+        assert_eq!(entry.get("eventdate").unwrap().format_verbatim(), "2020-01-02/..");
+        assert_eq!(
+            entry.pages().unwrap(),
+            crate::PermissiveType::Typed(vec![10..15, 20..20])
+        );
+        assert_eq!(entry.get("title+an:alt").unwrap().format_verbatim(), "=\"x\"");
+
+        assert_eq!(
+            Bibliography::from_biblatexml("<entries><entry/></entries>"),
+            Err(BltxmlError::MissingId(0))
+        );
+        assert_eq!(
+            Bibliography::from_biblatexml("<bib/>"),
+            Err(BltxmlError::UnexpectedRoot("bib".into()))
+        );
+        assert_eq!(
+            Bibliography::from_biblatexml("<entries>"),
+            Err(BltxmlError::Syntax(9))
+        );
+        let deep = format!("<entries>{}", "<entry>".repeat(100_000));
+        assert!(matches!(
+            Bibliography::from_biblatexml(&deep),
+            Err(BltxmlError::Syntax(_))
+        ));
+    }
+}
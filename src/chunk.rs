@@ -12,7 +12,7 @@ pub type Chunks = Vec<Spanned<Chunk>>;
 pub type ChunksRef<'a> = &'a [Spanned<Chunk>];
 
 /// Represents one part of a field value.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Chunk {
     /// Normal values within quotes or single braces subject to
@@ -61,7 +61,16 @@ impl Chunk {
     ///
     /// The `is_verbatim` argument indicates whether this string is intended for
     /// a verbatim field like `file` with limited escapes.
+    ///
+    /// Math chunks are returned unescaped, since they hold the TeX source
+    /// between the dollar signs. Escaping them would turn `$x_1$` into
+    /// `$x\_1$` when writing a bibliography back to a file.
     pub fn to_biblatex_string(&self, is_verbatim: bool) -> String {
+        // Math is stored as it appeared in the source.
+        if let Chunk::Math(math) = self {
+            return math.clone();
+        }
+
         let mut s = String::new();
         for c in self.get().chars() {
             if is_escapable(c, is_verbatim, false) {
@@ -84,6 +93,17 @@ pub trait ChunksExt {
     /// Format the chunks verbatim.
     fn format_verbatim(&self) -> String;
 
+    /// Format the chunks verbatim, collapsing runs of whitespace into a single
+    /// space and trimming the result.
+    fn format_normalized(&self) -> String;
+
+    /// Format the chunks verbatim and split them into paragraphs.
+    ///
+    /// Paragraph breaks are only preserved in long text fields like `abstract`
+    /// or `note`. Each paragraph is normalized like in
+    /// [`format_normalized`](Self::format_normalized).
+    fn format_paragraphs(&self) -> Vec<String>;
+
     /// Output a span for all chunks in the collection.
     fn span(&self) -> Span;
 
@@ -178,6 +198,44 @@ impl ChunksExt for [Spanned<Chunk>] {
         out
     }
 
+    fn format_normalized(&self) -> String {
+        self.format_verbatim()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn format_paragraphs(&self) -> Vec<String> {
+        let mut paragraphs = vec![];
+        let mut current = String::new();
+
+        for val in self {
+            match &val.v {
+                Chunk::Normal(s) => {
+                    let mut parts = s.split("\n\n");
+                    current += parts.next().unwrap_or_default();
+                    for part in parts {
+                        paragraphs.push(std::mem::take(&mut current));
+                        current += part;
+                    }
+                }
+                Chunk::Verbatim(s) => current += s,
+                Chunk::Math(s) => {
+                    current.push('$');
+                    current += s;
+                    current.push('$');
+                }
+            }
+        }
+
+        paragraphs.push(current);
+        paragraphs
+            .iter()
+            .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+
     fn span(&self) -> Span {
         let start = self.first().map(|c| c.span.start).unwrap_or(0);
         let end = self.last().map(|c| c.span.end).unwrap_or(start);
@@ -222,7 +280,9 @@ impl ChunksExt for [Spanned<Chunk>] {
 
 /// An iterator over the characters in each chunk, indicating whether they are
 /// verbatim or not. Chunk types other than `Normal` or `Verbatim` are omitted.
-pub(crate) fn chunk_chars(chunks: ChunksRef) -> impl Iterator<Item = (char, bool)> + '_ {
+pub(crate) fn chunk_chars(
+    chunks: ChunksRef<'_>,
+) -> impl Iterator<Item = (char, bool)> + '_ {
     chunks.iter().flat_map(|chunk| {
         let (s, verbatim) = chunk.v.get_and_verb();
 
@@ -268,12 +328,14 @@ pub(crate) fn split_token_lists(vals: ChunksRef, keyword: &str) -> Vec<Chunks> {
                 let first = target[..pos].trim_end();
                 latest.push(Spanned::new(
                     Chunk::Normal(first.to_string()),
-                    start..start + pos,
+                    start..start.saturating_add(pos),
                 ));
                 out.push(std::mem::take(&mut latest));
 
-                target = target[pos + keyword.len()..].trim_start();
-                start += pos + keyword.len();
+                let rest = &target[pos + keyword.len()..];
+                target = rest.trim_start();
+                start =
+                    start.saturating_add(pos + keyword.len() + rest.len() - target.len());
             }
 
             latest.push(Spanned::new(
@@ -515,6 +577,30 @@ pub(crate) mod tests {
         assert_eq!(split.1, ref2);
     }
 
+    #[test]
+    fn test_format_normalized() {
+        let vls = &[s(N(" Published \n by "), 0..16), s(V("the  author "), 18..30)];
+        assert_eq!(vls.format_normalized(), "Published by the author");
+    }
+
+    #[test]
+    fn test_format_paragraphs() {
+        let vls =
+            &[s(N("One {"), 0..5), s(V("A"), 5..6), s(N(" and\n\ntwo.\n\n"), 6..18)];
+        assert_eq!(vls.format_paragraphs(), vec!["One {A and", "two."]);
+    }
+
+    #[test]
+    fn test_to_biblatex_string() {
+        assert_eq!(N("x_1 & 50%").to_biblatex_string(false), r"x\_1 \& 50\%");
+        let math = Chunk::Math(r"\alpha_1^{2}".to_string());
+        assert_eq!(math.to_biblatex_string(false), r"\alpha_1^{2}");
+        assert_eq!(
+            [d(N("a ")), d(math)].to_biblatex_string(false),
+            r"{a $\alpha_1^{2}$}"
+        );
+    }
+
     #[test]
     fn test_split_at_normal_char() {
         let vls = &[
@@ -0,0 +1,185 @@
+//! Collection of cited keys from LaTeX documents.
+
+use unscanny::Scanner;
+
+use crate::scanner::ScannerExt;
+
+/// Collect the cite keys from the contents of a LaTeX `.aux` file.
+///
+/// Both the `\citation{key1,key2}` lines written for BibTeX and the
+/// `\abx@aux@cite{refsection}{key}` lines written by biblatex are recognized.
+/// The keys are returned in order of their first citation. A `\nocite{*}`
+/// shows up as the key `*`.
+///
+/// The result can be used to extract the cited entries with
+/// [`Bibliography::subset`](crate::Bibliography::subset).
+///
+/// ```
+/// # use biblatex::aux_citations;
+/// let aux = r"\relax
+/// \citation{knuth,lamport}
+/// \abx@aux@cite{0}{knuth}
+/// \abx@aux@cite{0}{tolkien}";
+/// assert_eq!(aux_citations(aux), ["knuth", "lamport", "tolkien"]);
+/// ```
+pub fn aux_citations(src: &str) -> Vec<String> {
+    let mut keys = vec![];
+    let mut s = Scanner::new(src);
+
+    while !s.done() {
+        if s.eat_if(r"\citation") {
+            if let Some(group) = group(&mut s) {
+                push_keys(&mut keys, group);
+            }
+        } else if s.eat_if(r"\abx@aux@cite") {
+            // Older biblatex versions omit the refsection argument.
+            let first = group(&mut s);
+            let second = group(&mut s);
+            if let Some(group) = second.or(first) {
+                push_keys(&mut keys, group);
+            }
+        } else {
+            s.eat();
+        }
+    }
+
+    keys
+}
+
+/// Collect the cite keys from the contents of a LaTeX source file.
+///
+/// All commands whose name starts or ends with `cite` or `cites` are
+/// recognized, which covers the citation commands of LaTeX, natbib, and
+/// biblatex like `\cite`, `\parencite`, `\textcite`, `\citeauthor`, and
+/// `\citep`. Starred forms, optional arguments, multiple keys, and the
+/// multicite commands like `\cites[see][5]{a}{b}` are supported. Comments are
+/// skipped. The keys are returned in order of their first citation.
+///
+/// ```
+/// # use biblatex::tex_citations;
+/// let tex = r"As shown by \textcite[12]{knuth}, see also \parencite*{lamport,knuth}.
+/// % \cite{commented}
+/// \cites(See)()[5]{tolkien}{lewis}";
+/// assert_eq!(tex_citations(tex), ["knuth", "lamport", "tolkien", "lewis"]);
+/// ```
+pub fn tex_citations(src: &str) -> Vec<String> {
+    let mut keys = vec![];
+    let mut s = Scanner::new(src);
+
+    while let Some(c) = s.eat() {
+        match c {
+            '%' => {
+                s.eat_until('\n');
+            }
+            '\\' => {
+                let name = s.eat_while(char::is_alphabetic);
+                if name.is_empty() {
+                    // An escaped character like `\%`.
+                    s.eat();
+                    continue;
+                }
+
+                let is_multi = name.ends_with("cites");
+                if !is_multi && !name.starts_with("cite") && !name.ends_with("cite") {
+                    continue;
+                }
+
+                s.eat_if('*');
+                loop {
+                    while delimited(&mut s, '(', ')').is_some()
+                        || delimited(&mut s, '[', ']').is_some()
+                    {}
+
+                    match group(&mut s) {
+                        Some(group) => push_keys(&mut keys, group),
+                        None => break,
+                    }
+
+                    if !is_multi {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    keys
+}
+
+/// Read a brace-delimited argument, possibly preceded by whitespace, and
+/// return its contents.
+fn group<'s>(s: &mut Scanner<'s>) -> Option<&'s str> {
+    delimited(s, '{', '}')
+}
+
+/// Read an argument enclosed in the given delimiters, possibly preceded by
+/// whitespace, and return its contents. Braces within the argument are
+/// balanced.
+fn delimited<'s>(s: &mut Scanner<'s>, open: char, close: char) -> Option<&'s str> {
+    let checkpoint = s.checkpoint();
+    s.eat_whitespace();
+    if !s.eat_if(open) {
+        s.restore(checkpoint);
+        return None;
+    }
+
+    let inner = s.cursor();
+    let mut depth = 0;
+    while let Some(c) = s.eat() {
+        match c {
+            '{' if open != '{' => depth += 1,
+            '}' if open != '{' => depth -= 1,
+            c if c == open => depth += 1,
+            c if c == close && depth == 0 => {
+                return Some(s.from(inner).strip_suffix(close).unwrap());
+            }
+            c if c == close => depth -= 1,
+            _ => {}
+        }
+    }
+
+    s.restore(checkpoint);
+    None
+}
+
+/// Add the comma-separated keys of an argument that were not seen before.
+fn push_keys(keys: &mut Vec<String>, group: &str) {
+    for key in group.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+        if !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aux_citations() {
+        let aux = r"\relax
+            \abx@aux@refcontext{nty/global//global/global}
+            \citation{a, b}
+            \abx@aux@cite{a}
+            \abx@aux@cite{0}{c}
+            \abx@aux@segm{0}{0}{c}
+            \citation{*}
+            \citation{broken";
+
+        assert_eq!(aux_citations(aux), ["a", "b", "c", "*"]);
+        assert!(aux_citations("").is_empty());
+    }
+
+    #[test]
+    fn test_tex_citations() {
+        let tex = r"\documentclass{article}
+            \begin{document}
+            Text \cite{a}, \Textcite [p.~5] {b , c} and \citep[see][{p. [3]}]{d}.
+            50\% \footcite{e} % \cite{ignored}
+            \autocites(Pre)(Post)[1]{f}[2]{g} \citeauthor*{a} \nocite{*}
+            \citestyle \section{h} \recite \cite";
+
+        assert_eq!(tex_citations(tex), ["a", "b", "c", "d", "e", "f", "g", "*"]);
+    }
+}
@@ -0,0 +1,664 @@
+//! Conversion between the BibTeX and BibLaTeX conventions.
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::mechanics::FIELD_ALIASES;
+use crate::{
+    Bibliography, Chunk, Chunks, ChunksExt, Entry, EntryType, FormatOptions,
+    PermissiveType, Spanned, ThesisType,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A flavor of the `.bib` format a bibliography can be converted to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TargetDialect {
+    /// Classic BibTeX with its entry types and fields.
+    BibTeX,
+    /// BibLaTeX with its entry types and fields.
+    BibLaTeX,
+}
+
+/// Information that was lost when writing an entry as classic BibTeX with
+/// [`Bibliography::write_bibtex_strict`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompatWarning {
+    /// The cite key of the affected entry.
+    pub key: String,
+    /// What was lost.
+    pub kind: CompatWarningKind,
+}
+
+/// What was lost when writing an entry as classic BibTeX.
+///
+/// Also see [`CompatWarning`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompatWarningKind {
+    /// The entry type has no BibTeX counterpart and was replaced with a more
+    /// general one.
+    Retyped {
+        /// The original entry type.
+        from: EntryType,
+        /// The BibTeX entry type it was written as.
+        to: EntryType,
+    },
+    /// The field was appended to another field, like a `subtitle` to the
+    /// `title`.
+    Merged {
+        /// The name of the merged field.
+        field: String,
+        /// The name of the field it was appended to.
+        into: String,
+    },
+    /// Only a part of the field was kept, like the year and month of a
+    /// `date`.
+    Truncated(String),
+    /// The field has no BibTeX counterpart and was dropped.
+    Dropped(String),
+}
+
+impl Display for CompatWarning {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.key)?;
+        match &self.kind {
+            CompatWarningKind::Retyped { from, to } => {
+                write!(f, "entry type {} was written as {}", from, to)
+            }
+            CompatWarningKind::Merged { field, into } => {
+                write!(f, "field {} was merged into {}", field, into)
+            }
+            CompatWarningKind::Truncated(field) => {
+                write!(f, "field {} was only partially kept", field)
+            }
+            CompatWarningKind::Dropped(field) => write!(f, "field {} was dropped", field),
+        }
+    }
+}
+
+/// The fields of classic BibTeX, together with widely supported extensions
+/// like `doi` and `url`.
+const BIBTEX_FIELDS: [&str; 33] = [
+    "abstract",
+    "address",
+    "annote",
+    "archiveprefix",
+    "author",
+    "booktitle",
+    "chapter",
+    "crossref",
+    "doi",
+    "edition",
+    "editor",
+    "eprint",
+    "howpublished",
+    "institution",
+    "isbn",
+    "issn",
+    "journal",
+    "key",
+    "keywords",
+    "month",
+    "note",
+    "number",
+    "organization",
+    "pages",
+    "primaryclass",
+    "publisher",
+    "school",
+    "series",
+    "title",
+    "type",
+    "url",
+    "volume",
+    "year",
+];
+
+impl Entry {
+    /// Replace a legacy BibTeX entry type with its BibLaTeX counterpart and
+    /// record the distinction in the `type` field: `@phdthesis` becomes
+    /// `@thesis` with `type = {phdthesis}`, `@mastersthesis` becomes `@thesis`
+    /// with `type = {mathesis}`, and `@techreport` becomes `@report` with
+    /// `type = {techreport}`. An existing `type` field is kept.
+    ///
+    /// Returns whether the entry type was changed.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, EntryType, ThesisType};
+    /// let src = "@phdthesis{a, author = {Doe, Jane}}";
+    /// let mut bibliography = Bibliography::parse(src).unwrap();
+    /// let entry = bibliography.get_mut("a").unwrap();
+    ///
+    /// assert!(entry.normalize_type());
+    /// assert_eq!(entry.entry_type, EntryType::Thesis);
+    /// assert_eq!(entry.thesis_type(), Ok(ThesisType::PhdThesis));
+    ///
+    /// assert!(entry.denormalize_type());
+    /// assert_eq!(entry.entry_type, EntryType::PhdThesis);
+    /// assert!(entry.get("type").is_none());
+    /// ```
+    pub fn normalize_type(&mut self) -> bool {
+        let (entry_type, kind) = match self.entry_type {
+            EntryType::PhdThesis => (EntryType::Thesis, "phdthesis"),
+            EntryType::MastersThesis => (EntryType::Thesis, "mathesis"),
+            EntryType::TechReport => (EntryType::Report, "techreport"),
+            _ => return false,
+        };
+
+        if self.get_non_empty("type").is_none() {
+            self.set("type", vec![Spanned::detached(Chunk::Normal(kind.to_string()))]);
+        }
+
+        self.entry_type = entry_type;
+        true
+    }
+
+    /// Replace a BibLaTeX `@thesis` or `@report` entry with the matching
+    /// legacy BibTeX type, the reverse of [`Entry::normalize_type`].
+    ///
+    /// Theses become `@mastersthesis` if their `type` is a master's thesis and
+    /// `@phdthesis` otherwise, reports become `@techreport`. A `type` field
+    /// holding a BibLaTeX localization key like `phdthesis` is removed because
+    /// BibTeX would print it verbatim, other values are kept.
+    ///
+    /// Returns whether the entry type was changed.
+    pub fn denormalize_type(&mut self) -> bool {
+        let (entry_type, keys): (_, &[&str]) = match self.entry_type {
+            EntryType::Thesis => match self.thesis_type() {
+                Ok(ThesisType::MaThesis) => (EntryType::MastersThesis, &["mathesis"]),
+                _ => (EntryType::PhdThesis, &["phdthesis"]),
+            },
+            EntryType::Report => (EntryType::TechReport, &["techreport"]),
+            _ => return false,
+        };
+
+        let is_key = self
+            .get("type")
+            .is_some_and(|chunks| keys.contains(&chunks.format_verbatim().trim()));
+        if is_key {
+            self.remove("type");
+        }
+
+        self.entry_type = entry_type;
+        true
+    }
+}
+
+impl Entry {
+    /// Convert the entry into one that only uses the BibTeX entry types and
+    /// fields, together with the names of the fields that cannot be
+    /// represented and were dropped.
+    ///
+    /// The entry type is converted with [`Entry::denormalize_type`] and
+    /// [`EntryType::to_bibtex`]. Fields are renamed to their BibTeX
+    /// counterparts, e.g. `journaltitle` to `journal` and `location` to
+    /// `address`, the `date` is split into `year` and `month`, and subtitles
+    /// and title addons are appended to their titles. Besides the classic
+    /// BibTeX fields, the widely supported `abstract`, `archiveprefix`, `doi`,
+    /// `eprint`, `isbn`, `issn`, `keywords`, `primaryclass`, and `url` fields
+    /// are kept. A `date` that cannot be parsed is dropped in favor of the
+    /// `year` and `month` fields, as are subtitles and addons without a title.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, ChunksExt, EntryType};
+    /// let src = "@thesis{a, title = {A}, type = {mathesis}, date = {2020-05-17},
+    ///     institution = {MIT}, journaltitle = {J}, urldate = {2021-01-01}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let (entry, dropped) = bibliography.get("a").unwrap().to_bibtex();
+    /// assert_eq!(entry.entry_type, EntryType::MastersThesis);
+    /// assert_eq!(entry.get("school").unwrap().format_verbatim(), "MIT");
+    /// assert_eq!(entry.get("journal").unwrap().format_verbatim(), "J");
+    /// assert_eq!(entry.get("month").unwrap().format_verbatim(), "05");
+    /// assert_eq!(dropped, ["date", "urldate"]);
+    /// ```
+    pub fn to_bibtex(&self) -> (Entry, Vec<String>) {
+        let mut source = self.clone();
+        source.denormalize_type();
+
+        let entry_type = source.entry_type.to_bibtex();
+        let thesis =
+            matches!(entry_type, EntryType::PhdThesis | EntryType::MastersThesis);
+        let mut entry = Entry::new(self.key.clone(), entry_type);
+        let mut dropped = vec![];
+
+        let date = source.get("date").map(|_| source.date());
+        let date_folded = matches!(date, Some(Ok(PermissiveType::Typed(_))));
+        if let Some(Ok(PermissiveType::Typed(date))) = date {
+            let mut lossy = false;
+            for (field, value) in date.to_fieldset() {
+                if field == "year" || field == "month" {
+                    entry.set(&field, vec![Spanned::detached(Chunk::Normal(value))]);
+                } else {
+                    lossy = true;
+                }
+            }
+
+            if lossy {
+                dropped.push("date".to_string());
+            }
+        }
+
+        for (title, parts) in [
+            ("title", ["subtitle", "titleaddon"]),
+            ("booktitle", ["booksubtitle", "booktitleaddon"]),
+        ] {
+            if let Some(joined) = join_title(&source, title, parts) {
+                entry.set(title, joined);
+            }
+        }
+
+        for (field, chunks) in &source.fields {
+            let name = match field.as_str() {
+                "date" => {
+                    if !entry.fields.contains_key("year") {
+                        dropped.push(field.to_string());
+                    }
+                    continue;
+                }
+                "year" | "month" | "day" if date_folded => continue,
+                "subtitle" | "titleaddon" if source.fields.contains_key("title") => {
+                    continue
+                }
+                "booksubtitle" | "booktitleaddon"
+                    if source.fields.contains_key("booktitle") =>
+                {
+                    continue
+                }
+                "institution" if thesis => "school",
+                "institution" => "institution",
+                field => FIELD_ALIASES
+                    .iter()
+                    .find(|(_, biblatex)| *biblatex == field)
+                    .map_or(field, |(bibtex, _)| *bibtex),
+            };
+
+            if !BIBTEX_FIELDS.contains(&name) {
+                dropped.push(field.to_string());
+            } else if !entry.fields.contains_key(name) {
+                entry.set(name, chunks.clone());
+            }
+        }
+
+        dropped.sort();
+        dropped.dedup();
+        (entry, dropped)
+    }
+}
+
+/// A title with its subtitle and addon appended, if it has any of them.
+fn join_title(
+    entry: &Entry,
+    title: &str,
+    [subtitle, addon]: [&str; 2],
+) -> Option<Chunks> {
+    let mut joined = entry.get(title)?.to_vec();
+    let mut changed = false;
+    for (field, separator) in [(subtitle, ": "), (addon, ". ")] {
+        if let Some(chunks) = entry.get(field) {
+            joined.push(Spanned::detached(Chunk::Normal(separator.to_string())));
+            joined.extend(chunks.iter().cloned());
+            changed = true;
+        }
+    }
+    changed.then_some(joined)
+}
+
+impl Bibliography {
+    /// Replace the legacy BibTeX entry types of all entries with their
+    /// BibLaTeX counterparts, see [`Entry::normalize_type`]. Returns the cite
+    /// keys of the changed entries.
+    pub fn normalize_types(&mut self) -> Vec<String> {
+        self.entries
+            .iter_mut()
+            .filter_map(|entry| entry.normalize_type().then(|| entry.key.clone()))
+            .collect()
+    }
+
+    /// Convert all entries to the entry types and fields of a dialect.
+    ///
+    /// For [`TargetDialect::BibTeX`], each entry is replaced as described in
+    /// [`Entry::to_bibtex`]. For [`TargetDialect::BibLaTeX`], legacy entry
+    /// types are normalized with [`Entry::normalize_type`], legacy field names
+    /// are renamed with [`Entry::normalize_aliases`], and the `year`, `month`,
+    /// and `day` fields are merged into a `date` field.
+    ///
+    /// Returns the cite keys of the entries that lost fields in the
+    /// conversion together with the names of the dropped fields.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, ChunksExt, TargetDialect};
+    /// let src = "@phdthesis{a, school = {MIT}, year = {2001}, month = {5}}";
+    /// let mut bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// bibliography.convert(TargetDialect::BibLaTeX);
+    /// let converted = bibliography.to_biblatex_string();
+    /// assert!(converted.starts_with("@thesis{a,\ndate = {2001-05},"));
+    ///
+    /// bibliography.convert(TargetDialect::BibTeX);
+    /// let a = bibliography.get("a").unwrap();
+    /// assert_eq!(a.get("school").unwrap().format_verbatim(), "MIT");
+    /// assert_eq!(a.get("year").unwrap().format_verbatim(), "2001");
+    /// ```
+    pub fn convert(&mut self, dialect: TargetDialect) -> Vec<(String, Vec<String>)> {
+        let mut dropped = vec![];
+        for index in 0..self.entries.len() {
+            let entry = &mut self.entries[index];
+            match dialect {
+                TargetDialect::BibTeX => {
+                    let (converted, fields) = entry.to_bibtex();
+                    if !fields.is_empty() {
+                        dropped.push((entry.key.clone(), fields));
+                    }
+
+                    // Aliases from a dropped `ids` field must not resolve
+                    // anymore.
+                    let key = entry.key.clone();
+                    self.replace(&key, converted).unwrap();
+                }
+                TargetDialect::BibLaTeX => {
+                    entry.normalize_type();
+                    entry.normalize_aliases();
+                    if entry.get("date").is_none() {
+                        if let Ok(PermissiveType::Typed(date)) = entry.date() {
+                            entry.set_date(PermissiveType::Typed(date));
+                        }
+                    }
+                }
+            }
+        }
+        dropped
+    }
+
+    /// Write the bibliography in the BibTeX format using only the entry types
+    /// and fields of classic BibTeX, for legacy bibliography styles.
+    ///
+    /// Each entry is converted as described in [`Entry::to_bibtex`]. Unlike
+    /// [`Bibliography::write_bibtex_with`], fields without a BibTeX
+    /// counterpart are not written. Returns every loss of information in the
+    /// order of the written entries.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, CompatWarningKind, FormatOptions};
+    /// let src = "@online{a, title = {A}, subtitle = {B}, urldate = {2020-01-01}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let mut bibtex = String::new();
+    /// let options = FormatOptions::default();
+    /// let warnings = bibliography.write_bibtex_strict(&mut bibtex, &options).unwrap();
+    /// assert_eq!(bibtex, "@misc{a,\ntitle = {A: B},\n}\n");
+    /// assert_eq!(warnings.len(), 3);
+    /// assert_eq!(warnings[2].kind, CompatWarningKind::Dropped("urldate".into()));
+    /// ```
+    pub fn write_bibtex_strict(
+        &self,
+        mut sink: impl Write,
+        options: &FormatOptions,
+    ) -> Result<Vec<CompatWarning>, fmt::Error> {
+        let mut warnings = vec![];
+        let mut first = !options.write_abbreviations(&mut sink, &[])?;
+        if !self.preamble.is_empty() {
+            if !first {
+                options.write_separator(&mut sink)?;
+            }
+            write!(sink, "@preamble{{{}}}", self.preamble)?;
+            first = false;
+        }
+
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        options.order_entries(&mut entries, |entry| &entry.key, |t| self.sorted_keys(t));
+        for entry in entries {
+            if !first {
+                options.write_separator(&mut sink)?;
+            }
+
+            let (converted, dropped) = entry.to_bibtex();
+            let warn = |kind| CompatWarning { key: entry.key.clone(), kind };
+
+            let mut denormalized = entry.clone();
+            denormalized.denormalize_type();
+            if denormalized.entry_type != converted.entry_type {
+                warnings.push(warn(CompatWarningKind::Retyped {
+                    from: entry.entry_type.clone(),
+                    to: converted.entry_type.clone(),
+                }));
+            }
+
+            for (title, parts) in [
+                ("title", ["subtitle", "titleaddon"]),
+                ("booktitle", ["booksubtitle", "booktitleaddon"]),
+            ] {
+                if entry.get(title).is_none() {
+                    continue;
+                }
+                for part in parts.into_iter().filter(|part| entry.get(part).is_some()) {
+                    warnings.push(warn(CompatWarningKind::Merged {
+                        field: part.to_string(),
+                        into: title.to_string(),
+                    }));
+                }
+            }
+
+            for field in dropped {
+                let kind = match field.as_str() {
+                    "date" if matches!(entry.date(), Ok(PermissiveType::Typed(_))) => {
+                        CompatWarningKind::Truncated(field)
+                    }
+                    _ => CompatWarningKind::Dropped(field),
+                };
+                warnings.push(warn(kind));
+            }
+
+            let bibtex =
+                converted.to_bibtex_string_with(options).map_err(|_| fmt::Error)?;
+            write!(sink, "{}", bibtex)?;
+            first = false;
+        }
+
+        options.write_end(&mut sink, first)?;
+        Ok(warnings)
+    }
+
+    /// Serialize this bibliography into a BibTeX string using only the entry
+    /// types and fields of classic BibTeX, see
+    /// [`Bibliography::write_bibtex_strict`].
+    pub fn to_bibtex_string_strict(
+        &self,
+        options: &FormatOptions,
+    ) -> (String, Vec<CompatWarning>) {
+        let mut bibtex = String::new();
+        let warnings = self.write_bibtex_strict(&mut bibtex, options).unwrap();
+        (bibtex, warnings)
+    }
+
+    /// Replace the BibLaTeX `@thesis` and `@report` types of all entries with
+    /// the matching legacy BibTeX types, see [`Entry::denormalize_type`].
+    /// Returns the cite keys of the changed entries.
+    pub fn denormalize_types(&mut self) -> Vec<String> {
+        self.entries
+            .iter_mut()
+            .filter_map(|entry| entry.denormalize_type().then(|| entry.key.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_types() {
+        let src = r#"@phdthesis{a, title = {A}}
+            @mastersthesis{b, title = {B}, type = {Diplomarbeit}}
+            @techreport{c, title = {C}}
+            @thesis{d, type = {mathesis}}
+            @report{e, type = {Research Memorandum}}
+            @article{f, title = {F}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        assert_eq!(bibliography.normalize_types(), ["a", "b", "c"]);
+        let types: Vec<_> = bibliography.iter().map(|e| e.entry_type.clone()).collect();
+        assert_eq!(
+            types[..5],
+            [
+                EntryType::Thesis,
+                EntryType::Thesis,
+                EntryType::Report,
+                EntryType::Thesis,
+                EntryType::Report
+            ]
+        );
+        let b = bibliography.get("b").unwrap();
+        assert_eq!(b.type_().unwrap(), "Diplomarbeit");
+        let c = bibliography.get("c").unwrap();
+        assert_eq!(c.type_().unwrap(), "techreport");
+
+        assert_eq!(bibliography.denormalize_types(), ["a", "b", "c", "d", "e"]);
+        let types: Vec<_> = bibliography.iter().map(|e| e.entry_type.clone()).collect();
+        assert_eq!(
+            types,
+            [
+                EntryType::PhdThesis,
+                EntryType::PhdThesis,
+                EntryType::TechReport,
+                EntryType::MastersThesis,
+                EntryType::TechReport,
+                EntryType::Article,
+            ]
+        );
+        assert!(bibliography
+            .iter()
+            .take(4)
+            .all(|e| e.get("type").is_none() || e.key == "b"));
+        let e = bibliography.get("e").unwrap();
+        assert_eq!(e.type_().unwrap(), "Research Memorandum");
+    }
+
+    #[test]
+    fn test_to_bibtex() {
+        let src = r#"@report{a, author = {Doe, Jane}, title = {Title},
+              subtitle = {Subtitle}, titleaddon = {Addon}, type = {techreport},
+              date = {2001/2003}, location = {Berlin}, institution = {TU},
+              doi = {10.1/2}, pagetotal = {12}, annotation = {Good},
+              eprinttype = {arXiv}, journal = {Legacy}}
+            @online{b, title = {B}, date = {2024-02}, year = {2023}, url = {https://x.org},
+              organization = {Org}}
+            @article{c, title = {C}, date = {circa}}
+            @inbook{d, date = {circa}, year = {1990}, day = {3}, subtitle = {Sub},
+              booktitle = {Book}, booksubtitle = {Part}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let (a, dropped) = bibliography.get("a").unwrap().to_bibtex();
+        assert_eq!(a.entry_type, EntryType::TechReport);
+        assert_eq!(a.title().unwrap().format_verbatim(), "Title: Subtitle. Addon");
+        assert_eq!(a.get("year").unwrap().format_verbatim(), "2001");
+        assert_eq!(a.get("address").unwrap().format_verbatim(), "Berlin");
+        assert_eq!(a.get("institution").unwrap().format_verbatim(), "TU");
+        assert_eq!(a.get("annote").unwrap().format_verbatim(), "Good");
+        assert_eq!(a.get("archiveprefix").unwrap().format_verbatim(), "arXiv");
+        assert_eq!(a.get("journal").unwrap().format_verbatim(), "Legacy");
+        assert!(a.get("type").is_none());
+        assert!(a.get("doi").is_some());
+        assert_eq!(dropped, ["date", "pagetotal"]);
+
+        let (b, dropped) = bibliography.get("b").unwrap().to_bibtex();
+        assert_eq!(b.entry_type, EntryType::Misc);
+        assert_eq!(b.get("year").unwrap().format_verbatim(), "2024");
+        assert_eq!(b.get("month").unwrap().format_verbatim(), "02");
+        assert!(dropped.is_empty());
+
+        let (c, dropped) = bibliography.get("c").unwrap().to_bibtex();
+        assert!(c.get("year").is_none());
+        assert_eq!(dropped, ["date"]);
+
+        let (d, dropped) = bibliography.get("d").unwrap().to_bibtex();
+        assert_eq!(d.get("year").unwrap().format_verbatim(), "1990");
+        assert_eq!(d.get("booktitle").unwrap().format_verbatim(), "Book: Part");
+        assert_eq!(dropped, ["date", "day", "subtitle"]);
+    }
+
+    #[test]
+    fn test_convert() {
+        let src = r#"@techreport{a, title = {A}, year = {1999}, month = {dec}, day = {24},
+              journal = {J}, address = {Paris}, ids = {alias}}
+            @online{b, title = {B}, date = {2024-02-29}, urldate = {2024-03-01}}
+            @article{c, title = {C}, year = {n.d.}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        assert!(bibliography.convert(TargetDialect::BibLaTeX).is_empty());
+        let a = bibliography.get("alias").unwrap();
+        assert_eq!(a.entry_type, EntryType::Report);
+        assert_eq!(a.get("date").unwrap().format_verbatim(), "1999-12-24");
+        assert_eq!(a.get("journaltitle").unwrap().format_verbatim(), "J");
+        assert_eq!(a.get("location").unwrap().format_verbatim(), "Paris");
+        assert!(a.get("year").is_none() && a.get("address").is_none());
+        let c = bibliography.get("c").unwrap();
+        assert_eq!(c.get("year").unwrap().format_verbatim(), "n.d.");
+
+        let dropped = bibliography.convert(TargetDialect::BibTeX);
+        assert_eq!(
+            dropped,
+            [
+                ("a".to_string(), vec!["date".to_string(), "ids".to_string()]),
+                ("b".to_string(), vec!["date".to_string(), "urldate".to_string()]),
+            ]
+        );
+        assert!(bibliography.get("alias").is_none());
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.entry_type, EntryType::TechReport);
+        assert_eq!(a.get("month").unwrap().format_verbatim(), "12");
+        assert_eq!(a.get("journal").unwrap().format_verbatim(), "J");
+        let b = bibliography.get("b").unwrap();
+        assert_eq!(b.entry_type, EntryType::Misc);
+    }
+
+    #[test]
+    fn test_write_bibtex_strict() {
+        let src = r#"@xdata{x, publisher = {P}}
+            @thesis{a, title = {A}, type = {mathesis}, date = {2020-05-17},
+              institution = {MIT}, xdata = {x}}
+            @mvbook{b, booksubtitle = {Sub}, titleaddon = {Addon}, title = {B},
+              eventdate = {2020}}
+            @article{c, journaltitle = {J}, date = {2021-03}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let (bibtex, warnings) =
+            bibliography.to_bibtex_string_strict(&FormatOptions::default());
+
+        let warning = |key: &str, kind| CompatWarning { key: key.into(), kind };
+        assert_eq!(
+            warnings,
+            [
+                warning("a", CompatWarningKind::Truncated("date".into())),
+                warning(
+                    "b",
+                    CompatWarningKind::Retyped {
+                        from: EntryType::MvBook,
+                        to: EntryType::Book
+                    }
+                ),
+                warning(
+                    "b",
+                    CompatWarningKind::Merged {
+                        field: "titleaddon".into(),
+                        into: "title".into()
+                    }
+                ),
+                warning("b", CompatWarningKind::Dropped("booksubtitle".into())),
+                warning("b", CompatWarningKind::Dropped("eventdate".into())),
+            ]
+        );
+        assert_eq!(warnings[3].to_string(), "b: field booksubtitle was dropped");
+
+        assert!(!bibtex.contains("@xdata"));
+        assert!(bibtex.contains("@mastersthesis{a,\n"));
+        assert!(bibtex.contains(
+            "publisher = {P},\nschool = {MIT},\ntitle = {A},\nyear = {2020},\n}"
+        ));
+        assert!(bibtex.contains("@book{b,\ntitle = {B. Addon},\n}"));
+        assert!(bibtex
+            .contains("@article{c,\njournal = {J},\nmonth = {03},\nyear = {2021},\n}"));
+    }
+}
@@ -0,0 +1,398 @@
+//! Importing entries from CSL-JSON.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::json::Json;
+use crate::keygen::{sanitize, suffixed_key};
+use crate::types::Type;
+use crate::{
+    Bibliography, Chunk, Date, DateValue, Datetime, Entry, EntryType, KeyPattern,
+    PermissiveType, Person, Spanned,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The entry types for CSL item types. Other types become `@misc` entries.
+const TYPES: [(&str, EntryType); 25] = [
+    ("article", EntryType::Article),
+    ("article-journal", EntryType::Article),
+    ("article-magazine", EntryType::Article),
+    ("article-newspaper", EntryType::Article),
+    ("bill", EntryType::Legislation),
+    ("book", EntryType::Book),
+    ("chapter", EntryType::InCollection),
+    ("dataset", EntryType::Dataset),
+    ("entry", EntryType::InReference),
+    ("entry-dictionary", EntryType::InReference),
+    ("entry-encyclopedia", EntryType::InReference),
+    ("graphic", EntryType::Image),
+    ("legal_case", EntryType::Jurisdiction),
+    ("legislation", EntryType::Legislation),
+    ("manuscript", EntryType::Unpublished),
+    ("motion_picture", EntryType::Movie),
+    ("paper-conference", EntryType::InProceedings),
+    ("patent", EntryType::Patent),
+    ("post", EntryType::Online),
+    ("post-weblog", EntryType::Online),
+    ("report", EntryType::Report),
+    ("review", EntryType::Review),
+    ("software", EntryType::Software),
+    ("thesis", EntryType::Thesis),
+    ("webpage", EntryType::Online),
+];
+
+/// The fields for CSL text and number variables. Variables mapping to a
+/// field that is already set are skipped.
+const VARIABLES: [(&str, &str); 24] = [
+    ("title", "title"),
+    ("title-short", "shorttitle"),
+    ("container-title-short", "shortjournal"),
+    ("collection-title", "series"),
+    ("volume", "volume"),
+    ("number-of-volumes", "volumes"),
+    ("issue", "number"),
+    ("number", "number"),
+    ("collection-number", "number"),
+    ("edition", "edition"),
+    ("page", "pages"),
+    ("number-of-pages", "pagetotal"),
+    ("publisher-place", "location"),
+    ("event-title", "eventtitle"),
+    ("event", "eventtitle"),
+    ("event-place", "venue"),
+    ("genre", "type"),
+    ("version", "version"),
+    ("DOI", "doi"),
+    ("URL", "url"),
+    ("ISBN", "isbn"),
+    ("ISSN", "issn"),
+    ("abstract", "abstract"),
+    ("note", "note"),
+];
+
+/// The fields for CSL name variables.
+const NAME_VARIABLES: [(&str, &str); 4] = [
+    ("author", "author"),
+    ("editor", "editor"),
+    ("translator", "translator"),
+    ("container-author", "bookauthor"),
+];
+
+/// The fields for CSL date variables.
+const DATE_VARIABLES: [(&str, &str); 4] = [
+    ("issued", "date"),
+    ("accessed", "urldate"),
+    ("original-date", "origdate"),
+    ("event-date", "eventdate"),
+];
+
+/// Errors that can occur when importing CSL-JSON.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CslError {
+    /// The input is not valid JSON or nested too deeply. Contains the byte
+    /// offset of the error.
+    Syntax(usize),
+    /// An item is not a JSON object. Contains the index of the item.
+    NotAnItem(usize),
+}
+
+impl Display for CslError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Syntax(offset) => write!(f, "invalid JSON at byte {}", offset),
+            Self::NotAnItem(index) => write!(f, "item {} is not an object", index),
+        }
+    }
+}
+
+impl std::error::Error for CslError {}
+
+impl Bibliography {
+    /// Construct a bibliography from CSL-JSON, as exported by Zotero or
+    /// returned by DOI content negotiation.
+    ///
+    /// The input is an array of CSL items or a single item. Item types are
+    /// mapped to the closest entry types and CSL variables to the
+    /// corresponding fields, e.g. `container-title` to `journaltitle` for
+    /// articles and to `booktitle` otherwise, `issued` to `date`, and `page`
+    /// to `pages`. Variables without a biblatex counterpart are dropped.
+    ///
+    /// The cite keys are generated from the `pattern` with alphabetic
+    /// suffixes for collisions, as in [`Bibliography::generate_keys`]. Items
+    /// for which the pattern produces an empty key are keyed by their CSL
+    /// `id`.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, KeyPattern};
+    /// let src = r#"[{
+    ///     "id": "http://zotero.org/items/X4C2",
+    ///     "type": "article-journal",
+    ///     "title": "On Computable Numbers",
+    ///     "container-title": "Proceedings of the London Mathematical Society",
+    ///     "author": [{"family": "Turing", "given": "Alan"}],
+    ///     "issued": {"date-parts": [[1936, 11, 12]]}
+    /// }]"#;
+    ///
+    /// let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+    /// let bibliography = Bibliography::from_csl_json(src, &pattern).unwrap();
+    /// let entry = bibliography.get("turing1936").unwrap();
+    /// assert_eq!(
+    ///     entry.to_biblatex_string(),
+    ///     "@article{turing1936,\n\
+    ///      author = {Turing, Alan},\n\
+    ///      date = {1936-11-12},\n\
+    ///      journaltitle = {Proceedings of the London Mathematical Society},\n\
+    ///      title = {On Computable Numbers},\n\
+    ///      }",
+    /// );
+    /// ```
+    pub fn from_csl_json(src: &str, pattern: &KeyPattern) -> Result<Self, CslError> {
+        let items = match Json::parse(src).map_err(CslError::Syntax)? {
+            Json::Array(items) => items,
+            item => vec![item],
+        };
+
+        let mut bibliography = Self::new();
+        for (i, item) in items.iter().enumerate() {
+            if !matches!(item, Json::Object(_)) {
+                return Err(CslError::NotAnItem(i));
+            }
+
+            let id = item.get("id").and_then(Json::to_text).map(|id| sanitize(&id));
+            let id = id.filter(|id| !id.is_empty()).unwrap_or_else(|| "item".into());
+            let key = if bibliography.is_taken(&id) {
+                suffixed_key(&id, |key| bibliography.is_taken(key))
+            } else {
+                id
+            };
+            bibliography.insert(csl_entry(key, item));
+        }
+
+        bibliography.generate_keys(pattern, |_| true);
+        Ok(bibliography)
+    }
+}
+
+/// Construct an entry from a CSL item.
+fn csl_entry(key: String, item: &Json) -> Entry {
+    let csl_type = item.get("type").and_then(Json::to_text).unwrap_or_default();
+    let entry_type = TYPES
+        .iter()
+        .find(|(name, _)| *name == csl_type)
+        .map_or(EntryType::Misc, |(_, entry_type)| entry_type.clone());
+
+    let mut entry = Entry::new(key, entry_type);
+
+    if let Some(title) = item.get("container-title").and_then(Json::to_text) {
+        let field = match entry.entry_type {
+            EntryType::Article => "journaltitle",
+            _ => "booktitle",
+        };
+        set_text(&mut entry, field, title);
+    }
+
+    if let Some(publisher) = item.get("publisher").and_then(Json::to_text) {
+        let field = match entry.entry_type {
+            EntryType::Thesis | EntryType::Report => "institution",
+            _ => "publisher",
+        };
+        set_text(&mut entry, field, publisher);
+    }
+
+    for (variable, field) in VARIABLES {
+        if let Some(text) = item.get(variable).and_then(Json::to_text) {
+            if entry.get(field).is_none() {
+                set_text(&mut entry, field, text);
+            }
+        }
+    }
+
+    for (variable, field) in NAME_VARIABLES {
+        if let Some(Json::Array(names)) = item.get(variable) {
+            let names: Vec<_> = names.iter().filter_map(csl_name).collect();
+            if !names.is_empty() {
+                entry.set(field, names.to_chunks());
+            }
+        }
+    }
+
+    for (variable, field) in DATE_VARIABLES {
+        if let Some(date) = item.get(variable).and_then(csl_date) {
+            entry.set(field, date.to_chunks());
+        }
+    }
+
+    entry
+}
+
+/// Set a field to the given text.
+fn set_text(entry: &mut Entry, field: &str, text: String) {
+    entry.set(field, vec![Spanned::detached(Chunk::Normal(text))]);
+}
+
+/// Convert a CSL name into the chunks of a single name. Literal names are
+/// protected from being split into name parts.
+fn csl_name(name: &Json) -> Option<Vec<Spanned<Chunk>>> {
+    if let Some(literal) = name.get("literal").and_then(Json::to_text) {
+        return Some(vec![Spanned::detached(Chunk::Verbatim(literal))]);
+    }
+
+    let part = |part: &str| name.get(part).and_then(Json::to_text).unwrap_or_default();
+    let prefix = [part("dropping-particle"), part("non-dropping-particle")]
+        .into_iter()
+        .filter(|particle| !particle.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let person = Person {
+        name: part("family"),
+        given_name: part("given"),
+        prefix,
+        suffix: part("suffix"),
+    };
+
+    if person.name.is_empty() {
+        return None;
+    }
+    Some(vec![person].to_chunks())
+}
+
+/// Convert a CSL date into a date, falling back to its textual form.
+fn csl_date(date: &Json) -> Option<PermissiveType<Date>> {
+    let datetime = |parts: &Json| {
+        let Json::Array(parts) = parts else { return None };
+        let mut parts =
+            parts.iter().map(|part| part.to_text()?.trim().parse::<i32>().ok());
+        let year = parts.next()??;
+        let mut component = |max: i32| match parts.next() {
+            Some(Some(n)) if (1..=max).contains(&n) => Some(Some(n as u8 - 1)),
+            Some(_) => None,
+            None => Some(None),
+        };
+        let month = component(12)?;
+        let day = component(31)?;
+        Some(Datetime { year, month, day: month.and(day), time: None })
+    };
+
+    let value = match date.get("date-parts") {
+        Some(Json::Array(parts)) => match parts.as_slice() {
+            [at] => datetime(at).map(DateValue::At),
+            [start, end] => datetime(start)
+                .zip(datetime(end))
+                .map(|(start, end)| DateValue::Between(start, end)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let approximate = match date.get("circa") {
+        Some(Json::Bool(circa)) => *circa,
+        Some(Json::Number(circa)) => *circa != 0.0,
+        Some(Json::String(circa)) => !circa.is_empty(),
+        _ => false,
+    };
+
+    match value {
+        Some(value) => {
+            Some(PermissiveType::Typed(Date { value, uncertain: false, approximate }))
+        }
+        None => {
+            let text = date
+                .get("raw")
+                .or_else(|| date.get("literal"))
+                .and_then(Json::to_text)?;
+            Some(PermissiveType::Chunks(vec![Spanned::detached(Chunk::Normal(text))]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunksExt;
+
+    #[test]
+    fn test_from_csl_json() {
+        let src = r#"[
+            {
+                "id": "a",
+                "type": "chapter",
+                "title": "Notes on 100% Coverage",
+                "container-title": "Testing",
+                "author": [
+                    {"family": "Berg", "given": "Jan", "non-dropping-particle": "van der"},
+                    {"literal": "Barnes and Noble"}
+                ],
+                "editor": [{"family": "Roe", "given": "Richard", "suffix": "Jr."}],
+                "issued": {"date-parts": [["2020", 2], [2021]], "circa": true},
+                "accessed": {"raw": "last spring"},
+                "page": "10-20",
+                "issue": 3,
+                "number": "ignored",
+                "DOI": "10.1000/a_b",
+                "publisher": "Springer",
+                "keyword": "dropped"
+            },
+            {"id": "b", "type": "thesis", "publisher": "MIT",
+             "author": [{"family": "Berg", "given": "Jan"}], "issued": {"date-parts": [[2020, 13]]}},
+            {"id": "http://example.org/ü", "type": "unknown-type"},
+            {"type": "book"},
+            {"id": "item", "type": "book"}
+        ]"#;
+
+        let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+        let bibliography = Bibliography::from_csl_json(src, &pattern).unwrap();
+        assert_eq!(
+            bibliography.keys().collect::<Vec<_>>(),
+            ["berg2020", "berg", "http://example.org/u", "item", "itema"]
+        );
+
+        let entry = bibliography.get("berg2020").unwrap();
+        assert_eq!(entry.entry_type, EntryType::InCollection);
+        assert_eq!(
+            entry.to_biblatex_string(),
+            "@incollection{berg2020,\n\
+             author = {van der Berg, Jan and {Barnes and Noble}},\n\
+             booktitle = {Testing},\n\
+             date = {2020-02/2021\\~},\n\
+             doi = {10.1000/a_b},\n\
+             editor = {Roe, Jr., Richard},\n\
+             number = {3},\n\
+             pages = {10-20},\n\
+             publisher = {Springer},\n\
+             title = {Notes on 100\\% Coverage},\n\
+             urldate = {last spring},\n\
+             }"
+        );
+        assert_eq!(entry.author().unwrap()[1].name, "Barnes and Noble");
+
+        let entry = bibliography.get("berg").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Thesis);
+        assert_eq!(entry.get("institution").unwrap().format_verbatim(), "MIT");
+        assert_eq!(entry.get("date"), None);
+        assert_eq!(
+            bibliography.get("http://example.org/u").unwrap().entry_type,
+            EntryType::Misc
+        );
+
+        assert_eq!(
+            Bibliography::from_csl_json(r#"{"id": "x", "title": "T"}"#, &pattern)
+                .unwrap()
+                .get("x")
+                .unwrap()
+                .title()
+                .unwrap()
+                .format_verbatim(),
+            "T"
+        );
+        assert_eq!(
+            Bibliography::from_csl_json("[{}, 1]", &pattern),
+            Err(CslError::NotAnItem(1))
+        );
+        assert_eq!(
+            Bibliography::from_csl_json("[{]", &pattern),
+            Err(CslError::Syntax(2))
+        );
+    }
+}
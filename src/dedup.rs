@@ -0,0 +1,244 @@
+//! Detection of entries that probably describe the same work.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::merge::union_fields;
+use crate::{Bibliography, ChunksExt, Entry};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Computes a normalized value that is equal for duplicate entries.
+type Fingerprint = fn(&Entry) -> Option<String>;
+
+/// Why entries were grouped into a [`DuplicateCluster`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DuplicateReason {
+    /// The entries have the same DOI.
+    Doi,
+    /// The entries have the same ISBN, after conversion to ISBN-13.
+    Isbn,
+    /// The entries have the same normalized title, year, and first author
+    /// (or editor) last name.
+    TitleYearAuthor,
+}
+
+/// A group of entries that probably describe the same work. Can be obtained
+/// through [`Bibliography::find_duplicates`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DuplicateCluster {
+    /// The cite keys of the entries in bibliography order.
+    pub keys: Vec<String>,
+    /// The criteria that matched for at least one pair of entries.
+    pub reasons: Vec<DuplicateReason>,
+}
+
+impl Bibliography {
+    /// Group the entries that probably describe the same work.
+    ///
+    /// Entries are considered duplicates if they share a DOI or an ISBN, or if
+    /// their normalized title, year, and first author match. The matches are
+    /// transitive. Only clusters with at least two entries are returned.
+    pub fn find_duplicates(&self) -> Vec<DuplicateCluster> {
+        let mut parents: Vec<usize> = (0..self.entries.len()).collect();
+        let mut reasons: Vec<Vec<DuplicateReason>> = vec![vec![]; self.entries.len()];
+
+        let fingerprints: [(DuplicateReason, Fingerprint); 3] = [
+            (DuplicateReason::Doi, doi_fingerprint),
+            (DuplicateReason::Isbn, isbn_fingerprint),
+            (DuplicateReason::TitleYearAuthor, title_fingerprint),
+        ];
+
+        for (reason, fingerprint) in fingerprints {
+            // The first entry with each fingerprint.
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for (i, entry) in self.entries.iter().enumerate() {
+                let Some(print) = fingerprint(entry) else { continue };
+                match seen.get(&print) {
+                    Some(&j) => {
+                        let root = union(&mut parents, i, j);
+                        reasons[root].push(reason);
+                    }
+                    None => {
+                        seen.insert(print, i);
+                    }
+                }
+            }
+        }
+
+        // Maps from the roots to the indices of their clusters.
+        let mut roots: HashMap<usize, usize> = HashMap::new();
+        let mut clusters: Vec<DuplicateCluster> = vec![];
+        for (i, entry) in self.entries.iter().enumerate() {
+            let root = find(&mut parents, i);
+            let index = *roots.entry(root).or_insert_with(|| {
+                clusters.push(DuplicateCluster { keys: vec![], reasons: vec![] });
+                clusters.len() - 1
+            });
+            clusters[index].keys.push(entry.key.clone());
+        }
+
+        for (i, reasons) in reasons.into_iter().enumerate() {
+            let cluster = &mut clusters[roots[&find(&mut parents, i)]];
+            for reason in reasons {
+                if !cluster.reasons.contains(&reason) {
+                    cluster.reasons.push(reason);
+                }
+            }
+        }
+
+        clusters
+            .into_iter()
+            .filter(|cluster| cluster.keys.len() > 1)
+            .collect()
+    }
+
+    /// Merge probable duplicates as found by
+    /// [`Bibliography::find_duplicates`].
+    ///
+    /// The first entry of each cluster is kept and receives the fields it
+    /// lacks from the other entries. The cite keys of the other entries are
+    /// added to its `ids` field, so they remain valid aliases. Returns the
+    /// merged clusters.
+    pub fn merge_duplicates(&mut self) -> Vec<DuplicateCluster> {
+        let clusters = self.find_duplicates();
+
+        for cluster in &clusters {
+            let (first, rest) = cluster.keys.split_first().unwrap();
+            let mut kept = self.get(first).unwrap().clone();
+            let mut ids = kept.ids().unwrap_or_default();
+
+            for key in rest {
+                let duplicate = self.remove(key).unwrap();
+                ids.push(duplicate.key.clone());
+                ids.extend(duplicate.ids().unwrap_or_default());
+                union_fields(&mut kept, duplicate);
+            }
+
+            let mut unique = HashSet::new();
+            ids.retain(|id| unique.insert(id.clone()));
+            kept.set_ids(ids);
+            self.insert(kept);
+        }
+
+        clusters
+    }
+}
+
+/// Find the representative of an element in the union-find forest.
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Join the sets of two elements and return the new representative, which
+/// is the smaller root so that clusters keep bibliography order.
+fn union(parents: &mut [usize], a: usize, b: usize) -> usize {
+    let (a, b) = (find(parents, a), find(parents, b));
+    let (root, child) = if a < b { (a, b) } else { (b, a) };
+    parents[child] = root;
+    root
+}
+
+/// The DOI in lowercase, without resolver prefixes.
+fn doi_fingerprint(entry: &Entry) -> Option<String> {
+    let doi = entry.doi().ok()?.trim().to_lowercase();
+    let doi = ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "doi:"]
+        .iter()
+        .find_map(|prefix| doi.strip_prefix(prefix))
+        .unwrap_or(&doi);
+    (!doi.is_empty()).then(|| doi.to_string())
+}
+
+/// The ISBN as ISBN-13 digits.
+fn isbn_fingerprint(entry: &Entry) -> Option<String> {
+    let isbn: String = entry
+        .isbn()
+        .ok()?
+        .format_verbatim()
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == 'X' || *c == 'x')
+        .collect();
+
+    match isbn.len() {
+        10 => {
+            let digits = format!("978{}", &isbn[..9]);
+            let sum: u32 = digits
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    c.to_digit(10).unwrap_or(0) * if i % 2 == 0 { 1 } else { 3 }
+                })
+                .sum();
+            Some(format!("{digits}{}", (10 - sum % 10) % 10))
+        }
+        13 => Some(isbn),
+        _ => None,
+    }
+}
+
+/// The lowercase alphanumeric title words, the year, and the last name of the
+/// first author or editor.
+fn title_fingerprint(entry: &Entry) -> Option<String> {
+    let title: String = entry
+        .title()
+        .ok()?
+        .format_verbatim()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let year = entry.sorting_year().ok()?;
+    let person = entry.label_name().ok()?.into_iter().next()?;
+    Some(format!("{title}|{year}|{}", person.name.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_duplicates() {
+        let contents = r#"@article{a, title = {The Art of {Computer} Programming},
+              author = {Knuth, Donald}, date = {1968}, doi = {10.1000/XYZ}}
+            @article{b, title = {The art of computer programming.},
+              author = {D. Knuth}, year = {1968}}
+            @book{c, title = {Other}, doi = {https://doi.org/10.1000/xyz}}
+            @book{d, title = {Something}, isbn = {0-306-40615-2}}
+            @book{e, title = {Something Else}, isbn = {978-0-306-40615-7}}
+            @book{f, title = {Unrelated}, author = {Knuth, Donald}, year = {1968}}"#;
+        let mut bibliography = Bibliography::parse(contents).unwrap();
+
+        let clusters = bibliography.find_duplicates();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].keys, ["a", "b", "c"]);
+        assert_eq!(
+            clusters[0].reasons,
+            [DuplicateReason::Doi, DuplicateReason::TitleYearAuthor]
+        );
+        assert_eq!(clusters[1].keys, ["d", "e"]);
+        assert_eq!(clusters[1].reasons, [DuplicateReason::Isbn]);
+
+        assert_eq!(bibliography.merge_duplicates(), clusters);
+        assert_eq!(bibliography.len(), 3);
+        let a = bibliography.get("b").unwrap();
+        assert_eq!(a.key, "a");
+        assert_eq!(a.ids().unwrap(), ["b", "c"]);
+        assert_eq!(bibliography.get("e").unwrap().key, "d");
+        assert!(bibliography.find_duplicates().is_empty());
+
+        // Aliases shared by several duplicates are only kept once.
+        let contents = r#"@book{a, doi = {10.1/x}, ids = {x}}
+            @book{b, doi = {10.1/x}, ids = {y}}
+            @book{c, doi = {10.1/x}, ids = {x}}"#;
+        let mut bibliography = Bibliography::parse(contents).unwrap();
+        bibliography.merge_duplicates();
+        assert_eq!(bibliography.get("a").unwrap().ids().unwrap(), ["x", "b", "y", "c"]);
+    }
+}
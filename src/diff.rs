@@ -0,0 +1,207 @@
+//! Semantic comparison of entries and bibliographies.
+
+use std::collections::BTreeMap;
+
+use crate::mechanics::canonical_field_name;
+use crate::{
+    Bibliography, ChunksExt, ChunksRef, Entry, EntryType, PermissiveType, TypedField,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A field that differs between two versions of an entry.
+///
+/// The values are decoded as [`TypedField`]s. Values that cannot be decoded
+/// are kept as [`TypedField::Unknown`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldDiff {
+    /// The BibLaTeX name of the field.
+    pub field: String,
+    /// The old value, or `None` if the field was added.
+    pub old: Option<TypedField>,
+    /// The new value, or `None` if the field was removed.
+    pub new: Option<TypedField>,
+}
+
+/// The differences between two versions of an entry. Can be obtained through
+/// [`Entry::diff`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EntryDiff {
+    /// The cite key of the old entry.
+    pub key: String,
+    /// The old and new entry type if it changed.
+    pub entry_type: Option<(EntryType, EntryType)>,
+    /// The added, removed, and changed fields, ordered by name.
+    pub fields: Vec<FieldDiff>,
+}
+
+impl EntryDiff {
+    /// Whether the entries are semantically equal.
+    pub fn is_empty(&self) -> bool {
+        self.entry_type.is_none() && self.fields.is_empty()
+    }
+}
+
+/// The differences between two versions of a bibliography. Can be obtained
+/// through [`Bibliography::diff`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BibliographyDiff {
+    /// The cite keys of entries only present in the new bibliography.
+    pub added: Vec<String>,
+    /// The cite keys of entries only present in the old bibliography.
+    pub removed: Vec<String>,
+    /// The entries present in both bibliographies that differ.
+    pub changed: Vec<EntryDiff>,
+}
+
+impl BibliographyDiff {
+    /// Whether the bibliographies are semantically equal.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Entry {
+    /// Compare this entry with a newer version of it.
+    ///
+    /// Differences that do not change the meaning are ignored: legacy field
+    /// names like `journal` are compared with their BibLaTeX counterparts,
+    /// whitespace is normalized, and typed values like names and dates are
+    /// compared after decoding, e.g., `Jane Doe` equals `Doe, Jane` and a
+    /// `date` of `2001-02` equals a `year` of `2001` with a `month` of `2`.
+    pub fn diff(&self, other: &Entry) -> EntryDiff {
+        let mut old = canonical_fields(self);
+        let mut new = canonical_fields(other);
+
+        // The date may be split into `year`, `month`, and `day` on one side.
+        if let (Ok(PermissiveType::Typed(a)), Ok(PermissiveType::Typed(b))) =
+            (self.date(), other.date())
+        {
+            if a == b {
+                for field in ["date", "year", "month", "day"] {
+                    old.remove(field);
+                    new.remove(field);
+                }
+            }
+        }
+
+        let mut names: Vec<&str> = old.keys().chain(new.keys()).copied().collect();
+        names.sort_unstable();
+        names.dedup();
+
+        let mut fields = vec![];
+        for name in names {
+            let old = old.get(name).map(|chunks| decode(name, chunks));
+            let new = new.get(name).map(|chunks| decode(name, chunks));
+            let equal = match (&old, &new) {
+                (Some(a), Some(b)) => {
+                    a.to_chunks().format_normalized() == b.to_chunks().format_normalized()
+                }
+                _ => false,
+            };
+
+            if !equal {
+                fields.push(FieldDiff { field: name.to_string(), old, new });
+            }
+        }
+
+        EntryDiff {
+            key: self.key.clone(),
+            entry_type: (self.entry_type != other.entry_type)
+                .then(|| (self.entry_type.clone(), other.entry_type.clone())),
+            fields,
+        }
+    }
+}
+
+impl Bibliography {
+    /// Compare this bibliography with a newer version of it. Entries are
+    /// matched by their cite key and compared with [`Entry::diff`].
+    pub fn diff(&self, other: &Bibliography) -> BibliographyDiff {
+        let mut diff = BibliographyDiff::default();
+
+        for entry in self.iter() {
+            match other.get(&entry.key).filter(|e| e.key == entry.key) {
+                Some(new) => {
+                    let entry_diff = entry.diff(new);
+                    if !entry_diff.is_empty() {
+                        diff.changed.push(entry_diff);
+                    }
+                }
+                None => diff.removed.push(entry.key.clone()),
+            }
+        }
+
+        for entry in other.iter() {
+            let present = self.get(&entry.key).is_some_and(|e| e.key == entry.key);
+            if !present {
+                diff.added.push(entry.key.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+/// The fields of an entry by their BibLaTeX name. A field under its BibLaTeX
+/// name takes precedence over one under a legacy name.
+fn canonical_fields(entry: &Entry) -> BTreeMap<&str, ChunksRef<'_>> {
+    let mut fields = BTreeMap::new();
+    for (key, chunks) in &entry.fields {
+        let name = canonical_field_name(key);
+        if name == key || !fields.contains_key(name) {
+            fields.insert(name, chunks.as_slice());
+        }
+    }
+    fields
+}
+
+/// Decode a field, falling back to its chunks.
+fn decode(name: &str, chunks: ChunksRef) -> TypedField {
+    TypedField::try_from((name, chunks))
+        .unwrap_or_else(|_| TypedField::Unknown(name.to_string(), chunks.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = r#"@article{a, author = {Doe, Jane}, journal = {Journal},
+              title = {A   Title}, date = {2001-02}, pages = {1--5}}
+            @book{b, title = {B}}
+            @book{c, title = {C}}"#;
+        let new = r#"@article{a, author = {Jane Doe}, journaltitle = {Journal},
+              title = {A Title}, year = {2001}, month = {2}, pages = {1-6},
+              note = {New}}
+            @misc{b, title = {B}}
+            @book{d, title = {D}}"#;
+        let old = Bibliography::parse(old).unwrap();
+        let new = Bibliography::parse(new).unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["d"]);
+        assert_eq!(diff.removed, ["c"]);
+        assert_eq!(diff.changed.len(), 2);
+
+        let a = &diff.changed[0];
+        assert_eq!(a.entry_type, None);
+        let fields: Vec<_> = a.fields.iter().map(|f| f.field.as_str()).collect();
+        assert_eq!(fields, ["note", "pages"]);
+        assert_eq!(a.fields[0].old, None);
+        let pages = |range| Some(TypedField::Pages(PermissiveType::Typed(vec![range])));
+        assert_eq!(a.fields[1].old, pages(1..5));
+        assert_eq!(a.fields[1].new, pages(1..6));
+
+        let b = &diff.changed[1];
+        assert_eq!(b.entry_type, Some((EntryType::Book, EntryType::Misc)));
+        assert!(b.fields.is_empty());
+
+        assert!(old.diff(&old).is_empty());
+    }
+}
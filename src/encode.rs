@@ -0,0 +1,154 @@
+//! Encoding of Unicode characters as LaTeX commands.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters with a dedicated command or ligature. Commands are terminated
+/// with an empty group so that they are not merged with subsequent letters.
+const SYMBOLS: &[(char, &str)] = &[
+    ('–', "--"),
+    ('—', "---"),
+    ('“', "\\textquotedblleft{}"),
+    ('”', "\\textquotedblright{}"),
+    ('‘', "\\textquoteleft{}"),
+    ('’', "\\textquoteright{}"),
+    ('„', "\\quotedblbase{}"),
+    ('‚', "\\quotesinglbase{}"),
+    ('«', "\\guillemotleft{}"),
+    ('»', "\\guillemotright{}"),
+    ('‹', "\\guilsinglleft{}"),
+    ('›', "\\guilsinglright{}"),
+    ('⁎', "\\textasteriskcentered{}"),
+    ('•', "\\textbullet{}"),
+    ('†', "\\textdagger{}"),
+    ('‡', "\\textdaggerdbl{}"),
+    ('¡', "\\textexclamdown{}"),
+    ('¿', "\\textquestiondown{}"),
+    ('ª', "\\textordfeminine{}"),
+    ('º', "\\textordmasculine{}"),
+    ('·', "\\textperiodcentered{}"),
+    ('®', "\\textregistered{}"),
+    ('™', "\\texttrademark{}"),
+    ('␣', "\\textvisiblespace{}"),
+    ('©', "\\copyright{}"),
+    ('…', "\\dots{}"),
+    ('£', "\\pounds{}"),
+    ('¶', "\\P{}"),
+    ('§', "\\S{}"),
+    ('å', "\\aa{}"),
+    ('Å', "\\AA{}"),
+    ('æ', "\\ae{}"),
+    ('Æ', "\\AE{}"),
+    ('ð', "\\dh{}"),
+    ('Ð', "\\DH{}"),
+    ('đ', "\\dj{}"),
+    ('Đ', "\\DJ{}"),
+    ('ŋ', "\\ng{}"),
+    ('Ŋ', "\\NG{}"),
+    ('ł', "\\l{}"),
+    ('Ł', "\\L{}"),
+    ('ı', "\\i{}"),
+    ('œ', "\\oe{}"),
+    ('Œ', "\\OE{}"),
+    ('ø', "\\o{}"),
+    ('Ø', "\\O{}"),
+    ('ß', "\\ss{}"),
+    ('ẞ', "\\SS{}"),
+    ('þ', "\\th{}"),
+    ('Þ', "\\TH{}"),
+];
+
+/// The accent commands for combining marks.
+const ACCENTS: &[(char, &str)] = &[
+    ('\u{300}', "`"),
+    ('\u{301}', "'"),
+    ('\u{302}', "^"),
+    ('\u{303}', "~"),
+    ('\u{304}', "="),
+    ('\u{306}', "u"),
+    ('\u{307}', "."),
+    ('\u{308}', "\""),
+    ('\u{30A}', "r"),
+    ('\u{30B}', "H"),
+    ('\u{30C}', "v"),
+    ('\u{323}', "d"),
+    ('\u{327}', "c"),
+    ('\u{328}', "k"),
+];
+
+/// Replace the non-ASCII characters of Bib(La)TeX source text with LaTeX
+/// commands, e.g. `ä` with `\"{a}`, so that it can be processed by
+/// toolchains without Unicode support. Math and characters without an
+/// encoding are kept.
+pub(crate) fn encode_latex(text: &str) -> String {
+    let mut res = String::new();
+    let mut math = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                res.push(c);
+                res.extend(chars.next());
+            }
+            '$' => {
+                math = !math;
+                res.push(c);
+            }
+            c if c.is_ascii() || math => res.push(c),
+            c => match encode_char(c) {
+                Some(encoded) => res.push_str(&encoded),
+                None => res.push(c),
+            },
+        }
+    }
+    res
+}
+
+/// Encode a single non-ASCII character.
+fn encode_char(c: char) -> Option<String> {
+    if let Some((_, command)) = SYMBOLS.iter().find(|(s, _)| *s == c) {
+        return Some(command.to_string());
+    }
+
+    let mut decomposed = std::iter::once(c).nfd();
+    let base = decomposed.next()?;
+    let marks: Vec<char> = decomposed.collect();
+    if marks.is_empty() {
+        return None;
+    }
+
+    let mut res = match base {
+        base if base.is_ascii_alphabetic() => base.to_string(),
+        base => SYMBOLS.iter().find(|(s, _)| *s == base)?.1.to_string(),
+    };
+
+    for mark in marks {
+        let (_, accent) = ACCENTS.iter().find(|(m, _)| *m == mark)?;
+        res = format!("\\{}{{{}}}", accent, res);
+    }
+
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bibliography, ChunksExt};
+
+    #[test]
+    fn test_encode_latex() {
+        let text = "Ærøskøbing – Gödel’s Straße, Dvořák, ǘ, $α ≤ ä$, 東京, \\\"a";
+        let encoded = encode_latex(text);
+        assert_eq!(
+            encoded,
+            "\\AE{}r\\o{}sk\\o{}bing -- G\\\"{o}del\\textquoteright{}s Stra\\ss{}e, \
+             Dvo\\v{r}\\'{a}k, \\'{\\\"{u}}, $α ≤ ä$, 東京, \\\"a"
+        );
+        assert!(encode_latex("Ǿ").is_ascii());
+
+        let src =
+            format!("@misc{{a, title = {{{}}}}}", encoded.replace("$α ≤ ä$, 東京, ", ""));
+        let bibliography = Bibliography::parse(&src).unwrap();
+        let title = bibliography.get("a").unwrap().title().unwrap().format_verbatim();
+        assert_eq!(title, "Ærøskøbing – Gödel’s Straße, Dvořák, ǘ, ä");
+    }
+}
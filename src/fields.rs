@@ -0,0 +1,218 @@
+//! Typed representations of whole fields.
+
+use crate::chunk::*;
+use crate::macros::typed_fields;
+use crate::types::*;
+use crate::{Spanned, TypeError};
+
+use paste::paste;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+typed_fields! {
+    Abstract: "abstract",
+    Addendum: "addendum",
+    Address: "address" => Vec<Chunks>,
+    Afterword: "afterword" => Vec<Person>,
+    Amendment: "amendment",
+    Annotation: "annotation",
+    Annotator: "annotator" => Vec<Person>,
+    ArticleNo: "articleno",
+    Author: "author" => Vec<Person>,
+    AuthorType: "authortype" => String,
+    BookAuthor: "bookauthor" => Vec<Person>,
+    BookPagination: "bookpagination" => Pagination,
+    BookSubtitle: "booksubtitle",
+    BookTitle: "booktitle",
+    BookTitleAddon: "booktitleaddon",
+    Chapter: "chapter" => PermissiveType<Vec<std::ops::Range<u32>>>,
+    Citation: "citation",
+    Commentator: "commentator" => Vec<Person>,
+    Crossref: "crossref" => String,
+    Date: "date" => PermissiveType<Date>,
+    Day: "day",
+    Doi: "doi" => String,
+    Edition: "edition" => PermissiveType<i64>,
+    Editor: "editor" => Vec<Person>,
+    EditorA: "editora" => Vec<Person>,
+    EditorAType: "editoratype" => EditorType,
+    EditorB: "editorb" => Vec<Person>,
+    EditorBType: "editorbtype" => EditorType,
+    EditorC: "editorc" => Vec<Person>,
+    EditorCType: "editorctype" => EditorType,
+    EditorType: "editortype" => EditorType,
+    Eid: "eid",
+    EntrySet: "entryset" => Vec<String>,
+    EntrySubtype: "entrysubtype" => EntrySubtype,
+    Eprint: "eprint" => String,
+    EprintClass: "eprintclass",
+    EprintType: "eprinttype",
+    EventDate: "eventdate" => PermissiveType<Date>,
+    EventTitle: "eventtitle",
+    EventTitleAddon: "eventtitleaddon",
+    File: "file" => String,
+    Foreword: "foreword" => Vec<Person>,
+    Gender: "gender" => Gender,
+    Holder: "holder" => Vec<Person>,
+    HowPublished: "howpublished",
+    Ids: "ids" => Vec<String>,
+    IndexTitle: "indextitle",
+    Institution: "institution" => Vec<Chunks>,
+    Introduction: "introduction" => Vec<Person>,
+    Isan: "isan",
+    Isbn: "isbn",
+    Ismn: "ismn",
+    Isrn: "isrn",
+    Issn: "issn",
+    Issue: "issue",
+    IssueSubtitle: "issuesubtitle",
+    IssueTitle: "issuetitle",
+    IssueTitleAddon: "issuetitleaddon",
+    Iswc: "iswc",
+    Journal: "journal",
+    JournalSubtitle: "journalsubtitle",
+    JournalTitle: "journaltitle",
+    JournalTitleAddon: "journaltitleaddon",
+    Key: "key" => String,
+    Keywords: "keywords",
+    Label: "label",
+    LangId: "langid" => String,
+    LangIdOpts: "langidopts",
+    Language: "language" => String,
+    Library: "library",
+    Location: "location" => Vec<Chunks>,
+    MainSubtitle: "mainsubtitle",
+    MainTitle: "maintitle",
+    MainTitleAddon: "maintitleaddon",
+    Month: "month",
+    NameAddon: "nameaddon",
+    Note: "note",
+    Number: "number" => PermissiveType<i64>,
+    Options: "options",
+    Organization: "organization" => Vec<Chunks>,
+    OrigDate: "origdate" => PermissiveType<Date>,
+    OrigLanguage: "origlanguage" => String,
+    OrigLocation: "origlocation" => Vec<Chunks>,
+    OrigPublisher: "origpublisher" => Vec<Chunks>,
+    OrigTitle: "origtitle",
+    Pages: "pages" => PermissiveType<Vec<std::ops::Range<u32>>>,
+    PageTotal: "pagetotal",
+    Pagination: "pagination" => Pagination,
+    Part: "part" => PermissiveType<i64>,
+    Publisher: "publisher" => Vec<Chunks>,
+    Presort: "presort" => String,
+    Pubstate: "pubstate",
+    Related: "related" => Vec<String>,
+    RelatedOptions: "relatedoptions",
+    RelatedString: "relatedstring",
+    RelatedType: "relatedtype" => String,
+    ReprintTitle: "reprinttitle",
+    School: "school" => Vec<Chunks>,
+    Series: "series",
+    ShortAuthor: "shortauthor" => Vec<Person>,
+    ShortEditor: "shorteditor" => Vec<Person>,
+    Shorthand: "shorthand",
+    ShorthandIntro: "shorthandintro",
+    ShortJournal: "shortjournal",
+    ShortSeries: "shortseries",
+    ShortTitle: "shorttitle",
+    SortName: "sortname" => Vec<Person>,
+    SortShorthand: "sortshorthand",
+    SortTitle: "sorttitle",
+    SortYear: "sortyear" => i64,
+    SortKey: "sortkey" => String,
+    Source: "source",
+    Subtitle: "subtitle",
+    Title: "title",
+    TitleAddon: "titleaddon",
+    Translator: "translator" => Vec<Person>,
+    Type: "type" => String,
+    Url: "url" => String,
+    UrlDate: "urldate" => PermissiveType<Date>,
+    Venue: "venue",
+    Version: "version",
+    Volume: "volume" => PermissiveType<i64>,
+    Volumes: "volumes" => i64,
+    Xdata: "xdata" => Vec<String>,
+    Xref: "xref" => String,
+    Year: "year",
+}
+
+impl TryFrom<(&str, ChunksRef<'_>)> for TypedField {
+    type Error = TypeError;
+
+    /// Parse a field from its lowercase name and its chunks.
+    fn try_from((name, chunks): (&str, ChunksRef<'_>)) -> Result<Self, Self::Error> {
+        match name {
+            "edition" => Ok(Self::Edition(parse_edition(chunks))),
+            _ => Self::parse_known(name, chunks),
+        }
+    }
+}
+
+impl TryFrom<(&str, &str)> for TypedField {
+    type Error = TypeError;
+
+    /// Parse a field from its lowercase name and its value as plain text.
+    ///
+    /// The value is taken as a single normal chunk with a detached span, so
+    /// that name and literal lists are still split at `and`.
+    fn try_from((name, value): (&str, &str)) -> Result<Self, Self::Error> {
+        let chunks = [Spanned::detached(Chunk::Normal(value.to_string()))];
+        Self::try_from((name, chunks.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::tests::*;
+
+    #[test]
+    fn test_typed_field() {
+        let chunks = vec![s(N("Doe, Jane and Roe, Richard"), 1..27)];
+        let field = TypedField::try_from(("author", chunks.as_slice())).unwrap();
+        match &field {
+            TypedField::Author(persons) => assert_eq!(persons.len(), 2),
+            other => panic!("expected author, got {:?}", other),
+        }
+        assert_eq!(field.name(), "author");
+
+        let chunks = vec![d(N("3rd"))];
+        assert_eq!(
+            TypedField::try_from(("edition", chunks.as_slice())),
+            Ok(TypedField::Edition(PermissiveType::Typed(3)))
+        );
+
+        let chunks = vec![d(N("whatever"))];
+        let field = TypedField::try_from(("myfield", chunks.as_slice())).unwrap();
+        assert_eq!(field, TypedField::Unknown("myfield".into(), chunks.clone()));
+        assert_eq!(field.name(), "myfield");
+        assert_eq!(field.to_chunks(), chunks);
+
+        let chunks = vec![d(N("e0123"))];
+        let field = TypedField::try_from(("articleno", chunks.as_slice())).unwrap();
+        assert_eq!(field, TypedField::ArticleNo(chunks.clone()));
+        assert_eq!(field.name(), "articleno");
+
+        let chunks = vec![s(N("sx"), 8..10)];
+        assert!(TypedField::try_from(("gender", chunks.as_slice())).is_err());
+
+        let field =
+            TypedField::try_from(("author", "Doe, Jane and Roe, Richard")).unwrap();
+        assert_eq!(field.to_chunks().format_verbatim(), "Doe, Jane and Roe, Richard");
+        match field {
+            TypedField::Author(persons) => assert_eq!(persons[1].name, "Roe"),
+            other => panic!("expected author, got {:?}", other),
+        }
+        assert_eq!(
+            TypedField::try_from(("volume", "12")),
+            Ok(TypedField::Volume(PermissiveType::Typed(12)))
+        );
+        assert_eq!(
+            TypedField::try_from(("myfield", "x")),
+            Ok(TypedField::Unknown("myfield".into(), vec![d(N("x"))]))
+        );
+        assert!(TypedField::try_from(("gender", "sx")).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! The graph of references between entries.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use strum::Display;
+
+use crate::{Bibliography, Entry};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The field through which an entry refers to another one.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[strum(serialize_all = "lowercase")]
+pub enum ReferenceKind {
+    /// The `crossref` field.
+    Crossref,
+    /// The `xref` field.
+    Xref,
+    /// The `xdata` field.
+    Xdata,
+    /// The `related` field.
+    Related,
+    /// The `entryset` field of a `@set`.
+    EntrySet,
+}
+
+impl ReferenceKind {
+    /// All kinds in the order they are collected.
+    pub const ALL: [ReferenceKind; 5] =
+        [Self::Crossref, Self::Xref, Self::Xdata, Self::Related, Self::EntrySet];
+
+    /// The name of the field holding the reference.
+    pub fn field(self) -> &'static str {
+        match self {
+            Self::Crossref => "crossref",
+            Self::Xref => "xref",
+            Self::Xdata => "xdata",
+            Self::Related => "related",
+            Self::EntrySet => "entryset",
+        }
+    }
+}
+
+/// A reference from one entry to another.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reference {
+    /// The cite key of the referring entry.
+    pub source: String,
+    /// The referenced cite key as written in the field.
+    pub target: String,
+    /// The field holding the reference.
+    pub kind: ReferenceKind,
+}
+
+/// The references between the entries of a bibliography. Can be obtained
+/// through [`Bibliography::reference_graph`].
+///
+/// Note that `xdata` references are resolved and removed while parsing, so
+/// they are only part of the graph for entries that were modified afterwards.
+/// Unused `@xdata` containers can be found with
+/// [`RawBibliography::collect_garbage`](crate::RawBibliography::collect_garbage).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ReferenceGraph {
+    /// The cite keys of the entries, followed by those of the `@xdata`
+    /// containers.
+    pub nodes: Vec<String>,
+    /// The references in bibliography order.
+    pub edges: Vec<Reference>,
+    /// The keys of parent-like entries, which are multi-volume works and
+    /// collections.
+    parents: Vec<String>,
+}
+
+impl ReferenceGraph {
+    /// The references made by the entry with this cite key.
+    pub fn references<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Reference> {
+        self.edges.iter().filter(move |edge| edge.source == key)
+    }
+
+    /// The references to the entry with this cite key.
+    pub fn referrers<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Reference> {
+        self.edges.iter().filter(move |edge| edge.target == key)
+    }
+
+    /// The references whose target is not part of the bibliography.
+    pub fn dangling(&self) -> Vec<&Reference> {
+        self.edges
+            .iter()
+            .filter(|edge| !self.nodes.contains(&edge.target))
+            .collect()
+    }
+
+    /// The multi-volume works and collections that no entry refers to, for
+    /// example a `@proceedings` entry whose papers were all removed.
+    pub fn orphaned_parents(&self) -> Vec<&str> {
+        self.parents
+            .iter()
+            .filter(|key| self.referrers(key).next().is_none())
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Serialize the graph in the DOT language of Graphviz. Edges are labeled
+    /// with their field and dangling targets are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let quote =
+            |key: &str| format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut dot = String::from("digraph bibliography {\n");
+        for node in &self.nodes {
+            writeln!(dot, "  {};", quote(node)).unwrap();
+        }
+
+        let mut dangling: Vec<&str> = vec![];
+        for edge in self.dangling() {
+            if !dangling.contains(&edge.target.as_str()) {
+                dangling.push(&edge.target);
+                writeln!(dot, "  {} [style=dashed];", quote(&edge.target)).unwrap();
+            }
+        }
+
+        for edge in &self.edges {
+            writeln!(
+                dot,
+                "  {} -> {} [label={}];",
+                quote(&edge.source),
+                quote(&edge.target),
+                edge.kind
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl Bibliography {
+    /// Collect the references between entries through the `crossref`,
+    /// `xref`, `xdata`, `related`, and `entryset` fields.
+    pub fn reference_graph(&self) -> ReferenceGraph {
+        let mut graph = ReferenceGraph::default();
+
+        for entry in self.entries.iter().chain(self.xdata.values()) {
+            graph.nodes.push(entry.key.clone());
+            if entry.entry_type.is_multi_volume() || entry.entry_type.is_collection() {
+                graph.parents.push(entry.key.clone());
+            }
+
+            for kind in ReferenceKind::ALL {
+                for target in targets(entry, kind) {
+                    graph.edges.push(Reference {
+                        source: entry.key.clone(),
+                        target,
+                        kind,
+                    });
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Extract the entries with the given cite keys or aliases together with
+    /// the entries they refer to, directly or transitively, through the
+    /// fields of [`Bibliography::reference_graph`].
+    ///
+    /// Entries keep their order and the preamble is retained. Unknown keys are
+    /// ignored. To also drop unused `@string` abbreviations, use
+    /// [`RawBibliography::subset`](crate::RawBibliography::subset) instead.
+    pub fn subset<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Bibliography {
+        let mut included = vec![false; self.entries.len()];
+        let mut containers: Vec<&Entry> = vec![];
+        let mut container_keys: HashSet<String> = HashSet::new();
+        let mut queue: Vec<String> = keys.into_iter().map(ToString::to_string).collect();
+
+        while let Some(key) = queue.pop() {
+            let entry = match self.keys.get(&key) {
+                Some(&index) if !included[index] => {
+                    included[index] = true;
+                    &self.entries[index]
+                }
+                Some(_) => continue,
+                None => match self.xdata.get(&key) {
+                    Some(entry) if container_keys.insert(key) => {
+                        containers.push(entry);
+                        entry
+                    }
+                    _ => continue,
+                },
+            };
+
+            for kind in ReferenceKind::ALL {
+                queue.extend(targets(entry, kind));
+            }
+        }
+
+        let mut subset = Bibliography::new();
+        subset.preamble = self.preamble.clone();
+        for container in containers {
+            subset.insert(container.clone());
+        }
+        for (entry, _) in self.entries.iter().zip(included).filter(|(_, i)| *i) {
+            subset.insert(entry.clone());
+        }
+        subset
+    }
+}
+
+/// The cite keys in a reference field.
+fn targets(entry: &Entry, kind: ReferenceKind) -> Vec<String> {
+    match kind {
+        ReferenceKind::Crossref => {
+            entry.get_as::<String>(kind.field()).into_iter().collect()
+        }
+        _ => entry.get_as::<Vec<String>>(kind.field()).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_graph() {
+        let src = r#"@proceedings{conf, title = {Conference}}
+            @proceedings{old, title = {Old Conference}}
+            @inproceedings{paper, crossref = {conf}, related = {other,gone}}
+            @article{other, xref = {conf}}
+            @set{set, entryset = {paper,other}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let graph = bibliography.reference_graph();
+        assert_eq!(graph.nodes, ["conf", "old", "paper", "other", "set"]);
+        assert_eq!(graph.edges.len(), 6);
+        assert_eq!(graph.referrers("conf").count(), 2);
+        assert_eq!(graph.references("set").count(), 2);
+        assert_eq!(graph.orphaned_parents(), ["old"]);
+
+        let dangling = graph.dangling();
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0].target, "gone");
+        assert_eq!(dangling[0].kind, ReferenceKind::Related);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph bibliography {\n  \"conf\";\n"));
+        assert!(dot.contains("  \"gone\" [style=dashed];\n"));
+        assert!(dot.contains("  \"paper\" -> \"conf\" [label=crossref];\n"));
+        assert!(dot.contains("  \"set\" -> \"other\" [label=entryset];\n"));
+    }
+
+    #[test]
+    fn test_subset() {
+        let src = r#"@preamble{"\\newcommand{\\x}{x}"}
+            @proceedings{conf, title = {Conference}}
+            @inproceedings{paper, crossref = {conf}, related = {other}, ids = {alias}}
+            @article{other, xref = {conf}}
+            @article{unrelated, title = {U}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let subset = bibliography.subset(["alias", "missing"]);
+        let keys: Vec<_> = subset.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["conf", "paper", "other"]);
+        assert_eq!(subset.preamble(), bibliography.preamble());
+        assert!(subset.get("alias").is_some());
+        assert!(bibliography.subset([]).is_empty());
+    }
+}
@@ -0,0 +1,458 @@
+//! Conversion between entries and Hayagriva entries.
+
+use hayagriva::types::{
+    self as hg, FormatString, MaybeTyped, Numeric, PageRanges, Publisher, QualifiedUrl,
+};
+
+use crate::types::Type;
+use crate::{
+    Chunk, ChunksExt, ChunksRef, Date, DateValue, Datetime, Entry, EntryType,
+    PermissiveType, Person, RetrievalError, Spanned, TypeError, TypeErrorKind,
+};
+
+/// The Hayagriva entry type for an entry type, with the type of the parent
+/// that holds the `journaltitle` or `booktitle`, if any.
+fn hayagriva_type(entry_type: &EntryType) -> (hg::EntryType, Option<hg::EntryType>) {
+    use hg::EntryType as Hg;
+    match entry_type {
+        EntryType::Article => (Hg::Article, Some(Hg::Periodical)),
+        EntryType::InBook | EntryType::BookInBook | EntryType::SuppBook => {
+            (Hg::Chapter, Some(Hg::Book))
+        }
+        EntryType::InCollection | EntryType::SuppCollection => {
+            (Hg::Anthos, Some(Hg::Anthology))
+        }
+        EntryType::InProceedings => (Hg::Article, Some(Hg::Proceedings)),
+        EntryType::InReference => (Hg::Entry, Some(Hg::Reference)),
+        EntryType::Book | EntryType::MvBook => (Hg::Book, None),
+        EntryType::Collection | EntryType::MvCollection => (Hg::Anthology, None),
+        EntryType::Proceedings | EntryType::MvProceedings => (Hg::Proceedings, None),
+        EntryType::Reference | EntryType::MvReference => (Hg::Reference, None),
+        EntryType::Periodical | EntryType::SuppPeriodical => (Hg::Periodical, None),
+        EntryType::Report | EntryType::TechReport => (Hg::Report, None),
+        EntryType::Thesis | EntryType::PhdThesis | EntryType::MastersThesis => {
+            (Hg::Thesis, None)
+        }
+        EntryType::Unpublished => (Hg::Manuscript, None),
+        EntryType::Online => (Hg::Web, None),
+        EntryType::Patent => (Hg::Patent, None),
+        EntryType::Software => (Hg::Repository, None),
+        EntryType::Artwork => (Hg::Artwork, None),
+        EntryType::Audio | EntryType::Music => (Hg::Audio, None),
+        EntryType::Movie | EntryType::Video => (Hg::Video, None),
+        EntryType::Performance => (Hg::Performance, None),
+        EntryType::Jurisdiction => (Hg::Case, None),
+        EntryType::Legislation => (Hg::Legislation, None),
+        _ => (Hg::Misc, None),
+    }
+}
+
+/// The entry type for a Hayagriva entry type and the type of its parent,
+/// with the field that receives the title of the parent.
+fn biblatex_type(
+    entry_type: &hg::EntryType,
+    parent: Option<&hg::EntryType>,
+) -> (EntryType, &'static str) {
+    use hg::EntryType as Hg;
+    match (entry_type, parent) {
+        (Hg::Article, Some(Hg::Proceedings | Hg::Conference)) => {
+            (EntryType::InProceedings, "booktitle")
+        }
+        (Hg::Article, _) => (EntryType::Article, "journaltitle"),
+        (Hg::Chapter, _) => (EntryType::InBook, "booktitle"),
+        (Hg::Anthos, _) => (EntryType::InCollection, "booktitle"),
+        (Hg::Entry, _) => (EntryType::InReference, "booktitle"),
+        (Hg::Book, _) => (EntryType::Book, "maintitle"),
+        (Hg::Anthology, _) => (EntryType::Collection, "maintitle"),
+        (Hg::Proceedings, _) => (EntryType::Proceedings, "maintitle"),
+        (Hg::Reference, _) => (EntryType::Reference, "maintitle"),
+        (Hg::Periodical | Hg::Newspaper, _) => (EntryType::Periodical, "series"),
+        (Hg::Report, _) => (EntryType::Report, "series"),
+        (Hg::Thesis, _) => (EntryType::Thesis, "series"),
+        (Hg::Manuscript, _) => (EntryType::Unpublished, "howpublished"),
+        (Hg::Web | Hg::Blog | Hg::Post | Hg::Thread, _) => {
+            (EntryType::Online, "organization")
+        }
+        (Hg::Patent, _) => (EntryType::Patent, "series"),
+        (Hg::Repository, _) => (EntryType::Software, "series"),
+        (Hg::Artwork, _) => (EntryType::Artwork, "series"),
+        (Hg::Audio, _) => (EntryType::Audio, "series"),
+        (Hg::Video, _) => (EntryType::Video, "series"),
+        (Hg::Performance, _) => (EntryType::Performance, "eventtitle"),
+        (Hg::Case, _) => (EntryType::Jurisdiction, "series"),
+        (Hg::Legislation, _) => (EntryType::Legislation, "series"),
+        _ => (EntryType::Misc, "howpublished"),
+    }
+}
+
+impl TryFrom<&Entry> for hayagriva::Entry {
+    type Error = TypeError;
+
+    /// Convert an entry into a Hayagriva entry.
+    ///
+    /// The `journaltitle` or `booktitle` of contained works becomes the title
+    /// of a parent entry. Fields without a Hayagriva counterpart are dropped.
+    /// Fails if the `date` or `urldate` is malformed or not a date, or if the
+    /// `url` is not a valid URL.
+    fn try_from(entry: &Entry) -> Result<Self, Self::Error> {
+        let (entry_type, parent_type) = hayagriva_type(&entry.entry_type);
+        let mut item = hayagriva::Entry::new(&entry.key, entry_type);
+        let text = |field: &str| entry.get(field).map(|chunks| chunks.format_verbatim());
+        let format = |field: &str| text(field).map(FormatString::with_value);
+
+        if let Some(title) = format("title") {
+            item.set_title(title);
+        }
+        if let Ok(authors) = entry.author() {
+            item.set_authors(authors.iter().map(hayagriva_person).collect());
+        }
+        if let Ok(editors) = entry.editor() {
+            item.set_editors(editors.iter().map(hayagriva_person).collect());
+        }
+        if let Some(date) = hayagriva_date(entry.date())? {
+            item.set_date(date);
+        }
+
+        let publisher = entry.publisher().ok().map(|p| p.to_chunks().format_verbatim());
+        let location = entry.location().ok().map(|l| l.to_chunks().format_verbatim());
+        if publisher.is_some() || location.is_some() {
+            item.set_publisher(Publisher::new(
+                publisher.map(FormatString::with_value),
+                location.map(FormatString::with_value),
+            ));
+        }
+        if let Some(organization) =
+            format("institution").or_else(|| format("organization"))
+        {
+            item.set_organization(organization);
+        }
+        if let Some(venue) = format("venue") {
+            item.set_location(venue);
+        }
+
+        let numeric = |field: &str| text(field).map(|text| numeric(&text));
+        if let Some(volume) = numeric("volume") {
+            item.set_volume(volume);
+        }
+        if let Some(issue) = numeric("number") {
+            item.set_issue(issue);
+        }
+        if let Some(edition) = numeric("edition") {
+            item.set_edition(edition);
+        }
+        if let Some(pages) = text("pages") {
+            item.set_page_range(match pages.parse::<PageRanges>() {
+                Ok(ranges) => MaybeTyped::Typed(ranges),
+                Err(_) => MaybeTyped::String(pages),
+            });
+        }
+        if let Some(total) = text("pagetotal").and_then(|t| t.parse::<Numeric>().ok()) {
+            item.set_page_total(total);
+        }
+        if let Some(total) = text("volumes").and_then(|t| t.parse::<Numeric>().ok()) {
+            item.set_volume_total(total);
+        }
+
+        if let Some(chunks) = entry.get("url") {
+            let mut url = chunks
+                .format_verbatim()
+                .parse::<QualifiedUrl>()
+                .map_err(|_| invalid_format(chunks))?;
+            url.visit_date = hayagriva_date(entry.url_date())?;
+            item.set_url(url);
+        }
+
+        if let Some(doi) = text("doi") {
+            item.set_doi(doi);
+        }
+        if let Some(isbn) = text("isbn") {
+            item.set_isbn(isbn);
+        }
+        if let Some(issn) = text("issn") {
+            item.set_issn(issn);
+        }
+        if let Some(eprint) = text("eprint") {
+            if text("eprinttype").is_some_and(|t| t.eq_ignore_ascii_case("arxiv")) {
+                item.set_arxiv(eprint);
+            }
+        }
+        if let Some(note) = format("note") {
+            item.set_note(note);
+        }
+        if let Some(abstract_) = format("abstract") {
+            item.set_abstract_(abstract_);
+        }
+        if let Some(genre) = format("type") {
+            item.set_genre(genre);
+        }
+
+        if let Some(parent_type) = parent_type {
+            let mut parent = hayagriva::Entry::new(&entry.key, parent_type);
+            if let Some(title) = format("journaltitle").or_else(|| format("booktitle")) {
+                parent.set_title(title);
+            }
+            item.set_parents(vec![parent]);
+        }
+
+        Ok(item)
+    }
+}
+
+impl From<&hayagriva::Entry> for Entry {
+    /// Convert a Hayagriva entry into an entry.
+    ///
+    /// The title of the first parent becomes the `journaltitle`, `booktitle`,
+    /// or a similar field, and the parent supplies the date, publisher, and
+    /// editors if the entry has none. Fields without a Bib(La)TeX counterpart
+    /// are dropped.
+    fn from(item: &hayagriva::Entry) -> Self {
+        let parent = item.parents().first();
+        let (entry_type, parent_field) =
+            biblatex_type(item.entry_type(), parent.map(|p| p.entry_type()));
+        let mut entry = Entry::new(item.key().to_string(), entry_type);
+
+        if let Some(title) = item.title() {
+            set_text(&mut entry, "title", title.to_string());
+        }
+        if let Some(title) = parent.and_then(|p| p.title()) {
+            set_text(&mut entry, parent_field, title.to_string());
+        }
+
+        let inherited = |get: fn(&hayagriva::Entry) -> bool| {
+            if get(item) {
+                Some(item)
+            } else {
+                parent.filter(|p| get(p))
+            }
+        };
+
+        if let Some(authors) = item.authors() {
+            entry.set_author(authors.iter().map(biblatex_person).collect());
+        }
+        if let Some(editors) =
+            inherited(|i| i.editors().is_some()).and_then(|s| s.editors())
+        {
+            entry.set_editor(editors.iter().map(biblatex_person).collect());
+        }
+        if let Some(date) = inherited(|i| i.date().is_some()).and_then(|s| s.date()) {
+            entry.set_date(PermissiveType::Typed(biblatex_date(date)));
+        }
+        if let Some(publisher) =
+            inherited(|i| i.publisher().is_some()).and_then(|s| s.publisher())
+        {
+            if let Some(name) = publisher.name() {
+                set_text(&mut entry, "publisher", name.to_string());
+            }
+            if let Some(location) = publisher.location() {
+                set_text(&mut entry, "location", location.to_string());
+            }
+        }
+        if let Some(organization) = item.organization() {
+            let field = match entry.entry_type {
+                EntryType::Thesis | EntryType::Report => "institution",
+                _ => "organization",
+            };
+            set_text(&mut entry, field, organization.to_string());
+        }
+        if let Some(location) = item.location() {
+            set_text(&mut entry, "venue", location.to_string());
+        }
+
+        let numbers = [
+            ("volume", item.volume().map(ToString::to_string)),
+            ("number", item.issue().map(ToString::to_string)),
+            ("edition", item.edition().map(ToString::to_string)),
+            ("pages", item.page_range().map(ToString::to_string)),
+            ("pagetotal", item.page_total().map(ToString::to_string)),
+            ("volumes", item.volume_total().map(ToString::to_string)),
+        ];
+        for (field, value) in numbers {
+            if let Some(value) = value {
+                set_text(&mut entry, field, value);
+            }
+        }
+
+        if let Some(url) = item.url() {
+            set_text(&mut entry, "url", url.value.to_string());
+            if let Some(date) = &url.visit_date {
+                entry.set_url_date(PermissiveType::Typed(biblatex_date(date)));
+            }
+        }
+
+        let serials = [("doi", item.doi()), ("isbn", item.isbn()), ("issn", item.issn())];
+        for (field, value) in serials {
+            if let Some(value) = value {
+                set_text(&mut entry, field, value.to_string());
+            }
+        }
+        if let Some(arxiv) = item.arxiv() {
+            set_text(&mut entry, "eprint", arxiv.to_string());
+            set_text(&mut entry, "eprinttype", "arxiv".to_string());
+        }
+
+        let texts = [
+            ("note", item.note()),
+            ("abstract", item.abstract_()),
+            ("type", item.genre()),
+        ];
+        for (field, value) in texts {
+            if let Some(value) = value {
+                set_text(&mut entry, field, value.to_string());
+            }
+        }
+
+        entry
+    }
+}
+
+/// Set a field to the given text.
+fn set_text(entry: &mut Entry, field: &str, text: String) {
+    entry.set(field, vec![Spanned::detached(Chunk::Normal(text))]);
+}
+
+/// Parse a number, keeping the text if it is not numeric.
+fn numeric(text: &str) -> MaybeTyped<Numeric> {
+    match text.parse::<Numeric>() {
+        Ok(numeric) => MaybeTyped::Typed(numeric),
+        Err(_) => MaybeTyped::String(text.to_string()),
+    }
+}
+
+/// The error for a field value that cannot be converted.
+fn invalid_format(chunks: ChunksRef) -> TypeError {
+    TypeError::new(chunks.span(), TypeErrorKind::InvalidFormat)
+}
+
+fn hayagriva_person(person: &Person) -> hg::Person {
+    let optional = |part: &str| Some(part.to_string()).filter(|part| !part.is_empty());
+    hg::Person {
+        name: person.name.clone(),
+        given_name: optional(&person.given_name),
+        prefix: optional(&person.prefix),
+        suffix: optional(&person.suffix),
+        alias: None,
+    }
+}
+
+fn biblatex_person(person: &hg::Person) -> Person {
+    Person {
+        name: person.name.clone(),
+        given_name: person.given_name.clone().unwrap_or_default(),
+        prefix: person.prefix.clone().unwrap_or_default(),
+        suffix: person.suffix.clone().unwrap_or_default(),
+    }
+}
+
+/// Convert a retrieved date. Ranges are represented by their start, or their
+/// end if they are open at the start.
+fn hayagriva_date(
+    date: Result<PermissiveType<Date>, RetrievalError>,
+) -> Result<Option<hg::Date>, TypeError> {
+    let date = match date {
+        Ok(PermissiveType::Typed(date)) => date,
+        Ok(PermissiveType::Chunks(chunks)) => return Err(invalid_format(&chunks)),
+        Err(RetrievalError::Missing(_)) => return Ok(None),
+        Err(RetrievalError::TypeError(err)) => return Err(err),
+    };
+
+    let datetime = match date.value {
+        DateValue::At(datetime)
+        | DateValue::After(datetime)
+        | DateValue::Before(datetime)
+        | DateValue::Between(datetime, _) => datetime,
+    };
+
+    Ok(Some(hg::Date {
+        year: datetime.year,
+        month: datetime.month,
+        day: datetime.day,
+        approximate: date.approximate || date.uncertain,
+    }))
+}
+
+fn biblatex_date(date: &hg::Date) -> Date {
+    Date {
+        value: DateValue::At(Datetime {
+            year: date.year,
+            month: date.month,
+            day: date.day,
+            time: None,
+        }),
+        uncertain: false,
+        approximate: date.approximate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bibliography;
+
+    #[test]
+    fn test_hayagriva() {
+        let src = r"@inproceedings{knuth,
+            author = {Knuth, Donald E.},
+            title = {Literate Programming},
+            booktitle = {Proceedings of the Conference},
+            date = {1984-05~},
+            publisher = {ACM},
+            location = {New York},
+            pages = {97--111},
+            url = {https://example.org/lp},
+            urldate = {2020-01-02},
+            doi = {10.1093/comjnl/27.2.97},
+            keywords = {dropped},
+        }
+        @misc{bad, date = {sometime}}
+        @online{worse, url = {not a url}}";
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let item =
+            hayagriva::Entry::try_from(bibliography.get("knuth").unwrap()).unwrap();
+        assert_eq!(item.entry_type(), &hg::EntryType::Article);
+        assert_eq!(item.title().unwrap().to_string(), "Literate Programming");
+        assert_eq!(item.authors().unwrap()[0].given_name.as_deref(), Some("Donald E."));
+        assert_eq!(item.date().unwrap().to_string(), "~1984-05");
+        assert_eq!(item.page_range().unwrap().to_string(), "97-111");
+        assert_eq!(item.url().unwrap().visit_date.as_ref().unwrap().day, Some(1));
+        assert_eq!(item.doi(), Some("10.1093/comjnl/27.2.97"));
+        let parent = &item.parents()[0];
+        assert_eq!(parent.entry_type(), &hg::EntryType::Proceedings);
+        assert_eq!(parent.title().unwrap().to_string(), "Proceedings of the Conference");
+
+        let entry = Entry::from(&item);
+        assert_eq!(
+            entry.to_biblatex_string(),
+            "@inproceedings{knuth,\n\
+             author = {Knuth, Donald E.},\n\
+             booktitle = {Proceedings of the Conference},\n\
+             date = {1984-05\\~},\n\
+             doi = {10.1093/comjnl/27.2.97},\n\
+             location = {New York},\n\
+             pages = {97-111},\n\
+             publisher = {ACM},\n\
+             title = {Literate Programming},\n\
+             url = {https://example.org/lp},\n\
+             urldate = {2020-01-02},\n\
+             }"
+        );
+
+        let err =
+            hayagriva::Entry::try_from(bibliography.get("bad").unwrap()).unwrap_err();
+        assert_eq!(err.kind, TypeErrorKind::InvalidFormat);
+        assert!(hayagriva::Entry::try_from(bibliography.get("worse").unwrap()).is_err());
+
+        let mut chapter = hayagriva::Entry::new("c", hg::EntryType::Chapter);
+        let mut book = hayagriva::Entry::new("c", hg::EntryType::Book);
+        book.set_title(FormatString::with_value("Book"));
+        book.set_date(hg::Date::from_year(2001));
+        chapter.set_parents(vec![book]);
+        let entry = Entry::from(&chapter);
+        assert_eq!(entry.entry_type, EntryType::InBook);
+        assert_eq!(entry.book_title().unwrap().format_verbatim(), "Book");
+        assert_eq!(
+            entry.date().unwrap(),
+            PermissiveType::Typed(biblatex_date(&hg::Date::from_year(2001)))
+        );
+    }
+}
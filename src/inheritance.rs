@@ -0,0 +1,388 @@
+//! Defines which fields an entry inherits from its parent entries.
+//!
+//! The default rules follow Appendix B of the [BibLaTeX Manual][manual].
+//!
+//! [manual]: http://ctan.ebinger.cc/tex-archive/macros/latex/contrib/biblatex/doc/biblatex.pdf
+
+use crate::mechanics::{canonical_field_name, FIELD_ALIASES};
+use crate::{ChunksRef, Entry, EntryType};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Fields that are never inherited from a parent entry.
+const NO_INHERIT: [&str; 17] = [
+    "crossref",
+    "entryset",
+    "entrysubtype",
+    "execute",
+    "ids",
+    "label",
+    "options",
+    "presort",
+    "related",
+    "relatedoptions",
+    "relatedstring",
+    "relatedtype",
+    "shorthand",
+    "shorthandintro",
+    "sortkey",
+    "xdata",
+    "xref",
+];
+
+/// The fields describing the publication date of an entry, which are only
+/// inherited together.
+const DATE_FIELDS: [&str; 4] = ["date", "year", "month", "day"];
+
+/// The title fields that parents pass on under a different name.
+const TITLE_FIELDS: [&str; 3] = ["title", "subtitle", "titleaddon"];
+
+/// Title fields that are never inherited by the children of collections.
+const NO_INHERIT_TITLES: [&str; 4] =
+    ["shorttitle", "sorttitle", "indextitle", "indexsorttitle"];
+
+/// The rules by which fields are inherited through `crossref`, mirroring the
+/// `\DefaultInheritance` and `\DeclareDataInheritance` commands of BibLaTeX.
+///
+/// The [`Default`] specification implements the default rules of BibLaTeX.
+/// Use [`Bibliography::parse_with_inheritance`](crate::Bibliography::parse_with_inheritance)
+/// to resolve a bibliography with custom rules.
+///
+/// ```
+/// # use biblatex::{Bibliography, EntryType, InheritanceRule, InheritanceSpec};
+/// let mut spec = InheritanceSpec::default();
+/// spec.add_rule(
+///     InheritanceRule::new(&[EntryType::Book], &[EntryType::InBook])
+///         .inherit("publisher", "origpublisher")
+///         .no_inherit("note"),
+/// );
+///
+/// let src = "@book{b, title = {B}, publisher = {P}, note = {N}}
+///            @inbook{c, title = {C}, crossref = {b}}";
+/// let bibliography = Bibliography::parse_with_inheritance(src, &spec).unwrap();
+/// let entry = bibliography.get("c").unwrap();
+/// assert!(entry.get("origpublisher").is_some());
+/// assert!(entry.get("note").is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InheritanceSpec {
+    /// Rules for specific pairs of parent and child entry types.
+    rules: Vec<InheritanceRule>,
+    /// Whether fields without a rule are inherited under the same name.
+    inherit_all: bool,
+    /// Whether inherited fields overwrite fields present in the child.
+    override_fields: bool,
+    /// Fields that are never inherited.
+    no_inherit: Vec<String>,
+}
+
+/// Maps fields of a parent to fields of a child for some combinations of
+/// parent and child entry types, like `\DeclareDataInheritance` in BibLaTeX.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InheritanceRule {
+    /// The types of parent entries the rule applies to.
+    sources: Vec<EntryType>,
+    /// The types of child entries the rule applies to.
+    targets: Vec<EntryType>,
+    /// Pairs of a parent field and the child field it is inherited as. The
+    /// parent field is not inherited if the child field is `None`.
+    fields: Vec<(String, Option<String>)>,
+}
+
+impl InheritanceRule {
+    /// Create a rule for parents of one of the `sources` types and children of
+    /// one of the `targets` types. An empty list matches all types.
+    pub fn new(sources: &[EntryType], targets: &[EntryType]) -> Self {
+        Self {
+            sources: sources.to_vec(),
+            targets: targets.to_vec(),
+            fields: vec![],
+        }
+    }
+
+    /// Inherit the `source` field of the parent as the `target` field of the
+    /// child, like `\inherit` in BibLaTeX.
+    pub fn inherit(mut self, source: &str, target: &str) -> Self {
+        self.fields.push((source.to_lowercase(), Some(target.to_lowercase())));
+        self
+    }
+
+    /// Do not inherit the `source` field of the parent, like `\noinherit` in
+    /// BibLaTeX.
+    pub fn no_inherit(mut self, source: &str) -> Self {
+        self.fields.push((source.to_lowercase(), None));
+        self
+    }
+
+    /// Whether the rule applies to this combination of parent and child.
+    fn applies(&self, source: &EntryType, target: &EntryType) -> bool {
+        (self.sources.is_empty() || self.sources.contains(source))
+            && (self.targets.is_empty() || self.targets.contains(target))
+    }
+}
+
+impl Default for InheritanceSpec {
+    fn default() -> Self {
+        use EntryType::*;
+
+        let prefixed = |prefix: &str| {
+            let mut fields: Vec<_> = TITLE_FIELDS
+                .iter()
+                .map(|f| (f.to_string(), Some(format!("{}{}", prefix, f))))
+                .collect();
+            fields.extend(NO_INHERIT_TITLES.iter().map(|f| (f.to_string(), None)));
+            fields
+        };
+
+        let rule = |sources: &[EntryType], targets: &[EntryType], fields| {
+            InheritanceRule { fields, ..InheritanceRule::new(sources, targets) }
+        };
+
+        let authors = vec![
+            ("author".to_string(), Some("author".to_string())),
+            ("author".to_string(), Some("bookauthor".to_string())),
+        ];
+
+        let journal = vec![
+            ("title".to_string(), Some("journaltitle".to_string())),
+            ("subtitle".to_string(), Some("journalsubtitle".to_string())),
+            ("shorttitle".to_string(), None),
+            ("sorttitle".to_string(), None),
+            ("indextitle".to_string(), None),
+            ("indexsorttitle".to_string(), None),
+        ];
+
+        Self {
+            rules: vec![
+                rule(&[MvBook, Book], &[InBook, BookInBook, SuppBook], authors),
+                rule(&[MvBook], &[Book, InBook, BookInBook, SuppBook], prefixed("main")),
+                rule(
+                    &[MvCollection, MvReference],
+                    &[Collection, Reference, InCollection, InReference, SuppCollection],
+                    prefixed("main"),
+                ),
+                rule(&[MvProceedings], &[Proceedings, InProceedings], prefixed("main")),
+                // BibTeX databases commonly use `@book` parents for
+                // `@incollection` entries.
+                rule(
+                    &[Book],
+                    &[InBook, BookInBook, SuppBook, InCollection],
+                    prefixed("book"),
+                ),
+                rule(
+                    &[Collection, Reference],
+                    &[InCollection, InReference, SuppCollection],
+                    prefixed("book"),
+                ),
+                rule(&[Proceedings], &[InProceedings], prefixed("book")),
+                rule(&[Periodical], &[Article, SuppPeriodical], journal),
+            ],
+            inherit_all: true,
+            override_fields: false,
+            no_inherit: NO_INHERIT.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl InheritanceSpec {
+    /// Create a specification without any rules that does not inherit any
+    /// fields except for those of `@xdata` entries.
+    pub fn empty() -> Self {
+        Self {
+            rules: vec![],
+            inherit_all: false,
+            override_fields: false,
+            no_inherit: NO_INHERIT.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Add a rule for specific entry types. Rules added later are applied
+    /// after the existing ones.
+    pub fn add_rule(&mut self, rule: InheritanceRule) {
+        self.rules.push(rule);
+    }
+
+    /// Remove all rules, including the default ones.
+    pub fn clear_rules(&mut self) {
+        self.rules.clear();
+    }
+
+    /// Set whether fields without a matching rule are inherited under the
+    /// same name, like the `all` option of `\DefaultInheritance`. Enabled by
+    /// default.
+    pub fn set_inherit_all(&mut self, inherit_all: bool) {
+        self.inherit_all = inherit_all;
+    }
+
+    /// Set whether inherited fields overwrite the fields of the child, like
+    /// the `override` option of `\DefaultInheritance`. Disabled by default.
+    ///
+    /// The `date`, `year`, `month`, and `day` fields are treated as a group:
+    /// if the parent has a date, it replaces all date fields of the child.
+    pub fn set_override(&mut self, override_fields: bool) {
+        self.override_fields = override_fields;
+    }
+
+    /// Never inherit a field, regardless of the entry types.
+    pub fn no_inherit(&mut self, field: &str) {
+        self.no_inherit.push(field.to_lowercase());
+    }
+
+    /// Copy the fields `child` inherits from `parent` into `child`.
+    ///
+    /// Unless overriding is enabled, fields already present in the child,
+    /// possibly under their legacy BibTeX name, are not overwritten. The
+    /// fields of `@xdata` parents are always inherited under the same name.
+    pub(crate) fn inherit(&self, child: &mut Entry, parent: &Entry) {
+        let xdata =
+            parent.entry_type == EntryType::XData || child.entry_type == EntryType::XData;
+
+        let mut mapped = vec![];
+        if !xdata {
+            for rule in &self.rules {
+                if !rule.applies(&parent.entry_type, &child.entry_type) {
+                    continue;
+                }
+
+                for (source, target) in &rule.fields {
+                    mapped.push(source.as_str());
+                    let Some(target) = target else { continue };
+                    if let Some(chunks) = get_canonical(parent, source) {
+                        if self.override_fields || !has_canonical(child, target) {
+                            child.set(target, chunks.to_vec());
+                        }
+                    }
+                }
+            }
+        }
+
+        if !xdata && !self.inherit_all {
+            return;
+        }
+
+        // The date fields are inherited as a group so that the child does
+        // not end up with a `year` and a conflicting `date`.
+        let inherited_date = DATE_FIELDS.iter().any(|f| {
+            parent.get(f).is_some()
+                && !mapped.contains(f)
+                && !self.no_inherit.iter().any(|n| n == f)
+        });
+        let inherit_date = inherited_date
+            && (self.override_fields
+                || !DATE_FIELDS.iter().any(|f| child.get(f).is_some()));
+        if inherit_date {
+            for field in DATE_FIELDS {
+                child.remove(field);
+            }
+        }
+
+        for (key, chunks) in &parent.fields {
+            let field = canonical_field_name(key);
+            if mapped.contains(&field) || self.no_inherit.iter().any(|f| f == field) {
+                continue;
+            }
+
+            if DATE_FIELDS.contains(&field) {
+                if !inherit_date {
+                    continue;
+                }
+            } else if !self.override_fields && has_canonical(child, field) {
+                continue;
+            }
+
+            child.set(field, chunks.clone());
+        }
+    }
+}
+
+/// Get a field by its BibLaTeX name, falling back on its legacy aliases.
+pub(crate) fn get_canonical<'a>(entry: &'a Entry, field: &str) -> Option<ChunksRef<'a>> {
+    entry.get(field).or_else(|| {
+        FIELD_ALIASES
+            .iter()
+            .filter(|(_, canonical)| *canonical == field)
+            .find_map(|(alias, _)| entry.get(alias))
+    })
+}
+
+/// Whether the entry contains the field under its BibLaTeX name or one of
+/// its legacy aliases.
+fn has_canonical(entry: &Entry, field: &str) -> bool {
+    get_canonical(entry, field).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bibliography, ChunksExt};
+
+    #[test]
+    fn test_default_inheritance() {
+        let raw = r#"@mvbook{mv, title = {Collected Works}, author = {Doe, Jane},
+            shorttitle = {Works}, date = {1990}, location = {Berlin}}
+          @book{vol, title = {Early Writings}, crossref = {mv}, year = {1991}}
+          @inbook{chap, title = {First Steps}, crossref = {vol}, address = {Paris}}
+          @misc{parent, title = {Parent}, note = {Inherited}, ids = {secret}}
+          @misc{child, crossref = {parent}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let format = |key, field| {
+            bibliography.get(key).unwrap().get(field).map(|c| c.format_verbatim())
+        };
+
+        assert_eq!(format("vol", "maintitle").as_deref(), Some("Collected Works"));
+        assert_eq!(format("vol", "title").as_deref(), Some("Early Writings"));
+        assert_eq!(format("vol", "shorttitle"), None);
+        assert_eq!(format("vol", "year").as_deref(), Some("1991"));
+        assert_eq!(format("vol", "date"), None);
+
+        assert_eq!(format("chap", "booktitle").as_deref(), Some("Early Writings"));
+        assert_eq!(format("chap", "maintitle").as_deref(), Some("Collected Works"));
+        assert_eq!(format("chap", "bookauthor").as_deref(), Some("Doe, Jane"));
+        assert_eq!(format("chap", "author").as_deref(), Some("Doe, Jane"));
+        assert_eq!(format("chap", "year").as_deref(), Some("1991"));
+        assert_eq!(format("chap", "date"), None);
+        assert_eq!(format("chap", "location"), None);
+
+        assert_eq!(format("child", "title").as_deref(), Some("Parent"));
+        assert_eq!(format("child", "note").as_deref(), Some("Inherited"));
+        assert_eq!(format("child", "ids"), None);
+    }
+
+    #[test]
+    fn test_custom_inheritance() {
+        let raw = r#"@collection{coll, title = {Proceedings}, editor = {Doe, Jane},
+            note = {Note}, date = {2001}}
+          @incollection{paper, title = {Paper}, crossref = {coll}, year = {2000}}"#;
+
+        let mut spec = InheritanceSpec::empty();
+        spec.add_rule(
+            InheritanceRule::new(&[], &[EntryType::InCollection])
+                .inherit("title", "booktitle")
+                .inherit("editor", "bookeditor"),
+        );
+
+        let bibliography = Bibliography::parse_with_inheritance(raw, &spec).unwrap();
+        let paper = bibliography.get("paper").unwrap();
+        assert_eq!(paper.get("booktitle").unwrap().format_verbatim(), "Proceedings");
+        assert_eq!(paper.get("bookeditor").unwrap().format_verbatim(), "Doe, Jane");
+        assert_eq!(paper.get("editor"), None);
+        assert_eq!(paper.get("note"), None);
+
+        let mut spec = InheritanceSpec::default();
+        spec.set_override(true);
+        spec.no_inherit("editor");
+
+        let bibliography = Bibliography::parse_with_inheritance(raw, &spec).unwrap();
+        let paper = bibliography.get("paper").unwrap();
+        assert_eq!(paper.get("title").unwrap().format_verbatim(), "Paper");
+        assert_eq!(paper.get("booktitle").unwrap().format_verbatim(), "Proceedings");
+        assert_eq!(paper.get("note").unwrap().format_verbatim(), "Note");
+        assert_eq!(paper.get("date").unwrap().format_verbatim(), "2001");
+        assert_eq!(paper.get("year"), None);
+        assert_eq!(paper.get("editor"), None);
+    }
+}
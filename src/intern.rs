@@ -0,0 +1,189 @@
+//! Interned field names.
+
+use std::borrow::{Borrow, Cow};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Deref;
+
+use crate::TypedField;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The name of a field of an [`Entry`](crate::Entry).
+///
+/// The names of the fields known to the crate refer to a static symbol
+/// table, so that the entries of a large bibliography share them instead of
+/// each allocating its own copy. Other names own their text.
+///
+/// A field name dereferences to a string slice and compares like one.
+///
+/// ```
+/// # use biblatex::FieldName;
+/// let name = FieldName::new("title");
+/// assert!(name.is_interned());
+/// assert_eq!(name, "title");
+/// assert!(!FieldName::new("myfield").is_interned());
+/// ```
+///
+/// Entry types are not interned through this type: the known ones are
+/// variants of [`EntryType`](crate::EntryType) and do not allocate, only
+/// unknown types own their name.
+///
+/// # Migration
+///
+/// The fields of an entry used to be keyed by `String`. Lookups with a `&str`
+/// work as before, a `String` converts into a field name with
+/// [`Into::into`], and a field name converts back with [`String::from`] or
+/// [`ToString::to_string`].
+///
+/// ```
+/// # use biblatex::{Entry, EntryType};
+/// let mut entry = Entry::new("a".into(), EntryType::Misc);
+/// let name: String = "title".into();
+/// entry.fields.insert(name.into(), vec![]);
+/// assert!(entry.fields.contains_key("title"));
+///
+/// let names: Vec<String> = entry.fields.keys().map(ToString::to_string).collect();
+/// assert_eq!(names, ["title"]);
+/// ```
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub struct FieldName(Cow<'static, str>);
+
+impl FieldName {
+    /// Create a field name, interning it if the field is known.
+    pub fn new(name: &str) -> Self {
+        match TypedField::static_name(name) {
+            Some(name) => Self(Cow::Borrowed(name)),
+            None => Self(Cow::Owned(name.into())),
+        }
+    }
+
+    /// The name as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the name refers to the static symbol table.
+    pub fn is_interned(&self) -> bool {
+        matches!(self.0, Cow::Borrowed(_))
+    }
+}
+
+impl Deref for FieldName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for FieldName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for FieldName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for FieldName {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Display for FieldName {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for FieldName {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for FieldName {
+    fn from(name: String) -> Self {
+        match TypedField::static_name(&name) {
+            Some(name) => Self(Cow::Borrowed(name)),
+            None => Self(Cow::Owned(name)),
+        }
+    }
+}
+
+impl From<FieldName> for String {
+    fn from(name: FieldName) -> Self {
+        name.0.into_owned()
+    }
+}
+
+impl PartialEq<str> for FieldName {
+    fn eq(&self, other: &str) -> bool {
+        *self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for FieldName {
+    fn eq(&self, other: &&str) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<String> for FieldName {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<FieldName> for str {
+    fn eq(&self, other: &FieldName) -> bool {
+        *self == *other.0
+    }
+}
+
+impl PartialEq<FieldName> for &str {
+    fn eq(&self, other: &FieldName) -> bool {
+        **self == *other.0
+    }
+}
+
+impl PartialEq<FieldName> for String {
+    fn eq(&self, other: &FieldName) -> bool {
+        **self == *other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bibliography;
+
+    #[test]
+    fn test_interned_fields() {
+        let src = "@misc{a, Title = {A}, myfield = {B}}";
+        let bibliography = Bibliography::parse(src).unwrap();
+        let entry = bibliography.get("a").unwrap();
+        let names: Vec<_> = entry.fields.keys().collect();
+        assert_eq!(names, ["myfield", "title"]);
+        assert!(!names[0].is_interned());
+        assert!(names[1].is_interned());
+        assert_eq!(entry.get("title").unwrap().len(), 1);
+
+        let mut entry = entry.clone();
+        entry.set("NOTE", vec![]);
+        assert!(entry
+            .fields
+            .keys()
+            .all(|name| name != "myfield" || !name.is_interned()));
+        assert!(entry.fields.get_key_value("note").unwrap().0.is_interned());
+        assert_eq!(String::from(FieldName::from("x".to_string())), "x");
+        assert_eq!("title".to_string(), FieldName::new("title"));
+    }
+}
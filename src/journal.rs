@@ -0,0 +1,447 @@
+//! Abbreviation and expansion of journal titles.
+
+use std::collections::HashMap;
+
+use crate::{Bibliography, Chunk, ChunksExt, Entry, Spanned};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A list of journal titles and their abbreviations, for example one of the
+/// journal abbreviation lists of JabRef.
+///
+/// Lookups ignore case, periods, and differences in whitespace, so
+/// `Phys Rev Lett` matches the abbreviation `Phys. Rev. Lett.`.
+///
+/// ```
+/// # use biblatex::{Bibliography, JournalAbbreviations};
+/// let list = JournalAbbreviations::parse(
+///     "Physical Review Letters;Phys. Rev. Lett.\n\
+///      \"Journal of Physics: Condensed Matter\";J. Phys.: Condens. Matter",
+/// );
+/// assert_eq!(list.abbreviate("physical review letters"), Some("Phys. Rev. Lett."));
+/// assert_eq!(list.expand("Phys Rev Lett"), Some("Physical Review Letters"));
+///
+/// let src = "@article{a, journal = {J. Phys.: Condens. Matter}}";
+/// let mut bibliography = Bibliography::parse(src).unwrap();
+/// assert_eq!(bibliography.expand_journals(&list), ["a"]);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JournalAbbreviations {
+    pairs: Vec<(String, String)>,
+    by_title: HashMap<String, usize>,
+    by_abbreviation: HashMap<String, usize>,
+}
+
+impl JournalAbbreviations {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a list with one journal per line.
+    ///
+    /// Both the CSV format of current JabRef lists (`Title;Abbreviation`,
+    /// possibly followed by further columns and with optionally quoted
+    /// values) and the older `Title = Abbreviation` format are supported.
+    /// Empty lines, lines starting with `#`, and lines without an
+    /// abbreviation are skipped.
+    pub fn parse(src: &str) -> Self {
+        let mut list = Self::new();
+        for line in src.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns = if line.contains(';') {
+                split_csv(line)
+            } else {
+                line.splitn(2, '=').map(|s| s.trim().to_string()).collect()
+            };
+
+            if let [title, abbreviation, ..] = columns.as_slice() {
+                if !title.is_empty() && !abbreviation.is_empty() {
+                    list.insert(title, abbreviation);
+                }
+            }
+        }
+        list
+    }
+
+    /// Add a journal title and its abbreviation. Earlier pairs take
+    /// precedence in lookups.
+    pub fn insert(&mut self, title: &str, abbreviation: &str) {
+        let index = self.pairs.len();
+        self.pairs.push((title.to_string(), abbreviation.to_string()));
+        self.by_title.entry(lookup_key(title)).or_insert(index);
+        self.by_abbreviation.entry(lookup_key(abbreviation)).or_insert(index);
+    }
+
+    /// The abbreviation of a full journal title.
+    pub fn abbreviate(&self, title: &str) -> Option<&str> {
+        let &index = self.by_title.get(&lookup_key(title))?;
+        Some(&self.pairs[index].1)
+    }
+
+    /// The full journal title of an abbreviation.
+    pub fn expand(&self, abbreviation: &str) -> Option<&str> {
+        let &index = self.by_abbreviation.get(&lookup_key(abbreviation))?;
+        Some(&self.pairs[index].0)
+    }
+
+    /// The pairs of journal titles and abbreviations in order of insertion.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(title, abbr)| (title.as_str(), abbr.as_str()))
+    }
+
+    /// The number of pairs.
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+/// The List of Title Word Abbreviations (LTWA) of the ISSN International
+/// Centre, used to abbreviate journal titles following ISO 4.
+///
+/// The list itself is not bundled with the crate and has to be read with
+/// [`Ltwa::parse`] or filled with [`Ltwa::insert`]. Patterns ending in a hyphen
+/// like `physic-` match the beginning of words, patterns starting with one like
+/// `-ology` their end.
+///
+/// ```
+/// # use biblatex::Ltwa;
+/// let ltwa = Ltwa::parse(
+///     "journal;j.;eng\namerican;am.;eng\nchemical;chem.;eng\nsociet-;soc.;eng",
+/// );
+/// let title = "Journal of the American Chemical Society";
+/// assert_eq!(ltwa.abbreviate(title), "J. Am. Chem. Soc.");
+/// assert_eq!(ltwa.abbreviate("The Journal"), "Journal");
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Ltwa {
+    words: HashMap<String, Option<String>>,
+    prefixes: Vec<(String, Option<String>)>,
+    suffixes: Vec<(String, Option<String>)>,
+}
+
+/// Articles, prepositions, and conjunctions, which ISO 4 omits.
+const OMITTED_WORDS: &[&str] = &[
+    "a", "an", "and", "at", "by", "de", "del", "der", "des", "die", "du", "el", "et",
+    "for", "from", "für", "in", "la", "le", "les", "of", "on", "the", "to", "und",
+    "with", "y",
+];
+
+impl Ltwa {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the LTWA in its CSV form, with the pattern and its abbreviation
+    /// in the first two columns, separated by a semicolon or tab. The
+    /// abbreviation `n.a.` marks words that are not abbreviated. Patterns of
+    /// multiple words and malformed lines are skipped, as is a header line
+    /// starting with `WORDS`.
+    pub fn parse(src: &str) -> Self {
+        let mut ltwa = Self::new();
+        for line in src.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("WORDS") {
+                continue;
+            }
+
+            let columns = if line.contains('\t') {
+                line.split('\t').map(|s| s.trim().to_string()).collect()
+            } else {
+                split_csv(line)
+            };
+
+            if let [pattern, abbreviation, ..] = columns.as_slice() {
+                if !pattern.is_empty() && !pattern.contains(' ') {
+                    let abbreviation = (abbreviation != "n.a.").then_some(abbreviation);
+                    ltwa.insert(pattern, abbreviation.map(String::as_str));
+                }
+            }
+        }
+        ltwa
+    }
+
+    /// Add a pattern and its abbreviation, or `None` if words matching the
+    /// pattern are not abbreviated.
+    pub fn insert(&mut self, pattern: &str, abbreviation: Option<&str>) {
+        let pattern = pattern.to_lowercase();
+        let abbreviation = abbreviation.map(|a| a.trim_start_matches('-').to_lowercase());
+        if let Some(prefix) = pattern.strip_suffix('-') {
+            self.prefixes.push((prefix.to_string(), abbreviation));
+        } else if let Some(suffix) = pattern.strip_prefix('-') {
+            self.suffixes.push((suffix.to_string(), abbreviation));
+        } else {
+            self.words.insert(pattern, abbreviation);
+        }
+    }
+
+    /// Abbreviate a journal title following ISO 4.
+    ///
+    /// Articles, prepositions, and conjunctions as well as commas are
+    /// omitted, the other words are abbreviated with the longest matching
+    /// pattern, and the capitalization of the first letter of each word is
+    /// preserved. Titles consisting of a single word are kept in full.
+    pub fn abbreviate(&self, title: &str) -> String {
+        let words: Vec<&str> = title
+            .split_whitespace()
+            .filter(|word| !OMITTED_WORDS.contains(&word.to_lowercase().as_str()))
+            .collect();
+
+        if words.len() <= 1 {
+            return words.first().map_or(title, |w| w.trim_end_matches(',')).to_string();
+        }
+
+        let words: Vec<String> = words
+            .iter()
+            .map(|word| {
+                let word = word.trim_end_matches(',');
+                let end = word.trim_end_matches(|c: char| !c.is_alphanumeric()).len();
+                let (core, rest) = word.split_at(end);
+                let parts: Vec<String> =
+                    core.split('-').map(|part| self.abbreviate_word(part)).collect();
+                let mut abbreviated = parts.join("-");
+                if abbreviated.ends_with('.') && rest.starts_with('.') {
+                    abbreviated.push_str(&rest[1..]);
+                } else {
+                    abbreviated.push_str(rest);
+                }
+                abbreviated
+            })
+            .collect();
+
+        words.join(" ")
+    }
+
+    /// Create an abbreviation list with the ISO 4 abbreviations of all
+    /// journal titles in a bibliography, for use with
+    /// [`Bibliography::abbreviate_journals`].
+    pub fn abbreviations(&self, bibliography: &Bibliography) -> JournalAbbreviations {
+        let mut list = JournalAbbreviations::new();
+        for entry in bibliography.iter() {
+            for field in ["journaltitle", "journal"] {
+                if let Some(chunks) = entry.get(field) {
+                    let title = chunks.format_verbatim();
+                    if list.abbreviate(&title).is_none() {
+                        list.insert(&title, &self.abbreviate(&title));
+                    }
+                }
+            }
+        }
+        list
+    }
+
+    /// Abbreviate a single word without punctuation.
+    fn abbreviate_word(&self, word: &str) -> String {
+        let lower = word.to_lowercase();
+        let abbreviation = match self.words.get(&lower) {
+            Some(abbreviation) => abbreviation.clone(),
+            None => {
+                let longest =
+                    |patterns: &[(String, Option<String>)],
+                     matches: fn(&str, &str) -> bool| {
+                        patterns
+                            .iter()
+                            .filter(|(pattern, _)| matches(&lower, pattern))
+                            .max_by_key(|(pattern, _)| pattern.len())
+                            .cloned()
+                    };
+
+                if let Some((_, abbreviation)) =
+                    longest(&self.prefixes, |word, p| word.starts_with(p))
+                {
+                    abbreviation
+                } else if let Some((suffix, abbreviation)) =
+                    longest(&self.suffixes, |word, p| word.ends_with(p) && word != p)
+                {
+                    abbreviation
+                        .map(|a| format!("{}{}", &lower[..lower.len() - suffix.len()], a))
+                } else {
+                    None
+                }
+            }
+        };
+
+        let Some(abbreviation) = abbreviation else { return word.to_string() };
+        if abbreviation.trim_end_matches('.') == lower {
+            return word.to_string();
+        }
+
+        let mut chars = abbreviation.chars();
+        match (word.chars().next(), chars.next()) {
+            (Some(first), Some(c)) if first.is_uppercase() => {
+                c.to_uppercase().chain(chars).collect()
+            }
+            _ => abbreviation,
+        }
+    }
+}
+
+impl Bibliography {
+    /// Replace abbreviated journal titles with their full form from a list.
+    ///
+    /// Both the `journaltitle` field and its legacy name `journal` are
+    /// changed. Returns the cite keys of the changed entries.
+    pub fn expand_journals(&mut self, list: &JournalAbbreviations) -> Vec<String> {
+        self.map_journals(|title| list.expand(title))
+    }
+
+    /// Replace full journal titles with their abbreviation from a list.
+    ///
+    /// Both the `journaltitle` field and its legacy name `journal` are
+    /// changed. Returns the cite keys of the changed entries.
+    pub fn abbreviate_journals(&mut self, list: &JournalAbbreviations) -> Vec<String> {
+        self.map_journals(|title| list.abbreviate(title))
+    }
+
+    fn map_journals<'a>(
+        &mut self,
+        mut map: impl FnMut(&str) -> Option<&'a str>,
+    ) -> Vec<String> {
+        let mut changed = vec![];
+        for entry in &mut self.entries {
+            let mut any = false;
+            for field in ["journaltitle", "journal"] {
+                any |= map_field(entry, field, &mut map);
+            }
+            if any {
+                changed.push(entry.key.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Replace the value of a field if the mapping knows it and yields a
+/// different value.
+fn map_field<'a>(
+    entry: &mut Entry,
+    field: &str,
+    map: &mut impl FnMut(&str) -> Option<&'a str>,
+) -> bool {
+    let Some(chunks) = entry.get(field) else { return false };
+    let value = chunks.format_verbatim();
+    match map(&value) {
+        Some(new) if new != value => {
+            entry.set(field, vec![Spanned::detached(Chunk::Normal(new.to_string()))]);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The form of a title or abbreviation used for lookups: lowercase, without
+/// periods, and with single spaces.
+fn lookup_key(title: &str) -> String {
+    let title = title.replace('.', " ").to_lowercase();
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split a line of semicolon-separated values, some of which may be quoted.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut columns = vec![];
+    let mut column = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                column.push('"');
+            }
+            '"' => quoted = !quoted,
+            ';' if !quoted => columns.push(std::mem::take(&mut column)),
+            c => column.push(c),
+        }
+    }
+
+    columns.push(column);
+    columns.iter().map(|c| c.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_abbreviations() {
+        let list = JournalAbbreviations::parse(
+            r#"# A comment
+            Nature;Nature
+            "Journal of ""Quoted"" Studies; Series A";J. Quoted Stud. A;JQSA
+            Annals of Mathematics = Ann. of Math.
+            Annals of Mathematics;Ann. Math.
+            Incomplete
+            "#,
+        );
+        assert_eq!(list.len(), 4);
+        assert_eq!(
+            list.expand("J Quoted  Stud A"),
+            Some(r#"Journal of "Quoted" Studies; Series A"#)
+        );
+        assert_eq!(list.abbreviate("ANNALS OF MATHEMATICS"), Some("Ann. of Math."));
+        assert_eq!(list.expand("Ann. Math."), Some("Annals of Mathematics"));
+        assert_eq!(list.expand("Unknown"), None);
+
+        let src = r#"@article{a, journal = {Annals of Mathematics}}
+            @article{b, journaltitle = {Nature}}
+            @article{c, journaltitle = {Ann. Math.}, title = {Ann. Math.}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        assert_eq!(bibliography.abbreviate_journals(&list), ["a"]);
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.journal_title().unwrap().format_verbatim(), "Ann. of Math.");
+
+        assert_eq!(bibliography.expand_journals(&list), ["a", "c"]);
+        let c = bibliography.get("c").unwrap();
+        assert_eq!(c.journal_title().unwrap().format_verbatim(), "Annals of Mathematics");
+        assert_eq!(c.title().unwrap().format_verbatim(), "Ann. Math.");
+    }
+
+    #[test]
+    fn test_ltwa() {
+        let ltwa = Ltwa::parse(
+            "WORDS;ABBREVIATIONS;LANGUAGES
+            journal;j.;eng
+            physic-;phys.;eng
+            physical;phys.;eng
+            review-;rev.;eng
+            letter-;lett.;eng
+            condensed;condens.;eng
+            matter;n.a.;eng
+            -ology\t-ol.\teng
+            united states;u. s.;eng",
+        );
+
+        assert_eq!(ltwa.abbreviate("Physical Review Letters"), "Phys. Rev. Lett.");
+        assert_eq!(
+            ltwa.abbreviate("Journal of Physics: Condensed Matter"),
+            "J. Phys.: Condens. Matter"
+        );
+        assert_eq!(ltwa.abbreviate("Biology, Physics-Reviews"), "Biol. Phys.-Rev.");
+        assert_eq!(ltwa.abbreviate("Journal."), "Journal.");
+        assert_eq!(ltwa.abbreviate("physical journal"), "phys. j.");
+        assert_eq!(ltwa.abbreviate("Matter and Ology"), "Matter Ology");
+
+        let src = r#"@article{a, journal = {Physical Review Letters}}
+            @article{b, journaltitle = {Nature}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+        let list = ltwa.abbreviations(&bibliography);
+        assert_eq!(list.len(), 2);
+        assert_eq!(bibliography.abbreviate_journals(&list), ["a"]);
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.journal_title().unwrap().format_verbatim(), "Phys. Rev. Lett.");
+    }
+}
@@ -0,0 +1,514 @@
+//! Reading JSON and exporting entries as JSON.
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::io;
+use std::ops::Range;
+
+use unscanny::Scanner;
+
+use crate::{
+    Bibliography, Chunks, ChunksExt, Date, DateValue, Datetime, EditorType, Entry,
+    EntrySubtype, Gender, Pagination, PermissiveType, Person, TypedField,
+};
+
+/// How deeply arrays and objects may be nested before parsing fails, so that
+/// malicious input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// The members of an object in order.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a JSON document. Returns the byte offset of the first error.
+    ///
+    /// Arrays and objects nested deeper than [`MAX_DEPTH`] are an error.
+    pub(crate) fn parse(src: &str) -> Result<Self, usize> {
+        let mut s = Scanner::new(src);
+        let value = parse_value(&mut s, 0)?;
+        s.eat_whitespace();
+        if s.done() {
+            Ok(value)
+        } else {
+            Err(s.cursor())
+        }
+    }
+
+    /// The member of an object with the given name.
+    pub(crate) fn get(&self, name: &str) -> Option<&Json> {
+        match self {
+            Self::Object(members) => {
+                members.iter().find(|(n, _)| n == name).map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of a string or a formatted number.
+    pub(crate) fn to_text(&self) -> Option<String> {
+        match self {
+            Self::String(s) => Some(s.clone()),
+            Self::Number(_) => Some(self.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Json {
+    /// Write the value in compact form.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Number(n) if !n.is_finite() => f.write_str("null"),
+            Self::Number(n) if n.fract() == 0.0 && n.abs() < 9e15 => {
+                write!(f, "{}", *n as i64)
+            }
+            Self::Number(n) => write!(f, "{}", n),
+            Self::String(s) => write_string(f, s),
+            Self::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            Self::Object(members) => {
+                f.write_char('{')?;
+                for (i, (name, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// Write a quoted and escaped JSON string.
+fn write_string(f: &mut impl Write, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Parse a value after optional whitespace, nested in `depth` arrays and
+/// objects.
+fn parse_value(s: &mut Scanner, depth: usize) -> Result<Json, usize> {
+    s.eat_whitespace();
+    let start = s.cursor();
+    match s.peek() {
+        Some('{' | '[') if depth == MAX_DEPTH => Err(start),
+        Some('{') => {
+            s.eat();
+            let mut members = vec![];
+            s.eat_whitespace();
+            if !s.eat_if('}') {
+                loop {
+                    s.eat_whitespace();
+                    let name = parse_string(s)?;
+                    s.eat_whitespace();
+                    if !s.eat_if(':') {
+                        return Err(s.cursor());
+                    }
+                    members.push((name, parse_value(s, depth + 1)?));
+                    s.eat_whitespace();
+                    if s.eat_if('}') {
+                        break;
+                    } else if !s.eat_if(',') {
+                        return Err(s.cursor());
+                    }
+                }
+            }
+            Ok(Json::Object(members))
+        }
+        Some('[') => {
+            s.eat();
+            let mut items = vec![];
+            s.eat_whitespace();
+            if !s.eat_if(']') {
+                loop {
+                    items.push(parse_value(s, depth + 1)?);
+                    s.eat_whitespace();
+                    if s.eat_if(']') {
+                        break;
+                    } else if !s.eat_if(',') {
+                        return Err(s.cursor());
+                    }
+                }
+            }
+            Ok(Json::Array(items))
+        }
+        Some('"') => parse_string(s).map(Json::String),
+        Some(c) if c == '-' || c.is_ascii_digit() => parse_number(s),
+        _ if s.eat_if("true") => Ok(Json::Bool(true)),
+        _ if s.eat_if("false") => Ok(Json::Bool(false)),
+        _ if s.eat_if("null") => Ok(Json::Null),
+        _ => Err(start),
+    }
+}
+
+/// Parse a number. Unlike Rust's float syntax, JSON forbids leading zeros
+/// and requires digits after the decimal point and the exponent marker.
+fn parse_number(s: &mut Scanner) -> Result<Json, usize> {
+    fn digits(s: &mut Scanner) -> Result<(), usize> {
+        let start = s.cursor();
+        s.eat_while(|c: char| c.is_ascii_digit());
+        if s.cursor() > start {
+            Ok(())
+        } else {
+            Err(start)
+        }
+    }
+
+    let start = s.cursor();
+    s.eat_if('-');
+    if !s.eat_if('0') {
+        digits(s)?;
+    }
+    if s.eat_if('.') {
+        digits(s)?;
+    }
+    if s.eat_if(|c: char| matches!(c, 'e' | 'E')) {
+        s.eat_if(|c: char| matches!(c, '+' | '-'));
+        digits(s)?;
+    }
+    s.from(start).parse().map(Json::Number).map_err(|_| start)
+}
+
+/// Parse a quoted string and resolve its escapes.
+fn parse_string(s: &mut Scanner) -> Result<String, usize> {
+    if !s.eat_if('"') {
+        return Err(s.cursor());
+    }
+
+    let mut res = String::new();
+    loop {
+        let pos = s.cursor();
+        let c = match s.eat() {
+            Some('"') => return Ok(res),
+            Some('\\') => match s.eat() {
+                Some(c @ ('"' | '\\' | '/')) => c,
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('u') => {
+                    let mut code = parse_hex(s)?;
+                    if (0xD800..0xDC00).contains(&code) && s.eat_if("\\u") {
+                        let low = parse_hex(s)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err(pos);
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    char::from_u32(code).ok_or(pos)?
+                }
+                _ => return Err(pos),
+            },
+            Some(c) if !c.is_control() => c,
+            _ => return Err(pos),
+        };
+        res.push(c);
+    }
+}
+
+/// Parse the four hexadecimal digits of a `\u` escape.
+fn parse_hex(s: &mut Scanner) -> Result<u32, usize> {
+    let start = s.cursor();
+    (0..4).try_fold(0, |code, _| {
+        let digit = s.eat().and_then(|c| c.to_digit(16)).ok_or(start)?;
+        Ok(code * 16 + digit)
+    })
+}
+
+/// Conversion of a typed field value into JSON.
+///
+/// Values without a dedicated representation return `None` and are exported
+/// as their decoded text.
+pub(crate) trait ToJson {
+    fn to_json(&self) -> Option<Json> {
+        None
+    }
+}
+
+impl ToJson for Chunks {}
+impl ToJson for String {}
+impl ToJson for Pagination {}
+impl ToJson for EditorType {}
+impl ToJson for EntrySubtype {}
+impl ToJson for Gender {}
+impl ToJson for PermissiveType<Vec<Range<u32>>> {}
+
+impl ToJson for i64 {
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::Number(*self as f64))
+    }
+}
+
+impl ToJson for PermissiveType<i64> {
+    fn to_json(&self) -> Option<Json> {
+        match self {
+            Self::Typed(n) => n.to_json(),
+            Self::Chunks(_) => None,
+        }
+    }
+}
+
+impl ToJson for Vec<String> {
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::Array(self.iter().cloned().map(Json::String).collect()))
+    }
+}
+
+impl ToJson for Vec<Chunks> {
+    fn to_json(&self) -> Option<Json> {
+        Some(Json::Array(
+            self.iter()
+                .map(|chunks| Json::String(chunks.format_verbatim()))
+                .collect(),
+        ))
+    }
+}
+
+impl ToJson for Vec<Person> {
+    /// Names become objects with their non-empty parts.
+    fn to_json(&self) -> Option<Json> {
+        let person = |person: &Person| {
+            let parts = [
+                ("family", &person.name),
+                ("given", &person.given_name),
+                ("prefix", &person.prefix),
+                ("suffix", &person.suffix),
+            ];
+            Json::Object(
+                parts
+                    .into_iter()
+                    .filter(|(_, part)| !part.is_empty())
+                    .map(|(name, part)| (name.to_string(), Json::String(part.clone())))
+                    .collect(),
+            )
+        };
+        Some(Json::Array(self.iter().map(person).collect()))
+    }
+}
+
+impl ToJson for PermissiveType<Date> {
+    /// Dates become objects with a one-based `year`, `month`, and `day`.
+    /// Ranges have a `start` and an `end` which are `null` when open.
+    fn to_json(&self) -> Option<Json> {
+        let Self::Typed(date) = self else { return None };
+        let datetime = |datetime: &Datetime| {
+            let mut members = vec![("year".into(), Json::Number(datetime.year.into()))];
+            let parts = [
+                ("month", datetime.month.map(|m| m + 1)),
+                ("day", datetime.day.map(|d| d + 1)),
+                ("hour", datetime.time.map(|t| t.hour)),
+                ("minute", datetime.time.map(|t| t.minute)),
+                ("second", datetime.time.map(|t| t.second)),
+            ];
+            for (name, part) in parts {
+                if let Some(part) = part {
+                    members.push((name.into(), Json::Number(part.into())));
+                }
+            }
+            members
+        };
+        let range = |start: Option<&Datetime>, end: Option<&Datetime>| {
+            vec![
+                ("start".into(), start.map_or(Json::Null, |d| Json::Object(datetime(d)))),
+                ("end".into(), end.map_or(Json::Null, |d| Json::Object(datetime(d)))),
+            ]
+        };
+
+        let mut members = match &date.value {
+            DateValue::At(at) => datetime(at),
+            DateValue::After(start) => range(Some(start), None),
+            DateValue::Before(end) => range(None, Some(end)),
+            DateValue::Between(start, end) => range(Some(start), Some(end)),
+        };
+        if date.uncertain {
+            members.push(("uncertain".into(), Json::Bool(true)));
+        }
+        if date.approximate {
+            members.push(("approximate".into(), Json::Bool(true)));
+        }
+        Some(Json::Object(members))
+    }
+}
+
+impl Entry {
+    /// Convert the entry into a JSON object with its `key`, its `type`, and
+    /// its fields in alphabetical order.
+    ///
+    /// Field values are decoded into text. Name lists become arrays of
+    /// objects with `family`, `given`, `prefix`, and `suffix` names, dates
+    /// become objects with one-based `year`, `month`, and `day` members, and
+    /// numbers and lists are exported as such where they can be parsed.
+    ///
+    /// ```
+    /// # use biblatex::Bibliography;
+    /// let src = "@article{doe, author = {Doe, Jane}, date = {2021-03}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    /// assert_eq!(
+    ///     bibliography.get("doe").unwrap().to_json_string(),
+    ///     r#"{"key":"doe","type":"article","author":[{"family":"Doe","given":"Jane"}],"date":{"year":2021,"month":3}}"#,
+    /// );
+    /// ```
+    pub fn to_json_string(&self) -> String {
+        self.to_json().to_string()
+    }
+
+    fn to_json(&self) -> Json {
+        let mut members = vec![
+            ("key".to_string(), Json::String(self.key.clone())),
+            ("type".to_string(), Json::String(self.entry_type.to_string())),
+        ];
+
+        for (name, chunks) in &self.fields {
+            let value = TypedField::try_from((name.as_str(), chunks.as_slice()))
+                .ok()
+                .and_then(|field| field.to_json())
+                .unwrap_or_else(|| Json::String(chunks.format_verbatim()));
+            members.push((name.to_string(), value));
+        }
+
+        Json::Object(members)
+    }
+}
+
+impl Bibliography {
+    /// Write the entries as [JSON Lines](https://jsonlines.org/), with one
+    /// JSON object per entry as produced by [`Entry::to_json_string`].
+    ///
+    /// The output can be processed line by line, for example by `jq` or
+    /// pandas' `read_json(..., lines=True)`.
+    pub fn write_jsonl(&self, mut sink: impl io::Write) -> io::Result<()> {
+        for entry in self.iter() {
+            writeln!(sink, "{}", entry.to_json())?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the entries into a [JSON Lines](https://jsonlines.org/)
+    /// string. See [`Bibliography::write_jsonl`].
+    pub fn to_jsonl_string(&self) -> String {
+        let mut buf = vec![];
+        self.write_jsonl(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl() {
+        let src = r#"@book{knuth,
+            author = {Knuth, Donald E. and van der Berg, Jr., Jan},
+            title = {The {\TeX}book: "Tab"},
+            date = {1984-05-02/1986~},
+            urldate = {2020-01-01T10:30:00},
+            publisher = {Addison-Wesley and Pearson},
+            volume = {A},
+            edition = {2},
+            pages = {1--10},
+        }
+        @misc{b, year = {2000?}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let jsonl = bibliography.to_jsonl_string();
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(jsonl.ends_with("}\n"));
+
+        assert_eq!(
+            lines[0],
+            concat!(
+                r#"{"key":"knuth","type":"book","author":[{"family":"Knuth","given":"Donald E."},"#,
+                r#"{"family":"Berg","given":"Jan","prefix":"van der","suffix":"Jr."}],"#,
+                r#""date":{"start":{"year":1984,"month":5,"day":2},"end":{"year":1986},"approximate":true},"#,
+                r#""edition":2,"pages":"1–10","publisher":["Addison-Wesley","Pearson"],"#,
+                r#""title":"The TeXbook: \"Tab\"","#,
+                r#""urldate":{"year":2020,"month":1,"day":1,"hour":10,"minute":30,"second":0},"#,
+                r#""volume":"A"}"#,
+            )
+        );
+        assert_eq!(lines[1], r#"{"key":"b","type":"misc","year":"2000?"}"#);
+
+        let json = Json::Array(vec![
+            Json::Null,
+            Json::Number(1.5),
+            Json::Number(-3.0),
+            Json::String("a\u{1}\n".into()),
+        ]);
+        assert_eq!(json.to_string(), r#"[null,1.5,-3,"a\u0001\n"]"#);
+        assert_eq!(Json::parse(&json.to_string()), Ok(json));
+
+        let src =
+            r#" {"a": [true, false, {}], "b": "\u00e9\ud83d\ude00\/", "c": -1.5e2} "#;
+        let json = Json::parse(src).unwrap();
+        assert_eq!(json.get("b"), Some(&Json::String("é😀/".into())));
+        assert_eq!(json.get("c").and_then(Json::to_text).as_deref(), Some("-150"));
+        assert_eq!(Json::parse(r#"{"a": [1,]}"#), Err(9));
+        assert_eq!(Json::parse(r#"["\ud800"]"#), Err(2));
+        assert_eq!(Json::parse("[1] x"), Err(4));
+
+        for (src, offset) in [
+            ("-", 1),
+            ("1e", 2),
+            ("1e+", 3),
+            ("1.", 2),
+            ("-.5", 1),
+            (".5", 0),
+            ("+1", 0),
+            ("01", 1),
+            ("[01]", 2),
+            ("[1,]", 3),
+            (r#"{"a": 1,}"#, 8),
+            ("[1 2]", 3),
+            ("tru", 0),
+            (r#""\udc00""#, 1),
+            (r#""\ud800\u0041""#, 1),
+            (r#""\ud800x""#, 1),
+            (r#""\u12""#, 3),
+            (r#""\x""#, 1),
+            ("\"a\tb\"", 2),
+            ("\"open", 5),
+            ("", 0),
+        ] {
+            assert_eq!(Json::parse(src), Err(offset), "{src}");
+        }
+        assert_eq!(Json::parse("-0.5E+2"), Ok(Json::Number(-50.0)));
+
+        let nested = |n| format!("{}{}", "[".repeat(n), "]".repeat(n));
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(Json::parse(&nested(MAX_DEPTH + 1)), Err(MAX_DEPTH));
+        assert_eq!(Json::parse(&nested(100_000)), Err(MAX_DEPTH));
+    }
+}
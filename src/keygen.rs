@@ -0,0 +1,721 @@
+//! Generation of cite keys from patterns.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{Bibliography, ChunksExt, Entry, LabelSuffix, PermissiveType, Person};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A pattern for generating cite keys from the fields of an entry, in the
+/// style of JabRef's key patterns.
+///
+/// Text in square brackets is replaced with a value from the entry, the rest
+/// is copied literally. The following markers are supported:
+///
+/// - `[auth]`: The last name of the first author (or editor).
+/// - `[authN]`: The first `N` letters of the last name of the first author.
+/// - `[authors]`: The last names of all authors.
+/// - `[authorsN]`: The last names of the first `N` authors, followed by
+///   `EtAl` if there are more.
+/// - `[authEtAl]`: The last name of the first author, followed by the last
+///   name of the second author joined with `And` if there are two, or by
+///   `EtAl` if there are more.
+/// - `[authorLast]`: The last name of the last author.
+/// - `[edtr]`: The last name of the first editor.
+/// - `[year]` and `[shortyear]`: The year with four or two digits.
+/// - `[title]`: All words of the title, capitalized.
+/// - `[shorttitle]`: The first three words of the title that are not
+///   function words like "the", capitalized.
+/// - `[veryshorttitle]`: The first word of the title that is not a function
+///   word.
+/// - `[firstpage]` and `[lastpage]`: The first and last page.
+/// - Any other name is replaced with the value of the field of that name.
+///
+/// Markers can be followed by modifiers: `[auth:lower]`, `[title:upper]`,
+/// and `[title:abbr]`, which keeps the first letter of each word.
+/// Characters that are not allowed in cite keys are removed and accented
+/// letters are replaced with their base letters.
+///
+/// ```
+/// # use biblatex::{Bibliography, KeyPattern};
+/// let src = "@article{x, author = {Müller, Jörg and Roe, Richard},
+///            title = {The Art of Testing}, year = {2020}}";
+/// let entry = Bibliography::parse(src).unwrap().into_iter().next().unwrap();
+///
+/// let pattern = KeyPattern::parse("[auth:lower][year][shorttitle]").unwrap();
+/// assert_eq!(pattern.generate(&entry), "muller2020ArtTesting");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyPattern {
+    parts: Vec<PatternPart>,
+}
+
+/// A piece of a [`KeyPattern`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum PatternPart {
+    /// Text that is copied as-is.
+    Literal(String),
+    /// A marker with its modifiers.
+    Marker(String, Vec<Modifier>),
+}
+
+/// A transformation of a marker value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum Modifier {
+    Lower,
+    Upper,
+    Abbr,
+}
+
+/// An error that occurs when parsing a [`KeyPattern`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KeyPatternError {
+    /// A marker opened at this byte offset is not closed.
+    Unclosed(usize),
+    /// The marker uses an unknown modifier.
+    UnknownModifier(String),
+}
+
+impl Display for KeyPatternError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Unclosed(offset) => write!(f, "unclosed marker at {}", offset),
+            Self::UnknownModifier(modifier) => {
+                write!(f, "unknown modifier {:?}", modifier)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyPatternError {}
+
+/// Words that are skipped in short titles.
+const FUNCTION_WORDS: [&str; 20] = [
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "nor", "of",
+    "on", "or", "the", "to", "up", "with", "without",
+];
+
+impl KeyPattern {
+    /// Parse a pattern like `[auth][year]`.
+    pub fn parse(src: &str) -> Result<Self, KeyPatternError> {
+        let mut parts = vec![];
+        let mut rest = src;
+
+        while !rest.is_empty() {
+            let offset = src.len() - rest.len();
+            match rest.strip_prefix('[') {
+                Some(marker) => {
+                    let end =
+                        marker.find(']').ok_or(KeyPatternError::Unclosed(offset))?;
+                    let mut pieces = marker[..end].split(':');
+                    let name = pieces.next().unwrap_or_default().to_string();
+                    let modifiers = pieces
+                        .map(|modifier| match modifier {
+                            "lower" => Ok(Modifier::Lower),
+                            "upper" => Ok(Modifier::Upper),
+                            "abbr" => Ok(Modifier::Abbr),
+                            _ => Err(KeyPatternError::UnknownModifier(modifier.into())),
+                        })
+                        .collect::<Result<_, _>>()?;
+                    parts.push(PatternPart::Marker(name, modifiers));
+                    rest = &marker[end + 1..];
+                }
+                None => {
+                    let end = rest.find('[').unwrap_or(rest.len());
+                    parts.push(PatternPart::Literal(rest[..end].to_string()));
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Generate a cite key for an entry. Markers whose value is missing are
+    /// replaced with nothing, so the key may be empty.
+    pub fn generate(&self, entry: &Entry) -> String {
+        let mut key = String::new();
+        for part in &self.parts {
+            match part {
+                PatternPart::Literal(text) => key.push_str(text),
+                PatternPart::Marker(name, modifiers) => {
+                    let mut value = marker_value(entry, name).unwrap_or_default();
+                    for modifier in modifiers {
+                        value = match modifier {
+                            Modifier::Lower => value.to_lowercase(),
+                            Modifier::Upper => value.to_uppercase(),
+                            Modifier::Abbr => abbreviate(&value),
+                        };
+                    }
+                    key.push_str(&value);
+                }
+            }
+        }
+
+        sanitize(&key)
+    }
+}
+
+impl Bibliography {
+    /// Replace the cite keys of the selected entries with keys generated from
+    /// a pattern. References to the entries are updated.
+    ///
+    /// Generated keys that collide with each other or with the cite key or
+    /// alias of an unselected entry receive alphabetic suffixes in
+    /// bibliography order, e.g. `smith2020a` and `smith2020b`. Entries for
+    /// which the pattern produces an empty key keep their key. Returns the old
+    /// and new cite keys of the changed entries.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, KeyPattern};
+    /// let src = "@article{x, author = {Smith, Ann}, year = {2020}}
+    ///            @article{y, author = {Smith, Bob}, year = {2020}}";
+    /// let mut bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+    /// let changes = bibliography.generate_keys(&pattern, |_| true);
+    /// assert_eq!(changes[0], ("x".to_string(), "smith2020a".to_string()));
+    /// assert_eq!(changes[1], ("y".to_string(), "smith2020b".to_string()));
+    /// ```
+    pub fn generate_keys(
+        &mut self,
+        pattern: &KeyPattern,
+        mut select: impl FnMut(&Entry) -> bool,
+    ) -> Vec<(String, String)> {
+        let generated: Vec<(String, String)> = self
+            .entries
+            .iter()
+            .filter(|entry| select(entry))
+            .map(|entry| (entry.key.clone(), pattern.generate(entry)))
+            .filter(|(_, base)| !base.is_empty())
+            .collect();
+
+        let old_keys: HashSet<&str> =
+            generated.iter().map(|(old, _)| old.as_str()).collect();
+        let is_taken = |key: &str| self.is_taken(key) && !old_keys.contains(key);
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, base) in &generated {
+            *counts.entry(base.as_str()).or_default() += 1;
+        }
+
+        let mut assigned: Vec<String> = vec![];
+        let mut assigned_set: HashSet<String> = HashSet::new();
+        for (_, base) in &generated {
+            let key = if counts[base.as_str()] == 1 && !is_taken(base) {
+                base.clone()
+            } else {
+                suffixed_key(base, |key| is_taken(key) || assigned_set.contains(key))
+            };
+            assigned_set.insert(key.clone());
+            assigned.push(key);
+        }
+
+        // Rename in two steps so that entries can swap their keys.
+        let changes: Vec<(String, String)> = generated
+            .into_iter()
+            .zip(assigned)
+            .map(|((old, _), new)| (old, new))
+            .filter(|(old, new)| old != new)
+            .collect();
+
+        for (i, (old, _)) in changes.iter().enumerate() {
+            self.rename_key(old, &format!("{old}\u{0}{i}"));
+        }
+        for (i, (old, new)) in changes.iter().enumerate() {
+            self.rename_key(&format!("{old}\u{0}{i}"), new);
+        }
+
+        changes
+    }
+}
+
+/// Append the first alphabetic suffix to a cite key that yields a free key,
+/// e.g. `smith2020a`, like biber disambiguates labels.
+pub(crate) fn suffixed_key(key: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    (0..)
+        .map(|i| format!("{key}{}", LabelSuffix::Lowercase.format(i)))
+        .find(|new| !is_taken(new))
+        .unwrap()
+}
+
+/// The value of a marker for an entry, if present.
+fn marker_value(entry: &Entry, name: &str) -> Option<String> {
+    let authors = || {
+        entry
+            .author()
+            .ok()
+            .filter(|authors| !authors.is_empty())
+            .or_else(|| editors(entry))
+    };
+    let last_names =
+        |persons: &[Person]| persons.iter().map(|p| compact(&p.name)).collect::<String>();
+
+    let value = match name {
+        "auth" => compact(&authors()?.first()?.name),
+        "authors" => last_names(&authors()?),
+        "authEtAl" | "auth.etal" => {
+            let authors = authors()?;
+            let first = compact(&authors.first()?.name);
+            let separator = if name == "authEtAl" { "" } else { "." };
+            match authors.len() {
+                1 => first,
+                2 => format!(
+                    "{first}{separator}And{separator}{}",
+                    compact(&authors[1].name)
+                ),
+                _ => format!("{first}{separator}EtAl"),
+            }
+        }
+        "authorLast" => compact(&authors()?.last()?.name),
+        "edtr" => compact(&editors(entry)?.first()?.name),
+        "year" => entry.sorting_year().ok()?.to_string(),
+        "shortyear" => format!("{:02}", entry.sorting_year().ok()?.rem_euclid(100)),
+        "title" => capitalize(&words(&entry.title().ok()?.format_verbatim())),
+        "shorttitle" | "veryshorttitle" => {
+            let title = entry.title().ok()?.format_verbatim();
+            let n = if name == "shorttitle" { 3 } else { 1 };
+            let words: Vec<&str> = words(&title)
+                .into_iter()
+                .filter(|word| !FUNCTION_WORDS.contains(&word.to_lowercase().as_str()))
+                .take(n)
+                .collect();
+            capitalize(&words)
+        }
+        "firstpage" | "lastpage" => match entry.pages().ok()? {
+            PermissiveType::Typed(ranges) if name == "firstpage" => {
+                ranges.first()?.start.to_string()
+            }
+            PermissiveType::Typed(ranges) => ranges.last()?.end.to_string(),
+            PermissiveType::Chunks(chunks) => chunks.format_verbatim(),
+        },
+        _ => {
+            if let Some(n) = name.strip_prefix("authors").and_then(|n| n.parse().ok()) {
+                let authors = authors()?;
+                let mut value = last_names(&authors[..authors.len().min(n)]);
+                if authors.len() > n {
+                    value.push_str("EtAl");
+                }
+                value
+            } else if let Some(n) = name.strip_prefix("auth").and_then(|n| n.parse().ok())
+            {
+                compact(&authors()?.first()?.name).chars().take(n).collect()
+            } else {
+                entry.get(&name.to_lowercase())?.format_verbatim()
+            }
+        }
+    };
+
+    Some(value)
+}
+
+/// The editors of an entry, if any.
+fn editors(entry: &Entry) -> Option<Vec<Person>> {
+    entry
+        .get_as::<Vec<Person>>("editor")
+        .ok()
+        .filter(|editors| !editors.is_empty())
+}
+
+/// The alphanumeric words of a text.
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// The first letter of each word, where words are separated by punctuation,
+/// whitespace, or a change from lowercase to uppercase like in `CamelCase`.
+fn abbreviate(text: &str) -> String {
+    let mut abbreviation = String::new();
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        let starts_word = c.is_alphanumeric()
+            && match previous {
+                None => true,
+                Some(p) => !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase()),
+            };
+        if starts_word {
+            abbreviation.push(c);
+        }
+        previous = Some(c);
+    }
+    abbreviation
+}
+
+/// Concatenate words with their first letter in uppercase.
+fn capitalize(words: &[&str]) -> String {
+    words
+        .iter()
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().into_iter().flat_map(char::to_uppercase);
+            first.chain(chars)
+        })
+        .collect()
+}
+
+/// Remove the whitespace and punctuation from a name.
+fn compact(name: &str) -> String {
+    words(name).concat()
+}
+
+/// Replace accented letters with their base letters and remove characters
+/// that are not allowed in cite keys.
+pub(crate) fn sanitize(key: &str) -> String {
+    transliterate(key)
+        .chars()
+        .filter(|c| {
+            c.is_ascii_graphic()
+                && !matches!(
+                    c,
+                    '{' | '}'
+                        | '('
+                        | ')'
+                        | ','
+                        | '"'
+                        | '#'
+                        | '%'
+                        | '~'
+                        | '\''
+                        | '='
+                        | '\\'
+                )
+        })
+        .collect()
+}
+
+/// Replace letters with their closest ASCII counterparts where possible,
+/// e.g. `é` with `e` and `ß` with `ss`. Other characters are kept.
+pub(crate) fn transliterate(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.nfd() {
+        let replacement = match c {
+            'ß' => "ss",
+            'æ' => "ae",
+            'Æ' => "AE",
+            'œ' => "oe",
+            'Œ' => "OE",
+            'ø' => "o",
+            'Ø' => "O",
+            'ł' => "l",
+            'Ł' => "L",
+            'đ' | 'ð' => "d",
+            'Đ' | 'Ð' => "D",
+            'þ' => "th",
+            'Þ' => "Th",
+            'ı' => "i",
+            // Combining marks left over from the decomposition.
+            '\u{300}'..='\u{36f}' => "",
+            _ => {
+                ascii.push(c);
+                continue;
+            }
+        };
+        ascii.push_str(replacement);
+    }
+    ascii
+}
+
+/// Rules for valid cite keys: allowed characters and a maximum length.
+///
+/// By default, keys may consist of ASCII letters, digits, and the characters
+/// `-_:./+` and have any length.
+///
+/// ```
+/// # use biblatex::{KeyPolicy, KeyViolation};
+/// let mut policy = KeyPolicy::default();
+/// policy.set_max_length(Some(10));
+/// assert_eq!(policy.check("smith2020"), []);
+/// assert_eq!(policy.check("Ölund 2020"), [
+///     KeyViolation::InvalidChar('Ö'),
+///     KeyViolation::InvalidChar(' '),
+/// ]);
+/// assert_eq!(policy.normalize("Ölund & Brække, 2020"), "OlundBraek");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyPolicy {
+    punctuation: String,
+    allow_non_ascii: bool,
+    max_length: Option<usize>,
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        Self {
+            punctuation: "-_:./+".into(),
+            allow_non_ascii: false,
+            max_length: None,
+        }
+    }
+}
+
+/// A way in which a cite key breaks a [`KeyPolicy`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KeyViolation {
+    /// The key is empty.
+    Empty,
+    /// The key contains a character that is not allowed.
+    InvalidChar(char),
+    /// The key has this many characters, more than allowed.
+    TooLong(usize),
+}
+
+impl Display for KeyViolation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty cite key"),
+            Self::InvalidChar(c) => write!(f, "invalid character {:?} in cite key", c),
+            Self::TooLong(length) => write!(f, "cite key has {} characters", length),
+        }
+    }
+}
+
+impl KeyPolicy {
+    /// Set the punctuation characters that are allowed besides ASCII letters
+    /// and digits.
+    pub fn set_punctuation(&mut self, punctuation: &str) {
+        self.punctuation = punctuation.to_string();
+    }
+
+    /// Set whether non-ASCII letters and digits like `é` are allowed.
+    /// Disabled by default.
+    pub fn set_allow_non_ascii(&mut self, allow: bool) {
+        self.allow_non_ascii = allow;
+    }
+
+    /// Set the maximum number of characters, or `None` for no limit.
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+    }
+
+    /// Whether a character is allowed in cite keys.
+    pub fn allows(&self, c: char) -> bool {
+        c.is_ascii_alphanumeric()
+            || self.punctuation.contains(c)
+            || (self.allow_non_ascii && !c.is_ascii() && c.is_alphanumeric())
+    }
+
+    /// All ways in which a cite key breaks the policy. Each invalid character
+    /// is reported once.
+    pub fn check(&self, key: &str) -> Vec<KeyViolation> {
+        let mut violations = vec![];
+        if key.is_empty() {
+            violations.push(KeyViolation::Empty);
+        }
+
+        for c in key.chars().filter(|&c| !self.allows(c)) {
+            let violation = KeyViolation::InvalidChar(c);
+            if !violations.contains(&violation) {
+                violations.push(violation);
+            }
+        }
+
+        let length = key.chars().count();
+        if self.max_length.is_some_and(|max| length > max) {
+            violations.push(KeyViolation::TooLong(length));
+        }
+
+        violations
+    }
+
+    /// Turn a cite key into one that follows the policy, except that it may
+    /// be empty: Letters are transliterated to ASCII unless non-ASCII letters
+    /// are allowed, other disallowed characters are removed, and the key is
+    /// truncated to the maximum length.
+    pub fn normalize(&self, key: &str) -> String {
+        let key = if self.allow_non_ascii { key.to_string() } else { transliterate(key) };
+        let chars = key.chars().filter(|&c| self.allows(c));
+        chars.take(self.max_length.unwrap_or(usize::MAX)).collect()
+    }
+}
+
+impl Bibliography {
+    /// Check the cite keys of all entries and `@xdata` containers against a
+    /// policy. Returns the violating keys with their violations.
+    pub fn check_keys(&self, policy: &KeyPolicy) -> Vec<(String, KeyViolation)> {
+        self.entries
+            .iter()
+            .chain(self.xdata.values())
+            .flat_map(|entry| {
+                let violations = policy.check(&entry.key);
+                violations.into_iter().map(|violation| (entry.key.clone(), violation))
+            })
+            .collect()
+    }
+
+    /// Replace the cite keys of the entries that break a policy with
+    /// normalized ones, see [`KeyPolicy::normalize`]. References to the
+    /// entries are updated.
+    ///
+    /// Normalized keys that collide with an existing key receive alphabetic
+    /// suffixes like those of [`Bibliography::generate_keys`], which may
+    /// exceed the maximum length. Entries whose normalized key would be empty
+    /// keep their key. Returns the old and new cite keys of the changed
+    /// entries.
+    pub fn normalize_keys(&mut self, policy: &KeyPolicy) -> Vec<(String, String)> {
+        let violating: Vec<String> = self
+            .entries
+            .iter()
+            .chain(self.xdata.values())
+            .map(|entry| entry.key.clone())
+            .filter(|key| !policy.check(key).is_empty())
+            .collect();
+
+        let mut changes = vec![];
+        for key in violating {
+            let normalized = policy.normalize(&key);
+            if normalized.is_empty() {
+                continue;
+            }
+
+            let new = if self.is_taken(&normalized) {
+                suffixed_key(&normalized, |key| self.is_taken(key))
+            } else {
+                normalized
+            };
+
+            self.rename_key(&key, &new);
+            changes.push((key, new));
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bibliography;
+
+    #[test]
+    fn test_key_patterns() {
+        let src = r#"@article{x, author = {Doe, Jane and van Roe, Richard and Poe, Al},
+              title = {On the Origin of {Species}}, date = {1859-11-24},
+              pages = {12--34}, journal = {Nature}}
+            @book{y, editor = {O'Brien, Pat}, title = {Café Society}, year = {2001}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let x = bibliography.get("x").unwrap();
+        let y = bibliography.get("y").unwrap();
+
+        let generate =
+            |pattern: &str, entry| KeyPattern::parse(pattern).unwrap().generate(entry);
+        assert_eq!(generate("[auth][year]", x), "Doe1859");
+        assert_eq!(generate("[auth3]:[shortyear]", x), "Doe:59");
+        assert_eq!(generate("[authors]", x), "DoeRoePoe");
+        assert_eq!(generate("[authors2]", x), "DoeRoeEtAl");
+        assert_eq!(generate("[authEtAl]-[auth.etal]", x), "DoeEtAl-Doe.EtAl");
+        assert_eq!(generate("[authorLast:upper]", x), "POE");
+        assert_eq!(generate("[shorttitle]", x), "OriginSpecies");
+        assert_eq!(generate("[veryshorttitle:lower]", x), "origin");
+        assert_eq!(generate("[title:abbr]", x), "OTOOS");
+        assert_eq!(generate("[firstpage]-[lastpage]", x), "12-34");
+        assert_eq!(generate("[journal]", x), "Nature");
+        assert_eq!(generate("[auth][edtr][title]", y), "OBrienOBrienCafeSociety");
+        assert_eq!(generate("[note]x", y), "x");
+
+        assert_eq!(KeyPattern::parse("[auth"), Err(KeyPatternError::Unclosed(0)));
+        assert_eq!(
+            KeyPattern::parse("[auth:shout]"),
+            Err(KeyPatternError::UnknownModifier("shout".into()))
+        );
+    }
+
+    #[test]
+    fn test_generate_keys() {
+        let src = r#"@article{doe2001, author = {Roe, Rita}, year = {2001}}
+            @article{roe2001, author = {Doe, Jane}, year = {2001}, related = {doe2001}}
+            @article{x, author = {Poe, Al}, year = {2001}}
+            @article{y, author = {Poe, Al}, year = {2001}}
+            @article{z, author = {Moe, Max}, year = {2001}}
+            @article{w, ids = {moe2001}}
+            @article{keep, title = {Anonymous}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+        let changes = bibliography.generate_keys(&pattern, |entry| entry.key != "w");
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("doe2001", "roe2001"),
+                ("roe2001", "doe2001"),
+                ("x", "poe2001a"),
+                ("y", "poe2001b"),
+                ("z", "moe2001a"),
+            ]
+        );
+
+        let keys: Vec<_> = bibliography.keys().collect();
+        assert_eq!(
+            keys,
+            ["roe2001", "doe2001", "poe2001a", "poe2001b", "moe2001a", "w", "keep"]
+        );
+        let jane = bibliography.get("doe2001").unwrap();
+        assert_eq!(jane.related().unwrap(), ["roe2001"]);
+
+        let pattern = KeyPattern::parse("[auth:lower]").unwrap();
+        bibliography.generate_keys(&pattern, |entry| entry.key.starts_with("poe"));
+        assert_eq!(bibliography.get("poeb").unwrap().key, "poeb");
+        assert_eq!(suffixed_key("smith2020", |key| key == "smith2020a"), "smith2020b");
+    }
+
+    #[test]
+    fn test_key_policy() {
+        let src = r#"@article{Müller:2001, author = {Müller, Jörg}}
+            @article{Muller:2001, author = {Muller, Joe}, related = {Müller:2001}}
+            @article{a?b, title = {Question}}
+            @article{ok, title = {Fine}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        let mut policy = KeyPolicy::default();
+        let violations = bibliography.check_keys(&policy);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(
+            violations[0],
+            ("Müller:2001".to_string(), KeyViolation::InvalidChar('ü'))
+        );
+
+        policy.set_punctuation("");
+        policy.set_max_length(Some(8));
+        let violations = bibliography.check_keys(&policy);
+        assert_eq!(violations.len(), 6);
+        assert_eq!(violations[2], ("Müller:2001".to_string(), KeyViolation::TooLong(11)));
+
+        let changes = bibliography.normalize_keys(&policy);
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [("Müller:2001", "Muller20"), ("Muller:2001", "Muller20a"), ("a?b", "ab")]
+        );
+        assert_eq!(
+            bibliography.get("Muller20a").unwrap().related().unwrap(),
+            ["Muller20"]
+        );
+        assert_eq!(
+            bibliography.check_keys(&policy),
+            [("Muller20a".to_string(), KeyViolation::TooLong(9))]
+        );
+
+        policy.set_allow_non_ascii(true);
+        assert!(policy.check("Müller").is_empty());
+        assert_eq!(policy.normalize("Straße 1"), "Straße1");
+        assert_eq!(KeyPolicy::default().check(""), [KeyViolation::Empty]);
+    }
+}
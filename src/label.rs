@@ -0,0 +1,347 @@
+//! Generation of citation labels.
+
+use std::collections::HashMap;
+
+use crate::{Bibliography, ChunksExt, Entry};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How labels that would otherwise be equal are told apart, like the
+/// `extraalpha` of BibLaTeX.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LabelSuffix {
+    /// Append `a`, `b`, …, `z`, `aa`, `ab`, and so on.
+    Lowercase,
+    /// Append `A`, `B`, …, `Z`, `AA`, `AB`, and so on.
+    Uppercase,
+    /// Append `-1`, `-2`, and so on. The hyphen keeps the number apart
+    /// from the year, which also consists of digits.
+    Number,
+}
+
+impl LabelSuffix {
+    /// The suffix for the zero-based position among equal labels.
+    pub(crate) fn format(self, index: usize) -> String {
+        let letters = |base: u8| {
+            let mut n = index + 1;
+            let mut suffix = vec![];
+            while n > 0 {
+                n -= 1;
+                suffix.push((base + (n % 26) as u8) as char);
+                n /= 26;
+            }
+            suffix.into_iter().rev().collect()
+        };
+
+        match self {
+            Self::Lowercase => letters(b'a'),
+            Self::Uppercase => letters(b'A'),
+            Self::Number => format!("-{}", index + 1),
+        }
+    }
+}
+
+/// Generates alphabetic labels like `Knu84` or `Gam+95`, following the
+/// default `labelalpha` template of BibLaTeX.
+///
+/// The `shorthand` is used as-is if present. Otherwise, the label consists of
+/// the `label` field or a part derived from the label name, followed by the
+/// last two digits of the year. A single name contributes its first three
+/// letters, multiple names their initials. Lists longer than the maximum are
+/// truncated to the minimum and marked with a `+`.
+///
+/// ```
+/// # use biblatex::{AlphaLabeler, Bibliography};
+/// let src = "@book{a, author = {Knuth, Donald}, year = {1984}}
+///            @book{b, author = {Knuth, Donald}, year = {1984}}
+///            @book{c, author = {A and B and C and D}, year = {2020}}";
+/// let bibliography = Bibliography::parse(src).unwrap();
+///
+/// let labeler = AlphaLabeler::default();
+/// let labels = labeler.labels(bibliography.iter());
+/// assert_eq!(labels[0], ("a", "Knu84a".to_string()));
+/// assert_eq!(labels[1], ("b", "Knu84b".to_string()));
+/// assert_eq!(labels[2], ("c", "A+20".to_string()));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AlphaLabeler {
+    max_names: usize,
+    min_names: usize,
+    suffix: LabelSuffix,
+}
+
+impl Default for AlphaLabeler {
+    fn default() -> Self {
+        Self {
+            max_names: 3,
+            min_names: 1,
+            suffix: LabelSuffix::Lowercase,
+        }
+    }
+}
+
+impl AlphaLabeler {
+    /// Set the maximum number of names before the list is truncated, like
+    /// the `maxalphanames` option. Defaults to 3.
+    pub fn set_max_names(&mut self, max_names: usize) {
+        self.max_names = max_names.max(1);
+    }
+
+    /// Set the number of names a truncated list is shortened to, like the
+    /// `minalphanames` option. Defaults to 1.
+    pub fn set_min_names(&mut self, min_names: usize) {
+        self.min_names = min_names.max(1);
+    }
+
+    /// Set how equal labels are told apart. Defaults to
+    /// [`LabelSuffix::Lowercase`].
+    pub fn set_suffix(&mut self, suffix: LabelSuffix) {
+        self.suffix = suffix;
+    }
+
+    /// The label of a single entry without disambiguation.
+    pub fn label(&self, entry: &Entry) -> String {
+        if let Ok(shorthand) = entry.shorthand() {
+            return shorthand.format_verbatim();
+        }
+
+        let mut label = String::new();
+        if let Ok(field) = entry.label() {
+            label = field.format_verbatim();
+        } else if let Ok(names) = entry.label_name() {
+            let letters = |name: &str, n| {
+                name.chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .take(n)
+                    .collect::<String>()
+            };
+
+            let truncated = names.len() > self.max_names;
+            let shown =
+                if truncated { self.min_names.min(names.len()) } else { names.len() };
+            label = match shown {
+                0 => String::new(),
+                1 => letters(&names[0].name, 3),
+                n => names[..n].iter().map(|person| letters(&person.name, 1)).collect(),
+            };
+
+            if truncated {
+                label.push('+');
+            }
+        }
+
+        if let Ok(year) = entry.sorting_year() {
+            label.push_str(&format!("{:02}", year.rem_euclid(100)));
+        }
+
+        label
+    }
+
+    /// The labels of multiple entries, paired with their cite keys. Equal
+    /// labels receive suffixes in the order of the entries, so the entries
+    /// should be passed in bibliography order, e.g. from
+    /// [`Bibliography::sorted`](crate::Bibliography::sorted). Shorthands are
+    /// never suffixed.
+    pub fn labels<'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a Entry>,
+    ) -> Vec<(&'a str, String)> {
+        let mut labels: Vec<(&str, String, bool)> = entries
+            .into_iter()
+            .map(|entry| {
+                (entry.key.as_str(), self.label(entry), entry.shorthand().is_ok())
+            })
+            .collect();
+
+        let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+        for (_, label, is_shorthand) in &labels {
+            if !*is_shorthand {
+                counts.entry(label.clone()).or_default().0 += 1;
+            }
+        }
+
+        for (_, label, is_shorthand) in &mut labels {
+            if *is_shorthand {
+                continue;
+            }
+            let (total, next) = counts.get_mut(label.as_str()).unwrap();
+            if *total > 1 {
+                label.push_str(&self.suffix.format(*next));
+                *next += 1;
+            }
+        }
+
+        labels.into_iter().map(|(key, label, _)| (key, label)).collect()
+    }
+}
+
+/// Numeric labels assigned in order of first citation, like the `numeric`
+/// style of BibLaTeX with `sorting=none`. Can be created from cite keys or
+/// through [`Bibliography::numeric_labels`].
+///
+/// ```
+/// # use biblatex::NumericLabels;
+/// let labels = NumericLabels::new(["b", "a", "e", "b", "c", "d"]);
+/// assert_eq!(labels.number("e"), Some(3));
+/// assert_eq!(labels.compress(["a", "e", "c", "d", "b"]), "1–5");
+/// assert_eq!(labels.compress(["b", "a", "d"]), "1, 2, 5");
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NumericLabels {
+    keys: Vec<String>,
+    numbers: HashMap<String, usize>,
+}
+
+impl NumericLabels {
+    /// Number the cite keys in order of their first occurrence, starting
+    /// at 1.
+    pub fn new<'a>(citations: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut labels = Self::default();
+        for key in citations {
+            if !labels.numbers.contains_key(key) {
+                labels.keys.push(key.to_string());
+                labels.numbers.insert(key.to_string(), labels.keys.len());
+            }
+        }
+        labels
+    }
+
+    /// The number of a cite key.
+    pub fn number(&self, key: &str) -> Option<usize> {
+        self.numbers.get(key).copied()
+    }
+
+    /// The numbered cite keys in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(String::as_str)
+    }
+
+    /// The number of numbered cite keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no cite key is numbered.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Format the numbers of multiple cite keys for a single citation like
+    /// `[1, 3–6]`, without the brackets. The numbers are sorted and runs of
+    /// at least three consecutive numbers are compressed into a range. Keys
+    /// without a number are ignored.
+    pub fn compress<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> String {
+        let mut numbers: Vec<usize> =
+            keys.into_iter().filter_map(|k| self.number(k)).collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        let mut parts = vec![];
+        let mut i = 0;
+        while i < numbers.len() {
+            let mut j = i;
+            while j + 1 < numbers.len() && numbers[j + 1] == numbers[j] + 1 {
+                j += 1;
+            }
+
+            if j - i >= 2 {
+                parts.push(format!("{}–{}", numbers[i], numbers[j]));
+            } else {
+                parts.extend(numbers[i..=j].iter().map(ToString::to_string));
+            }
+            i = j + 1;
+        }
+
+        parts.join(", ")
+    }
+}
+
+impl Bibliography {
+    /// Number the cited entries in order of their first citation.
+    ///
+    /// Aliases from the `ids` field are mapped to the cite key of their
+    /// entry, so an entry cited under different names receives one number.
+    /// Keys without an entry are skipped.
+    pub fn numeric_labels<'a>(
+        &self,
+        citations: impl IntoIterator<Item = &'a str>,
+    ) -> NumericLabels {
+        NumericLabels::new(
+            citations
+                .into_iter()
+                .filter_map(|key| self.get(key))
+                .map(|e| e.key.as_str()),
+        )
+    }
+}
+
+/// The alphabetic label of an entry with the default options and without
+/// disambiguation.
+pub(crate) fn alpha_label(entry: &Entry) -> String {
+    AlphaLabeler::default().label(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bibliography;
+
+    #[test]
+    fn test_alpha_labels() {
+        let src = r#"@book{knuth, author = {Knuth, Donald}, year = {1984}}
+            @book{gof, author = {Gamma, Erich and Helm, Richard and Johnson, Ralph
+              and Vlissides, John}, year = {1995}}
+            @book{three, author = {Abel, A. and Brown, B. and Cole, C.}, date = {2020-05}}
+            @book{three2, author = {Abel, A. and Brown, B. and Cole, C.}, date = {2020}}
+            @book{who, author = {{World Health Organization}}, shorthand = {WHO}}
+            @book{who2, author = {Other}, shorthand = {WHO}}
+            @book{labeled, label = {Anon}, year = {1066}}
+            @book{none, title = {Nothing}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let labeler = AlphaLabeler::default();
+        let labels: Vec<_> = labeler
+            .labels(bibliography.iter())
+            .into_iter()
+            .map(|(_, l)| l)
+            .collect();
+        assert_eq!(
+            labels,
+            ["Knu84", "Gam+95", "ABC20a", "ABC20b", "WHO", "WHO", "Anon66", ""]
+        );
+
+        let mut labeler = AlphaLabeler::default();
+        labeler.set_min_names(3);
+        labeler.set_suffix(LabelSuffix::Number);
+        let gof = bibliography.get("gof").unwrap();
+        assert_eq!(labeler.label(gof), "GHJ+95");
+        let labels = labeler.labels([bibliography.get("three2").unwrap(); 2]);
+        assert_eq!(labels[1], ("three2", "ABC20-2".to_string()));
+
+        assert_eq!(LabelSuffix::Lowercase.format(25), "z");
+        assert_eq!(LabelSuffix::Lowercase.format(26), "aa");
+        assert_eq!(LabelSuffix::Uppercase.format(27), "AB");
+        assert_eq!(LabelSuffix::Number.format(11), "-12");
+    }
+
+    #[test]
+    fn test_numeric_labels() {
+        let src = "@book{a, ids = {alias}} @book{b,} @book{c,} @book{d,}";
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let labels =
+            bibliography.numeric_labels(["c", "alias", "missing", "a", "d", "b"]);
+        assert_eq!(labels.keys().collect::<Vec<_>>(), ["c", "a", "d", "b"]);
+        assert_eq!(labels.len(), 4);
+        assert_eq!(labels.number("a"), Some(2));
+        assert_eq!(labels.number("alias"), None);
+        assert_eq!(labels.compress(["b", "c", "d"]), "1, 3, 4");
+        assert_eq!(labels.compress(["b", "c", "d", "a", "missing"]), "1–4");
+        assert_eq!(labels.compress([]), "");
+        assert!(NumericLabels::default().is_empty());
+    }
+}
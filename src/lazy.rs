@@ -0,0 +1,286 @@
+//! Bibliographies whose field values are decoded on demand.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::resolve::parse_field;
+use crate::{
+    Chunks, ChunksExt, ChunksRef, Entry, EntryType, ParseError, RawBibliography, Type,
+    TypeError,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A bibliography that keeps the raw field values and decodes them into
+/// chunks only when they are first accessed.
+///
+/// Parsing a [`Bibliography`](crate::Bibliography) decodes every field of
+/// every entry up front. When only a few fields per entry are of interest,
+/// e.g. to list the titles of a large file, most of that work is wasted. A
+/// lazy bibliography only checks the syntax up front and caches each decoded
+/// value and each typed value parsed from it, so that later accesses are
+/// free.
+///
+/// Abbreviations are resolved, but `crossref` and `xdata` links are not
+/// followed.
+///
+/// ```
+/// # use biblatex::{ChunksExt, LazyBibliography};
+/// let src = "@book{knuth, title = {The {TeX}book}, date = {1984}, note = undefined}";
+/// let bibliography = LazyBibliography::parse(src).unwrap();
+///
+/// let entry = bibliography.get("knuth").unwrap();
+/// let title = entry.get("title").unwrap().unwrap();
+/// assert_eq!(title.format_verbatim(), "The TeXbook");
+///
+/// let year: i64 = entry.get_as("date").unwrap().unwrap();
+/// assert_eq!(year, 1984);
+///
+/// // Errors only surface for the fields that are accessed.
+/// assert!(entry.get("note").unwrap().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazyBibliography<'s> {
+    /// The raw bibliography.
+    raw: RawBibliography<'s>,
+    /// The index of the first entry with each cite key.
+    keys: HashMap<String, usize>,
+    /// The decoded values by entry and field index.
+    cache: Vec<Vec<Slot>>,
+}
+
+/// The cached values of a field.
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    /// The decoded chunks.
+    chunks: OnceLock<Result<Chunks, ParseError>>,
+    /// The typed values parsed from the chunks, by type.
+    typed: TypedCache,
+}
+
+/// Typed values of different types, each stored as a `Result<T, TypeError>`.
+#[derive(Default)]
+struct TypedCache(Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>);
+
+impl Clone for TypedCache {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl Debug for TypedCache {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("TypedCache").finish_non_exhaustive()
+    }
+}
+
+/// An error that occurred while reading a typed value from a
+/// [`LazyEntry`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LazyError {
+    /// The value could not be decoded.
+    Parse(ParseError),
+    /// The decoded value has the wrong format.
+    Type(TypeError),
+}
+
+impl Display for LazyError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Parse(err) => Display::fmt(err, f),
+            Self::Type(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for LazyError {}
+
+impl From<ParseError> for LazyError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<TypeError> for LazyError {
+    fn from(err: TypeError) -> Self {
+        Self::Type(err)
+    }
+}
+
+/// An entry of a [`LazyBibliography`].
+#[derive(Debug, Copy, Clone)]
+pub struct LazyEntry<'a, 's> {
+    bibliography: &'a LazyBibliography<'s>,
+    index: usize,
+}
+
+impl<'s> LazyBibliography<'s> {
+    /// Parse a lazy bibliography from a source string. Only syntax errors
+    /// are reported here.
+    pub fn parse(src: &'s str) -> Result<Self, ParseError> {
+        RawBibliography::parse(src).map(Self::from_raw)
+    }
+
+    /// Construct a lazy bibliography from a raw bibliography.
+    pub fn from_raw(raw: RawBibliography<'s>) -> Self {
+        let mut keys = HashMap::new();
+        for (i, entry) in raw.entries.iter().enumerate() {
+            keys.entry(entry.v.key.v.to_string()).or_insert(i);
+        }
+
+        let cache = raw
+            .entries
+            .iter()
+            .map(|entry| entry.v.fields.iter().map(|_| Slot::default()).collect())
+            .collect();
+        Self { raw, keys, cache }
+    }
+
+    /// The raw bibliography.
+    pub fn raw(&self) -> &RawBibliography<'s> {
+        &self.raw
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.raw.entries.len()
+    }
+
+    /// Whether the bibliography is empty.
+    pub fn is_empty(&self) -> bool {
+        self.raw.entries.is_empty()
+    }
+
+    /// Get the first entry with the given cite key.
+    pub fn get(&self, key: &str) -> Option<LazyEntry<'_, 's>> {
+        let index = *self.keys.get(key)?;
+        Some(LazyEntry { bibliography: self, index })
+    }
+
+    /// An iterator over the entries in source order.
+    pub fn iter(&self) -> impl Iterator<Item = LazyEntry<'_, 's>> {
+        (0..self.len()).map(move |index| LazyEntry { bibliography: self, index })
+    }
+}
+
+impl<'a> LazyEntry<'a, '_> {
+    /// The citation key.
+    pub fn key(&self) -> &'a str {
+        &self.bibliography.raw.entries[self.index].v.key.v
+    }
+
+    /// The type of the entry.
+    pub fn entry_type(&self) -> EntryType {
+        EntryType::new(&self.bibliography.raw.entries[self.index].v.kind.v)
+    }
+
+    /// Decode the value of a field, or return the cached value if it was
+    /// decoded before. Returns `None` if the entry has no such field.
+    ///
+    /// The field key is matched case-insensitively. If a field is repeated,
+    /// the last value is used, as when parsing a
+    /// [`Bibliography`](crate::Bibliography).
+    pub fn get(&self, key: &str) -> Option<Result<ChunksRef<'a>, ParseError>> {
+        self.position(key).map(|i| self.decode(i))
+    }
+
+    /// Parse the value of a field into a specific type, or return a copy of
+    /// the cached value if it was parsed into this type before. Returns `None`
+    /// if the entry has no such field.
+    ///
+    /// The field key is matched like in [`get`](Self::get).
+    pub fn get_as<T>(&self, key: &str) -> Option<Result<T, LazyError>>
+    where
+        T: Type + Clone + Send + Sync + 'static,
+    {
+        let i = self.position(key)?;
+        let chunks = match self.decode(i) {
+            Ok(chunks) => chunks,
+            Err(err) => return Some(Err(err.into())),
+        };
+
+        let typed = &self.bibliography.cache[self.index][i].typed;
+        let mut typed = typed.0.lock().unwrap();
+        let value = typed
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(chunks.parse::<T>()))
+            .downcast_ref::<Result<T, TypeError>>()
+            .unwrap();
+        Some(value.clone().map_err(Into::into))
+    }
+
+    /// Decode all fields into an entry.
+    pub fn to_entry(&self) -> Result<Entry, ParseError> {
+        let fields = &self.bibliography.raw.entries[self.index].v.fields;
+        let mut entry = Entry::new(self.key().into(), self.entry_type());
+        for (i, pair) in fields.iter().enumerate() {
+            entry.set(&pair.key.v, self.decode(i)?.to_vec());
+        }
+        Ok(entry)
+    }
+
+    /// The index of the last field with the given key.
+    fn position(&self, key: &str) -> Option<usize> {
+        let fields = &self.bibliography.raw.entries[self.index].v.fields;
+        fields.iter().rposition(|pair| pair.key.v.eq_ignore_ascii_case(key))
+    }
+
+    /// Decode the field with the given index, caching the result.
+    fn decode(&self, i: usize) -> Result<ChunksRef<'a>, ParseError> {
+        let bibliography = self.bibliography;
+        let pair = &bibliography.raw.entries[self.index].v.fields[i];
+        bibliography.cache[self.index][i]
+            .chunks
+            .get_or_init(|| {
+                let key = pair.key.v.to_ascii_lowercase();
+                parse_field(&key, &pair.value.v, &bibliography.raw.abbreviations)
+            })
+            .as_ref()
+            .map(AsRef::as_ref)
+            .map_err(Clone::clone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bibliography, Person};
+
+    #[test]
+    fn test_lazy() {
+        let src = r#"@string{tug = "TeX Users Group"}
+            @article{a, Title = {First}, publisher = tug # { Press}, title = {Second}}
+            @misc{b, note = missing, author = {Doe, Jane}}"#;
+        let lazy = LazyBibliography::parse(src).unwrap();
+        assert_eq!(lazy.len(), 2);
+        assert_eq!(lazy.iter().map(|entry| entry.key()).collect::<Vec<_>>(), ["a", "b"]);
+
+        let a = lazy.get("a").unwrap();
+        assert_eq!(a.entry_type(), EntryType::Article);
+        let first = a.get("title").unwrap().unwrap();
+        let second = a.get("title").unwrap().unwrap();
+        assert!(std::ptr::eq(first, second));
+        assert!(a.get("author").is_none());
+        assert_eq!(a.get("TITLE").unwrap().unwrap(), second);
+
+        let bibliography = Bibliography::parse(&src[..src.rfind("@misc").unwrap()]);
+        assert_eq!(Some(&a.to_entry().unwrap()), bibliography.unwrap().get("a"));
+
+        let b = lazy.get("b").unwrap();
+        assert_eq!(b.get("author").unwrap().unwrap().len(), 1);
+        assert!(b.get("note").unwrap().is_err());
+        assert!(b.to_entry().is_err());
+        assert!(lazy.get("c").is_none());
+
+        let authors: Vec<Person> = b.get_as("author").unwrap().unwrap();
+        assert_eq!(authors[0].name, "Doe");
+        assert_eq!(b.get_as::<Vec<Person>>("author").unwrap().unwrap(), authors);
+        assert!(matches!(b.get_as::<i64>("author"), Some(Err(LazyError::Type(_)))));
+        assert!(matches!(b.get_as::<String>("note"), Some(Err(LazyError::Parse(_)))));
+        assert!(b.get_as::<String>("title").is_none());
+    }
+}
@@ -1,7 +1,20 @@
 /*!
 A crate for parsing Bib(La)TeX files.
 
-The main API entrypoint is the [`Bibliography`] struct.
+The main API entrypoint is the [`Bibliography`] struct. It owns its data and
+is built from the syntax tree of a file, a [`RawBibliography`], by
+
+- resolving `@string` abbreviations and `#` concatenations,
+- decoding the field values into [chunks](Chunk),
+- indexing the entries by their cite keys and the aliases in their `ids`
+  fields,
+- and applying the `crossref` and `xdata` inheritance rules.
+
+Legacy BibTeX field names like `journal` or `address` are kept as written so
+that files can be written back without changes. The getters of [`Entry`] fall
+back on these aliases, and [`Bibliography::normalize_aliases`] renames them to
+their BibLaTeX counterparts. Only use the [`RawBibliography`] if you need the
+unresolved syntax of a file.
 
 # Example
 
@@ -21,26 +34,106 @@ assert_eq!(author[0].name, "Tolkien");
 
 #![deny(missing_docs)]
 
+#[cfg(feature = "bumpalo")]
+mod arena;
+mod bltxml;
 mod chunk;
+mod cite;
+mod convert;
+mod csl;
+mod dedup;
+mod diff;
+mod encode;
+mod fields;
+mod graph;
+#[cfg(feature = "hayagriva")]
+mod hayagriva;
+mod inheritance;
+mod intern;
+mod journal;
+mod json;
+mod keygen;
+mod label;
+mod lazy;
+mod lint;
+mod load;
 mod macros;
+mod map;
 mod mechanics;
+mod merge;
+mod model;
+mod names;
+mod nbib;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod patch;
+mod preserve;
+mod query;
 mod raw;
 mod resolve;
+mod scanner;
+mod search;
+mod sort;
+mod stats;
+#[cfg(feature = "tokio")]
+mod stream;
+mod swar;
 mod types;
+mod write;
+mod xml;
 
+#[cfg(feature = "bumpalo")]
+pub use arena::ArenaChunk;
+pub use bltxml::BltxmlError;
 pub use chunk::{Chunk, Chunks, ChunksExt, ChunksRef};
+pub use cite::{aux_citations, tex_citations};
+pub use convert::{CompatWarning, CompatWarningKind, TargetDialect};
+pub use csl::CslError;
+pub use dedup::{DuplicateCluster, DuplicateReason};
+pub use diff::{BibliographyDiff, EntryDiff, FieldDiff};
+pub use fields::TypedField;
+pub use graph::{Reference, ReferenceGraph, ReferenceKind};
+pub use inheritance::{InheritanceRule, InheritanceSpec};
+pub use intern::FieldName;
+pub use journal::{JournalAbbreviations, Ltwa};
+pub use keygen::{KeyPattern, KeyPatternError, KeyPolicy, KeyViolation};
+pub use label::{AlphaLabeler, LabelSuffix, NumericLabels};
+pub use lazy::{LazyBibliography, LazyEntry, LazyError};
+pub use lint::{Fix, Lint, LintRule, Linter};
+#[cfg(feature = "mmap")]
+pub use load::MappedFile;
+pub use load::{LoadError, LoadedBibliography, Provenance};
+pub use map::{FieldIntoIter, FieldIter, FieldIterMut, FieldMap};
 pub use mechanics::EntryType;
+pub use merge::{MergeConflict, MergeStrategy};
+pub use model::{Constraint, DataModel, DataModelError, Violation};
+pub use names::NameCluster;
+pub use nbib::NbibError;
+pub use patch::{Patch, PatchError, PatchOperation};
+pub use query::{Query, QueryError};
 pub use raw::{
-    Field, Pair, ParseError, ParseErrorKind, RawBibliography, RawChunk, RawEntry, Token,
+    Field, Garbage, Pair, ParseError, ParseErrorKind, RawBibliography, RawChunk,
+    RawEntries, RawEntry, Token,
 };
+pub use search::SearchIndex;
+pub use sort::{SortElement, SortingScheme, SortingTemplate};
+pub use stats::Statistics;
+#[cfg(feature = "tokio")]
+pub use stream::{EntryStream, StreamError};
 pub use types::*;
+pub use write::{
+    normalize_delimiters, BibWriter, Delimiter, EntryOrder, FieldOrder, FormatOptions,
+};
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter, Write};
 
+use chunk::split_token_lists;
 use macros::*;
-use mechanics::{is_verbatim_field, AuthorMode, PagesChapterMode};
+use mechanics::{
+    canonical_field_name, is_list_field, AuthorMode, PagesChapterMode, FIELD_ALIASES,
+};
 
 use paste::paste;
 
@@ -53,8 +146,13 @@ use serde::{Deserialize, Serialize};
 pub struct Bibliography {
     /// The bibliography entries.
     entries: Vec<Entry>,
-    /// Maps from citation keys to indices in `items`.
-    keys: BTreeMap<String, usize>,
+    /// Maps from citation keys and their aliases to indices in `entries`.
+    keys: HashMap<String, usize>,
+    /// The unevaluated contents of the `@preamble` entries.
+    preamble: String,
+    /// The `@xdata` containers by their cite key. They are not part of the
+    /// regular entries.
+    xdata: BTreeMap<String, Entry>,
 }
 
 /// A bibliography entry containing chunk fields, which can be parsed into more
@@ -67,11 +165,15 @@ pub struct Entry {
     /// Denotes the type of bibliography item (e.g., `Article`).
     pub entry_type: EntryType,
     /// Maps from field names to their associated chunk vectors.
-    pub fields: BTreeMap<String, Chunks>,
+    ///
+    /// This used to be a `BTreeMap<String, Chunks>`; see [`FieldMap`] for how
+    /// to migrate.
+    pub fields: FieldMap,
 }
 
 /// Errors that can occur when retrieving a field of an [`Entry`].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RetrievalError {
     /// The entry has no field with this name.
     Missing(String),
@@ -94,6 +196,26 @@ impl From<TypeError> for RetrievalError {
     }
 }
 
+/// The position of a field value in the source, or `usize::MAX` if it was
+/// not parsed.
+fn source_position(chunks: ChunksRef) -> usize {
+    chunks.first().map_or(usize::MAX, |chunk| chunk.span.start)
+}
+
+/// Decode the fields of a raw entry, resolving abbreviations.
+pub(crate) fn decode_fields(
+    entry: &RawEntry,
+    abbreviations: &[Pair],
+) -> Result<FieldMap, ParseError> {
+    let mut fields = FieldMap::with_capacity(entry.fields.len());
+    for pair in &entry.fields {
+        let key = pair.key.v.to_ascii_lowercase();
+        let parsed = resolve::parse_field(&key, &pair.value.v, abbreviations)?;
+        fields.insert(key.into(), parsed);
+    }
+    Ok(fields)
+}
+
 fn convert_result<T>(err: Result<T, RetrievalError>) -> Result<Option<T>, TypeError> {
     match err {
         Ok(val) => Ok(Some(val)),
@@ -113,42 +235,86 @@ impl Bibliography {
         Self::from_raw(RawBibliography::parse(src)?)
     }
 
+    /// Parse a bibliography from a source string, resolving `crossref` links
+    /// with custom inheritance rules.
+    pub fn parse_with_inheritance(
+        src: &str,
+        spec: &InheritanceSpec,
+    ) -> Result<Self, ParseError> {
+        Self::from_raw_with_inheritance(RawBibliography::parse(src)?, spec)
+    }
+
     /// Construct a bibliography from a raw bibliography, with the `xdata` and
     /// `crossref` links resolved.
     pub fn from_raw(raw: RawBibliography) -> Result<Self, ParseError> {
-        let mut res = Self::new();
+        Self::from_raw_with_inheritance(raw, &InheritanceSpec::default())
+    }
+
+    /// Construct a bibliography from a raw bibliography, with the `xdata` and
+    /// `crossref` links resolved according to custom inheritance rules.
+    pub fn from_raw_with_inheritance(
+        raw: RawBibliography,
+        spec: &InheritanceSpec,
+    ) -> Result<Self, ParseError> {
         let abbr = &raw.abbreviations;
+        let fields = raw.entries.iter().map(|entry| decode_fields(&entry.v, abbr));
+        Self::from_decoded(raw.preamble, &raw.entries, fields, spec)
+    }
+
+    /// Construct a bibliography from the raw entries and their decoded
+    /// fields, which are only consumed until the first error.
+    pub(crate) fn from_decoded(
+        preamble: String,
+        entries: &[Spanned<RawEntry>],
+        mut fields: impl Iterator<Item = Result<FieldMap, ParseError>>,
+        spec: &InheritanceSpec,
+    ) -> Result<Self, ParseError> {
+        let mut res = Self::new();
+        res.preamble = preamble;
 
-        for entry in raw.entries {
+        for entry in entries {
             // Check that the key is not repeated
-            if res.get(entry.v.key.v).is_some() {
+            let key = &*entry.v.key.v;
+            if res.get(key).is_some_and(|prev| prev.key == key)
+                || res.xdata.contains_key(key)
+            {
                 return Err(ParseError::new(
-                    entry.span,
-                    ParseErrorKind::DuplicateKey(entry.v.key.v.to_string()),
+                    entry.span.clone(),
+                    ParseErrorKind::DuplicateKey(key.to_string()),
                 ));
             }
 
-            let mut fields: BTreeMap<String, Vec<Spanned<Chunk>>> = BTreeMap::new();
-            for spanned_field in entry.v.fields.into_iter() {
-                let field_key = spanned_field.key.v.to_string().to_ascii_lowercase();
-                let parsed =
-                    resolve::parse_field(&field_key, &spanned_field.value.v, abbr)?;
-                fields.insert(field_key, parsed);
-            }
+            let fields = fields.next().expect("one set of fields per entry")?;
             res.insert(Entry {
-                key: entry.v.key.v.to_string(),
-                entry_type: EntryType::new(entry.v.kind.v),
+                key: key.to_string(),
+                entry_type: EntryType::new(&entry.v.kind.v),
                 fields,
             });
         }
 
+        let to_parse_error = |e: TypeError| {
+            ParseError::new(e.span, ParseErrorKind::ResolutionError(e.kind))
+        };
+
+        let mut resolver = Resolver::new(&res, spec);
         let mut entries = res.entries.clone();
         for entry in &mut entries {
-            entry.resolve_crossrefs(&res).map_err(|e| {
-                ParseError::new(e.span, ParseErrorKind::ResolutionError(e.kind))
-            })?;
+            match resolver.entries.get(&entry.key) {
+                Some(resolved) => *entry = resolved.clone(),
+                None => resolver.resolve(entry).map_err(to_parse_error)?,
+            }
+        }
+
+        let mut xdata = res.xdata.clone();
+        for (key, entry) in &mut xdata {
+            match resolver.xdata.get(key) {
+                Some(resolved) => *entry = resolved.clone(),
+                None => resolver.resolve(entry).map_err(to_parse_error)?,
+            }
         }
+
         res.entries = entries;
+        res.xdata = xdata;
 
         Ok(res)
     }
@@ -178,27 +344,132 @@ impl Bibliography {
     /// Insert an entry into the bibliography.
     ///
     /// If an entry with the same cite key is already present, the entry is
-    /// updated and the old entry is returned.
+    /// updated and the old entry is returned. The aliases of the old entry
+    /// are dropped like in [`Bibliography::replace`]. `@xdata` entries are
+    /// stored as containers and can be retrieved with
+    /// [`Bibliography::xdata`].
+    ///
+    /// The aliases in the `ids` field of the entry are registered unless they
+    /// are already taken. The cite key of an entry always takes precedence over
+    /// the aliases of other entries. Use [`Bibliography::alias_conflicts`] to
+    /// find aliases that could not be registered.
     pub fn insert(&mut self, entry: Entry) -> Option<Entry> {
-        if let Some(prev) = self.get_mut(&entry.key) {
-            Some(std::mem::replace(prev, entry))
-        } else {
-            let index = self.entries.len();
-            self.keys.insert(entry.key.clone(), index);
-            if let Some(ids) = convert_result(entry.get_as::<Vec<String>>("ids")).unwrap()
-            {
-                for alias in ids {
-                    self.keys.insert(alias, index);
+        if entry.entry_type == EntryType::XData {
+            return self.xdata.insert(entry.key.clone(), entry);
+        }
+
+        if let Some(&index) = self
+            .keys
+            .get(&entry.key)
+            .filter(|&&i| self.entries[i].key == entry.key)
+        {
+            let prev = std::mem::replace(&mut self.entries[index], entry);
+            self.keys.retain(|_, v| *v != index);
+            self.register(index);
+            return Some(prev);
+        }
+
+        self.entries.push(entry);
+        self.register(self.entries.len() - 1);
+        None
+    }
+
+    /// Insert an entry into the bibliography unless its cite key is already
+    /// taken by another entry, an alias, or an `@xdata` container.
+    ///
+    /// Returns the entry back if it could not be inserted.
+    pub fn try_insert(&mut self, entry: Entry) -> Result<(), Entry> {
+        if self.keys.contains_key(&entry.key) || self.xdata.contains_key(&entry.key) {
+            return Err(entry);
+        }
+
+        self.insert(entry);
+        Ok(())
+    }
+
+    /// Replace the entry with the given cite key or alias by another entry,
+    /// which may have a different cite key, and return the old entry.
+    ///
+    /// The aliases of the old entry, including those added through
+    /// [`Bibliography::alias`], are dropped and the aliases in the `ids` field
+    /// of the new entry are registered. Returns the new entry back if no entry
+    /// with the given key exists, if the new cite key is taken by another
+    /// entry, or if the new entry is an `@xdata` container.
+    pub fn replace(&mut self, key: &str, entry: Entry) -> Result<Entry, Entry> {
+        let Some(&index) = self.keys.get(key) else { return Err(entry) };
+        if self.keys.get(&entry.key).is_some_and(|&i| i != index)
+            || self.xdata.contains_key(&entry.key)
+            || entry.entry_type == EntryType::XData
+        {
+            return Err(entry);
+        }
+
+        let prev = std::mem::replace(&mut self.entries[index], entry);
+        self.keys.retain(|_, v| *v != index);
+        self.register(index);
+        Ok(prev)
+    }
+
+    /// Retain only the entries for which the predicate returns `true`.
+    ///
+    /// The `@xdata` containers are not affected.
+    pub fn retain(&mut self, mut f: impl FnMut(&Entry) -> bool) {
+        let mut indices = Vec::with_capacity(self.entries.len());
+        let mut next = 0;
+        for entry in &self.entries {
+            if f(entry) {
+                indices.push(Some(next));
+                next += 1;
+            } else {
+                indices.push(None);
+            }
+        }
+
+        let mut kept = indices.iter();
+        self.entries.retain(|_| kept.next().unwrap().is_some());
+        self.keys.retain(|_, v| match indices[*v] {
+            Some(index) => {
+                *v = index;
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Register the cite key and the aliases in the `ids` field of the entry
+    /// at the given index.
+    fn register(&mut self, index: usize) {
+        let entry = &self.entries[index];
+        self.keys.insert(entry.key.clone(), index);
+        if let Ok(ids) = entry.ids() {
+            for alias in ids {
+                self.keys.entry(alias).or_insert(index);
+            }
+        }
+    }
+
+    /// Aliases from the `ids` fields that do not resolve to the entry they are
+    /// declared in because they clash with the cite key or alias of another
+    /// entry.
+    ///
+    /// Returns pairs of the alias and the cite key of the declaring entry.
+    pub fn alias_conflicts(&self) -> Vec<(String, String)> {
+        let mut conflicts = vec![];
+
+        for entry in &self.entries {
+            for alias in entry.ids().unwrap_or_default() {
+                if self.get(&alias).is_some_and(|other| other.key != entry.key) {
+                    conflicts.push((alias, entry.key.clone()));
                 }
             }
-            self.entries.push(entry);
-            None
         }
+
+        conflicts
     }
 
-    /// Remove the entry with the given cite key.
+    /// Remove the entry or `@xdata` container with the given cite key.
     pub fn remove(&mut self, key: &str) -> Option<Entry> {
-        let index = *self.keys.get(key)?;
+        let Some(&index) = self.keys.get(key) else { return self.xdata.remove(key) };
         let entry = self.entries.remove(index);
 
         // Remove equal indices and update later indices.
@@ -214,6 +485,64 @@ impl Bibliography {
         Some(entry)
     }
 
+    /// Change the cite key of an entry or `@xdata` container and rewrite all
+    /// references to it in the `crossref`, `xref`, `xdata`, `related`,
+    /// `entryset` and `ids` fields and in granular `xdata=key-field`
+    /// references throughout the bibliography. The formatting of the
+    /// rewritten fields is otherwise kept.
+    ///
+    /// Returns `false` and does nothing if there is no entry with the cite key
+    /// `old` or if `new` is already taken.
+    pub fn rename_key(&mut self, old: &str, new: &str) -> bool {
+        if self.keys.contains_key(new) || self.xdata.contains_key(new) {
+            return false;
+        }
+
+        // The entries whose cite key or aliases change and must be indexed
+        // again.
+        let mut changed = vec![];
+        if let Some(mut container) = self.xdata.remove(old) {
+            container.key = new.to_string();
+            self.xdata.insert(new.to_string(), container);
+        } else if let Some(&index) =
+            self.keys.get(old).filter(|&&i| self.entries[i].key == old)
+        {
+            self.entries[index].key = new.to_string();
+            changed.push(index);
+        } else {
+            return false;
+        }
+
+        let renamed_xdata = self.xdata.contains_key(new);
+        for (i, entry) in self.entries.iter_mut().enumerate() {
+            if rename_references(entry, old, new, renamed_xdata) {
+                changed.push(i);
+            }
+        }
+        for entry in self.xdata.values_mut() {
+            rename_references(entry, old, new, renamed_xdata);
+        }
+
+        changed.sort_unstable();
+        changed.dedup();
+        self.keys.retain(|_, v| !changed.contains(v));
+        for index in changed {
+            self.register(index);
+        }
+
+        true
+    }
+
+    /// Returns the `@xdata` container with the given cite key.
+    pub fn xdata(&self, key: &str) -> Option<&Entry> {
+        self.xdata.get(key)
+    }
+
+    /// An iterator over the bibliography's `@xdata` containers.
+    pub fn xdata_entries(&self) -> impl Iterator<Item = &Entry> {
+        self.xdata.values()
+    }
+
     /// Add an alias for a cite key.
     ///
     /// Does nothing if no entry with the given cite key exists.
@@ -223,13 +552,79 @@ impl Bibliography {
         }
     }
 
+    /// Resolve the `related` field of an entry into the entries it refers to,
+    /// together with the `relatedtype` and `relatedstring` fields.
+    ///
+    /// Returns `None` if the entry has no `related` field and an error if
+    /// one of the keys does not exist in the bibliography.
+    pub fn related(&self, entry: &Entry) -> Result<Option<Related<'_>>, TypeError> {
+        let Some(chunks) = entry.get("related") else { return Ok(None) };
+
+        let mut entries = vec![];
+        for key in split_token_lists(chunks, ",") {
+            let related = self.get(key.format_verbatim().trim()).ok_or_else(|| {
+                TypeError::new(key.span(), TypeErrorKind::UnknownEntryKey)
+            })?;
+            entries.push(related);
+        }
+
+        Ok(Some(Related {
+            entries,
+            related_type: convert_result(entry.related_type())?,
+            related_string: entry.get("relatedstring").map(<[_]>::to_vec),
+        }))
+    }
+
+    /// The `crossref` parents that must be included as standalone entries when
+    /// citing the entries with the given keys, like BibTeX's `-min-crossrefs`.
+    ///
+    /// A parent is included if at least `min_crossrefs` distinct cited entries
+    /// refer to it. BibTeX uses a default of 2. Parents that are cited
+    /// themselves are not returned. The parents are ordered by their first
+    /// reference in the citations.
+    pub fn standalone_parents<'a>(
+        &self,
+        citations: impl IntoIterator<Item = &'a str>,
+        min_crossrefs: usize,
+    ) -> Vec<&Entry> {
+        let mut cited: Vec<&Entry> = vec![];
+        let mut cited_keys = HashSet::new();
+        for key in citations {
+            if let Some(entry) = self.get(key) {
+                if cited_keys.insert(entry.key.as_str()) {
+                    cited.push(entry);
+                }
+            }
+        }
+
+        let mut parents: Vec<&Entry> = vec![];
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for entry in &cited {
+            let Ok(crossref) = entry.crossref() else { continue };
+            let Some(parent) = self.get(&crossref) else { continue };
+            let count = counts.entry(parent.key.as_str()).or_insert(0);
+            if *count == 0 {
+                parents.push(parent);
+            }
+            *count += 1;
+        }
+
+        parents
+            .into_iter()
+            .filter(|parent| {
+                counts[parent.key.as_str()] >= min_crossrefs
+                    && !cited_keys.contains(parent.key.as_str())
+            })
+            .collect()
+    }
+
     /// An iterator over the bibliography's entries.
-    pub fn iter(&self) -> std::slice::Iter<Entry> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Entry> {
         self.entries.iter()
     }
 
     /// A mutable iterator over the bibliography's entries.
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<Entry> {
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Entry> {
         self.entries.iter_mut()
     }
 
@@ -238,48 +633,142 @@ impl Bibliography {
         self.entries.iter().map(|entry| &*entry.key)
     }
 
+    /// The TeX commands to be prepended to the document, collected from all
+    /// `@preamble` entries.
+    ///
+    /// The preamble is kept as written in the source, including delimiters
+    /// and `#` concatenations, e.g. `"\newcommand{\noop}[1]{}"`.
+    pub fn preamble(&self) -> &str {
+        &self.preamble
+    }
+
+    /// Set the contents of the `@preamble` entry.
+    ///
+    /// The value is written as-is and must be a valid BibTeX string
+    /// expression, e.g. `"\newcommand{\noop}[1]{}"`.
+    pub fn set_preamble(&mut self, preamble: impl Into<String>) {
+        self.preamble = preamble.into();
+    }
+
+    /// Rename fields with legacy BibTeX names like `journal` or `school` to
+    /// their BibLaTeX counterparts in all entries and `@xdata` containers.
+    ///
+    /// See [`Entry::normalize_aliases`] for details.
+    ///
+    /// ```
+    /// # use biblatex::Bibliography;
+    /// let src = "@article{a, journal = {Nature}, school = {MIT}}";
+    /// let mut bibliography = Bibliography::parse(src).unwrap();
+    /// bibliography.normalize_aliases();
+    ///
+    /// let entry = bibliography.get("a").unwrap();
+    /// assert!(entry.get("journal").is_none());
+    /// assert!(entry.get("journaltitle").is_some());
+    /// assert!(entry.get("institution").is_some());
+    /// ```
+    pub fn normalize_aliases(&mut self) {
+        for entry in self.entries.iter_mut().chain(self.xdata.values_mut()) {
+            entry.normalize_aliases();
+        }
+    }
+
     /// Consume this struct and return a vector of the bibliography's entries.
     pub fn into_vec(self) -> Vec<Entry> {
         self.entries
     }
 
     /// Write the entry into a writer in the BibLaTeX format.
-    pub fn write_biblatex(&self, mut sink: impl Write) -> fmt::Result {
-        let mut first = true;
-        for entry in &self.entries {
+    pub fn write_biblatex(&self, sink: impl Write) -> fmt::Result {
+        self.write_biblatex_with(sink, &FormatOptions::default())
+    }
+
+    /// Write the entry into a writer in the BibLaTeX format with a custom
+    /// layout.
+    pub fn write_biblatex_with(
+        &self,
+        mut sink: impl Write,
+        options: &FormatOptions,
+    ) -> fmt::Result {
+        let mut first = !options.write_abbreviations(&mut sink, &[])?;
+        if !self.preamble.is_empty() {
             if !first {
-                writeln!(sink)?;
+                options.write_separator(&mut sink)?;
             }
-            writeln!(sink, "{}", entry.to_biblatex_string())?;
+            write!(sink, "@preamble{{{}}}", self.preamble)?;
             first = false;
         }
-        Ok(())
+
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        options.order_entries(&mut entries, |entry| &entry.key, |t| self.sorted_keys(t));
+        for entry in self.xdata.values().chain(entries) {
+            if !first {
+                options.write_separator(&mut sink)?;
+            }
+            write!(sink, "{}", entry.to_biblatex_string_with(options))?;
+            first = false;
+        }
+        options.write_end(&mut sink, first)
     }
 
     /// Serialize this bibliography into a BibLaTeX string.
     pub fn to_biblatex_string(&self) -> String {
+        self.to_biblatex_string_with(&FormatOptions::default())
+    }
+
+    /// Serialize this bibliography into a BibLaTeX string with a custom
+    /// layout.
+    pub fn to_biblatex_string_with(&self, options: &FormatOptions) -> String {
         let mut biblatex = String::new();
-        self.write_biblatex(&mut biblatex).unwrap();
+        self.write_biblatex_with(&mut biblatex, options).unwrap();
         biblatex
     }
 
     /// Write the entry into a writer in the BibTeX format.
-    pub fn write_bibtex(&self, mut sink: impl Write) -> fmt::Result {
-        let mut first = true;
-        for entry in &self.entries {
+    ///
+    /// The `@xdata` containers are omitted because BibTeX does not support
+    /// them. Their fields are already inherited by the entries.
+    pub fn write_bibtex(&self, sink: impl Write) -> fmt::Result {
+        self.write_bibtex_with(sink, &FormatOptions::default())
+    }
+
+    /// Write the entry into a writer in the BibTeX format with a custom
+    /// layout.
+    pub fn write_bibtex_with(
+        &self,
+        mut sink: impl Write,
+        options: &FormatOptions,
+    ) -> fmt::Result {
+        let mut first = !options.write_abbreviations(&mut sink, &[])?;
+        if !self.preamble.is_empty() {
             if !first {
-                writeln!(sink)?;
+                options.write_separator(&mut sink)?;
             }
-            writeln!(sink, "{}", entry.to_bibtex_string().map_err(|_| fmt::Error)?)?;
+            write!(sink, "@preamble{{{}}}", self.preamble)?;
             first = false;
         }
-        Ok(())
+
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        options.order_entries(&mut entries, |entry| &entry.key, |t| self.sorted_keys(t));
+        for entry in entries {
+            if !first {
+                options.write_separator(&mut sink)?;
+            }
+            let bibtex = entry.to_bibtex_string_with(options).map_err(|_| fmt::Error)?;
+            write!(sink, "{}", bibtex)?;
+            first = false;
+        }
+        options.write_end(&mut sink, first)
     }
 
     /// Serialize this bibliography into a BibTeX string.
     pub fn to_bibtex_string(&self) -> String {
+        self.to_bibtex_string_with(&FormatOptions::default())
+    }
+
+    /// Serialize this bibliography into a BibTeX string with a custom layout.
+    pub fn to_bibtex_string_with(&self, options: &FormatOptions) -> String {
         let mut bibtex = String::new();
-        self.write_bibtex(&mut bibtex).unwrap();
+        self.write_bibtex_with(&mut bibtex, options).unwrap();
         bibtex
     }
 }
@@ -293,16 +782,24 @@ impl IntoIterator for Bibliography {
     }
 }
 
+/// Formats the entry in the BibLaTeX format like
+/// [`Entry::to_biblatex_string`], e.g. to print or copy a single entry.
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.to_biblatex_string())
+    }
+}
+
 impl Entry {
     /// Construct new, empty entry.
     pub fn new(key: String, entry_type: EntryType) -> Self {
-        Self { key, entry_type, fields: BTreeMap::new() }
+        Self { key, entry_type, fields: FieldMap::new() }
     }
 
     /// Get the chunk slice of a field.
     ///
     /// The field key must be lowercase.
-    pub fn get(&self, key: &str) -> Option<ChunksRef> {
+    pub fn get(&self, key: &str) -> Option<ChunksRef<'_>> {
         self.fields.get(key).map(AsRef::as_ref)
     }
 
@@ -320,7 +817,12 @@ impl Entry {
     ///
     /// The field key is lowercase before insertion.
     pub fn set(&mut self, key: &str, chunks: Chunks) {
-        self.fields.insert(key.to_lowercase(), chunks);
+        let key = if key.chars().any(char::is_uppercase) {
+            key.to_lowercase().into()
+        } else {
+            FieldName::new(key)
+        };
+        self.fields.insert(key, chunks);
     }
 
     /// Set the value of a field as a specific type.
@@ -335,6 +837,41 @@ impl Entry {
         self.fields.remove(key)
     }
 
+    /// Rename fields with legacy BibTeX names like `journal` or `school` to
+    /// their BibLaTeX counterparts, e.g. `journaltitle` and `institution`.
+    ///
+    /// A field is only renamed if the entry does not already contain its
+    /// BibLaTeX counterpart. Returns the applied aliases as pairs of the
+    /// legacy and the new field name.
+    pub fn normalize_aliases(&mut self) -> Vec<(String, String)> {
+        let mut applied = vec![];
+
+        for (alias, field) in FIELD_ALIASES {
+            if self.fields.contains_key(field) {
+                continue;
+            }
+
+            if let Some(chunks) = self.fields.remove(alias) {
+                self.fields.insert(FieldName::new(field), chunks);
+                applied.push((alias.to_string(), field.to_string()));
+            }
+        }
+
+        applied
+    }
+
+    /// An iterator over all fields of the entry parsed into their respective
+    /// types, in alphabetical order of their names.
+    ///
+    /// Fields not known to the crate are yielded as [`TypedField::Unknown`].
+    pub fn typed_fields(
+        &self,
+    ) -> impl Iterator<Item = Result<TypedField, TypeError>> + '_ {
+        self.fields
+            .iter()
+            .map(|(key, chunks)| TypedField::try_from((key.as_str(), chunks.as_slice())))
+    }
+
     /// The parents of an entry in a semantic sense (`crossref` and `xref`).
     pub fn parents(&self) -> Result<Vec<String>, TypeError> {
         let mut parents = vec![];
@@ -404,11 +941,11 @@ impl Entry {
         }
 
         match reqs.author_eds_field {
-            AuthorMode::OneRequired => {
-                if self.author().is_err() && self.editors().unwrap_or_default().is_empty()
-                {
-                    missing.push("author");
-                }
+            AuthorMode::OneRequired
+                if self.author().is_err()
+                    && self.editors().unwrap_or_default().is_empty() =>
+            {
+                missing.push("author");
             }
             AuthorMode::BothRequired => {
                 if self.editors().unwrap_or_default().is_empty() {
@@ -418,10 +955,10 @@ impl Entry {
                     missing.push("author");
                 }
             }
-            AuthorMode::AuthorRequired | AuthorMode::AuthorRequiredEditorOptional => {
-                if self.author().is_err() {
-                    missing.push("author");
-                }
+            AuthorMode::AuthorRequired | AuthorMode::AuthorRequiredEditorOptional
+                if self.author().is_err() =>
+            {
+                missing.push("author");
             }
             AuthorMode::EditorRequiredAuthorForbidden => {
                 if self.editors().unwrap_or_default().is_empty() {
@@ -435,10 +972,10 @@ impl Entry {
         }
 
         match reqs.page_chapter_field {
-            PagesChapterMode::OneRequired => {
-                if self.pages().is_err() && self.chapter().is_err() {
-                    missing.push("pages");
-                }
+            PagesChapterMode::OneRequired
+                if self.pages().is_err() && self.chapter().is_err() =>
+            {
+                missing.push("pages");
             }
             PagesChapterMode::BothForbidden => {
                 if self.pages().is_ok() {
@@ -448,10 +985,8 @@ impl Entry {
                     superfluous.push("chapter");
                 }
             }
-            PagesChapterMode::PagesRequired => {
-                if self.pages().is_err() {
-                    missing.push("pages");
-                }
+            PagesChapterMode::PagesRequired if self.pages().is_err() => {
+                missing.push("pages");
             }
             _ => {}
         }
@@ -460,11 +995,9 @@ impl Entry {
 
         for (key, chunks) in &self.fields {
             let error = match key.as_str() {
-                "edition" => chunks.parse::<PermissiveType<i64>>().err(),
                 "organization" => chunks.parse::<Vec<Chunks>>().err(),
                 "pages" => chunks.parse::<Vec<std::ops::Range<u32>>>().err(),
                 "publisher" => chunks.parse::<Vec<Chunks>>().err(),
-                "volume" => chunks.parse::<i64>().err(),
                 "bookpagination" => chunks.parse::<Pagination>().err(),
                 "pagination" => chunks.parse::<Pagination>().err(),
                 "volumes" => chunks.parse::<i64>().err(),
@@ -480,7 +1013,7 @@ impl Entry {
             };
 
             if let Some(err) = error {
-                malformed.push((key.clone(), err))
+                malformed.push((key.to_string(), err))
             }
         }
 
@@ -506,29 +1039,26 @@ impl Entry {
 
     /// Serialize this entry into a BibLaTeX string.
     pub fn to_biblatex_string(&self) -> String {
-        let mut biblatex = String::new();
-        let ty = self.entry_type.to_biblatex();
-
-        writeln!(biblatex, "@{}{{{},", ty, self.key).unwrap();
+        self.to_biblatex_string_with(&FormatOptions::default())
+    }
 
+    /// Serialize this entry into a BibLaTeX string with a custom layout.
+    pub fn to_biblatex_string_with(&self, options: &FormatOptions) -> String {
+        let mut fields = vec![];
         for (key, value) in &self.fields {
-            let key = match key.as_ref() {
-                "journal" => "journaltitle",
-                "address" => "location",
-                "school" => "institution",
-                k => k,
+            let key = match canonical_field_name(key) {
+                field if !self.fields.contains_key(field) => field,
+                _ => key,
             };
 
-            writeln!(
-                biblatex,
-                "{} = {},",
-                key,
-                value.to_biblatex_string(is_verbatim_field(key))
-            )
-            .unwrap();
+            fields.push((key, options.value(key, value), source_position(value)));
         }
 
-        biblatex.push('}');
+        let mut biblatex = String::new();
+        let ty = self.entry_type.to_biblatex();
+        options
+            .write_entry(&mut biblatex, &ty.to_string(), &self.key, &mut fields)
+            .unwrap();
         biblatex
     }
 
@@ -536,20 +1066,29 @@ impl Entry {
     ///
     /// This function can return an error if there is a malformed date field.
     pub fn to_bibtex_string(&self) -> Result<String, TypeError> {
-        let mut bibtex = String::new();
+        self.to_bibtex_string_with(&FormatOptions::default())
+    }
+
+    /// Serialize this entry into a BibTeX string with a custom layout.
+    ///
+    /// This function can return an error if there is a malformed date field.
+    pub fn to_bibtex_string_with(
+        &self,
+        options: &FormatOptions,
+    ) -> Result<String, TypeError> {
         let ty = self.entry_type.to_bibtex();
         let thesis = matches!(ty, EntryType::PhdThesis | EntryType::MastersThesis);
 
-        writeln!(bibtex, "@{}{{{},", ty, self.key).unwrap();
-
+        let mut fields = vec![];
         for (key, value) in &self.fields {
+            let position = source_position(value);
             if key == "date" {
                 if let Some(date) = convert_result(self.date())? {
                     if let PermissiveType::Typed(date) = date {
                         for (key, value) in date.to_fieldset() {
-                            let v = [Spanned::zero(Chunk::Normal(value))]
-                                .to_biblatex_string(false);
-                            writeln!(bibtex, "{} = {},", key, v).unwrap();
+                            let v = options
+                                .value(&key, &[Spanned::zero(Chunk::Normal(value))]);
+                            fields.push((key, v, position));
                         }
                         continue;
                     }
@@ -565,21 +1104,18 @@ impl Entry {
                 k => k,
             };
 
-            writeln!(
-                bibtex,
-                "{} = {},",
-                key,
-                value.to_biblatex_string(is_verbatim_field(key))
-            )
-            .unwrap();
+            fields.push((key.to_string(), options.value(key, value), position));
         }
 
-        bibtex.push('}');
+        let mut bibtex = String::new();
+        options
+            .write_entry(&mut bibtex, &ty.to_string(), &self.key, &mut fields)
+            .unwrap();
         Ok(bibtex)
     }
 
     /// Get an entry but return None for empty chunk slices.
-    fn get_non_empty(&self, key: &str) -> Option<ChunksRef> {
+    fn get_non_empty(&self, key: &str) -> Option<ChunksRef<'_>> {
         let entry = self.get(key)?;
         if !entry.is_empty() {
             Some(entry)
@@ -587,171 +1123,317 @@ impl Entry {
             None
         }
     }
+}
+
+/// Resolves the data dependencies defined by `crossref` and `xdata` fields.
+///
+/// Parents are resolved transitively, but only once, so that shared
+/// ancestors do not have to be resolved again for each of their
+/// descendants.
+struct Resolver<'a> {
+    bib: &'a Bibliography,
+    spec: &'a InheritanceSpec,
+    /// The keys of the entries currently being resolved, used to detect
+    /// cycles.
+    chain: Vec<String>,
+    /// Entries that were already resolved as a `crossref` parent.
+    entries: HashMap<String, Entry>,
+    /// `@xdata` containers that were already resolved.
+    xdata: HashMap<String, Entry>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(bib: &'a Bibliography, spec: &'a InheritanceSpec) -> Self {
+        Self {
+            bib,
+            spec,
+            chain: vec![],
+            entries: HashMap::new(),
+            xdata: HashMap::new(),
+        }
+    }
 
-    /// Resolves all data dependencies defined by `crossref` and `xdata` fields.
-    fn resolve_crossrefs(&mut self, bib: &Bibliography) -> Result<(), TypeError> {
+    /// Resolves the `crossref` and `xdata` fields of an entry and its granular
+    /// `xdata=` references.
+    fn resolve(&mut self, entry: &mut Entry) -> Result<(), TypeError> {
         let mut refs = vec![];
 
-        if let Some(crossref) = convert_result(self.get_as::<String>("crossref"))? {
-            refs.extend(bib.get(&crossref).cloned());
+        if let Some(crossref) = convert_result(entry.get_as::<String>("crossref"))? {
+            let span = entry.get("crossref").unwrap().span();
+            if let Some(parent) = self.bib.get(&crossref) {
+                refs.push((false, parent.key.clone(), span));
+            }
         }
 
-        if let Some(keys) = convert_result(self.get_as::<Vec<String>>("xdata"))? {
+        if let Some(keys) = convert_result(entry.get_as::<Vec<String>>("xdata"))? {
+            let span = entry.get("xdata").unwrap().span();
             for key in keys {
-                refs.extend(bib.get(&key).cloned());
+                if self.bib.xdata(&key).is_some() {
+                    refs.push((true, key, span.clone()));
+                }
             }
         }
 
-        for mut crossref in refs {
-            crossref.resolve_crossrefs(bib)?;
-            self.resolve_single_crossref(crossref)?;
+        let spec = self.spec;
+        self.chain.push(entry.key.clone());
+        for (xdata, key, span) in refs {
+            spec.inherit(entry, self.parent(xdata, &key, span)?);
         }
 
-        self.remove("xdata");
+        entry.remove("xdata");
+        self.resolve_granular_xdata(entry)?;
+        self.chain.pop();
 
         Ok(())
     }
 
-    /// Resolve data dependencies using another entry.
-    fn resolve_single_crossref(&mut self, crossref: Entry) -> Result<(), TypeError> {
-        let req = self.entry_type.requirements();
-
-        let mut relevant = req.required;
-        relevant.extend(req.optional);
-        relevant.extend(req.page_chapter_field.possible());
-        relevant.extend(req.author_eds_field.possible());
+    /// Get the resolved `crossref` parent or `@xdata` container with the
+    /// given key, which must exist. The `span` is that of the reference.
+    fn parent(
+        &mut self,
+        xdata: bool,
+        key: &str,
+        span: Span,
+    ) -> Result<&Entry, TypeError> {
+        if self.chain.iter().any(|k| k == key) {
+            return Err(TypeError::new(span, TypeErrorKind::CyclicReference));
+        }
 
-        if self.entry_type == EntryType::XData {
-            for f in crossref.fields.keys() {
-                relevant.push(f);
-            }
+        let resolved = if xdata { &self.xdata } else { &self.entries };
+        if !resolved.contains_key(key) {
+            let parent = if xdata { self.bib.xdata(key) } else { self.bib.get(key) };
+            let mut parent = parent.expect("parent exists").clone();
+            self.resolve(&mut parent)?;
+            let resolved = if xdata { &mut self.xdata } else { &mut self.entries };
+            resolved.insert(key.to_string(), parent);
         }
 
-        for f in relevant {
-            if self.get(f).is_some() {
-                continue;
-            }
+        let resolved = if xdata { &self.xdata } else { &self.entries };
+        Ok(&resolved[key])
+    }
 
-            match f {
-                "journaltitle" | "journalsubtitle"
-                    if crossref.entry_type == EntryType::Periodical =>
-                {
-                    let key = if f.contains('s') { "subtitle" } else { "title" };
+    /// Replaces granular references of the form `xdata=key-field` or
+    /// `xdata=key-field-index` with the field (or the `index`-th item of the
+    /// field's list, starting at one) of the referenced `@xdata` container.
+    ///
+    /// References can take the place of the whole field or of an item in a
+    /// name or literal list, and only items of such lists can be selected by
+    /// index. Unresolvable references are left untouched.
+    fn resolve_granular_xdata(&mut self, entry: &mut Entry) -> Result<(), TypeError> {
+        let keys: Vec<FieldName> = entry
+            .fields
+            .iter()
+            .filter(|(_, chunks)| chunks.format_verbatim().contains("xdata="))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in keys {
+            let items = if is_list_field(key.as_str()) {
+                split_token_lists(&entry.fields[&key], " and ")
+            } else {
+                vec![entry.fields[&key].clone()]
+            };
+            let mut resolved = false;
+            let mut chunks = vec![];
 
-                    if let Some(item) = crossref.get(key) {
-                        self.set(f, item.to_vec())
-                    }
-                }
-                "booktitle" | "booksubtitle" | "booktitleaddon"
-                    if crossref.entry_type.is_collection() =>
-                {
-                    let key = if f.contains('s') {
-                        "subtitle"
-                    } else if f.contains('a') {
-                        "titleaddon"
-                    } else {
-                        "title"
-                    };
-
-                    if let Some(item) = crossref.get(key) {
-                        self.set(f, item.to_vec())
-                    }
-                }
-                "maintitle" | "mainsubtitle" | "maintitleaddon"
-                    if crossref.entry_type.is_multi_volume() =>
-                {
-                    let key = if f.contains('s') {
-                        "subtitle"
-                    } else if f.contains('a') {
-                        "titleaddon"
-                    } else {
-                        "title"
-                    };
-
-                    if let Some(item) = crossref.get(key) {
-                        self.set(f, item.to_vec())
-                    }
+            for (i, item) in items.into_iter().enumerate() {
+                if i > 0 {
+                    chunks.push(Spanned::detached(Chunk::Normal(" and ".to_string())));
                 }
-                "address" => {
-                    if let Some(item) =
-                        crossref.get(f).or_else(|| crossref.get("location"))
-                    {
-                        self.set(f, item.to_vec())
-                    }
-                }
-                "institution" => {
-                    if let Some(item) = crossref.get(f).or_else(|| crossref.get("school"))
-                    {
-                        self.set(f, item.to_vec())
-                    }
-                }
-                "school" => {
-                    if let Some(item) =
-                        crossref.get(f).or_else(|| crossref.get("institution"))
-                    {
-                        self.set(f, item.to_vec())
-                    }
-                }
-                "journaltitle" => {
-                    if let Some(item) =
-                        crossref.get(f).or_else(|| crossref.get("journal"))
-                    {
-                        self.set(f, item.to_vec())
-                    }
-                }
-                "title" | "addendum" | "note" => {}
-                _ => {
-                    if let Some(item) = crossref.get(f) {
-                        self.set(f, item.to_vec())
+
+                match self.resolve_xdata_reference(&item)? {
+                    Some(replacement) => {
+                        chunks.extend(replacement);
+                        resolved = true;
                     }
+                    None => chunks.extend(item),
                 }
             }
+
+            if resolved {
+                entry.fields.insert(key, chunks);
+            }
         }
 
-        if self.entry_type == EntryType::XData {
-            return Ok(());
+        Ok(())
+    }
+
+    /// Resolve a single granular `xdata=key-field[-index]` reference.
+    fn resolve_xdata_reference(
+        &mut self,
+        item: ChunksRef,
+    ) -> Result<Option<Chunks>, TypeError> {
+        let text = item.format_verbatim();
+        let Some(reference) = text.trim().strip_prefix("xdata=") else {
+            return Ok(None);
+        };
+
+        let Some((key, field, index)) = parse_xdata_reference(reference) else {
+            return Ok(None);
+        };
+        if self.bib.xdata(key).is_none() {
+            return Ok(None);
         }
 
-        if req.needs_date {
-            if let Some(date) = convert_result(crossref.date())? {
-                self.set_date(date);
-            }
+        let field = field.to_lowercase();
+        if index.is_some() && !is_list_field(&field) {
+            return Err(TypeError::new(item.span(), TypeErrorKind::NotAList));
         }
 
-        Ok(())
+        let container = self.parent(true, key, item.span())?;
+        let Some(chunks) = container.get(&field) else {
+            return Ok(None);
+        };
+
+        Ok(match index {
+            Some(index) => index
+                .checked_sub(1)
+                .and_then(|i| split_token_lists(chunks, " and ").into_iter().nth(i)),
+            None => Some(chunks.to_vec()),
+        })
     }
 }
 
-/// A report of the validity of an `Entry`. Can be obtained by calling [`Entry::verify`].
-pub struct Report {
-    /// These fields were missing, although they are required for the entry type.
-    pub missing: Vec<&'static str>,
-    /// These fields were present but are not allowed for the entry type.
-    pub superfluous: Vec<&'static str>,
-    /// These fields were present but contained malformed data.
-    pub malformed: Vec<(String, TypeError)>,
+/// Split the part of a granular reference after `xdata=` into the key, the
+/// field, and the optional one-based index.
+fn parse_xdata_reference(reference: &str) -> Option<(&str, &str, Option<usize>)> {
+    let (rest, index) = match reference.rsplit_once('-') {
+        Some((rest, index)) if index.parse::<usize>().is_ok() => {
+            (rest, index.parse::<usize>().ok())
+        }
+        _ => (reference, None),
+    };
+    let (key, field) = rest.rsplit_once('-')?;
+    Some((key, field, index))
 }
 
-impl Report {
-    /// Whether the report is empty and contains no errors.
-    pub fn is_ok(&self) -> bool {
-        self.missing.is_empty()
-            && self.superfluous.is_empty()
-            && self.malformed.is_empty()
+/// Replace the key `old` by `new` in the reference fields of an entry.
+/// Returns whether its `ids` field changed.
+fn rename_references(entry: &mut Entry, old: &str, new: &str, xdata: bool) -> bool {
+    let mut ids = false;
+    for (key, chunks) in entry.fields.iter_mut() {
+        match key.as_str() {
+            "crossref" | "xref" | "xdata" | "related" | "entryset" => {
+                rename_in_key_list(chunks, old, new);
+            }
+            "ids" => ids = rename_in_key_list(chunks, old, new),
+            _ if xdata => rename_in_xdata_references(chunks, old, new),
+            _ => {}
+        }
     }
+    ids
 }
 
-impl Entry {
-    // BibTeX fields.
-    fields! {
+/// Replace the cite key `old` by `new` in a comma-separated list of keys.
+///
+/// Only keys within a single chunk are replaced, so that the formatting of
+/// the list is kept. Returns whether a key was replaced.
+fn rename_in_key_list(chunks: &mut Chunks, old: &str, new: &str) -> bool {
+    let text: String = chunks.iter().map(|chunk| chunk.v.get()).collect();
+    let mut replacements = vec![];
+    let mut token_start = 0;
+
+    for token in text.split(',') {
+        let key = token.trim();
+        let key_start = token_start + token.len() - token.trim_start().len();
+        let key_end = key_start + key.len();
+        token_start += token.len() + 1;
+        if key != old {
+            continue;
+        }
+
+        let mut chunk_start = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let chunk_end = chunk_start + chunk.v.get().len();
+            if chunk_start <= key_start && key_end <= chunk_end {
+                replacements.push((i, key_start - chunk_start..key_end - chunk_start));
+                break;
+            }
+            chunk_start = chunk_end;
+        }
+    }
+
+    let changed = !replacements.is_empty();
+    for (i, range) in replacements.into_iter().rev() {
+        chunks[i].v.get_mut().replace_range(range, new);
+    }
+    changed
+}
+
+/// Replace the key `old` by `new` in granular `xdata=key-field` references.
+fn rename_in_xdata_references(chunks: &mut Chunks, old: &str, new: &str) {
+    for chunk in chunks.iter_mut() {
+        let value = chunk.v.get();
+        if !value.contains("xdata=") {
+            continue;
+        }
+
+        let mut out = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(pos) = rest.find("xdata=") {
+            let (before, after) = rest.split_at(pos + "xdata=".len());
+            out.push_str(before);
+            let len = after.find(|c: char| c.is_whitespace()).unwrap_or(after.len());
+            let (reference, tail) = after.split_at(len);
+            match parse_xdata_reference(reference) {
+                Some((key, _, _)) if key == old => {
+                    out.push_str(new);
+                    out.push_str(&reference[key.len()..]);
+                }
+                _ => out.push_str(reference),
+            }
+            rest = tail;
+        }
+
+        out.push_str(rest);
+        *chunk.v.get_mut() = out;
+    }
+}
+
+/// The entries related to an `Entry` through its `related` field. Can be
+/// obtained by calling [`Bibliography::related`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Related<'a> {
+    /// The related entries in the order of the `related` field.
+    pub entries: Vec<&'a Entry>,
+    /// The kind of relation (`relatedtype`), e.g., `translationof`.
+    pub related_type: Option<String>,
+    /// A replacement for the localized description of the relation
+    /// (`relatedstring`).
+    pub related_string: Option<Chunks>,
+}
+
+/// A report of the validity of an `Entry`. Can be obtained by calling [`Entry::verify`].
+pub struct Report {
+    /// These fields were missing, although they are required for the entry type.
+    pub missing: Vec<&'static str>,
+    /// These fields were present but are not allowed for the entry type.
+    pub superfluous: Vec<&'static str>,
+    /// These fields were present but contained malformed data.
+    pub malformed: Vec<(String, TypeError)>,
+}
+
+impl Report {
+    /// Whether the report is empty and contains no errors.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.superfluous.is_empty()
+            && self.malformed.is_empty()
+    }
+}
+
+impl Entry {
+    // BibTeX fields.
+    fields! {
         // Fields without a specified return type simply return `ChunksRef`.
         author: "author" => Vec<Person>,
         book_title: "booktitle",
-        chapter: "chapter",
-        edition: "edition" => PermissiveType<i64>,
+        chapter: "chapter" => PermissiveType<Vec<std::ops::Range<u32>>>,
+        crossref: "crossref" => String,
+        editor: "editor" => Vec<Person>,
         how_published: "howpublished",
         note: "note",
-        number: "number",
+        number: "number" => PermissiveType<i64>,
         organization: "organization" => Vec<Chunks>,
         pages: "pages" => PermissiveType<Vec<std::ops::Range<u32>>>,
         publisher: "publisher" => Vec<Chunks>,
@@ -762,16 +1444,18 @@ impl Entry {
     }
 
     alias_fields! {
-        address: "address" | "location",
-        location: "location" | "address",
+        address: "address" | "location" => Vec<Chunks>,
+        location: "location" | "address" => Vec<Chunks>,
         annotation: "annotation" | "annote",
+        eprint_class: "eprintclass" | "primaryclass",
         eprint_type: "eprinttype" | "archiveprefix",
         journal: "journal" | "journaltitle",
         journal_title: "journaltitle" | "journal",
-        sort_key: "key" | "sortkey" => String,
+        lang_id: "langid" | "hyphenation" => String,
+        sort_key: "sortkey" | "key" => String,
         file: "file" | "pdf" => String,
-        school: "school" | "institution",
-        institution: "institution" | "school",
+        school: "school" | "institution" => Vec<Chunks>,
+        institution: "institution" | "school" => Vec<Chunks>,
     }
 
     date_fields! {
@@ -781,6 +1465,83 @@ impl Entry {
         url_date: "url",
     }
 
+    /// Get the `edition` field.
+    ///
+    /// Integers and ordinal numbers like `2nd` are returned as a typed value,
+    /// any other value (e.g., `Revised and expanded`) is kept as chunks.
+    pub fn edition(&self) -> Result<PermissiveType<i64>, RetrievalError> {
+        let chunks = self
+            .get("edition")
+            .ok_or_else(|| RetrievalError::Missing("edition".to_string()))?;
+
+        Ok(parse_edition(chunks))
+    }
+
+    /// Set the value of the `edition` field.
+    pub fn set_edition(&mut self, item: PermissiveType<i64>) {
+        self.set("edition", item.to_chunks());
+    }
+
+    /// Get the `location` field, falling back on `address`, together with the
+    /// name of the field that supplied the value.
+    pub fn location_with_source(
+        &self,
+    ) -> Result<(Vec<Chunks>, &'static str), RetrievalError> {
+        let (chunks, source) = match self.get("location") {
+            Some(chunks) => (chunks, "location"),
+            None => match self.get("address") {
+                Some(chunks) => (chunks, "address"),
+                None => return Err(RetrievalError::Missing("location".to_string())),
+            },
+        };
+
+        Ok((chunks.parse::<Vec<Chunks>>()?, source))
+    }
+
+    /// Get the kind of thesis from the `type` field, falling back on the
+    /// entry type for `@phdthesis` and `@mastersthesis` entries.
+    pub fn thesis_type(&self) -> Result<ThesisType, RetrievalError> {
+        if let Some(chunks) = self.get_non_empty("type") {
+            return chunks.parse::<ThesisType>().map_err(Into::into);
+        }
+
+        match self.entry_type {
+            EntryType::PhdThesis => Ok(ThesisType::PhdThesis),
+            EntryType::MastersThesis => Ok(ThesisType::MaThesis),
+            _ => Err(RetrievalError::Missing("type".to_string())),
+        }
+    }
+
+    /// Get the `howpublished` field with runs of whitespace collapsed into a
+    /// single space.
+    pub fn how_published_normalized(&self) -> Result<String, RetrievalError> {
+        self.get_non_empty("howpublished")
+            .map(|chunks| chunks.format_normalized())
+            .ok_or_else(|| RetrievalError::Missing("howpublished".to_string()))
+    }
+
+    /// Get the `type` field with runs of whitespace collapsed into a single
+    /// space.
+    ///
+    /// For theses, recognizable kinds are mapped to their BibLaTeX
+    /// localization keys like `phdthesis` or `mathesis` (see
+    /// [`thesis_type`](Self::thesis_type)).
+    pub fn type_normalized(&self) -> Result<String, RetrievalError> {
+        if matches!(
+            self.entry_type,
+            EntryType::Thesis | EntryType::PhdThesis | EntryType::MastersThesis
+        ) {
+            return self.thesis_type().map(|kind| match kind {
+                ThesisType::Unknown(value) => value,
+                kind => kind.to_string(),
+            });
+        }
+
+        self.get_non_empty("type")
+            .map(|chunks| chunks.format_normalized())
+            .ok_or_else(|| RetrievalError::Missing("type".to_string()))
+    }
+
     /// Get the `editor` and `editora` through `editorc` fields and their
     /// respective `editortype` annotation fields, returning a vector with zero
     /// to four entries, one for each editorial role.
@@ -789,26 +1550,94 @@ impl Entry {
     pub fn editors(&self) -> Result<Vec<(Vec<Person>, EditorType)>, TypeError> {
         let mut editors = vec![];
 
-        let mut parse = |name_field: &str, editor_field: &str| -> Result<(), TypeError> {
+        for (name_field, type_field) in EDITOR_FIELDS {
             if let Some(persons) = convert_result(self.get_as::<Vec<Person>>(name_field))?
             {
                 let editor_type = self
-                    .get(editor_field)
+                    .get_non_empty(type_field)
                     .map(|chunks| chunks.parse::<EditorType>())
                     .transpose()?
                     .unwrap_or(EditorType::Editor);
                 editors.push((persons, editor_type));
             }
+        }
 
-            Ok(())
-        };
+        Ok(editors)
+    }
+
+    /// Set the `editor` and `editora` through `editorc` fields and their
+    /// respective `editortype` annotation fields from a list of up to four
+    /// editorial roles.
+    ///
+    /// Unused editor fields are removed. The type field is omitted for the
+    /// default `EditorType::Editor`. Roles beyond the fourth are ignored.
+    pub fn set_editors(&mut self, editors: Vec<(Vec<Person>, EditorType)>) {
+        let mut editors = editors.into_iter();
+        for (name_field, type_field) in EDITOR_FIELDS {
+            self.remove(name_field);
+            self.remove(type_field);
+
+            if let Some((persons, editor_type)) = editors.next() {
+                self.set_as(name_field, &persons);
+                if editor_type != EditorType::Editor {
+                    self.set_as(type_field, &editor_type);
+                }
+            }
+        }
+    }
 
-        parse("editor", "editortype")?;
-        parse("editora", "editoratype")?;
-        parse("editorb", "editorbtype")?;
-        parse("editorc", "editorctype")?;
+    /// Get the list of names used to label the entry in citations, resolved
+    /// like the default `labelname` of BibLaTeX: The first present field out
+    /// of `shortauthor`, `author`, `shorteditor`, `editor`, and `translator`.
+    pub fn label_name(&self) -> Result<Vec<Person>, RetrievalError> {
+        for field in LABEL_NAME_FIELDS {
+            if let Some(chunks) = self.get_non_empty(field) {
+                return chunks.parse::<Vec<Person>>().map_err(Into::into);
+            }
+        }
 
-        Ok(editors)
+        Err(RetrievalError::Missing("author".to_string()))
+    }
+
+    /// Get the names the entry is sorted by: The `sortname` field, falling
+    /// back on `author`, `editor`, and `translator` like the default sorting
+    /// schemes of BibLaTeX.
+    pub fn sorting_name(&self) -> Result<Vec<Person>, RetrievalError> {
+        for field in SORTING_NAME_FIELDS {
+            if let Some(chunks) = self.get_non_empty(field) {
+                return chunks.parse::<Vec<Person>>().map_err(Into::into);
+            }
+        }
+
+        Err(RetrievalError::Missing("sortname".to_string()))
+    }
+
+    /// Get the title the entry is sorted by: The `sorttitle` field, falling
+    /// back on `title`.
+    pub fn sorting_title(&self) -> Result<ChunksRef<'_>, RetrievalError> {
+        self.get_non_empty("sorttitle")
+            .or_else(|| self.get("title"))
+            .ok_or_else(|| RetrievalError::Missing("sorttitle".to_string()))
+    }
+
+    /// Get the year the entry is sorted by: The `sortyear` field, falling back
+    /// on the (start) year of the `date` field or the `year` field.
+    pub fn sorting_year(&self) -> Result<i64, RetrievalError> {
+        if self.get_non_empty("sortyear").is_some() {
+            return self.sort_year();
+        }
+
+        match self.date()? {
+            PermissiveType::Typed(date) => Ok(match date.value {
+                DateValue::At(start)
+                | DateValue::After(start)
+                | DateValue::Between(start, _) => start.year as i64,
+                DateValue::Before(end) => end.year as i64,
+            }),
+            PermissiveType::Chunks(chunks) => {
+                Err(TypeError::new(chunks.span(), TypeErrorKind::InvalidFormat).into())
+            }
+        }
     }
 
     // BibLaTeX supplemental fields.
@@ -824,14 +1653,22 @@ impl Entry {
         book_title_addon: "booktitleaddon",
         commentator: "commentator" => Vec<Person>,
         doi: "doi" => String,
+        editor_type: "editortype" => EditorType,
+        editor_a: "editora" => Vec<Person>,
+        editor_a_type: "editoratype" => EditorType,
+        editor_b: "editorb" => Vec<Person>,
+        editor_b_type: "editorbtype" => EditorType,
+        editor_c: "editorc" => Vec<Person>,
+        editor_c_type: "editorctype" => EditorType,
         eid: "eid",
-        entry_subtype: "entrysubtype",
+        entry_set: "entryset" => Vec<String>,
+        entry_subtype: "entrysubtype" => EntrySubtype,
         eprint: "eprint" => String,
-        eprint_class: "eprintclass",
         eventtitle: "eventtitle",
         eventtitle_addon: "eventtitleaddon",
         foreword: "foreword" => Vec<Person>,
         holder: "holder" => Vec<Person>,
+        ids: "ids" => Vec<String>,
         index_title: "indextitle",
         introduction: "introduction" => Vec<Person>,
         isan: "isan",
@@ -848,6 +1685,7 @@ impl Entry {
         journal_title_addon: "journaltitleaddon",
         keywords: "keywords",
         label: "label",
+        lang_id_opts: "langidopts",
         language: "language" => String,
         library: "library",
         main_subtitle: "mainsubtitle",
@@ -856,11 +1694,18 @@ impl Entry {
         name_addon: "nameaddon",
         options: "options",
         orig_language: "origlanguage" => String,
-        orig_location: "origlocation",
+        orig_location: "origlocation" => Vec<Chunks>,
+        orig_publisher: "origpublisher" => Vec<Chunks>,
+        orig_title: "origtitle",
         page_total: "pagetotal",
         pagination: "pagination" => Pagination,
-        part: "part",
+        part: "part" => PermissiveType<i64>,
+        presort: "presort" => String,
         pubstate: "pubstate",
+        related: "related" => Vec<String>,
+        related_options: "relatedoptions",
+        related_string: "relatedstring",
+        related_type: "relatedtype" => String,
         reprint_title: "reprinttitle",
         short_author: "shortauthor" => Vec<Person>,
         short_editor: "shorteditor" => Vec<Person>,
@@ -869,6 +1714,10 @@ impl Entry {
         short_journal: "shortjournal",
         short_series: "shortseries",
         short_title: "shorttitle",
+        sort_name: "sortname" => Vec<Person>,
+        sort_shorthand: "sortshorthand",
+        sort_title: "sorttitle",
+        sort_year: "sortyear" => i64,
         subtitle: "subtitle",
         title_addon: "titleaddon",
         translator: "translator" => Vec<Person>,
@@ -876,10 +1725,35 @@ impl Entry {
         venue: "venue",
         version: "version",
         volumes: "volumes" => i64,
+        xdata: "xdata" => Vec<String>,
+        xref: "xref" => String,
         gender: "gender" => Gender,
     }
+
+    // biblatex-apa fields.
+    fields! {
+        amendment: "amendment",
+        article_no: "articleno",
+        citation: "citation",
+        source: "source",
+    }
 }
 
+/// The fields considered for the label name list in order of precedence.
+const LABEL_NAME_FIELDS: [&str; 5] =
+    ["shortauthor", "author", "shorteditor", "editor", "translator"];
+
+/// The fields considered for the sorting name list in order of precedence.
+const SORTING_NAME_FIELDS: [&str; 4] = ["sortname", "author", "editor", "translator"];
+
+/// The fields holding editor names and their respective editorial roles.
+const EDITOR_FIELDS: [(&str, &str); 4] = [
+    ("editor", "editortype"),
+    ("editora", "editoratype"),
+    ("editorb", "editorbtype"),
+    ("editorc", "editorctype"),
+];
+
 type Span = std::ops::Range<usize>;
 
 /// A value with the span it corresponds to in the source code.
@@ -996,9 +1870,12 @@ mod tests {
 
         let bibliography = Bibliography::parse(&contents).unwrap();
         let rashid = bibliography.get("rashid2016").unwrap();
-        match rashid.pagination() {
+        assert_eq!(rashid.pagination(), Ok(Pagination::Other("printed".into())));
+
+        let bibliography = Bibliography::parse("@book{a, pagination = { }}").unwrap();
+        match bibliography.get("a").unwrap().pagination() {
             Err(RetrievalError::TypeError(s)) => {
-                assert_eq!(s, TypeError::new(352..359, TypeErrorKind::UnknownPagination));
+                assert_eq!(s.kind, TypeErrorKind::UnknownPagination);
             }
             _ => {
                 panic!()
@@ -1006,6 +1883,114 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_mutation() {
+        let contents = r#"@book{a, title = {A}, ids = {alpha}}
+          @book{b, title = {B}}
+          @article{c, title = {C}, ids = {gamma}}
+          @article{d, title = {D}}"#;
+        let mut bibliography = Bibliography::parse(contents).unwrap();
+
+        let duplicate = Entry::new("alpha".into(), EntryType::Misc);
+        assert!(bibliography.try_insert(duplicate).is_err());
+        assert!(bibliography
+            .try_insert(Entry::new("e".into(), EntryType::Misc))
+            .is_ok());
+        assert_eq!(bibliography.len(), 5);
+
+        let mut new = Entry::new("f".into(), EntryType::Misc);
+        new.set_ids(vec!["phi".into()]);
+        assert!(bibliography.replace("b", new.clone()).is_ok());
+        assert_eq!(bibliography.get("b"), None);
+        assert_eq!(bibliography.get("phi").unwrap().key, "f");
+        assert!(bibliography
+            .replace("f", Entry::new("c".into(), EntryType::Misc))
+            .is_err());
+        assert!(bibliography.replace("missing", new).is_err());
+
+        bibliography.retain(|entry| entry.entry_type != EntryType::Book);
+        assert_eq!(bibliography.keys().collect::<Vec<_>>(), ["f", "c", "d", "e"]);
+        assert_eq!(bibliography.get("alpha"), None);
+        assert_eq!(bibliography.get("gamma").unwrap().key, "c");
+        assert_eq!(bibliography.get("e").unwrap().key, "e");
+        assert_eq!(bibliography.get("phi").unwrap().key, "f");
+    }
+
+    #[test]
+    fn test_rename_key() {
+        let contents = r#"@xdata{pub, publisher = {Macmillan}}
+          @mvbook{works, title = {Works}, ids = {opera}}
+          @book{vol, title = {Volume}, crossref = {works}, related = {other, works}}
+          @set{set, entryset = {works, vol}}
+          @misc{other, xref = {works}, ids = {works2}}"#;
+        let mut bibliography = Bibliography::parse(contents).unwrap();
+
+        assert!(!bibliography.rename_key("works", "vol"));
+        assert!(!bibliography.rename_key("opera", "collected"));
+        assert!(!bibliography.rename_key("missing", "collected"));
+        assert!(bibliography.rename_key("works", "collected"));
+
+        assert_eq!(bibliography.get("works"), None);
+        assert_eq!(bibliography.get("opera").unwrap().key, "collected");
+        let vol = bibliography.get("vol").unwrap();
+        assert_eq!(vol.crossref().unwrap(), "collected");
+        assert_eq!(vol.related().unwrap(), ["other", "collected"]);
+        let set = bibliography.get("set").unwrap();
+        assert_eq!(set.entry_set().unwrap(), ["collected", "vol"]);
+        let other = bibliography.get("other").unwrap();
+        assert_eq!(other.xref().unwrap(), "collected");
+        assert_eq!(other.ids().unwrap(), ["works2"]);
+
+        let mut aliased =
+            Bibliography::parse("@book{a, ids = {x, y}} @misc{b, ids = {a, z}}").unwrap();
+        assert!(aliased.rename_key("a", "c"));
+        assert_eq!(aliased.get("a"), None);
+        assert_eq!(aliased.get("x").unwrap().key, "c");
+        assert_eq!(aliased.get("y").unwrap().key, "c");
+        assert_eq!(aliased.get("c").unwrap().key, "c");
+        assert_eq!(aliased.get("z").unwrap().key, "b");
+        assert_eq!(aliased.alias_conflicts(), [("c".into(), "b".into())]);
+
+        bibliography.get_mut("vol").unwrap().set_xdata(vec!["pub".into()]);
+        assert!(bibliography.rename_key("pub", "macmillan"));
+        assert_eq!(bibliography.xdata("macmillan").unwrap().key, "macmillan");
+        assert_eq!(bibliography.get("vol").unwrap().xdata().unwrap(), ["macmillan"]);
+
+        // The formatting of the rewritten fields is kept. The granular
+        // reference cannot be resolved because there is no `title`.
+        let contents = r#"@xdata{pub, publisher = {Macmillan}}
+          @book{a, related = {b, {Works}, works}, note = {xdata=pub-title}}
+          @book{works,}"#;
+        let mut bibliography = Bibliography::parse(contents).unwrap();
+        assert!(bibliography.rename_key("works", "opera"));
+        assert!(bibliography.rename_key("pub", "macmillan"));
+
+        let a = bibliography.get("a").unwrap();
+        let related = a.get("related").unwrap();
+        assert_eq!(related.to_biblatex_string(false), "{b, {Works}, opera}");
+        assert!(!related.iter().any(|chunk| chunk.is_detached()));
+        assert_eq!(a.note().unwrap().format_verbatim(), "xdata=macmillan-title");
+    }
+
+    #[test]
+    fn test_large_bibliography() {
+        let mut bibliography = Bibliography::new();
+        for i in 0..10_000 {
+            let mut entry = Entry::new(format!("key{i}"), EntryType::Misc);
+            entry.set_ids(vec![format!("alias{i}")]);
+            bibliography.insert(entry);
+        }
+
+        assert_eq!(bibliography.len(), 10_000);
+        assert_eq!(bibliography.get("key4711").unwrap().key, "key4711");
+        assert_eq!(bibliography.get("alias9999").unwrap().key, "key9999");
+
+        bibliography.remove("key0");
+        assert_eq!(bibliography.get("alias0"), None);
+        assert_eq!(bibliography.get("alias1").unwrap().key, "key1");
+        assert_eq!(bibliography.get("key9999").unwrap().key, "key9999");
+    }
+
     #[test]
     fn test_keys() {
         let contents = fs::read_to_string("tests/editortypes.bib").unwrap();
@@ -1063,6 +2048,252 @@ mod tests {
         assert_eq!(entry.entry_type, EntryType::Misc);
     }
 
+    #[test]
+    fn test_entry_types() {
+        assert_eq!(EntryType::new("Article"), EntryType::Article);
+        assert_eq!(EntryType::new("conference"), EntryType::InProceedings);
+        assert_eq!(EntryType::new("www"), EntryType::Online);
+        assert_eq!(EntryType::new("WebPage"), EntryType::Online);
+        assert_eq!(EntryType::new("bibnote"), EntryType::BibNote);
+        assert_eq!(EntryType::new("customc"), EntryType::CustomC);
+        assert_eq!(EntryType::new("Gadget"), EntryType::Unknown("gadget".into()));
+
+        assert_eq!(EntryType::InProceedings.to_string(), "inproceedings");
+        assert_eq!(EntryType::Unknown("gadget".into()).to_string(), "gadget");
+        assert_eq!(EntryType::Video.to_bibtex(), EntryType::Misc);
+        assert_eq!(EntryType::Video.to_biblatex(), EntryType::Video);
+    }
+
+    #[test]
+    fn test_software_and_dataset() {
+        let raw = r#"@software{tool, author = {Doe, Jane}, title = {Tool},
+            version = {2.1.0}, date = {2023}, doi = {10.5281/zenodo.1},
+            url = {https://example.com}, urldate = {2024-01-05},
+            organization = {ACME}, license = {MIT}}
+          @dataset{data, editor = {Roe, Richard}, title = {Data}, version = {3}}"#;
+        let bibliography = Bibliography::parse(raw).unwrap();
+
+        let tool = bibliography.get("tool").unwrap();
+        assert_eq!(tool.entry_type, EntryType::Software);
+        assert_eq!(tool.version().unwrap().format_verbatim(), "2.1.0");
+        assert_eq!(tool.doi().unwrap(), "10.5281/zenodo.1");
+        assert_eq!(tool.organization().unwrap()[0].format_verbatim(), "ACME");
+        assert!(tool.verify().is_ok());
+        assert!(tool.validate().is_empty());
+        assert!(tool.to_biblatex_string().starts_with("@software{tool,"));
+        assert!(EntryType::Software.requirements().optional.contains(&"version"));
+
+        let data = bibliography.get("data").unwrap();
+        assert_eq!(data.entry_type, EntryType::Dataset);
+        let report = data.verify();
+        assert_eq!(report.missing, ["year"]);
+        assert!(data.to_bibtex_string().unwrap().starts_with("@misc{data,"));
+    }
+
+    #[test]
+    fn test_apa_fields() {
+        let raw = r#"@article{a, articleno = {e0123}, source = {Original work},
+            citation = {123 U.S. 456}, amendment = {U.S. Const. amend. XIV}}"#;
+        let bibliography = Bibliography::parse(raw).unwrap();
+
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.article_no().unwrap().format_verbatim(), "e0123");
+        assert_eq!(a.source().unwrap().format_verbatim(), "Original work");
+        assert_eq!(a.citation().unwrap().format_verbatim(), "123 U.S. 456");
+        assert_eq!(a.amendment().unwrap().format_verbatim(), "U.S. Const. amend. XIV");
+        assert!(a
+            .typed_fields()
+            .all(|field| !matches!(field, Ok(TypedField::Unknown(..)))));
+    }
+
+    #[test]
+    fn test_alias_conflicts() {
+        let raw = r#"@misc{a, title = {A}, ids = {b, c}}
+          @misc{b, title = {B}, ids = {c, d}}"#;
+
+        let mut bibliography = Bibliography::parse(raw).unwrap();
+        assert_eq!(bibliography.len(), 2);
+        assert_eq!(bibliography.get("b").unwrap().key, "b");
+        assert_eq!(bibliography.get("c").unwrap().key, "a");
+        assert_eq!(bibliography.get("d").unwrap().key, "b");
+        assert_eq!(
+            bibliography.alias_conflicts(),
+            vec![("b".to_string(), "a".to_string()), ("c".to_string(), "b".to_string()),]
+        );
+
+        let mut entry = Entry::new("a".into(), EntryType::Book);
+        entry.set_ids(vec!["e".into()]);
+        let prev = bibliography.insert(entry).unwrap();
+        assert_eq!(prev.entry_type, EntryType::Misc);
+        assert_eq!(bibliography.len(), 2);
+
+        assert!(Bibliography::parse("@misc{a, ids = {x}} @misc{a, title = {A}}").is_err());
+    }
+
+    #[test]
+    fn test_insert_drops_old_aliases() {
+        let mut bibliography = Bibliography::parse("@misc{a, ids = {b, c}}").unwrap();
+        let mut entry = Entry::new("a".into(), EntryType::Book);
+        entry.set_ids(vec!["c".into(), "d".into()]);
+        bibliography.insert(entry);
+
+        assert!(bibliography.get("b").is_none());
+        assert_eq!(bibliography.get("c").unwrap().entry_type, EntryType::Book);
+        assert_eq!(bibliography.get("d").unwrap().key, "a");
+        assert_eq!(bibliography.keys().collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn test_detached_ids() {
+        let mut entry = Entry::new("a".into(), EntryType::Book);
+        entry.set_ids(vec!["e".into(), "f".into()]);
+        assert_eq!(entry.ids(), Ok(vec!["e".to_string(), "f".to_string()]));
+
+        let mut bibliography = Bibliography::new();
+        bibliography.insert(entry);
+        assert_eq!(bibliography.get("f").unwrap().key, "a");
+    }
+
+    #[test]
+    fn test_related() {
+        let raw = r#"@book{orig, title = {Der Process}}
+          @book{trans, title = {The Trial}, related = {orig}, relatedtype = {translationof}}
+          @book{dangling, title = {Nothing}, related = {orig, nowhere}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let orig = bibliography.get("orig").unwrap();
+        assert_eq!(bibliography.related(orig), Ok(None));
+
+        let related = bibliography.related(bibliography.get("trans").unwrap());
+        let related = related.unwrap().unwrap();
+        assert_eq!(related.entries, vec![orig]);
+        assert_eq!(related.related_type.as_deref(), Some("translationof"));
+        assert_eq!(related.related_string, None);
+
+        let dangling = bibliography.get("dangling").unwrap();
+        let err = bibliography.related(dangling).unwrap_err();
+        assert_eq!(err.kind, TypeErrorKind::UnknownEntryKey);
+        assert_eq!(&raw[err.span], "nowhere");
+    }
+
+    #[test]
+    fn test_preamble() {
+        let raw = r#"@preamble{"\newcommand{\noop}[1]{}"}
+          @preamble{"\newcommand{\sortas}[1]{}"}
+          @book{a, title = {\noop{b}A}}"#;
+
+        let mut bibliography = Bibliography::parse(raw).unwrap();
+        let preamble = r#""\newcommand{\noop}[1]{}" # "\newcommand{\sortas}[1]{}""#;
+        assert_eq!(bibliography.preamble(), preamble);
+
+        let written = bibliography.to_biblatex_string();
+        assert!(written.starts_with("@preamble{"));
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.preamble(), preamble);
+        assert_eq!(reparsed.len(), 1);
+
+        bibliography.set_preamble("");
+        assert!(bibliography.to_bibtex_string().starts_with("@book{a,"));
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let content = |bibliography: &Bibliography| -> Vec<_> {
+            bibliography
+                .iter()
+                .map(|entry| {
+                    let mut entry = entry.clone();
+                    entry.normalize_aliases();
+                    entry.entry_type = entry.entry_type.to_biblatex();
+                    let fields: Vec<_> = entry
+                        .fields
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.format_verbatim()))
+                        .collect();
+                    (entry.key, entry.entry_type, fields)
+                })
+                .collect()
+        };
+
+        for path in ["tests/gral.bib", "tests/cross.bib", "tests/polaritons.bib"] {
+            let contents = fs::read_to_string(path).unwrap();
+            let bibliography = Bibliography::parse(&contents).unwrap();
+
+            let written = bibliography.to_biblatex_string();
+            let reparsed = Bibliography::parse(&written).unwrap();
+            assert_eq!(content(&reparsed), content(&bibliography));
+
+            let written = RawBibliography::parse(&contents).unwrap().to_biblatex_string();
+            let reparsed = Bibliography::parse(&written).unwrap();
+            assert_eq!(content(&reparsed), content(&bibliography));
+        }
+    }
+
+    #[test]
+    fn test_entry_display() {
+        let contents = fs::read_to_string("tests/gral.bib").unwrap();
+        let bibliography = Bibliography::parse(&contents).unwrap();
+        let entry = bibliography.get("kim2009").unwrap();
+
+        let displayed = entry.to_string();
+        assert_eq!(displayed, entry.to_biblatex_string());
+        assert!(displayed.starts_with("@inproceedings{kim2009,\n"));
+        assert!(displayed.ends_with("\n}"));
+
+        let reparsed = Bibliography::parse(&displayed).unwrap();
+        assert_eq!(reparsed.len(), 1);
+        assert_eq!(reparsed.get("kim2009").unwrap().to_string(), displayed);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let contents = fs::read_to_string("tests/gral.bib").unwrap();
+        let bibliography = Bibliography::parse(&contents).unwrap();
+        let json = serde_json::to_string(&bibliography).unwrap();
+        let deserialized: Bibliography = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, bibliography);
+
+        let entry = bibliography.get("kim2009").unwrap();
+        let authors = entry.author().unwrap();
+        let json = serde_json::to_string(&authors).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Person>>(&json).unwrap(), authors);
+        let date = entry.date().unwrap();
+        let json = serde_json::to_string(&date).unwrap();
+        assert_eq!(serde_json::from_str::<PermissiveType<Date>>(&json).unwrap(), date);
+
+        let err = Bibliography::parse("@article{a, title = {A}").unwrap_err();
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(serde_json::from_str::<ParseError>(&json).unwrap(), err);
+
+        let raw = RawBibliography::parse(&contents).unwrap();
+        let json = serde_json::to_string(&raw).unwrap();
+        let deserialized: RawBibliography = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.to_biblatex_string(), raw.to_biblatex_string());
+        let json = serde_json::to_string(&RawChunk::Normal("Title".into())).unwrap();
+        let chunk: RawChunk = serde_json::from_str(&json).unwrap();
+        assert!(matches!(chunk, RawChunk::Normal(std::borrow::Cow::Borrowed("Title"))));
+
+        let index = bibliography.search_index();
+        let json = serde_json::to_string(&index).unwrap();
+        let deserialized: SearchIndex = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.search("graph"), index.search("graph"));
+
+        let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+        let json = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(serde_json::from_str::<KeyPattern>(&json).unwrap(), pattern);
+        let spec = InheritanceSpec::default();
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(serde_json::from_str::<InheritanceSpec>(&json).unwrap(), spec);
+        let model = DataModel::default();
+        let json = serde_json::to_string(&model).unwrap();
+        assert_eq!(serde_json::from_str::<DataModel>(&json).unwrap(), model);
+        let labels = bibliography.numeric_labels(["kim2009"]);
+        let json = serde_json::to_string(&labels).unwrap();
+        assert_eq!(serde_json::from_str::<NumericLabels>(&json).unwrap(), labels);
+        assert!(serde_json::to_string(&EntryType::Article.requirements()).is_ok());
+    }
+
     #[test]
     fn test_bibtex_conversion() {
         let contents = fs::read_to_string("tests/cross.bib").unwrap();
@@ -1080,6 +2311,102 @@ mod tests {
         assert!(!bibtex.contains("date"));
     }
 
+    #[test]
+    fn test_setter_round_trip() {
+        let mut entry = Entry::new("roundtrip".into(), EntryType::Book);
+        let authors = vec![
+            Person {
+                name: "Beethoven".into(),
+                given_name: "Ludwig".into(),
+                prefix: "van".into(),
+                suffix: "".into(),
+            },
+            Person {
+                name: "King".into(),
+                given_name: "Martin Luther".into(),
+                prefix: "".into(),
+                suffix: "Jr.".into(),
+            },
+            Person {
+                name: "Barnes and Noble".into(),
+                given_name: "".into(),
+                prefix: "".into(),
+                suffix: "".into(),
+            },
+        ];
+        let title =
+            vec![Spanned::detached(Chunk::Normal("Rock & Roll: 50% {off}".into()))];
+        let date =
+            Date::parse(&[Spanned::detached(Chunk::Normal("2019-10/2020-03~".into()))])
+                .unwrap();
+        let pages = PermissiveType::Typed(vec![5..5, 10..20]);
+        let publisher = vec![
+            vec![Spanned::detached(Chunk::Normal("Springer".into()))],
+            vec![Spanned::detached(Chunk::Normal("Wiley, Inc.".into()))],
+        ];
+
+        entry.set_author(authors.clone());
+        entry.set_title(title.clone());
+        entry.set_date(PermissiveType::Typed(date));
+        entry.set_pages(pages.clone());
+        entry.set_publisher(publisher.clone());
+        entry.set_url("https://example.org/a_b%20c#d".into());
+        entry.set_ids(vec!["alias1".into(), "alias2".into()]);
+        entry.set_volume(PermissiveType::Typed(3));
+        entry.set_pagination(Pagination::Verse);
+
+        let src = entry.to_biblatex_string();
+        let bibliography = Bibliography::parse(&src).unwrap();
+        let parsed = bibliography.get("roundtrip").unwrap();
+
+        assert_eq!(parsed.author().unwrap(), authors);
+        assert_eq!(parsed.title().unwrap().format_verbatim(), title.format_verbatim());
+        assert_eq!(parsed.date().unwrap(), PermissiveType::Typed(date));
+        assert_eq!(parsed.pages().unwrap(), pages);
+        assert_eq!(
+            parsed
+                .publisher()
+                .unwrap()
+                .iter()
+                .map(|p| p.format_verbatim())
+                .collect::<Vec<_>>(),
+            vec!["Springer", "Wiley, Inc."]
+        );
+        assert_eq!(parsed.url().unwrap(), "https://example.org/a_b%20c#d");
+        assert_eq!(parsed.ids().unwrap(), vec!["alias1", "alias2"]);
+        assert_eq!(parsed.volume().unwrap(), PermissiveType::Typed(3));
+        assert_eq!(parsed.pagination().unwrap(), Pagination::Verse);
+    }
+
+    #[test]
+    fn test_normalize_aliases() {
+        let raw = r#"@article{a, journal = {Nature}, primaryclass = {cs.DL},
+            address = {London}, location = {Berlin}, hyphenation = {british}}"#;
+
+        let mut bibliography = Bibliography::parse(raw).unwrap();
+        let entry = bibliography.get_mut("a").unwrap();
+        assert_eq!(entry.eprint_class().unwrap().format_verbatim(), "cs.DL");
+        assert_eq!(entry.lang_id(), Ok("british".to_string()));
+
+        let biblatex = entry.to_biblatex_string();
+        assert!(biblatex.contains("journaltitle = {Nature},"));
+        assert!(biblatex.contains("address = {London},"));
+
+        let applied = entry.normalize_aliases();
+        assert_eq!(
+            applied,
+            vec![
+                ("hyphenation".to_string(), "langid".to_string()),
+                ("journal".to_string(), "journaltitle".to_string()),
+                ("primaryclass".to_string(), "eprintclass".to_string()),
+            ]
+        );
+        assert!(entry.get("journal").is_none());
+        assert_eq!(entry.journal_title().unwrap().format_verbatim(), "Nature");
+        assert_eq!(entry.get("address").unwrap().format_verbatim(), "London");
+        assert_eq!(entry.location().unwrap()[0].format_verbatim(), "Berlin");
+    }
+
     #[test]
     fn test_verify() {
         let contents = fs::read_to_string("tests/cross.bib").unwrap();
@@ -1102,18 +2429,163 @@ mod tests {
         assert_eq!(report.malformed[0].0.as_str(), "gender");
     }
 
+    #[test]
+    fn test_xdata() {
+        let contents = r#"@xdata{people, author = {Doe, Jane and Roe, Richard}}
+          @xdata{pub, publisher = {Macmillan}, location = {New York and London},
+            xdata = {people}}
+          @book{whole, title = {Whole}, xdata = {pub}}
+          @book{granular, title = {Granular},
+            author = {Poe, Edgar and xdata=people-author-2},
+            location = {xdata=pub-location-1}, publisher = {xdata=pub-publisher},
+            note = {xdata=missing-note}}"#;
+        let bibliography = Bibliography::parse(contents).unwrap();
+
+        assert_eq!(bibliography.len(), 2);
+        assert_eq!(bibliography.xdata_entries().count(), 2);
+        assert_eq!(bibliography.get("pub"), None);
+        assert!(bibliography.xdata("pub").unwrap().get("author").is_some());
+
+        let whole = bibliography.get("whole").unwrap();
+        assert_eq!(whole.publisher().unwrap()[0].format_verbatim(), "Macmillan");
+        assert_eq!(whole.author().unwrap()[1].name, "Roe");
+        assert_eq!(whole.get("xdata"), None);
+
+        let granular = bibliography.get("granular").unwrap();
+        let authors = granular.author().unwrap();
+        assert_eq!(authors.len(), 2);
+        assert_eq!(authors[0].name, "Poe");
+        assert_eq!(authors[1].name, "Roe");
+        assert_eq!(granular.location().unwrap().len(), 1);
+        assert_eq!(granular.location().unwrap()[0].format_verbatim(), "New York");
+        assert_eq!(granular.publisher().unwrap()[0].format_verbatim(), "Macmillan");
+        assert_eq!(granular.note().unwrap().format_verbatim(), "xdata=missing-note");
+
+        let biblatex = bibliography.to_biblatex_string();
+        assert!(biblatex.starts_with("@xdata{people,"));
+        assert!(!bibliography.to_bibtex_string().contains("@xdata"));
+    }
+
+    #[test]
+    fn test_reference_chains() {
+        let contents = r#"@xdata{x1, publisher = {Macmillan}}
+          @xdata{x2, xdata = {x1}, location = {London}}
+          @mvbook{grand, title = {Collected Works}, xdata = {x2}}
+          @book{parent, title = {Volume}, crossref = {grand}}
+          @inbook{child, title = {Chapter}, crossref = {parent}}"#;
+        let bibliography = Bibliography::parse(contents).unwrap();
+        let child = bibliography.get("child").unwrap();
+        assert_eq!(child.book_title().unwrap().format_verbatim(), "Volume");
+        assert_eq!(child.main_title().unwrap().format_verbatim(), "Collected Works");
+        assert_eq!(child.publisher().unwrap()[0].format_verbatim(), "Macmillan");
+        assert_eq!(child.location().unwrap()[0].format_verbatim(), "London");
+
+        let cycle = |contents: &str| {
+            let err = Bibliography::parse(contents).unwrap_err();
+            assert_eq!(
+                err.kind,
+                ParseErrorKind::ResolutionError(TypeErrorKind::CyclicReference)
+            );
+        };
+
+        cycle("@book{a, crossref = {a}}");
+        cycle(
+            "@book{a, crossref = {b}} @book{b, crossref = {c}} @book{c, crossref = {a}}",
+        );
+        cycle("@xdata{a, xdata = {b}} @xdata{b, xdata = {a}} @book{c, xdata = {a}}");
+        cycle("@xdata{a, note = {xdata=a-title}}");
+
+        let contents = r#"@xdata{x, title = {Tom and Jerry}, publisher = {A and B}}
+          @book{a, title = {xdata=x-title-1}}"#;
+        let err = Bibliography::parse(contents).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::ResolutionError(TypeErrorKind::NotAList));
+
+        let contents = r#"@xdata{x, title = {Tom and Jerry}, publisher = {A and B}}
+          @book{a, title = {Cats and xdata=x-title}, publisher = {xdata=x-publisher-2}}"#;
+        let bibliography = Bibliography::parse(contents).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.title().unwrap().format_verbatim(), "Cats and xdata=x-title");
+        assert_eq!(a.publisher().unwrap()[0].format_verbatim(), "B");
+    }
+
+    #[test]
+    fn test_shared_ancestors() {
+        // Every level references both entries of the level below, so there
+        // are 2^40 paths to the root.
+        let mut contents = String::from("@xdata{a0, note = {Root}} @xdata{b0,}\n");
+        for i in 1..=40 {
+            let j = i - 1;
+            contents += &format!(
+                "@xdata{{a{i}, xdata = {{a{j}, b{j}}}}} @xdata{{b{i}, xdata = {{a{j}, b{j}}}}}\n"
+            );
+        }
+        contents += "@book{c, crossref = {d}} @book{d, xdata = {a40, b40}}";
+
+        let bibliography = Bibliography::parse(&contents).unwrap();
+        let c = bibliography.get("c").unwrap();
+        assert_eq!(c.note().unwrap().format_verbatim(), "Root");
+        assert_eq!(
+            bibliography.xdata("b40").unwrap().note().unwrap().format_verbatim(),
+            "Root"
+        );
+
+        // The same holds for granular references.
+        let mut contents = String::from("@xdata{x0, note = {Root}}\n");
+        for i in 1..=40 {
+            let j = i - 1;
+            contents += &format!(
+                "@xdata{{x{i}, note = {{xdata=x{j}-note}}, title = {{xdata=x{j}-note}}}}\n"
+            );
+        }
+        contents += "@book{c, note = {xdata=x40-note}, title = {xdata=x40-title}}";
+
+        let bibliography = Bibliography::parse(&contents).unwrap();
+        let c = bibliography.get("c").unwrap();
+        assert_eq!(c.note().unwrap().format_verbatim(), "Root");
+        assert_eq!(c.title().unwrap().format_verbatim(), "Root");
+    }
+
+    #[test]
+    fn test_standalone_parents() {
+        let contents = r#"@proceedings{conf, title = {Conference}}
+          @proceedings{other, title = {Other}}
+          @inproceedings{a, title = {A}, crossref = {conf}, ids = {alias}}
+          @inproceedings{b, title = {B}, crossref = {conf}}
+          @inproceedings{c, title = {C}, crossref = {other}}"#;
+        let bibliography = Bibliography::parse(contents).unwrap();
+        let keys = |parents: Vec<&Entry>| {
+            parents.into_iter().map(|e| e.key.clone()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(keys(bibliography.standalone_parents(["a", "b", "c"], 2)), ["conf"]);
+        assert_eq!(
+            keys(bibliography.standalone_parents(["a", "alias"], 2)),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            keys(bibliography.standalone_parents(["c", "a"], 1)),
+            ["other", "conf"]
+        );
+        assert_eq!(
+            keys(bibliography.standalone_parents(["a", "b", "conf"], 2)),
+            Vec::<String>::new()
+        );
+    }
+
     #[test]
     fn test_crossref() {
         let contents = fs::read_to_string("tests/cross.bib").unwrap();
         let bibliography = Bibliography::parse(&contents).unwrap();
 
-        let e = bibliography.get("macmillan").unwrap();
+        assert_eq!(bibliography.get("macmillan"), None);
+        let e = bibliography.xdata("macmillan").unwrap();
         assert_eq!(e.publisher().unwrap()[0].format_verbatim(), "Macmillan");
-        assert_eq!(e.location().unwrap().format_verbatim(), "New York and London");
+        assert_eq!(e.location().unwrap()[0].format_verbatim(), "New York");
+        assert_eq!(e.location().unwrap()[1].format_verbatim(), "London");
 
         let book = bibliography.get("recursive").unwrap();
         assert_eq!(book.publisher().unwrap()[0].format_verbatim(), "Macmillan");
-        assert_eq!(book.location().unwrap().format_verbatim(), "New York and London");
+        assert_eq!(book.location().unwrap()[1].format_verbatim(), "London");
         assert_eq!(
             book.title().unwrap().format_verbatim(),
             "Recursive shennenigans and other important stuff"
@@ -1127,7 +2599,7 @@ mod tests {
         assert_eq!(arrgh.entry_type, EntryType::Article);
         assert_eq!(arrgh.volume().unwrap(), PermissiveType::Typed(115));
         assert_eq!(arrgh.editors().unwrap()[0].0[0].name, "Uhlig");
-        assert_eq!(arrgh.number().unwrap().format_verbatim(), "6");
+        assert_eq!(arrgh.number().unwrap(), PermissiveType::Typed(6));
         assert_eq!(
             arrgh.journal().unwrap().format_verbatim(),
             "Journal of Political Economy"
@@ -1293,6 +2765,275 @@ Martin}}"#;
         );
     }
 
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_chapter_ranges() {
+        let raw = r#"@inbook{test, chapter = {4--7}}
+          @inbook{test1, chapter = {3}}
+          @inbook{test2, chapter = {Appendix}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        assert_eq!(
+            bibliography.get("test").unwrap().chapter(),
+            Ok(PermissiveType::Typed(vec![4..7]))
+        );
+        assert_eq!(
+            bibliography.get("test1").unwrap().chapter(),
+            Ok(PermissiveType::Typed(vec![3..3]))
+        );
+        assert!(matches!(
+            bibliography.get("test2").unwrap().chapter(),
+            Ok(PermissiveType::Chunks(_))
+        ));
+    }
+
+    #[test]
+    fn test_edition() {
+        let raw = r#"@book{a, edition = 2}
+          @book{b, edition = {2nd}}
+          @book{c, edition = {Revised and expanded}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        assert_eq!(
+            bibliography.get("a").unwrap().edition(),
+            Ok(PermissiveType::Typed(2))
+        );
+        assert_eq!(
+            bibliography.get("b").unwrap().edition(),
+            Ok(PermissiveType::Typed(2))
+        );
+        match bibliography.get("c").unwrap().edition() {
+            Ok(PermissiveType::Chunks(chunks)) => {
+                assert_eq!(chunks.format_verbatim(), "Revised and expanded")
+            }
+            other => panic!("expected chunks, got {:?}", other),
+        }
+
+        assert_eq!(parse_ordinal("3rd"), Some(3));
+        assert_eq!(parse_ordinal("4."), Some(4));
+        assert_eq!(parse_ordinal("2e"), Some(2));
+        assert_eq!(parse_ordinal("2 Auflage"), None);
+        assert_eq!(parse_ordinal("nd"), None);
+    }
+
+    #[test]
+    fn test_linking_fields() {
+        let raw = r#"@inbook{a, title = {Chapter}, crossref = {b}, xref = {c},
+            ids = {alpha, beta}, related = {c,d}, relatedtype = {translationof},
+            langid = {ngerman}, origpublisher = {Springer and Wiley}}
+          @book{b, title = {Book}, entryset = {x, y}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.crossref(), Ok("b".to_string()));
+        assert_eq!(a.xref(), Ok("c".to_string()));
+        assert_eq!(a.ids(), Ok(vec!["alpha".to_string(), "beta".to_string()]));
+        assert_eq!(a.related(), Ok(vec!["c".to_string(), "d".to_string()]));
+        assert_eq!(a.related_type(), Ok("translationof".to_string()));
+        assert_eq!(a.lang_id(), Ok("ngerman".to_string()));
+        assert_eq!(a.orig_publisher().unwrap().len(), 2);
+        assert_eq!(
+            bibliography.get("b").unwrap().entry_set(),
+            Ok(vec!["x".to_string(), "y".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let contents = fs::read_to_string("tests/gral.bib").unwrap();
+        let bibliography = Bibliography::parse(&contents).unwrap();
+        let entry = bibliography.get("kim2009").unwrap();
+
+        let fields: Vec<_> = entry.typed_fields().collect::<Result<_, _>>().unwrap();
+        assert_eq!(fields.len(), entry.fields.len());
+        assert!(fields
+            .iter()
+            .zip(entry.fields.keys())
+            .all(|(field, key)| field.name() == key));
+        assert!(fields
+            .iter()
+            .any(|field| matches!(field, TypedField::Author(a) if a.len() == 8)));
+    }
+
+    #[test]
+    fn test_label_name() {
+        let raw = r#"@book{a, author = {World Health Organization},
+            shortauthor = {{WHO}}, shorttitle = {Report}, shorthand = {WHR}}
+          @collection{b, editor = {Doe, Jane}, translator = {Roe, Richard}}
+          @misc{c, title = {Anonymous}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.label_name().unwrap()[0].name, "WHO");
+        assert_eq!(a.short_author().unwrap()[0].name, "WHO");
+        assert_eq!(a.short_title().unwrap().format_verbatim(), "Report");
+        assert_eq!(a.shorthand().unwrap().format_verbatim(), "WHR");
+        assert_eq!(bibliography.get("b").unwrap().label_name().unwrap()[0].name, "Doe");
+        assert_eq!(
+            bibliography.get("c").unwrap().label_name(),
+            Err(RetrievalError::Missing("author".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_sorting_fields() {
+        let raw = r#"@book{a, author = {de Gaulle, Charles}, sortname = {Gaulle, Charles de},
+            title = {The Edge of the Sword}, sorttitle = {Edge of the Sword},
+            date = {1932/1934}, presort = {mm}, sortkey = {gaulle}}
+          @book{b, editor = {Doe, Jane}, title = {Collected Works}, year = {1990},
+            sortyear = {1989}, key = {doe}}
+          @misc{c, date = {../2001}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.sorting_name().unwrap()[0].name, "Gaulle");
+        assert_eq!(a.sorting_title().unwrap().format_verbatim(), "Edge of the Sword");
+        assert_eq!(a.sorting_year(), Ok(1932));
+        assert_eq!(a.presort(), Ok("mm".to_string()));
+        assert_eq!(a.sort_key(), Ok("gaulle".to_string()));
+
+        let b = bibliography.get("b").unwrap();
+        assert_eq!(b.sorting_name().unwrap()[0].name, "Doe");
+        assert_eq!(b.sorting_title().unwrap().format_verbatim(), "Collected Works");
+        assert_eq!(b.sorting_year(), Ok(1989));
+        assert_eq!(b.sort_key(), Ok("doe".to_string()));
+
+        let c = bibliography.get("c").unwrap();
+        assert_eq!(c.sorting_year(), Ok(2001));
+        assert!(c.sorting_name().is_err());
+        assert!(c.sorting_title().is_err());
+    }
+
+    #[test]
+    fn test_thesis_type() {
+        let raw = r#"@thesis{a, type = {Ph.D.\ thesis}, howpublished = {Printed
+              by   the author}}
+          @mastersthesis{b, title = {Something}}
+          @thesis{c, type = {Habilitationsschrift}}
+          @report{d, title = {Something}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.thesis_type(), Ok(ThesisType::PhdThesis));
+        assert_eq!(a.how_published_normalized().unwrap(), "Printed by the author");
+        assert_eq!(a.type_normalized().unwrap(), "phdthesis");
+        assert_eq!(
+            bibliography.get("b").unwrap().thesis_type(),
+            Ok(ThesisType::MaThesis)
+        );
+        assert_eq!(
+            bibliography.get("c").unwrap().thesis_type(),
+            Ok(ThesisType::Unknown("Habilitationsschrift".into()))
+        );
+        assert!(bibliography.get("d").unwrap().thesis_type().is_err());
+        assert_eq!(bibliography.get("b").unwrap().type_normalized().unwrap(), "mathesis");
+        assert_eq!(
+            bibliography.get("c").unwrap().type_normalized().unwrap(),
+            "Habilitationsschrift"
+        );
+        assert!(bibliography.get("d").unwrap().type_normalized().is_err());
+    }
+
+    #[test]
+    fn test_literal_lists() {
+        let raw = r#"@report{a, title = {Report},
+            institution = {{Barnes and Noble} and Anderson College},
+            organization = {{IEEE} and
+              ACM}, publisher = {Penguin}}
+          @phdthesis{b, school = {Technische Universität Berlin}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let format = |list: Vec<Chunks>| {
+            list.iter().map(|item| item.format_verbatim()).collect::<Vec<_>>()
+        };
+
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(
+            format(a.institution().unwrap()),
+            vec!["Barnes and Noble", "Anderson College"]
+        );
+        assert_eq!(format(a.organization().unwrap()), vec!["IEEE", "ACM"]);
+        assert_eq!(format(a.publisher().unwrap()), vec!["Penguin"]);
+
+        let b = bibliography.get("b").unwrap();
+        assert_eq!(
+            format(b.institution().unwrap()),
+            vec!["Technische Universität Berlin"]
+        );
+    }
+
+    #[test]
+    fn test_location() {
+        let raw = r#"@book{a, location = {Berlin and Heidelberg}, address = {Paris}}
+          @book{b, address = {New York and London}}
+          @book{c, title = {Nowhere}}"#;
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let a = bibliography.get("a").unwrap();
+        let (location, source) = a.location_with_source().unwrap();
+        assert_eq!(location.len(), 2);
+        assert_eq!(location[1].format_verbatim(), "Heidelberg");
+        assert_eq!(source, "location");
+
+        let b = bibliography.get("b").unwrap();
+        assert_eq!(b.location().unwrap()[0].format_verbatim(), "New York");
+        assert_eq!(b.location_with_source().unwrap().1, "address");
+        assert_eq!(
+            bibliography.get("c").unwrap().location_with_source(),
+            Err(RetrievalError::Missing("location".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_long_text_fields() {
+        let raw = "@misc{a, title = {A\n\nTitle}, abstract = {We study \\\"{u}mlauts.\n\n                   They are {\\em nice}.}}";
+
+        let bibliography = Bibliography::parse(raw).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.title().unwrap().format_paragraphs(), vec!["A Title"]);
+        assert_eq!(
+            a.abstract_().unwrap().format_paragraphs(),
+            vec!["We study ümlauts.", "They are \\em nice."]
+        );
+        assert_eq!(
+            a.abstract_().unwrap().format_verbatim(),
+            "We study ümlauts. They are \\em nice."
+        );
+    }
+
+    #[test]
+    fn test_set_editors() {
+        let mut entry = Entry::new("key".into(), EntryType::Collection);
+        let person = |name: &str| Person {
+            name: name.into(),
+            given_name: "".into(),
+            prefix: "".into(),
+            suffix: "".into(),
+        };
+
+        entry.set("editortype", vec![Spanned::zero(Chunk::Normal(" ".into()))]);
+        entry.set_editor(vec![person("Uhlig")]);
+        assert_eq!(
+            entry.editors(),
+            Ok(vec![(vec![person("Uhlig")], EditorType::Editor)])
+        );
+
+        let editors = vec![
+            (vec![person("Uhlig")], EditorType::Editor),
+            (vec![person("Heckman")], EditorType::Compiler),
+            (vec![person("Kamencia")], EditorType::Unknown("host".into())),
+        ];
+        entry.set_editors(editors.clone());
+        assert_eq!(entry.editors(), Ok(editors));
+        assert!(entry.get("editortype").is_none());
+        assert_eq!(entry.editor_a_type(), Ok(EditorType::Compiler));
+        assert_eq!(entry.get("editorbtype").unwrap().format_verbatim(), "host");
+
+        entry.set_editors(vec![]);
+        assert_eq!(entry.editors(), Ok(vec![]));
+        assert!(entry.fields.is_empty());
+    }
+
     #[test]
     fn test_editor_types() {
         let contents = fs::read_to_string("tests/editortypes.bib").unwrap();
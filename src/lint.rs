@@ -0,0 +1,413 @@
+//! Style checks for entries that go beyond the data model.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::mechanics::{canonical_field_name, is_verbatim_field};
+use crate::{Bibliography, Chunk, Chunks, ChunksExt, Entry, EntryType, Span, Spanned};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A check performed by a [`Linter`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LintRule {
+    /// An article has no `doi` field.
+    MissingDoi,
+    /// A page range uses a single hyphen instead of `--`.
+    SingleHyphenPages,
+    /// A title contains acronyms or words with inner capitals (e.g. `DNA` or
+    /// `iPhone`) that are not protected by braces and may be lowercased by
+    /// the bibliography style.
+    UnprotectedTitle,
+    /// An entry has a `url` field, but no `urldate` field.
+    UrlWithoutUrlDate,
+    /// A field value starts or ends with whitespace.
+    TrailingWhitespace,
+    /// A field uses a legacy BibTeX name like `journal` instead of its
+    /// BibLaTeX counterpart.
+    LegacyFieldName,
+}
+
+impl LintRule {
+    /// All available rules.
+    pub const ALL: [LintRule; 6] = [
+        Self::MissingDoi,
+        Self::SingleHyphenPages,
+        Self::UnprotectedTitle,
+        Self::UrlWithoutUrlDate,
+        Self::TrailingWhitespace,
+        Self::LegacyFieldName,
+    ];
+
+    /// A short, human-readable description of the problem.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::MissingDoi => "article without doi",
+            Self::SingleHyphenPages => "page range uses a single hyphen",
+            Self::UnprotectedTitle => "title lacks case protection",
+            Self::UrlWithoutUrlDate => "url without urldate",
+            Self::TrailingWhitespace => "field value has surrounding whitespace",
+            Self::LegacyFieldName => "legacy field name",
+        }
+    }
+}
+
+/// A problem found by a [`Linter`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Lint {
+    /// The cite key of the entry.
+    pub key: String,
+    /// The rule that found the problem.
+    pub rule: LintRule,
+    /// The field the problem was found in, if any.
+    pub field: Option<String>,
+    /// Where in the source the problem was found. For problems that do not
+    /// concern a single field, this spans all fields of the entry. The span
+    /// is detached if the entry was not parsed from a source.
+    pub span: Span,
+    /// A mechanical fix for the problem, if there is one.
+    pub fix: Option<Fix>,
+}
+
+/// A correction for a [`Lint`] that can be applied without human judgement.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fix {
+    /// The name of the field to correct.
+    pub field: String,
+    /// The name of the field after the correction. Differs from `field` if
+    /// the field is renamed.
+    pub new_field: String,
+    /// The corrected value.
+    pub value: Chunks,
+    /// The corrected value in BibLaTeX syntax, e.g. `{1--5}`.
+    pub raw: String,
+}
+
+impl Fix {
+    /// Create a fix that replaces the value of a field and possibly renames
+    /// it.
+    fn new(field: &str, new_field: &str, value: Chunks) -> Self {
+        let mut raw = value.to_biblatex_string(is_verbatim_field(new_field));
+        if new_field == "pages" {
+            raw = raw.replace('–', "--");
+        }
+
+        Self {
+            field: field.into(),
+            new_field: new_field.into(),
+            value,
+            raw,
+        }
+    }
+
+    /// Apply the fix to an entry.
+    pub fn apply(&self, entry: &mut Entry) {
+        entry.remove(&self.field);
+        entry.set(&self.new_field, self.value.clone());
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.rule.description())?;
+        if let Some(field) = &self.field {
+            write!(f, " (field {})", field)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks entries for common style problems. All rules are enabled by
+/// default.
+///
+/// ```
+/// # use biblatex::{Bibliography, Linter, LintRule};
+/// let src = "@article{a, title = {On DNA}, pages = {1-5}, doi = {10.1/2}}";
+/// let bibliography = Bibliography::parse(src).unwrap();
+///
+/// let mut linter = Linter::default();
+/// linter.disable(LintRule::UnprotectedTitle);
+///
+/// let lints = linter.lint(&bibliography);
+/// assert_eq!(lints.len(), 1);
+/// assert_eq!(lints[0].rule, LintRule::SingleHyphenPages);
+/// assert_eq!(&src[lints[0].span.clone()], "1-5");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linter {
+    /// The enabled rules.
+    rules: Vec<LintRule>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self { rules: LintRule::ALL.to_vec() }
+    }
+}
+
+impl Linter {
+    /// Create a linter without any enabled rules.
+    pub fn empty() -> Self {
+        Self { rules: vec![] }
+    }
+
+    /// Enable a rule.
+    pub fn enable(&mut self, rule: LintRule) {
+        if !self.rules.contains(&rule) {
+            self.rules.push(rule);
+        }
+    }
+
+    /// Disable a rule.
+    pub fn disable(&mut self, rule: LintRule) {
+        self.rules.retain(|&r| r != rule);
+    }
+
+    /// Whether a rule is enabled.
+    pub fn is_enabled(&self, rule: LintRule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    /// Apply the fixes for the problems of enabled rules to all entries and
+    /// return the fixed problems.
+    pub fn fix(&self, bibliography: &mut Bibliography) -> Vec<Lint> {
+        bibliography
+            .iter_mut()
+            .flat_map(|entry| self.fix_entry(entry))
+            .collect()
+    }
+
+    /// Apply the fixes for the problems of enabled rules to an entry and
+    /// return the fixed problems.
+    ///
+    /// Fixes are applied one at a time, because multiple fixes may concern
+    /// the same field.
+    pub fn fix_entry(&self, entry: &mut Entry) -> Vec<Lint> {
+        let mut fixed = vec![];
+        while let Some(lint) =
+            self.lint_entry(entry).into_iter().find(|l| l.fix.is_some())
+        {
+            lint.fix.as_ref().unwrap().apply(entry);
+            fixed.push(lint);
+        }
+        fixed
+    }
+
+    /// Check all entries of a bibliography in bibliography order.
+    pub fn lint(&self, bibliography: &Bibliography) -> Vec<Lint> {
+        bibliography.iter().flat_map(|entry| self.lint_entry(entry)).collect()
+    }
+
+    /// Check a single entry.
+    pub fn lint_entry(&self, entry: &Entry) -> Vec<Lint> {
+        let mut lints = vec![];
+        let mut push = |rule, field: Option<&str>, span, fix| {
+            if self.is_enabled(rule) {
+                lints.push(Lint {
+                    key: entry.key.clone(),
+                    rule,
+                    field: field.map(ToString::to_string),
+                    span,
+                    fix,
+                });
+            }
+        };
+
+        if entry.entry_type == EntryType::Article && entry.get("doi").is_none() {
+            push(LintRule::MissingDoi, None, entry_span(entry), None);
+        }
+
+        if let Some(pages) = entry.get("pages") {
+            if let Some(chunk) = pages.iter().find(|chunk| match &chunk.v {
+                Chunk::Normal(s) => has_single_hyphen(s),
+                _ => false,
+            }) {
+                let fixed = pages
+                    .iter()
+                    .map(|chunk| match &chunk.v {
+                        Chunk::Normal(s) => {
+                            Spanned::detached(Chunk::Normal(replace_single_hyphens(s)))
+                        }
+                        _ => chunk.clone(),
+                    })
+                    .collect();
+                let fix = Fix::new("pages", "pages", fixed);
+                push(
+                    LintRule::SingleHyphenPages,
+                    Some("pages"),
+                    chunk.span.clone(),
+                    Some(fix),
+                );
+            }
+        }
+
+        for field in ["title", "booktitle", "maintitle", "journaltitle"] {
+            let Some(title) = entry.get(field) else { continue };
+            for chunk in title {
+                if let Chunk::Normal(s) = &chunk.v {
+                    if s.split_whitespace().any(needs_protection) {
+                        let span = chunk.span.clone();
+                        push(LintRule::UnprotectedTitle, Some(field), span, None);
+                    }
+                }
+            }
+        }
+
+        if let Some(url) = entry.get("url") {
+            if entry.get("urldate").is_none() {
+                push(LintRule::UrlWithoutUrlDate, Some("url"), url.span(), None);
+            }
+        }
+
+        for (key, chunks) in &entry.fields {
+            if let Some(trimmed) = trim(chunks) {
+                let fix = Fix::new(key, key, trimmed);
+                push(LintRule::TrailingWhitespace, Some(key), chunks.span(), Some(fix));
+            }
+
+            let canonical = canonical_field_name(key);
+            if canonical != key && !entry.fields.contains_key(canonical) {
+                let fix = Fix::new(key, canonical, chunks.clone());
+                push(LintRule::LegacyFieldName, Some(key), chunks.span(), Some(fix));
+            }
+        }
+
+        lints
+    }
+}
+
+/// Whether a word has an uppercase letter after its first character and
+/// would be mangled by sentence casing.
+fn needs_protection(word: &str) -> bool {
+    word.chars().skip(1).any(char::is_uppercase)
+}
+
+/// Whether the text contains a single hyphen between two digits.
+fn has_single_hyphen(s: &str) -> bool {
+    let chars: Vec<char> = s.chars().collect();
+    chars
+        .windows(3)
+        .any(|w| w[0].is_ascii_digit() && w[1] == '-' && w[2].is_ascii_digit())
+}
+
+/// Replace single hyphens between digits with en dashes, which is what `--`
+/// is parsed into.
+fn replace_single_hyphens(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::with_capacity(s.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let between_digits = i > 0
+            && chars[i - 1].is_ascii_digit()
+            && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+        out.push(if c == '-' && between_digits { '–' } else { c });
+    }
+    out
+}
+
+/// The chunks without surrounding whitespace, or `None` if there is none.
+fn trim(chunks: &[Spanned<Chunk>]) -> Option<Chunks> {
+    let is_normal = |c: Option<&Spanned<Chunk>>, f: fn(&str) -> &str| matches!(c.map(|c| &c.v), Some(Chunk::Normal(s)) if f(s) != s);
+    if !is_normal(chunks.first(), str::trim_start)
+        && !is_normal(chunks.last(), str::trim_end)
+    {
+        return None;
+    }
+
+    let mut trimmed = chunks.to_vec();
+    let last = trimmed.len() - 1;
+    for (i, chunk) in trimmed.iter_mut().enumerate() {
+        if let Chunk::Normal(s) = &mut chunk.v {
+            let mut t = s.as_str();
+            if i == 0 {
+                t = t.trim_start();
+            }
+            if i == last {
+                t = t.trim_end();
+            }
+            *chunk = Spanned::detached(Chunk::Normal(t.to_string()));
+        }
+    }
+    trimmed.retain(|c| !matches!(&c.v, Chunk::Normal(s) if s.is_empty()));
+    Some(trimmed)
+}
+
+/// The span covering all fields of an entry that have a source location.
+fn entry_span(entry: &Entry) -> Span {
+    let spans = entry.fields.values().flatten().filter(|c| !c.is_detached());
+    let start = spans.clone().map(|c| c.span.start).min();
+    let end = spans.map(|c| c.span.end).max();
+    match (start, end) {
+        (Some(start), Some(end)) => start..end,
+        _ => usize::MAX..usize::MAX,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let src = r#"@article{a, title = {A Study of DNA in {RNA} and Bacteria},
+              pages = {1-5, 7--9}, url = {https://example.com}}
+            @article{b, title = {{DNA} structure}, doi = {10.1/2}, pages = {1--5}}
+            @online{c, title = {Page}, url = {https://example.com},
+              urldate = {2020-01-01}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let lints = Linter::default().lint(&bibliography);
+        let rules: Vec<_> = lints.iter().map(|lint| lint.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                LintRule::MissingDoi,
+                LintRule::SingleHyphenPages,
+                LintRule::UnprotectedTitle,
+                LintRule::UrlWithoutUrlDate,
+            ]
+        );
+        assert!(lints.iter().all(|lint| lint.key == "a"));
+        assert_eq!(&src[lints[2].span.clone()], "A Study of DNA in ");
+        assert_eq!(&src[lints[3].span.clone()], "https://example.com");
+        assert_eq!(
+            lints[1].to_string(),
+            "a: page range uses a single hyphen (field pages)"
+        );
+
+        assert_eq!(lints[1].fix.as_ref().unwrap().raw, "{1--5, 7--9}");
+
+        let mut linter = Linter::empty();
+        linter.enable(LintRule::MissingDoi);
+        assert_eq!(linter.lint(&bibliography).len(), 1);
+        linter.disable(LintRule::MissingDoi);
+        assert!(linter.lint(&bibliography).is_empty());
+    }
+
+    #[test]
+    fn test_fix() {
+        let src = r#"@article{a, journal = { Journal }, pages = {1-5}, title = {A},
+              doi = {10.1/2}, address = {Berlin}, location = {London}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        let fixed = Linter::default().fix(&mut bibliography);
+        let rules: Vec<_> = fixed.iter().map(|lint| lint.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                LintRule::SingleHyphenPages,
+                LintRule::TrailingWhitespace,
+                LintRule::LegacyFieldName,
+            ]
+        );
+
+        let entry = bibliography.get("a").unwrap();
+        assert_eq!(entry.get("journal"), None);
+        assert_eq!(entry.get("journaltitle").unwrap().format_verbatim(), "Journal");
+        assert_eq!(entry.get("pages").unwrap().format_verbatim(), "1–5");
+        assert_eq!(entry.get("address").unwrap().format_verbatim(), "Berlin");
+        assert!(Linter::default().lint(&bibliography).iter().all(|l| l.fix.is_none()));
+    }
+}
@@ -0,0 +1,340 @@
+//! Loading a bibliography from multiple files.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::raw::entry_span;
+use crate::{Bibliography, MergeConflict, MergeStrategy, ParseError, RawBibliography};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Where an entry of a [`LoadedBibliography`] was defined.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Provenance {
+    /// The file the entry was read from.
+    pub path: PathBuf,
+    /// The byte range of the entry in the file.
+    pub span: Range<usize>,
+}
+
+/// A bibliography combined from multiple files. Can be obtained through
+/// [`Bibliography::load_files`] or [`Bibliography::load_dir`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LoadedBibliography {
+    /// The merged bibliography.
+    pub bibliography: Bibliography,
+    /// Where each entry and `@xdata` container was defined, by cite key.
+    pub sources: HashMap<String, Provenance>,
+    /// The cite key collisions between files, with the file of the incoming
+    /// entry, in the order they were handled.
+    pub conflicts: Vec<(PathBuf, MergeConflict)>,
+}
+
+impl LoadedBibliography {
+    /// Where the entry with the given cite key or alias was defined.
+    pub fn source(&self, key: &str) -> Option<&Provenance> {
+        let key = match self.bibliography.get(key) {
+            Some(entry) => &entry.key,
+            None => key,
+        };
+        self.sources.get(key)
+    }
+}
+
+/// Errors that can occur when loading a bibliography from files.
+#[derive(Debug)]
+pub enum LoadError {
+    /// A file or directory could not be read.
+    Io(PathBuf, io::Error),
+    /// A file could not be parsed.
+    Parse(PathBuf, ParseError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+        }
+    }
+}
+
+impl Bibliography {
+    /// Parse the given files and merge them into one bibliography in order.
+    ///
+    /// Cite key collisions between files are handled according to the
+    /// `strategy`, like in [`Bibliography::merge`]. Cross-references are
+    /// resolved within each file.
+    pub fn load_files<P: AsRef<Path>>(
+        paths: impl IntoIterator<Item = P>,
+        strategy: MergeStrategy,
+    ) -> Result<LoadedBibliography, LoadError> {
+        let mut loaded = LoadedBibliography::default();
+
+        for path in paths {
+            let path = path.as_ref();
+            let src = fs::read_to_string(path)
+                .map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+            let parse_err = |err| LoadError::Parse(path.to_path_buf(), err);
+
+            let raw = RawBibliography::parse(&src).map_err(parse_err)?;
+            let spans: Vec<(String, Range<usize>)> = raw
+                .entries
+                .iter()
+                .map(|entry| (entry.v.key.v.to_string(), entry_span(&src, &entry.span)))
+                .collect();
+
+            let bibliography = Bibliography::from_raw(raw).map_err(parse_err)?;
+            let conflicts = loaded.bibliography.merge(bibliography, strategy);
+
+            let provenance = |span: &Range<usize>| Provenance {
+                path: path.to_path_buf(),
+                span: span.clone(),
+            };
+
+            for (key, span) in &spans {
+                let conflict = conflicts.iter().find(|conflict| match conflict {
+                    MergeConflict::Skipped(k)
+                    | MergeConflict::Overwritten(k)
+                    | MergeConflict::Renamed { key: k, .. }
+                    | MergeConflict::Merged { key: k, .. } => k == key,
+                });
+
+                match conflict {
+                    None | Some(MergeConflict::Overwritten(_)) => {
+                        loaded.sources.insert(key.clone(), provenance(span));
+                    }
+                    Some(MergeConflict::Renamed { new_key, .. }) => {
+                        loaded.sources.insert(new_key.clone(), provenance(span));
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            let bibliography = &loaded.bibliography;
+            loaded.sources.retain(|key, _| {
+                bibliography.get(key).is_some_and(|entry| entry.key == *key)
+                    || bibliography.xdata(key).is_some()
+            });
+
+            loaded
+                .conflicts
+                .extend(conflicts.into_iter().map(|c| (path.to_path_buf(), c)));
+        }
+
+        Ok(loaded)
+    }
+
+    /// Parse a bibliography from a memory-mapped file, see [`MappedFile`].
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is parsed, see
+    /// [`MappedFile::open`].
+    #[cfg(feature = "mmap")]
+    pub unsafe fn parse_path(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        MappedFile::open(path)?.parse()
+    }
+
+    /// Parse all `.bib` files in a directory and its subdirectories and merge
+    /// them into one bibliography.
+    ///
+    /// The files are merged in the lexicographic order of their paths, with
+    /// collisions handled as in [`Bibliography::load_files`].
+    pub fn load_dir(
+        dir: impl AsRef<Path>,
+        strategy: MergeStrategy,
+    ) -> Result<LoadedBibliography, LoadError> {
+        let mut paths = vec![];
+        collect_bib_files(dir.as_ref(), &mut paths)?;
+        paths.sort();
+        Self::load_files(paths, strategy)
+    }
+}
+
+/// A memory-mapped `.bib` file.
+///
+/// The text of the file is not read into a string, which saves a copy of
+/// large files. [`MappedFile::parse_raw`] borrows all field values from the
+/// mapping without copying them.
+///
+/// ```no_run
+/// # use biblatex::MappedFile;
+/// // SAFETY: The file is not modified while it is mapped.
+/// let file = unsafe { MappedFile::open("huge.bib") }.unwrap();
+/// let raw = file.parse_raw().unwrap();
+/// println!("{} entries", raw.entries.len());
+/// ```
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedFile {
+    path: PathBuf,
+    map: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedFile {
+    /// Map a file into memory and check that it is valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by this or another process
+    /// while it is mapped. Otherwise, the text may change or become invalid
+    /// UTF-8 while it is borrowed, which is undefined behavior.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref().to_path_buf();
+        let io_err = |err| LoadError::Io(path.clone(), err);
+        let file = fs::File::open(&path).map_err(io_err)?;
+
+        // SAFETY: The caller guarantees that the file is not modified.
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(io_err)?;
+        if let Err(err) = std::str::from_utf8(&map) {
+            return Err(io_err(io::Error::new(io::ErrorKind::InvalidData, err)));
+        }
+
+        Ok(Self { path, map })
+    }
+
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The text of the file.
+    pub fn as_str(&self) -> &str {
+        // SAFETY: The text was checked in `open` and the caller guaranteed
+        // that it does not change.
+        unsafe { std::str::from_utf8_unchecked(&self.map) }
+    }
+
+    /// Parse the file into a raw bibliography that borrows from the mapping.
+    pub fn parse_raw(&self) -> Result<RawBibliography<'_>, LoadError> {
+        RawBibliography::parse(self.as_str())
+            .map_err(|err| LoadError::Parse(self.path.clone(), err))
+    }
+
+    /// Parse the file into a bibliography.
+    pub fn parse(&self) -> Result<Bibliography, LoadError> {
+        Bibliography::parse(self.as_str())
+            .map_err(|err| LoadError::Parse(self.path.clone(), err))
+    }
+}
+
+/// Recursively collect the paths of the `.bib` files in a directory.
+fn collect_bib_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), LoadError> {
+    let io_err = |err| LoadError::Io(dir.to_path_buf(), err);
+    for entry in fs::read_dir(dir).map_err(io_err)? {
+        let path = entry.map_err(io_err)?.path();
+        if path.is_dir() {
+            collect_bib_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bib")) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "mmap")]
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_load_dir() {
+        let dir =
+            std::env::temp_dir().join(format!("biblatex-load-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+
+        let first = "@book{a, title = {A}}\n@book{b, title = {B}, ids = {beta}}";
+        let second = "@article{a, title = {Other A}}\n\n@article{c, title = {C}}";
+        fs::write(dir.join("first.bib"), first).unwrap();
+        fs::write(dir.join("sub").join("second.BIB"), second).unwrap();
+        fs::write(dir.join("notes.txt"), "@book{x,}").unwrap();
+
+        let loaded = Bibliography::load_dir(&dir, MergeStrategy::Rename).unwrap();
+        assert_eq!(loaded.bibliography.keys().collect::<Vec<_>>(), ["a", "b", "aa", "c"]);
+        assert_eq!(
+            loaded.conflicts,
+            [(
+                dir.join("sub").join("second.BIB"),
+                MergeConflict::Renamed { key: "a".into(), new_key: "aa".into() }
+            )]
+        );
+
+        let source = loaded.source("beta").unwrap();
+        assert_eq!(source.path, dir.join("first.bib"));
+        assert_eq!(&first[source.span.clone()], "@book{b, title = {B}, ids = {beta}}");
+        let source = loaded.source("aa").unwrap();
+        assert_eq!(&second[source.span.clone()], "@article{a, title = {Other A}}");
+        assert_eq!(loaded.source("c").unwrap().span.start, 32);
+
+        let paths = [dir.join("sub").join("second.BIB"), dir.join("first.bib")];
+        let loaded = Bibliography::load_files(&paths, MergeStrategy::Skip).unwrap();
+        assert_eq!(loaded.source("a").unwrap().path, paths[0]);
+
+        fs::write(dir.join("broken.bib"), "@book{a, title = {A}").unwrap();
+        let err = Bibliography::load_dir(&dir, MergeStrategy::Skip).unwrap_err();
+        assert!(
+            matches!(err, LoadError::Parse(path, _) if path == dir.join("broken.bib"))
+        );
+        assert!(matches!(
+            Bibliography::load_files([dir.join("missing.bib")], MergeStrategy::Skip),
+            Err(LoadError::Io(..))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_parse_path() {
+        let dir =
+            std::env::temp_dir().join(format!("biblatex-mmap-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // SAFETY: The files are not modified while they are mapped.
+        let open = |name| unsafe { MappedFile::open(dir.join(name)) };
+        let parse = |name| unsafe { Bibliography::parse_path(dir.join(name)) };
+
+        let src = "@book{a, title = {A}, publisher = {P}}";
+        fs::write(dir.join("first.bib"), src).unwrap();
+        assert_eq!(parse("first.bib").unwrap(), Bibliography::parse(src).unwrap());
+        let file = open("first.bib").unwrap();
+        assert_eq!(file.path(), dir.join("first.bib"));
+        let raw = file.parse_raw().unwrap();
+        assert!(matches!(&raw.entries[0].v.key.v, Cow::Borrowed("a")));
+        assert_eq!(raw.entries[0].v.key.v.as_ptr(), file.as_str()[6..].as_ptr());
+
+        fs::write(dir.join("empty.bib"), "").unwrap();
+        assert!(parse("empty.bib").unwrap().is_empty());
+        fs::write(dir.join("latin1.bib"), b"@book{a, title = {\xe9}}").unwrap();
+        assert!(matches!(
+            open("latin1.bib"),
+            Err(LoadError::Io(_, err)) if err.kind() == io::ErrorKind::InvalidData
+        ));
+        fs::write(dir.join("broken.bib"), "@book{a, title = {A}").unwrap();
+        assert!(matches!(parse("broken.bib"), Err(LoadError::Parse(..))));
+        assert!(matches!(parse("missing.bib"), Err(LoadError::Io(..))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,7 +13,7 @@ macro_rules! fields {
         })*
     };
 
-    (@ret) => {ChunksRef};
+    (@ret) => {ChunksRef<'_>};
     (@ret $ret:ty) => {$ret};
 
     (@set $name:ident => $field:literal, ) => {
@@ -89,3 +89,85 @@ macro_rules! date_fields {
 }
 
 pub(crate) use date_fields;
+
+macro_rules! typed_fields {
+    ($($variant:ident: $field:literal $(=> $ty:ty)?),* $(,)*) => {
+        paste! {
+            /// A field value parsed into the type of the field with the same
+            /// name.
+            ///
+            /// Fields without a dedicated type hold their chunks. Fields that
+            /// are not known to the crate are kept in the `Unknown` variant.
+            #[derive(Debug, Clone, PartialEq)]
+            #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+            pub enum TypedField {
+                $(
+                    #[doc = "The `" $field "` field."]
+                    $variant(typed_fields!(@ty $($ty)?)),
+                )*
+                /// A field that is not known to the crate with its name and
+                /// value.
+                Unknown(String, Chunks),
+            }
+        }
+
+        impl TypedField {
+            /// The name of the field.
+            pub fn name(&self) -> &str {
+                match self {
+                    $(Self::$variant(_) => $field,)*
+                    Self::Unknown(name, _) => name,
+                }
+            }
+
+            /// Serialize the value back into chunks.
+            ///
+            /// This produces chunks with _detached spans_ unless the field
+            /// holds its original chunks.
+            pub fn to_chunks(&self) -> Chunks {
+                match self {
+                    $(Self::$variant(v) => typed_fields!(@to_chunks v, $($ty)?),)*
+                    Self::Unknown(_, chunks) => chunks.clone(),
+                }
+            }
+
+            /// Convert the value into JSON if it has a dedicated
+            /// representation.
+            pub(crate) fn to_json(&self) -> Option<crate::json::Json> {
+                match self {
+                    $(Self::$variant(v) => crate::json::ToJson::to_json(v),)*
+                    Self::Unknown(..) => None,
+                }
+            }
+
+            /// The static copy of the name of a field known to the crate.
+            pub(crate) fn static_name(name: &str) -> Option<&'static str> {
+                match name {
+                    $($field => Some($field),)*
+                    _ => None,
+                }
+            }
+
+            /// Parse the chunks of the field with the given name.
+            fn parse_known(name: &str, chunks: ChunksRef) -> Result<Self, TypeError> {
+                Ok(match name {
+                    $($field => Self::$variant(
+                        typed_fields!(@parse chunks, $($ty)?)
+                    ),)*
+                    _ => Self::Unknown(name.to_string(), chunks.to_vec()),
+                })
+            }
+        }
+    };
+
+    (@ty) => {Chunks};
+    (@ty $ty:ty) => {$ty};
+
+    (@to_chunks $v:ident, ) => {$v.clone()};
+    (@to_chunks $v:ident, $ty:ty) => {$v.to_chunks()};
+
+    (@parse $chunks:ident, ) => {$chunks.to_vec()};
+    (@parse $chunks:ident, $ty:ty) => {$chunks.parse::<$ty>()?};
+}
+
+pub(crate) use typed_fields;
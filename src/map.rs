@@ -0,0 +1,423 @@
+//! Compact storage for the fields of an entry.
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+use std::ops::Index;
+
+use crate::{Chunks, FieldName};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The fields of an [`Entry`](crate::Entry), ordered by name.
+///
+/// Most entries have only a handful of fields, so they are kept in a single
+/// vector sorted by name instead of a tree of separately allocated nodes.
+/// Lookups use binary search. The interface follows the one of a
+/// [`BTreeMap`](std::collections::BTreeMap).
+///
+/// ```
+/// # use biblatex::FieldMap;
+/// let mut fields = FieldMap::new();
+/// fields.insert("title".into(), vec![]);
+/// fields.insert("author".into(), vec![]);
+/// assert_eq!(fields.keys().collect::<Vec<_>>(), ["author", "title"]);
+/// assert!(fields.contains_key("title"));
+/// assert_eq!(fields.remove("title"), Some(vec![]));
+/// assert_eq!(fields.len(), 1);
+/// ```
+///
+/// # Migration
+///
+/// [`Entry::fields`](crate::Entry::fields) used to be a
+/// `BTreeMap<String, Chunks>`. Most code that reads or edits fields through
+/// the methods above keeps compiling, since lookups accept a `&str` and keys
+/// convert from a `String`. Code that needs a `BTreeMap`, e.g. to call a
+/// function expecting one, can convert in both directions.
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use biblatex::{Bibliography, Chunks, FieldMap};
+/// let mut bibliography = Bibliography::parse("@misc{a, title = {A}}").unwrap();
+/// let entry = bibliography.get_mut("a").unwrap();
+///
+/// let mut map: BTreeMap<String, Chunks> = entry.fields.clone().into();
+/// map.insert("note".to_string(), vec![]);
+/// entry.fields = FieldMap::from(map);
+/// assert_eq!(entry.fields.keys().collect::<Vec<_>>(), ["note", "title"]);
+/// ```
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct FieldMap(Vec<(FieldName, Chunks)>);
+
+impl FieldMap {
+    /// Create an empty map.
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Create an empty map with space for at least `capacity` fields.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    /// The number of fields.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Remove all fields.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// The position of a field, or the position where it would be inserted.
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        FieldName: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.binary_search_by(|(name, _)| name.borrow().cmp(key))
+    }
+
+    /// The value of a field.
+    pub fn get<Q>(&self, key: &Q) -> Option<&Chunks>
+    where
+        FieldName: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, chunks)| chunks)
+    }
+
+    /// The name and value of a field.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&FieldName, &Chunks)>
+    where
+        FieldName: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (name, chunks) = &self.0[self.search(key).ok()?];
+        Some((name, chunks))
+    }
+
+    /// A mutable reference to the value of a field.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut Chunks>
+    where
+        FieldName: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let i = self.search(key).ok()?;
+        Some(&mut self.0[i].1)
+    }
+
+    /// Whether the map contains a field.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        FieldName: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).is_ok()
+    }
+
+    /// Set the value of a field and return the previous value, if any.
+    pub fn insert(&mut self, key: FieldName, chunks: Chunks) -> Option<Chunks> {
+        match self.search(&key) {
+            Ok(i) => Some(std::mem::replace(&mut self.0[i].1, chunks)),
+            Err(i) => {
+                self.0.insert(i, (key, chunks));
+                None
+            }
+        }
+    }
+
+    /// Remove a field and return its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Chunks>
+    where
+        FieldName: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let i = self.search(key).ok()?;
+        Some(self.0.remove(i).1)
+    }
+
+    /// Keep only the fields for which the predicate returns `true`.
+    pub fn retain(&mut self, mut f: impl FnMut(&FieldName, &mut Chunks) -> bool) {
+        self.0.retain_mut(|(name, chunks)| f(name, chunks));
+    }
+
+    /// An iterator over the names and values in order.
+    pub fn iter(&self) -> FieldIter<'_> {
+        FieldIter(self.0.iter())
+    }
+
+    /// An iterator over the names and mutable values in order.
+    pub fn iter_mut(&mut self) -> FieldIterMut<'_> {
+        FieldIterMut(self.0.iter_mut())
+    }
+
+    /// An iterator over the names in order.
+    pub fn keys(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &FieldName> + ExactSizeIterator + Clone {
+        self.0.iter().map(|(name, _)| name)
+    }
+
+    /// An iterator over the values in the order of their names.
+    pub fn values(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &Chunks> + ExactSizeIterator + Clone {
+        self.0.iter().map(|(_, chunks)| chunks)
+    }
+
+    /// An iterator over mutable references to the values in the order of
+    /// their names.
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut Chunks> + ExactSizeIterator {
+        self.0.iter_mut().map(|(_, chunks)| chunks)
+    }
+
+    /// The field with the first name in order.
+    pub fn first_key_value(&self) -> Option<(&FieldName, &Chunks)> {
+        self.0.first().map(|(name, chunks)| (name, chunks))
+    }
+
+    /// The field with the last name in order.
+    pub fn last_key_value(&self) -> Option<(&FieldName, &Chunks)> {
+        self.0.last().map(|(name, chunks)| (name, chunks))
+    }
+
+    /// An owning iterator over the names in order.
+    pub fn into_keys(
+        self,
+    ) -> impl DoubleEndedIterator<Item = FieldName> + ExactSizeIterator {
+        self.0.into_iter().map(|(name, _)| name)
+    }
+
+    /// An owning iterator over the values in the order of their names.
+    pub fn into_values(
+        self,
+    ) -> impl DoubleEndedIterator<Item = Chunks> + ExactSizeIterator {
+        self.0.into_iter().map(|(_, chunks)| chunks)
+    }
+
+    /// Move all fields from another map into this one, overwriting the values
+    /// of fields present in both.
+    pub fn append(&mut self, other: &mut Self) {
+        self.extend(std::mem::take(other));
+    }
+}
+
+impl Debug for FieldMap {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<Q> Index<&Q> for FieldMap
+where
+    FieldName: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = Chunks;
+
+    fn index(&self, key: &Q) -> &Chunks {
+        self.get(key).expect("no field with this name")
+    }
+}
+
+impl Extend<(FieldName, Chunks)> for FieldMap {
+    fn extend<T: IntoIterator<Item = (FieldName, Chunks)>>(&mut self, iter: T) {
+        for (key, chunks) in iter {
+            self.insert(key, chunks);
+        }
+    }
+}
+
+impl FromIterator<(FieldName, Chunks)> for FieldMap {
+    fn from_iter<T: IntoIterator<Item = (FieldName, Chunks)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<const N: usize> From<[(FieldName, Chunks); N]> for FieldMap {
+    fn from(fields: [(FieldName, Chunks); N]) -> Self {
+        fields.into_iter().collect()
+    }
+}
+
+impl From<BTreeMap<String, Chunks>> for FieldMap {
+    fn from(map: BTreeMap<String, Chunks>) -> Self {
+        // The keys are already in order.
+        Self(map.into_iter().map(|(name, chunks)| (name.into(), chunks)).collect())
+    }
+}
+
+impl From<FieldMap> for BTreeMap<String, Chunks> {
+    fn from(map: FieldMap) -> Self {
+        map.into_iter().map(|(name, chunks)| (name.into(), chunks)).collect()
+    }
+}
+
+/// An iterator over the fields of a [`FieldMap`].
+#[derive(Debug, Clone)]
+pub struct FieldIter<'a>(std::slice::Iter<'a, (FieldName, Chunks)>);
+
+impl<'a> Iterator for FieldIter<'a> {
+    type Item = (&'a FieldName, &'a Chunks);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(name, chunks)| (name, chunks))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for FieldIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(name, chunks)| (name, chunks))
+    }
+}
+
+impl ExactSizeIterator for FieldIter<'_> {}
+
+/// An iterator over the fields of a [`FieldMap`] with mutable values.
+#[derive(Debug)]
+pub struct FieldIterMut<'a>(std::slice::IterMut<'a, (FieldName, Chunks)>);
+
+impl<'a> Iterator for FieldIterMut<'a> {
+    type Item = (&'a FieldName, &'a mut Chunks);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(name, chunks)| (&*name, chunks))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for FieldIterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(name, chunks)| (&*name, chunks))
+    }
+}
+
+impl ExactSizeIterator for FieldIterMut<'_> {}
+
+/// An owning iterator over the fields of a [`FieldMap`].
+#[derive(Debug)]
+pub struct FieldIntoIter(std::vec::IntoIter<(FieldName, Chunks)>);
+
+impl Iterator for FieldIntoIter {
+    type Item = (FieldName, Chunks);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for FieldIntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for FieldIntoIter {}
+
+impl IntoIterator for FieldMap {
+    type Item = (FieldName, Chunks);
+    type IntoIter = FieldIntoIter;
+
+    fn into_iter(self) -> FieldIntoIter {
+        FieldIntoIter(self.0.into_iter())
+    }
+}
+
+impl<'a> IntoIterator for &'a FieldMap {
+    type Item = (&'a FieldName, &'a Chunks);
+    type IntoIter = FieldIter<'a>;
+
+    fn into_iter(self) -> FieldIter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut FieldMap {
+    type Item = (&'a FieldName, &'a mut Chunks);
+    type IntoIter = FieldIterMut<'a>;
+
+    fn into_iter(self) -> FieldIterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for FieldMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for FieldMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map =
+            std::collections::BTreeMap::<FieldName, Chunks>::deserialize(deserializer)?;
+        Ok(map.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chunk, Spanned};
+
+    fn chunks(text: &str) -> Chunks {
+        vec![Spanned::detached(Chunk::Normal(text.into()))]
+    }
+
+    #[test]
+    fn test_field_map() {
+        let mut fields: FieldMap = [
+            ("title".into(), chunks("A")),
+            ("author".into(), chunks("B")),
+            ("note".into(), chunks("C")),
+        ]
+        .into();
+        assert_eq!(fields.keys().collect::<Vec<_>>(), ["author", "note", "title"]);
+        assert_eq!(fields.insert("title".into(), chunks("D")), Some(chunks("A")));
+        assert_eq!(fields["title"], chunks("D"));
+        assert_eq!(fields.get_mut("zzz"), None);
+
+        fields.retain(|name, _| name != "note");
+        fields.insert("abstract".into(), chunks("E"));
+        let names: Vec<_> = fields.iter().rev().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["title", "author", "abstract"]);
+        assert_eq!(fields.remove("author"), Some(chunks("B")));
+        assert!(!fields.contains_key("author"));
+        assert_eq!(fields.first_key_value().unwrap().0, "abstract");
+        assert_eq!(fields.last_key_value().unwrap().1, &chunks("D"));
+
+        let mut other: FieldMap = [("zzz".into(), chunks("F"))].into();
+        fields.append(&mut other);
+        assert!(other.is_empty());
+        let map = BTreeMap::<String, Chunks>::from(fields.clone());
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["abstract", "title", "zzz"]);
+        assert_eq!(FieldMap::from(map), fields);
+        assert_eq!(fields.clone().into_values().len(), 3);
+        assert_eq!(fields.into_keys().next_back().unwrap(), "zzz");
+    }
+}
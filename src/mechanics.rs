@@ -5,6 +5,8 @@ use std::str::FromStr;
 
 use strum::{Display, EnumString};
 
+use crate::names::NAME_FIELDS;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +14,9 @@ use serde::{Deserialize, Serialize};
 ///
 /// Each type comes with a different set of required and allowable fields that
 /// are taken into consideration in [`Entry::verify`](crate::Entry::verify).
+///
+/// Types that are neither defined by BibTeX nor BibLaTeX are preserved in the
+/// `Unknown` variant and displayed verbatim.
 #[derive(Debug, Clone, Eq, PartialEq, Display, EnumString)]
 #[allow(missing_docs)]
 #[strum(serialize_all = "lowercase")]
@@ -54,11 +59,45 @@ pub enum EntryType {
     Set,
     XData,
 
+    // BibLaTeX non-standard types
+    Artwork,
+    Audio,
+    BibNote,
+    Commentary,
+    Image,
+    Jurisdiction,
+    Legislation,
+    Legal,
+    Letter,
+    Movie,
+    Music,
+    Performance,
+    Review,
+    Standard,
+    Video,
+    CustomA,
+    CustomB,
+    CustomC,
+    CustomD,
+    CustomE,
+    CustomF,
+
+    #[strum(default)]
     Unknown(String),
 }
 
+/// Legacy entry types that are mapped to a BibTeX or BibLaTeX type when
+/// parsing. The web types stem from older reference managers.
+pub(crate) const TYPE_ALIASES: [(&str, EntryType); 4] = [
+    ("conference", EntryType::InProceedings),
+    ("electronic", EntryType::Online),
+    ("webpage", EntryType::Online),
+    ("www", EntryType::Online),
+];
+
 /// Describes the optionality mode of the `author` and `editor` fields.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AuthorMode {
     /// Neither of the fields are required to be set.
     NoneRequired,
@@ -67,6 +106,7 @@ pub enum AuthorMode {
     /// Both fields must be set.
     BothRequired,
     /// The `author` field must be present.
+    #[default]
     AuthorRequired,
     /// The `author` field must be present, the `editor` field is optional.
     AuthorRequiredEditorOptional,
@@ -74,29 +114,12 @@ pub enum AuthorMode {
     EditorRequiredAuthorForbidden,
 }
 
-impl Default for AuthorMode {
-    fn default() -> Self {
-        Self::AuthorRequired
-    }
-}
-
-impl AuthorMode {
-    pub(crate) fn possible(&self) -> &'static [&'static str] {
-        match self {
-            Self::OneRequired
-            | Self::BothRequired
-            | Self::AuthorRequiredEditorOptional => &["author", "editor"],
-            Self::AuthorRequired => &["author"],
-            Self::EditorRequiredAuthorForbidden => &["editor"],
-            _ => &[],
-        }
-    }
-}
-
 /// Describes the optionality mode of the `pages` and `chapter` field
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PagesChapterMode {
     /// No specification for the `page` and `chapter` field is given.
+    #[default]
     None,
     /// At least one of the fields must be present.
     OneRequired,
@@ -112,24 +135,12 @@ pub enum PagesChapterMode {
     PagesRequired,
 }
 
-impl Default for PagesChapterMode {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
-impl PagesChapterMode {
-    pub(crate) fn possible(&self) -> &'static [&'static str] {
-        match self {
-            Self::OneRequired | Self::BothOptional => &["pages", "chapter"],
-            Self::PagesOptional | Self::PagesRequired => &["pages"],
-            _ => &[],
-        }
-    }
-}
-
 /// Specifies what kinds of fields an entry might have to hold.
+///
+/// Only implements `Serialize` with the `serde` feature because the field
+/// names are static strings.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Requirements {
     /// Fields that have to be present for the entry to be valid.
     pub required: Vec<&'static str>,
@@ -156,16 +167,39 @@ impl EntryType {
     pub fn new(name: &str) -> Self {
         let name = name.to_lowercase();
 
-        if let Ok(ty) = EntryType::from_str(&name) {
-            return ty;
+        match TYPE_ALIASES.iter().find(|(alias, _)| *alias == name) {
+            Some((_, entry_type)) => entry_type.clone(),
+            None => EntryType::from_str(&name).unwrap_or(EntryType::Unknown(name)),
         }
+    }
 
-        match name.as_str() {
-            "conference" => EntryType::InProceedings,
-            "electronic" => EntryType::Online,
-            "www" => EntryType::Online,
-            _ => EntryType::Unknown(name),
-        }
+    /// Is this one of the non-standard types that BibLaTeX reserves for
+    /// specialized styles?
+    pub fn is_non_standard(&self) -> bool {
+        matches!(
+            self,
+            Self::Artwork
+                | Self::Audio
+                | Self::BibNote
+                | Self::Commentary
+                | Self::Image
+                | Self::Jurisdiction
+                | Self::Legislation
+                | Self::Legal
+                | Self::Letter
+                | Self::Movie
+                | Self::Music
+                | Self::Performance
+                | Self::Review
+                | Self::Standard
+                | Self::Video
+                | Self::CustomA
+                | Self::CustomB
+                | Self::CustomC
+                | Self::CustomD
+                | Self::CustomE
+                | Self::CustomF
+        )
     }
 
     /// Is this a multi-volume work?
@@ -223,6 +257,7 @@ impl EntryType {
             Self::Set => Self::Misc,
             Self::XData => Self::Misc,
             Self::Unknown(_) => Self::Misc,
+            _ if self.is_non_standard() => Self::Misc,
             _ => self.clone(),
         }
     }
@@ -597,6 +632,19 @@ impl EntryType {
 
                 reqs.author_eds_field = AuthorMode::OneRequired;
             }
+            Self::Software => {
+                reqs.optional.push("version");
+                reqs.optional.push("type");
+                reqs.optional.push("howpublished");
+                reqs.optional.push("organization");
+                reqs.optional.push("institution");
+                reqs.optional.push("publisher");
+                reqs.optional.push("license");
+                reqs.optional.push("repository");
+                reqs.optional.push("swhid");
+
+                reqs.author_eds_field = AuthorMode::OneRequired;
+            }
             Self::PhdThesis => {
                 reqs = Self::MastersThesis.requirements();
             }
@@ -621,9 +669,6 @@ impl EntryType {
             Self::InReference => {
                 reqs = Self::InCollection.requirements();
             }
-            Self::Software => {
-                reqs = Self::Misc.requirements();
-            }
             Self::Set => {
                 reqs.optional.clear();
                 reqs.required = vec!["entryset"];
@@ -639,12 +684,69 @@ impl EntryType {
             Self::Unknown(_) => {
                 reqs = Self::MvCollection.requirements();
             }
+            _ if self.is_non_standard() => {
+                reqs = Self::Misc.requirements();
+            }
+            _ => {}
         }
 
         reqs
     }
 }
 
+/// Legacy BibTeX field names and the BibLaTeX fields they are aliases of, as
+/// mapped by Biber.
+pub(crate) const FIELD_ALIASES: [(&str, &str); 9] = [
+    ("address", "location"),
+    ("annote", "annotation"),
+    ("archiveprefix", "eprinttype"),
+    ("hyphenation", "langid"),
+    ("journal", "journaltitle"),
+    ("key", "sortkey"),
+    ("pdf", "file"),
+    ("primaryclass", "eprintclass"),
+    ("school", "institution"),
+];
+
+/// Get the BibLaTeX name of a field, resolving legacy BibTeX aliases.
+pub fn canonical_field_name(key: &str) -> &str {
+    FIELD_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map(|(_, field)| *field)
+        .unwrap_or(key)
+}
+
+/// Whether a field with this key contains long text whose paragraph breaks
+/// should be preserved.
+pub fn is_paragraph_field(key: &str) -> bool {
+    matches!(key, "abstract" | "annotation" | "annote" | "library" | "note")
+}
+
+/// Whether a field with this key holds a name list or a literal list whose
+/// items are separated by `and`.
+pub fn is_list_field(key: &str) -> bool {
+    NAME_FIELDS.contains(&key)
+        || matches!(
+            key,
+            "address"
+                | "institution"
+                | "language"
+                | "location"
+                | "organization"
+                | "origlocation"
+                | "origpublisher"
+                | "publisher"
+                | "school"
+                | "lista"
+                | "listb"
+                | "listc"
+                | "listd"
+                | "liste"
+                | "listf"
+        )
+}
+
 /// Whether a field with this key should be parsed with commands and most
 /// escapes turned off.
 pub fn is_verbatim_field(key: &str) -> bool {
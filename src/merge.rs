@@ -0,0 +1,282 @@
+//! Merging two bibliographies into one.
+
+use crate::keygen::suffixed_key;
+use crate::{Bibliography, Entry, EntryType};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How [`Bibliography::merge`] handles an incoming entry whose cite key is
+/// already taken.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MergeStrategy {
+    /// Keep the existing entry and drop the incoming one.
+    Skip,
+    /// Replace the existing entry with the incoming one.
+    Overwrite,
+    /// Insert the incoming entry under a fresh cite key with an alphabetic
+    /// suffix like those of [`Bibliography::generate_keys`], e.g.
+    /// `smith2020a`. References to it in the incoming bibliography are
+    /// updated.
+    Rename,
+    /// Keep the existing entry and add the fields of the incoming entry that
+    /// it lacks.
+    UnionFields,
+}
+
+/// A cite key collision handled by [`Bibliography::merge`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MergeConflict {
+    /// The incoming entry was dropped.
+    Skipped(String),
+    /// The existing entry was replaced.
+    Overwritten(String),
+    /// The incoming entry was inserted under a new cite key.
+    Renamed {
+        /// The original cite key of the incoming entry.
+        key: String,
+        /// The cite key the incoming entry was inserted under.
+        new_key: String,
+    },
+    /// The fields of both entries were combined.
+    Merged {
+        /// The cite key of the entry.
+        key: String,
+        /// Fields present in both entries with different values. The value of
+        /// the existing entry was kept.
+        conflicting_fields: Vec<String>,
+    },
+}
+
+impl Bibliography {
+    /// Merge the entries, `@xdata` containers and preamble of another
+    /// bibliography into this one.
+    ///
+    /// Entries whose cite key collides with the cite key or an alias of an
+    /// existing entry are handled according to the `strategy`. Returns every
+    /// collision in the order it was handled.
+    ///
+    /// Entries can only be overwritten by or merged with an entry of the same
+    /// kind and cite key. Incoming entries that collide with an alias or with
+    /// an entry of the other kind (regular entry or `@xdata` container) are
+    /// skipped unless they are renamed.
+    pub fn merge(
+        &mut self,
+        mut other: Bibliography,
+        strategy: MergeStrategy,
+    ) -> Vec<MergeConflict> {
+        let mut conflicts = vec![];
+
+        if strategy == MergeStrategy::Rename {
+            let colliding: Vec<String> = other
+                .entries
+                .iter()
+                .chain(other.xdata.values())
+                .map(|entry| entry.key.clone())
+                .filter(|key| self.is_taken(key))
+                .collect();
+
+            for key in colliding {
+                let new_key =
+                    suffixed_key(&key, |new| self.is_taken(new) || other.is_taken(new));
+                other.rename_key(&key, &new_key);
+                conflicts.push(MergeConflict::Renamed { key, new_key });
+            }
+        }
+
+        let incoming =
+            std::mem::take(&mut other.xdata).into_values().chain(other.entries);
+        for entry in incoming {
+            if !self.is_taken(&entry.key) {
+                self.insert(entry);
+                continue;
+            }
+
+            // Only an entry of the same kind with exactly this cite key can be
+            // replaced or merged into, not an alias or an `@xdata` container
+            // of a regular entry.
+            let key = entry.key.clone();
+            let same = if entry.entry_type == EntryType::XData {
+                self.xdata.contains_key(&key)
+            } else {
+                self.keys.get(&key).is_some_and(|&i| self.entries[i].key == key)
+            };
+
+            match strategy {
+                MergeStrategy::Overwrite if same => {
+                    self.insert(entry);
+                    conflicts.push(MergeConflict::Overwritten(key));
+                }
+                MergeStrategy::UnionFields if same => {
+                    let existing = if entry.entry_type == EntryType::XData {
+                        self.xdata.get_mut(&key)
+                    } else {
+                        self.get_mut(&key)
+                    };
+                    let existing = existing.expect("entry exists");
+                    let conflicting_fields = union_fields(existing, entry);
+                    conflicts.push(MergeConflict::Merged { key, conflicting_fields });
+                }
+                _ => conflicts.push(MergeConflict::Skipped(key)),
+            }
+        }
+
+        if !other.preamble.is_empty() && other.preamble != self.preamble {
+            if !self.preamble.is_empty() {
+                self.preamble.push_str(" # ");
+            }
+            self.preamble.push_str(&other.preamble);
+        }
+
+        conflicts
+    }
+
+    /// Whether the key is taken by an entry, an alias, or an `@xdata`
+    /// container.
+    pub(crate) fn is_taken(&self, key: &str) -> bool {
+        self.keys.contains_key(key) || self.xdata.contains_key(key)
+    }
+}
+
+/// Add the fields of `incoming` that `existing` lacks and return the fields
+/// both have with different values.
+pub(crate) fn union_fields(existing: &mut Entry, incoming: Entry) -> Vec<String> {
+    let mut conflicting = vec![];
+
+    for (key, chunks) in incoming.fields {
+        match existing.fields.get(&key) {
+            Some(prev) => {
+                if !prev.iter().map(|c| &c.v).eq(chunks.iter().map(|c| &c.v)) {
+                    conflicting.push(key.into());
+                }
+            }
+            None => {
+                existing.fields.insert(key, chunks);
+            }
+        }
+    }
+
+    conflicting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunksExt;
+
+    #[test]
+    fn test_merge() {
+        let ours = r#"@book{a, title = {A}, author = {Doe, Jane}}
+            @book{b, title = {B}, ids = {beta}}"#;
+        let theirs = r#"@book{a, title = {Another A}, date = {2001}}
+            @incollection{beta, title = {Chapter}, crossref = {c}}
+            @collection{c, title = {C}}"#;
+        let ours = Bibliography::parse(ours).unwrap();
+        let theirs = Bibliography::parse(theirs).unwrap();
+
+        let mut bib = ours.clone();
+        let conflicts = bib.merge(theirs.clone(), MergeStrategy::Skip);
+        assert_eq!(
+            conflicts,
+            [MergeConflict::Skipped("a".into()), MergeConflict::Skipped("beta".into())]
+        );
+        assert_eq!(bib.len(), 3);
+        assert_eq!(bib.get("a").unwrap().title().unwrap().format_verbatim(), "A");
+
+        let mut bib = ours.clone();
+        let conflicts = bib.merge(theirs.clone(), MergeStrategy::Overwrite);
+        assert_eq!(
+            conflicts,
+            [
+                MergeConflict::Overwritten("a".into()),
+                MergeConflict::Skipped("beta".into())
+            ]
+        );
+        assert_eq!(bib.get("a").unwrap().title().unwrap().format_verbatim(), "Another A");
+        assert_eq!(bib.get("beta").unwrap().key, "b");
+
+        let mut bib = ours.clone();
+        let conflicts = bib.merge(theirs.clone(), MergeStrategy::Rename);
+        assert_eq!(
+            conflicts,
+            [
+                MergeConflict::Renamed { key: "a".into(), new_key: "aa".into() },
+                MergeConflict::Renamed { key: "beta".into(), new_key: "betaa".into() },
+            ]
+        );
+        assert_eq!(bib.keys().collect::<Vec<_>>(), ["a", "b", "aa", "betaa", "c"]);
+        assert_eq!(
+            bib.get("aa").unwrap().title().unwrap().format_verbatim(),
+            "Another A"
+        );
+
+        let mut bib = ours;
+        let conflicts = bib.merge(theirs, MergeStrategy::UnionFields);
+        assert_eq!(
+            conflicts,
+            [
+                MergeConflict::Merged {
+                    key: "a".into(),
+                    conflicting_fields: vec!["title".into()]
+                },
+                MergeConflict::Skipped("beta".into())
+            ]
+        );
+        let a = bib.get("a").unwrap();
+        assert_eq!(a.title().unwrap().format_verbatim(), "A");
+        assert_eq!(a.get("date").unwrap().format_verbatim(), "2001");
+        assert_eq!(bib.get("b").unwrap().get("crossref"), None);
+    }
+
+    #[test]
+    fn test_merge_rename() {
+        let ours = "@book{smith2020, title = {A}} @book{smith2020a, title = {B}}";
+        let theirs = r#"@book{smith2020, title = {C}}
+            @incollection{d, title = {D}, crossref = {smith2020}}"#;
+        let mut bib = Bibliography::parse(ours).unwrap();
+        let conflicts =
+            bib.merge(Bibliography::parse(theirs).unwrap(), MergeStrategy::Rename);
+        assert_eq!(
+            conflicts,
+            [MergeConflict::Renamed {
+                key: "smith2020".into(),
+                new_key: "smith2020b".into()
+            }]
+        );
+        assert_eq!(
+            bib.get("smith2020b").unwrap().title().unwrap().format_verbatim(),
+            "C"
+        );
+        assert_eq!(
+            bib.get("d").unwrap().get("crossref").unwrap().format_verbatim(),
+            "smith2020b"
+        );
+    }
+
+    #[test]
+    fn test_merge_kinds() {
+        let ours = "@xdata{x, publisher = {P}} @book{y, title = {Y}}";
+        let theirs = "@book{x, title = {X}} @xdata{y, location = {L}}";
+        let ours = Bibliography::parse(ours).unwrap();
+        let theirs = Bibliography::parse(theirs).unwrap();
+
+        for strategy in [MergeStrategy::Overwrite, MergeStrategy::UnionFields] {
+            let mut bib = ours.clone();
+            let mut conflicts = bib.merge(theirs.clone(), strategy);
+            conflicts.sort_by_key(|conflict| format!("{conflict:?}"));
+            assert_eq!(
+                conflicts,
+                [MergeConflict::Skipped("x".into()), MergeConflict::Skipped("y".into())]
+            );
+            assert_eq!(bib, ours);
+        }
+
+        let mut bib = ours.clone();
+        let conflicts = bib.merge(theirs, MergeStrategy::Rename);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(bib.len(), 2);
+        assert_eq!(bib.xdata_entries().count(), 2);
+    }
+}
@@ -0,0 +1,430 @@
+//! The BibLaTeX data model constraints for validating entries.
+//!
+//! The constraints follow the `\DeclareDatamodelConstraints` of the default
+//! data model (`blx-dm.def`) and the entry type descriptions in Section 2.1 of
+//! the [BibLaTeX Manual][manual].
+//!
+//! [manual]: http://ctan.ebinger.cc/tex-archive/macros/latex/contrib/biblatex/doc/biblatex.pdf
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::inheritance::get_canonical;
+use crate::{Bibliography, ChunksExt, Date, Entry, EntryType, TypeError, TypedField};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A constraint on the fields of an entry.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Constraint {
+    /// At least one of the fields must be present.
+    Mandatory(Vec<String>),
+    /// Exactly one of the fields must be present.
+    ExactlyOne(Vec<String>),
+    /// The field must not be present.
+    Forbidden(String),
+}
+
+/// The constraints of a data model, i.e., which fields entries of each type
+/// must have.
+///
+/// The [`Default`] model implements the constraints of BibLaTeX. Custom
+/// models for specific styles can be built in Rust or read from a simple
+/// declarative format with [`DataModel::parse`]. Entry types unknown to
+/// BibLaTeX are matched through [`EntryType::Unknown`].
+///
+/// ```
+/// # use biblatex::{Bibliography, DataModel, Violation};
+/// let mut model = DataModel::default();
+/// model.extend(DataModel::parse("legalcase: title, court, date ^ year").unwrap());
+///
+/// let bibliography = Bibliography::parse("@legalcase{roe, title = {Roe}}").unwrap();
+/// let violations = bibliography.validate_with(&model);
+/// assert_eq!(violations[0].1.to_string(), "missing field court");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DataModel {
+    /// Pairs of entry types and the constraints that apply to them. An empty
+    /// list of types matches all types.
+    constraints: Vec<(Vec<EntryType>, Constraint)>,
+}
+
+/// An error that occurred while reading a [`DataModel`] with
+/// [`DataModel::parse`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DataModelError {
+    /// The (zero-based) index of the malformed line.
+    pub line: usize,
+}
+
+impl Display for DataModelError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "malformed data model line {}", self.line + 1)
+    }
+}
+
+impl std::error::Error for DataModelError {}
+
+/// A violation of the data model found by [`Entry::validate`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Violation {
+    /// None of these fields is present, but one of them is required.
+    Missing(Vec<String>),
+    /// More than one of these fields is present, but they are mutually
+    /// exclusive.
+    Conflicting(Vec<String>),
+    /// The field is present, but not allowed for the entry type.
+    Forbidden(String),
+    /// The field does not contain data of the type required by the data model.
+    Malformed(String, TypeError),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Missing(fields) => write!(f, "missing field {}", fields.join(" or ")),
+            Self::Conflicting(fields) => {
+                write!(f, "conflicting fields {}", fields.join(" and "))
+            }
+            Self::Forbidden(field) => write!(f, "forbidden field {}", field),
+            Self::Malformed(field, err) => {
+                write!(f, "malformed field {}: {}", field, err)
+            }
+        }
+    }
+}
+
+impl Default for DataModel {
+    fn default() -> Self {
+        use EntryType::*;
+
+        let mandatory =
+            |types: &[EntryType], fields: &[&str]| -> Vec<(Vec<EntryType>, Constraint)> {
+                fields
+                    .iter()
+                    .map(|field| {
+                        let alternatives = field.split('|').map(ToString::to_string);
+                        (types.to_vec(), Constraint::Mandatory(alternatives.collect()))
+                    })
+                    .collect()
+            };
+
+        let dated = [
+            Article,
+            Book,
+            InBook,
+            BookInBook,
+            SuppBook,
+            Booklet,
+            Collection,
+            InCollection,
+            SuppCollection,
+            Dataset,
+            Manual,
+            Misc,
+            MvBook,
+            MvCollection,
+            MvProceedings,
+            MvReference,
+            Online,
+            Patent,
+            Periodical,
+            SuppPeriodical,
+            Proceedings,
+            InProceedings,
+            Reference,
+            InReference,
+            Report,
+            Software,
+            Thesis,
+            MastersThesis,
+            PhdThesis,
+            TechReport,
+            Unpublished,
+        ];
+
+        let mut constraints = vec![(
+            dated.to_vec(),
+            Constraint::ExactlyOne(vec!["date".into(), "year".into()]),
+        )];
+
+        for (types, fields) in [
+            (&[Article][..], &["author", "title", "journaltitle"][..]),
+            (&[Book, MvBook], &["author", "title"]),
+            (&[InBook, BookInBook, SuppBook], &["author", "title", "booktitle"]),
+            (&[Booklet, Manual, Misc, Dataset, Software], &["author|editor", "title"]),
+            (&[Collection, MvCollection, Reference, MvReference], &["editor", "title"]),
+            (
+                &[InCollection, SuppCollection, InReference],
+                &["author", "title", "booktitle"],
+            ),
+            (&[Online], &["author|editor", "title", "doi|eprint|url"]),
+            (&[Patent], &["author", "title", "number"]),
+            (&[Periodical], &["editor", "title"]),
+            (&[SuppPeriodical], &["author", "title", "journaltitle"]),
+            (&[Proceedings, MvProceedings], &["title"]),
+            (&[InProceedings], &["author", "title", "booktitle"]),
+            (&[Report, Thesis], &["author", "title", "type", "institution"]),
+            (
+                &[MastersThesis, PhdThesis, TechReport],
+                &["author", "title", "institution"],
+            ),
+            (&[Unpublished], &["author", "title"]),
+            (&[Set], &["entryset"]),
+        ] {
+            constraints.extend(mandatory(types, fields));
+        }
+
+        Self { constraints }
+    }
+}
+
+impl DataModel {
+    /// Create a data model without any constraints.
+    pub fn empty() -> Self {
+        Self { constraints: vec![] }
+    }
+
+    /// Read a data model from a declarative description.
+    ///
+    /// Each line lists comma-separated entry types (or `*` for all types),
+    /// a colon, and comma-separated constraints:
+    ///
+    /// - `author | editor` requires at least one of the fields,
+    /// - `date ^ year` requires exactly one of the fields, and
+    /// - `!pages` forbids a field.
+    ///
+    /// Empty lines and lines starting with `%` are ignored.
+    pub fn parse(src: &str) -> Result<Self, DataModelError> {
+        let mut model = Self::empty();
+
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') {
+                continue;
+            }
+
+            let error = DataModelError { line: i };
+            let (types, constraints) = line.split_once(':').ok_or(error.clone())?;
+            let types: Vec<EntryType> = match types.trim() {
+                "*" => vec![],
+                types => types.split(',').map(|t| EntryType::new(t.trim())).collect(),
+            };
+
+            for constraint in constraints.split(',') {
+                let constraint = constraint.trim();
+                let is_field = |f: &&str| !f.is_empty() && f.chars().all(is_field_char);
+                let fields = |sep| -> Option<Vec<String>> {
+                    constraint
+                        .split(sep)
+                        .map(str::trim)
+                        .map(|f| Some(f).filter(is_field).map(str::to_lowercase))
+                        .collect()
+                };
+
+                let constraint = if let Some(field) = constraint.strip_prefix('!') {
+                    let field = field.trim();
+                    is_field(&field).then(|| Constraint::Forbidden(field.to_lowercase()))
+                } else if constraint.contains('^') {
+                    fields('^').map(Constraint::ExactlyOne)
+                } else {
+                    fields('|').map(Constraint::Mandatory)
+                };
+
+                model.add_constraint(&types, constraint.ok_or(error.clone())?);
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Add a constraint for entries of the given types. An empty list of
+    /// types matches all types.
+    pub fn add_constraint(&mut self, types: &[EntryType], constraint: Constraint) {
+        self.constraints.push((types.to_vec(), constraint));
+    }
+
+    /// Add all constraints of another model.
+    pub fn extend(&mut self, other: DataModel) {
+        self.constraints.extend(other.constraints);
+    }
+
+    /// Check an entry against the constraints of the model.
+    fn validate(&self, entry: &Entry) -> Vec<Violation> {
+        let mut violations = vec![];
+        let present =
+            |field: &String| get_canonical(entry, field).is_some_and(|c| !c.is_empty());
+
+        for (types, constraint) in &self.constraints {
+            if !types.is_empty() && !types.contains(&entry.entry_type) {
+                continue;
+            }
+
+            match constraint {
+                Constraint::Mandatory(fields) => {
+                    if !fields.iter().any(present) {
+                        violations.push(Violation::Missing(fields.clone()));
+                    }
+                }
+                Constraint::ExactlyOne(fields) => {
+                    match fields.iter().filter(|f| present(f)).count() {
+                        0 => violations.push(Violation::Missing(fields.clone())),
+                        1 => {}
+                        _ => violations.push(Violation::Conflicting(fields.clone())),
+                    }
+                }
+                Constraint::Forbidden(field) => {
+                    if present(field) {
+                        violations.push(Violation::Forbidden(field.clone()));
+                    }
+                }
+            }
+        }
+
+        for (key, chunks) in &entry.fields {
+            // Dates are kept as chunks if they cannot be parsed, but the data
+            // model requires them to be well-formed.
+            let result = match key.as_str() {
+                "date" | "eventdate" | "origdate" | "urldate" => {
+                    chunks.parse::<Date>().map(|_| ())
+                }
+                _ => TypedField::try_from((key.as_str(), chunks.as_slice())).map(|_| ()),
+            };
+
+            if let Err(err) = result {
+                violations.push(Violation::Malformed(key.to_string(), err));
+            }
+        }
+
+        violations
+    }
+}
+
+impl Entry {
+    /// Validate the entry against the constraints of the default BibLaTeX data
+    /// model.
+    ///
+    /// Unlike [`Entry::verify`], this reports which of several alternative
+    /// fields are missing (e.g. `author` or `editor`), mutually exclusive
+    /// fields, and malformed data in any known field.
+    pub fn validate(&self) -> Vec<Violation> {
+        DataModel::default().validate(self)
+    }
+
+    /// Validate the entry against the constraints of a custom data model.
+    pub fn validate_with(&self, model: &DataModel) -> Vec<Violation> {
+        model.validate(self)
+    }
+}
+
+impl Bibliography {
+    /// Validate all entries against the constraints of the default BibLaTeX
+    /// data model.
+    ///
+    /// Returns pairs of cite keys and violations in bibliography order.
+    pub fn validate(&self) -> Vec<(String, Violation)> {
+        self.validate_with(&DataModel::default())
+    }
+
+    /// Validate all entries against the constraints of a custom data model.
+    pub fn validate_with(&self, model: &DataModel) -> Vec<(String, Violation)> {
+        self.iter()
+            .flat_map(|entry| {
+                model
+                    .validate(entry)
+                    .into_iter()
+                    .map(|violation| (entry.key.clone(), violation))
+            })
+            .collect()
+    }
+}
+
+/// Whether the character can appear in a field name.
+fn is_field_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TypeErrorKind;
+
+    #[test]
+    fn test_validate() {
+        let contents = r#"@article{a, author = {Doe, Jane}, title = {A},
+              journal = {J}, date = {2001}}
+            @article{b, title = {B}, date = {2001}, year = {2001}}
+            @booklet{c, editor = {Roe, Richard}, title = {C}, year = {2001}}
+            @online{d, title = {D}, date = {2001-13}, url = {https://example.com}}
+            @thesis{e, author = {Doe, Jane}, title = {E}, school = {TU},
+              type = {phdthesis}, year = {2001}}
+            @customa{f, note = {Anything goes}}"#;
+        let bibliography = Bibliography::parse(contents).unwrap();
+
+        let violations = bibliography.validate();
+        let missing = |fields: &[&str]| {
+            Violation::Missing(fields.iter().map(ToString::to_string).collect())
+        };
+
+        assert_eq!(
+            violations[..3],
+            [
+                ("b".into(), Violation::Conflicting(vec!["date".into(), "year".into()])),
+                ("b".into(), missing(&["author"])),
+                ("b".into(), missing(&["journaltitle"])),
+            ]
+        );
+        assert_eq!(violations[3].0, "d");
+        assert_eq!(violations[3].1, missing(&["author", "editor"]));
+        match &violations[4] {
+            (key, Violation::Malformed(field, err)) => {
+                assert_eq!(key, "d");
+                assert_eq!(field, "date");
+                assert_eq!(err.kind, TypeErrorKind::MonthOutOfRange);
+            }
+            other => panic!("expected malformed date, got {:?}", other),
+        }
+        assert_eq!(violations.len(), 5);
+        assert_eq!(violations[0].1.to_string(), "conflicting fields date and year");
+    }
+
+    #[test]
+    fn test_custom_model() {
+        let model = DataModel::parse(
+            "% Legal citations
+            legalcase, jurisdiction: title, court | institution
+            legalcase: date ^ year, !author
+            *: !abstract",
+        )
+        .unwrap();
+
+        let contents = r#"@legalcase{a, title = {A}, court = {Court}, year = {2001}}
+            @legalcase{b, title = {B}, author = {Doe, Jane}, abstract = {B}}
+            @jurisdiction{c, title = {C}, institution = {Court}}"#;
+        let bibliography = Bibliography::parse(contents).unwrap();
+
+        let violations = bibliography.validate_with(&model);
+        let key = |key: &str| violations.iter().filter(|(k, _)| k == key).count();
+        assert_eq!(key("a"), 0);
+        assert_eq!(key("c"), 0);
+        assert_eq!(
+            violations,
+            [
+                (
+                    "b".into(),
+                    Violation::Missing(vec!["court".into(), "institution".into()])
+                ),
+                ("b".into(), Violation::Missing(vec!["date".into(), "year".into()])),
+                ("b".into(), Violation::Forbidden("author".into())),
+                ("b".into(), Violation::Forbidden("abstract".into())),
+            ]
+        );
+
+        assert_eq!(DataModel::parse("article"), Err(DataModelError { line: 0 }));
+        assert_eq!(DataModel::parse("\narticle: a |"), Err(DataModelError { line: 1 }));
+    }
+}
@@ -0,0 +1,275 @@
+//! Detection of spelling variants of person names.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::keygen::transliterate;
+use crate::{Bibliography, Chunks, Person, Type};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The fields holding name lists.
+pub(crate) const NAME_FIELDS: [&str; 16] = [
+    "afterword",
+    "annotator",
+    "author",
+    "bookauthor",
+    "commentator",
+    "editor",
+    "editora",
+    "editorb",
+    "editorc",
+    "foreword",
+    "holder",
+    "introduction",
+    "shortauthor",
+    "shorteditor",
+    "sortname",
+    "translator",
+];
+
+/// Different spellings of what is probably the same person's name, like
+/// `J. Smith`, `Smith, John`, and `John Smith`. Can be obtained through
+/// [`Bibliography::name_variants`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NameCluster {
+    /// The distinct spellings with their number of occurrences, most frequent
+    /// first and otherwise in order of first appearance.
+    pub variants: Vec<(Person, usize)>,
+}
+
+impl NameCluster {
+    /// The most complete spelling, which has the most given names written
+    /// out in full. Ties are broken by frequency.
+    pub fn canonical(&self) -> &Person {
+        let mut best = &self.variants[0].0;
+        for (person, _) in &self.variants[1..] {
+            if completeness(person) > completeness(best) {
+                best = person;
+            }
+        }
+        best
+    }
+}
+
+impl Bibliography {
+    /// Group the spellings of names in all name list fields that probably
+    /// refer to the same person.
+    ///
+    /// Names are considered the same if their family names and prefixes
+    /// match regardless of case and diacritics, their suffixes do not
+    /// conflict, and their given names agree, where an initial matches any
+    /// name with the same first letter and a shorter list of given names may
+    /// omit trailing ones. A spelling that fits multiple distinct names, like
+    /// `J. Smith` next to `John Smith` and `Jane Smith`, is ambiguous and left
+    /// out. Only clusters with at least two spellings are returned, in order
+    /// of first appearance.
+    pub fn name_variants(&self) -> Vec<NameCluster> {
+        let mut counts: Vec<(Person, usize)> = vec![];
+        let mut index: HashMap<Person, usize> = HashMap::new();
+        for entry in &self.entries {
+            for field in NAME_FIELDS {
+                for person in entry.get_as::<Vec<Person>>(field).unwrap_or_default() {
+                    match index.get(&person) {
+                        Some(&i) => counts[i].1 += 1,
+                        None => {
+                            index.insert(person.clone(), counts.len());
+                            counts.push((person, 1));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Assign the most complete spellings first, so that initials are
+        // matched against all full names they could abbreviate.
+        let mut order: Vec<usize> = (0..counts.len()).collect();
+        order.sort_by_key(|&i| Reverse(completeness(&counts[i].0)));
+
+        let mut clusters: Vec<Vec<usize>> = vec![];
+        for i in order {
+            let person = &counts[i].0;
+            let fitting: Vec<usize> = clusters
+                .iter()
+                .enumerate()
+                .filter(|(_, cluster)| {
+                    cluster.iter().all(|&j| is_variant(person, &counts[j].0))
+                })
+                .map(|(c, _)| c)
+                .collect();
+
+            match fitting.as_slice() {
+                [] => clusters.push(vec![i]),
+                &[c] => clusters[c].push(i),
+                _ => {}
+            }
+        }
+
+        clusters.retain(|cluster| cluster.len() > 1);
+        for cluster in &mut clusters {
+            cluster.sort_by_key(|&i| (Reverse(counts[i].1), i));
+        }
+        clusters.sort_by_key(|cluster| cluster.iter().min().copied());
+
+        clusters
+            .into_iter()
+            .map(|cluster| NameCluster {
+                variants: cluster.into_iter().map(|i| counts[i].clone()).collect(),
+            })
+            .collect()
+    }
+
+    /// Replace the spellings of a cluster in the name list fields with a
+    /// canonical one, e.g. [`NameCluster::canonical`]. Returns the cite keys
+    /// of the changed entries.
+    ///
+    /// A spelling is only replaced if the canonical one carries at least the
+    /// same information, so `J. Smith` becomes `John Smith`, but `J. R. Smith`
+    /// is kept. The other names in a list keep their formatting.
+    pub fn unify_names(
+        &mut self,
+        cluster: &NameCluster,
+        canonical: &Person,
+    ) -> Vec<String> {
+        let mut changed = vec![];
+        for entry in &mut self.entries {
+            let mut any = false;
+            for field in NAME_FIELDS {
+                let Ok(mut names) = entry.get_as::<Vec<Chunks>>(field) else {
+                    continue;
+                };
+
+                let mut replaced = false;
+                for chunks in &mut names {
+                    let person = Person::parse(chunks);
+                    if person != *canonical
+                        && covers(canonical, &person)
+                        && cluster.variants.iter().any(|(variant, _)| *variant == person)
+                    {
+                        *chunks = vec![canonical.clone()].to_chunks();
+                        replaced = true;
+                    }
+                }
+
+                if replaced {
+                    entry.set_as(field, &names);
+                    any = true;
+                }
+            }
+
+            if any {
+                changed.push(entry.key.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Whether two names probably belong to the same person.
+fn is_variant(a: &Person, b: &Person) -> bool {
+    let family = |p: &Person| normalize(&format!("{} {}", p.prefix, p.name));
+    if family(a).is_empty() || family(a) != family(b) {
+        return false;
+    }
+
+    let (sa, sb) = (normalize(&a.suffix), normalize(&b.suffix));
+    if !sa.is_empty() && !sb.is_empty() && sa != sb {
+        return false;
+    }
+
+    let (ga, gb) = (given_names(a), given_names(b));
+    if ga.is_empty() || gb.is_empty() {
+        return ga.is_empty() && gb.is_empty();
+    }
+
+    ga.iter().zip(&gb).all(|(x, y)| {
+        let initial = x.chars().count() == 1 || y.chars().count() == 1;
+        if initial {
+            x.chars().next() == y.chars().next()
+        } else {
+            x == y
+        }
+    })
+}
+
+/// Whether the `canonical` spelling of a name keeps all information of
+/// another `spelling`: every given name and the suffix.
+fn covers(canonical: &Person, spelling: &Person) -> bool {
+    let (gc, gs) = (given_names(canonical), given_names(spelling));
+    is_variant(canonical, spelling)
+        && gc.len() >= gs.len()
+        && gc
+            .iter()
+            .zip(&gs)
+            .all(|(c, s)| c.chars().count() >= s.chars().count())
+        && (spelling.suffix.is_empty() || !canonical.suffix.is_empty())
+}
+
+/// How complete a spelling is: the number of given names written out in
+/// full, the number of given names, and the length of the given names.
+fn completeness(person: &Person) -> (usize, usize, usize) {
+    let names = given_names(person);
+    let full = names.iter().filter(|name| name.chars().count() > 1).count();
+    (full, names.len(), person.given_name.chars().count())
+}
+
+/// The normalized given names, with initials reduced to their letter.
+fn given_names(person: &Person) -> Vec<String> {
+    person
+        .given_name
+        .split(|c: char| c.is_whitespace() || c == '.' || c == '-')
+        .map(normalize)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Lowercase ASCII letters and digits, with diacritics removed.
+fn normalize(name: &str) -> String {
+    transliterate(name)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_variants() {
+        let src = r#"@article{a, author = {Smith, John and Doe, Jane}}
+            @article{b, author = {J. Smith and Müller, H.-J.}}
+            @article{c, author = {John Smith}, editor = {Müller, Hans-Jürgen}}
+            @article{d, author = {Doe, J. and Doe, Jim and Smith}}
+            @article{e, author = {Mueller, Hans}, translator = {Smith, J. R.}}"#;
+        let mut bibliography = Bibliography::parse(src).unwrap();
+
+        let clusters = bibliography.name_variants();
+        assert_eq!(clusters.len(), 2);
+
+        let smith = &clusters[0];
+        let spellings: Vec<_> = smith
+            .variants
+            .iter()
+            .map(|(p, n)| (p.given_name.as_str(), *n))
+            .collect();
+        assert_eq!(spellings, [("John", 2), ("J.", 1), ("J. R.", 1)]);
+        assert_eq!(smith.canonical().given_name, "John");
+
+        let muller = &clusters[1];
+        assert_eq!(muller.variants.len(), 2);
+        assert_eq!(muller.canonical().given_name, "Hans-Jürgen");
+
+        let canonical = smith.canonical().clone();
+        assert_eq!(bibliography.unify_names(smith, &canonical), ["b"]);
+        let b = bibliography.get("b").unwrap();
+        assert_eq!(b.author().unwrap()[0], canonical);
+        assert!(!b.get("author").unwrap().last().unwrap().is_detached());
+        let e = bibliography.get("e").unwrap();
+        assert_eq!(e.translator().unwrap()[0].given_name, "J. R.");
+        assert_eq!(bibliography.name_variants().len(), 2);
+    }
+}
@@ -0,0 +1,516 @@
+//! Importing entries from PubMed's MEDLINE format.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::keygen::{sanitize, suffixed_key};
+use crate::types::Type;
+use crate::{
+    Bibliography, Chunk, Chunks, Date, DateValue, Datetime, Entry, EntryType, KeyPattern,
+    PermissiveType, Person, Spanned,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The abbreviated month names used in publication dates.
+const MONTHS: [&str; 12] =
+    ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+/// The language identifiers for the most common MEDLINE language codes.
+/// Other codes are kept as they are.
+const LANGUAGES: [(&str, &str); 12] = [
+    ("chi", "chinese"),
+    ("dan", "danish"),
+    ("dut", "dutch"),
+    ("eng", "english"),
+    ("fre", "french"),
+    ("ger", "german"),
+    ("ita", "italian"),
+    ("jpn", "japanese"),
+    ("pol", "polish"),
+    ("por", "portuguese"),
+    ("rus", "russian"),
+    ("spa", "spanish"),
+];
+
+/// Errors that can occur when importing MEDLINE records.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NbibError {
+    /// A line is neither a tagged field nor the continuation of one.
+    /// Contains the byte offset of the line.
+    InvalidLine(usize),
+}
+
+impl Display for NbibError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidLine(offset) => write!(f, "invalid line at byte {}", offset),
+        }
+    }
+}
+
+impl std::error::Error for NbibError {}
+
+/// The tagged fields of a MEDLINE record in order.
+type Record<'a> = Vec<(&'a str, String)>;
+
+impl Bibliography {
+    /// Construct a bibliography from records in the MEDLINE format, as
+    /// downloaded from PubMed in `.nbib` files.
+    ///
+    /// Records become `@article` entries, or `@book` and `@incollection`
+    /// entries for books and chapters with a book title (`BTI`). The full
+    /// author names (`FAU`) are preferred over the abbreviated ones (`AU`),
+    /// publication dates (`DP`) like `2020 Jan 15` become typed dates, and
+    /// abbreviated page ranges like `123-30` are expanded. The `PMID` is
+    /// stored as a PubMed eprint.
+    ///
+    /// The cite keys are generated from the `pattern` with alphabetic
+    /// suffixes for collisions, as in [`Bibliography::generate_keys`].
+    /// Records for which the pattern produces an empty key are keyed by
+    /// their PMID.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, KeyPattern};
+    /// let src = "\
+    /// PMID- 10000001
+    /// DP  - 2019 Mar 4
+    /// TI  - Gut microbiota in
+    ///       health and disease.
+    /// FAU - Smith, Jane A
+    /// AU  - Smith JA
+    /// JT  - Nature reviews. Microbiology
+    /// VI  - 17
+    /// PG  - 123-30
+    /// ";
+    ///
+    /// let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+    /// let bibliography = Bibliography::from_nbib(src, &pattern).unwrap();
+    /// let entry = bibliography.get("smith2019").unwrap();
+    /// assert_eq!(
+    ///     entry.to_biblatex_string(),
+    ///     "@article{smith2019,\n\
+    ///      author = {Smith, Jane A.},\n\
+    ///      date = {2019-03-04},\n\
+    ///      eprint = {10000001},\n\
+    ///      eprinttype = {pubmed},\n\
+    ///      journaltitle = {Nature reviews. Microbiology},\n\
+    ///      pages = {123--130},\n\
+    ///      title = {Gut microbiota in health and disease},\n\
+    ///      volume = {17},\n\
+    ///      }",
+    /// );
+    /// ```
+    pub fn from_nbib(src: &str, pattern: &KeyPattern) -> Result<Self, NbibError> {
+        let mut bibliography = Self::new();
+        for record in parse_records(src)? {
+            let id = value(&record, "PMID").map(sanitize);
+            let id = id.filter(|id| !id.is_empty()).unwrap_or_else(|| "item".into());
+            let key = if bibliography.is_taken(&id) {
+                suffixed_key(&id, |key| bibliography.is_taken(key))
+            } else {
+                id
+            };
+            bibliography.insert(nbib_entry(key, &record));
+        }
+
+        bibliography.generate_keys(pattern, |_| true);
+        Ok(bibliography)
+    }
+}
+
+/// Split the source into records. Records are separated by blank lines or
+/// start with a `PMID` tag.
+fn parse_records(src: &str) -> Result<Vec<Record<'_>>, NbibError> {
+    let mut records = vec![];
+    let mut record = Record::new();
+    let mut offset = 0;
+
+    for line in src.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+
+        if line.is_empty() {
+            if !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+        } else if line.starts_with(' ') {
+            let Some((_, value)) = record.last_mut() else {
+                return Err(NbibError::InvalidLine(start));
+            };
+            value.push(' ');
+            value.push_str(line.trim_start());
+        } else {
+            let Some((tag, value)) = parse_field(line) else {
+                return Err(NbibError::InvalidLine(start));
+            };
+            if tag == "PMID" && !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+            record.push((tag, value.into()));
+        }
+    }
+
+    if !record.is_empty() {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Split a line like `TI  - Title` into its tag and value.
+fn parse_field(line: &str) -> Option<(&str, &str)> {
+    let (tag, value) = line.split_once('-')?;
+    let tag = tag.trim_end();
+    let valid = (1..=4).contains(&tag.len())
+        && tag.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+    valid.then(|| (tag, value.trim_start()))
+}
+
+/// The values of all fields with the given tag.
+fn values<'a>(record: &'a Record, tag: &'a str) -> impl Iterator<Item = &'a str> {
+    record
+        .iter()
+        .filter(move |(t, _)| *t == tag)
+        .map(|(_, value)| value.as_str())
+}
+
+/// The value of the first field with the given tag.
+fn value<'a>(record: &'a Record, tag: &'a str) -> Option<&'a str> {
+    values(record, tag).next().filter(|value| !value.is_empty())
+}
+
+/// Construct an entry from a MEDLINE record.
+fn nbib_entry(key: String, record: &Record) -> Entry {
+    let book_title = value(record, "BTI");
+    let title = value(record, "TI");
+    let entry_type = match (book_title, title) {
+        (Some(_), Some(_)) => EntryType::InCollection,
+        (Some(_), None) => EntryType::Book,
+        (None, _) => EntryType::Article,
+    };
+    let mut entry = Entry::new(key, entry_type);
+
+    match book_title {
+        Some(book_title) => {
+            if let Some(title) = title {
+                set_text(&mut entry, "title", nbib_title(title));
+            }
+            let field = if title.is_some() { "booktitle" } else { "title" };
+            set_text(&mut entry, field, nbib_title(book_title));
+            if let Some(publisher) = value(record, "PB") {
+                set_text(&mut entry, "publisher", publisher);
+            }
+            if let Some(location) = value(record, "PL") {
+                set_text(&mut entry, "location", location);
+            }
+        }
+        None => {
+            if let Some(title) = title {
+                set_text(&mut entry, "title", nbib_title(title));
+            }
+            if let Some(journal) = value(record, "JT") {
+                set_text(&mut entry, "journaltitle", journal);
+            }
+            if let Some(journal) = value(record, "TA") {
+                set_text(&mut entry, "shortjournal", journal);
+            }
+        }
+    }
+
+    let authors = nbib_names(record, "FAU", "AU", Some("CN"));
+    if !authors.is_empty() {
+        entry.set("author", authors.to_chunks());
+    }
+    let editors = nbib_names(record, "FED", "ED", None);
+    if !editors.is_empty() {
+        entry.set("editor", editors.to_chunks());
+    }
+
+    if let Some(date) = value(record, "DP") {
+        entry.set("date", nbib_date(date).to_chunks());
+    }
+
+    for (tag, field) in [("VI", "volume"), ("IP", "number"), ("EN", "edition")] {
+        if let Some(value) = value(record, tag) {
+            set_text(&mut entry, field, value);
+        }
+    }
+
+    if let Some(pages) = value(record, "PG") {
+        set_text(&mut entry, "pages", &nbib_pages(pages));
+    }
+
+    let doi = values(record, "LID")
+        .chain(values(record, "AID"))
+        .find_map(|id| id.strip_suffix("[doi]"));
+    if let Some(doi) = doi {
+        set_text(&mut entry, "doi", doi.trim());
+    }
+
+    for (tag, field) in [("IS", "issn"), ("ISBN", "isbn")] {
+        if let Some(number) = value(record, tag) {
+            let number = number.split(" (").next().unwrap_or_default();
+            set_text(&mut entry, field, number);
+        }
+    }
+
+    if let Some(pmid) = value(record, "PMID") {
+        set_text(&mut entry, "eprint", pmid);
+        set_text(&mut entry, "eprinttype", "pubmed");
+    }
+
+    if let Some(language) = value(record, "LA") {
+        let language = LANGUAGES
+            .iter()
+            .find(|(code, _)| language.eq_ignore_ascii_case(code))
+            .map_or(language, |(_, id)| id);
+        set_text(&mut entry, "langid", language);
+    }
+
+    let keywords: Vec<_> = values(record, "OT").collect();
+    if !keywords.is_empty() {
+        set_text(&mut entry, "keywords", &keywords.join(", "));
+    }
+
+    if let Some(text) = value(record, "AB") {
+        set_text(&mut entry, "abstract", text);
+    }
+
+    entry
+}
+
+/// Set a field to the given text.
+fn set_text(entry: &mut Entry, field: &str, text: &str) {
+    entry.set(field, vec![Spanned::detached(Chunk::Normal(text.into()))]);
+}
+
+/// Remove the final period of a title and the brackets around titles that
+/// were translated into English.
+fn nbib_title(title: &str) -> &str {
+    let title = title.strip_suffix('.').unwrap_or(title);
+    title
+        .strip_prefix('[')
+        .and_then(|title| title.strip_suffix(']'))
+        .unwrap_or(title)
+}
+
+/// Collect the names in the order of the record. The full names are used if
+/// there are any and the abbreviated ones otherwise. Corporate names are
+/// protected from being split into name parts.
+fn nbib_names(
+    record: &Record,
+    full: &str,
+    short: &str,
+    corporate: Option<&str>,
+) -> Vec<Chunks> {
+    let has_full = values(record, full).next().is_some();
+    record
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .filter_map(|(tag, value)| {
+            if Some(*tag) == corporate {
+                Some(vec![Spanned::detached(Chunk::Verbatim(value.clone()))])
+            } else if *tag == full && has_full {
+                Some(vec![nbib_full_name(value)].to_chunks())
+            } else if *tag == short && !has_full {
+                Some(vec![nbib_short_name(value)].to_chunks())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Convert a full name like `Smith, Jane A`.
+fn nbib_full_name(name: &str) -> Person {
+    let Some((family, given)) = name.split_once(',') else {
+        return person(name, String::new());
+    };
+    let given = given
+        .split_whitespace()
+        .map(|part| {
+            if part.len() == 1 && part.chars().all(|c| c.is_alphabetic()) {
+                format!("{}.", part)
+            } else {
+                part.into()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    person(family.trim(), given)
+}
+
+/// Convert an abbreviated name like `Smith JA`.
+fn nbib_short_name(name: &str) -> Person {
+    match name.rsplit_once(' ') {
+        Some((family, initials)) if initials.chars().all(|c| c.is_ascii_uppercase()) => {
+            let given = initials
+                .chars()
+                .map(|c| format!("{}.", c))
+                .collect::<Vec<_>>()
+                .join(" ");
+            person(family, given)
+        }
+        _ => person(name, String::new()),
+    }
+}
+
+/// Construct a person without prefix and suffix.
+fn person(name: &str, given_name: String) -> Person {
+    Person {
+        name: name.into(),
+        given_name,
+        prefix: String::new(),
+        suffix: String::new(),
+    }
+}
+
+/// Convert a publication date like `2020 Jan 15`, `2020 Jan-Feb`, or
+/// `2020 Spring`, falling back to its textual form.
+fn nbib_date(text: &str) -> PermissiveType<Date> {
+    let mut parts = text.split_whitespace();
+    let Some(year) = parts.next().and_then(|year| year.parse::<i32>().ok()) else {
+        return PermissiveType::Chunks(vec![Spanned::detached(Chunk::Normal(
+            text.into(),
+        ))]);
+    };
+
+    let month = parts.next().and_then(|month| {
+        let month = month.get(..3)?;
+        MONTHS.iter().position(|name| month.eq_ignore_ascii_case(name))
+    });
+    let day = parts.next().filter(|_| month.is_some()).and_then(|day| {
+        let digits: String = day.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse::<u8>().ok().filter(|day| (1..=31).contains(day))
+    });
+
+    PermissiveType::Typed(Date {
+        value: DateValue::At(Datetime {
+            year,
+            month: month.map(|month| month as u8),
+            day: day.map(|day| day - 1),
+            time: None,
+        }),
+        uncertain: false,
+        approximate: false,
+    })
+}
+
+/// Expand abbreviated page ranges like `123-30` to `123--130`.
+fn nbib_pages(pages: &str) -> String {
+    pages
+        .split(',')
+        .map(|range| {
+            let range = range.trim();
+            let Some((start, end)) = range.split_once('-') else {
+                return range.to_string();
+            };
+            let numeric =
+                |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+            if numeric(start) && numeric(end) && end.len() < start.len() {
+                format!("{}--{}{}", start, &start[..start.len() - end.len()], end)
+            } else {
+                format!("{}--{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunksExt;
+
+    #[test]
+    fn test_from_nbib() {
+        let src = "\u{feff}PMID- 31000001
+OWN - NLM
+DP  - 2020 Jan-Feb
+TI  - [Effects of coffee on
+      sleep].
+FAU - van der Berg, Jan
+AU  - van der Berg J
+CN  - WHO Study Group
+FAU - Roe, R
+AU  - Roe R
+LA  - ger
+PT  - Journal Article
+TA  - J Sleep Res
+JT  - Journal of sleep research
+IP  - 1
+PG  - 1001-9, e12
+LID - S0000-0000(20)00001-1 [pii]
+LID - 10.1111/jsr.1 [doi]
+IS  - 0962-1105 (Print)
+IS  - 1365-2869 (Electronic)
+OT  - coffee
+OT  - sleep
+\r
+PMID- 31000002
+DP  - 2021 Spring
+TI  - Second.
+AU  - Doe JA
+AU  - Madonna
+PMID- 31000003
+BTI - Handbook of Sleep
+ED  - Roe R
+PB  - Springer
+PL  - Berlin
+DP  - 2018
+
+PMID- 31000004
+TI  - Anonymous.
+";
+
+        let pattern = KeyPattern::parse("[auth:lower][year]").unwrap();
+        let bibliography = Bibliography::from_nbib(src, &pattern).unwrap();
+        assert_eq!(
+            bibliography.keys().collect::<Vec<_>>(),
+            ["berg2020", "doe2021", "roe2018", "31000004"]
+        );
+
+        let entry = bibliography.get("berg2020").unwrap();
+        assert_eq!(
+            entry.to_biblatex_string(),
+            "@article{berg2020,\n\
+             author = {van der Berg, Jan and {WHO Study Group} and Roe, R.},\n\
+             date = {2020-01},\n\
+             doi = {10.1111/jsr.1},\n\
+             eprint = {31000001},\n\
+             eprinttype = {pubmed},\n\
+             issn = {0962-1105},\n\
+             journaltitle = {Journal of sleep research},\n\
+             keywords = {coffee, sleep},\n\
+             langid = {german},\n\
+             number = {1},\n\
+             pages = {1001--1009, e12},\n\
+             shortjournal = {J Sleep Res},\n\
+             title = {Effects of coffee on sleep},\n\
+             }"
+        );
+
+        let entry = bibliography.get("doe2021").unwrap();
+        let authors = entry.author().unwrap();
+        assert_eq!(authors[0].given_name, "J. A.");
+        assert_eq!(authors[1].name, "Madonna");
+        assert_eq!(entry.get("date").unwrap().format_verbatim(), "2021");
+
+        let entry = bibliography.get("roe2018").unwrap();
+        assert_eq!(entry.entry_type, EntryType::Book);
+        assert_eq!(entry.title().unwrap().format_verbatim(), "Handbook of Sleep");
+        assert_eq!(entry.editors().unwrap()[0].0[0].given_name, "R.");
+        assert_eq!(entry.get("location").unwrap().format_verbatim(), "Berlin");
+
+        assert_eq!(
+            Bibliography::from_nbib("PMID- 1\nnot a field\n", &pattern),
+            Err(NbibError::InvalidLine(8))
+        );
+        assert_eq!(
+            Bibliography::from_nbib("   continued\n", &pattern),
+            Err(NbibError::InvalidLine(0))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Parsing large bibliographies on multiple threads.
+
+use memchr::memmem;
+use rayon::prelude::*;
+
+use crate::{decode_fields, Bibliography, InheritanceSpec, ParseError, RawBibliography};
+
+/// The number of pieces per thread that the source is split into, so that
+/// threads that finish early can pick up more work.
+const PIECES_PER_THREAD: usize = 4;
+
+impl<'s> RawBibliography<'s> {
+    /// Parse a raw bibliography on multiple threads.
+    ///
+    /// A pre-scan splits the source into pieces at entries that start on a
+    /// new line, which are then parsed in parallel. The result is the same
+    /// as that of [`RawBibliography::parse`]. If a piece fails to parse, the
+    /// whole source is parsed again on the current thread to report the
+    /// same error.
+    pub fn par_parse(src: &'s str) -> Result<Self, ParseError> {
+        let bounds = piece_bounds(src, rayon::current_num_threads() * PIECES_PER_THREAD);
+        let pieces: Result<Vec<_>, _> = bounds
+            .par_windows(2)
+            .map(|window| Self::parse_range(src, window[0]..window[1]))
+            .collect();
+
+        let Ok(pieces) = pieces else {
+            return Self::parse(src);
+        };
+
+        let mut res = RawBibliography {
+            preamble: String::new(),
+            entries: Vec::with_capacity(pieces.iter().map(|p| p.entries.len()).sum()),
+            abbreviations: vec![],
+        };
+
+        for piece in pieces {
+            if !piece.preamble.is_empty() {
+                if !res.preamble.is_empty() {
+                    res.preamble.push_str(" # ");
+                }
+                res.preamble.push_str(&piece.preamble);
+            }
+            res.entries.extend(piece.entries);
+            res.abbreviations.extend(piece.abbreviations);
+        }
+
+        Ok(res)
+    }
+}
+
+impl Bibliography {
+    /// Parse a bibliography on multiple threads.
+    ///
+    /// Both the syntax and the field values are parsed in parallel. The
+    /// result is the same as that of [`Bibliography::parse`].
+    ///
+    /// ```
+    /// # use biblatex::Bibliography;
+    /// let src = "@book{a, title = {A}}\n@book{b, title = {B}}";
+    /// assert_eq!(Bibliography::par_parse(src), Bibliography::parse(src));
+    /// ```
+    pub fn par_parse(src: &str) -> Result<Self, ParseError> {
+        Self::par_from_raw(RawBibliography::par_parse(src)?)
+    }
+
+    /// Construct a bibliography from a raw bibliography, decoding the fields
+    /// of the entries in parallel.
+    pub fn par_from_raw(raw: RawBibliography) -> Result<Self, ParseError> {
+        let abbr = &raw.abbreviations;
+        let fields: Vec<_> = raw
+            .entries
+            .par_iter()
+            .map(|entry| decode_fields(&entry.v, abbr))
+            .collect();
+
+        Self::from_decoded(
+            raw.preamble,
+            &raw.entries,
+            fields.into_iter(),
+            &InheritanceSpec::default(),
+        )
+    }
+}
+
+/// Split the source into about `count` pieces of similar size. Each piece
+/// but the first starts with an `@` at the start of a line. Returns the
+/// start offsets of the pieces followed by the length of the source.
+fn piece_bounds(src: &str, count: usize) -> Vec<usize> {
+    let size = src.len() / count.max(1) + 1;
+    let finder = memmem::Finder::new("\n@");
+    let mut bounds = vec![0];
+
+    while let Some(&last) = bounds.last() {
+        let from = last + size;
+        let Some(offset) = src.get(from..).and_then(|rest| finder.find(rest.as_bytes()))
+        else {
+            break;
+        };
+        bounds.push(from + offset + 1);
+    }
+
+    bounds.push(src.len());
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_par_parse() {
+        let mut src = String::from("@preamble{\"a\"}\n@string{acm = \"ACM\"}\n");
+        for i in 0..200 {
+            src.push_str(&format!(
+                "@article{{key{i},\n  author = {{Doe, J.}},\n  \
+                 note = {{mail@example.org}},\n  publisher = acm,\n}}\n"
+            ));
+            if i % 50 == 0 {
+                src.push_str("@comment{ignored}\n@preamble{\"b\"}\n");
+            }
+        }
+
+        let bounds = piece_bounds(&src, 8);
+        assert!(bounds.len() > 8);
+        assert!(bounds[1..bounds.len() - 1].iter().all(|&i| src[i..].starts_with('@')));
+
+        let raw = RawBibliography::par_parse(&src).unwrap();
+        assert_eq!(raw.entries.len(), 200);
+        assert_eq!(raw.preamble, RawBibliography::parse(&src).unwrap().preamble);
+        assert_eq!(Bibliography::par_parse(&src), Bibliography::parse(&src));
+
+        src.push_str("@article{key3, title = {Duplicate}}\n@book{broken, title = {}");
+        assert_eq!(Bibliography::par_parse(&src), Bibliography::parse(&src));
+        src.truncate(src.rfind('@').unwrap());
+        assert_eq!(Bibliography::par_parse(&src), Bibliography::parse(&src));
+        assert!(Bibliography::par_parse(&src).is_err());
+    }
+}
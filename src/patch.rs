@@ -0,0 +1,352 @@
+//! A line-based patch format for bibliographies.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::mechanics::{canonical_field_name, is_verbatim_field, FIELD_ALIASES};
+use crate::raw::RawBibliography;
+use crate::resolve::parse_field;
+use crate::{Bibliography, Chunks, ChunksExt, Entry, EntryType};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A change to a bibliography, part of a [`Patch`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PatchOperation {
+    /// Add an entry.
+    AddEntry(Entry),
+    /// Remove the entry with this cite key.
+    RemoveEntry(String),
+    /// Change the type of an entry.
+    SetType {
+        /// The cite key of the entry.
+        key: String,
+        /// The new entry type.
+        entry_type: EntryType,
+    },
+    /// Add or change a field of an entry.
+    SetField {
+        /// The cite key of the entry.
+        key: String,
+        /// The BibLaTeX name of the field.
+        field: String,
+        /// The new value.
+        value: Chunks,
+    },
+    /// Remove a field of an entry.
+    RemoveField {
+        /// The cite key of the entry.
+        key: String,
+        /// The BibLaTeX name of the field.
+        field: String,
+    },
+}
+
+/// A set of changes that turns one version of a bibliography into another.
+///
+/// A patch can be created with [`Bibliography::patch`], serialized with its
+/// [`Display`] implementation, read with [`Patch::parse`] and applied with
+/// [`Bibliography::apply_patch`]. Each line of the serialized form holds one
+/// operation:
+///
+/// - `+ @book{key, title = {Title},}` adds an entry,
+/// - `- key` removes an entry,
+/// - `@ key misc` changes the type of an entry,
+/// - `= key field {value}` sets a field, and
+/// - `! key field` removes a field.
+///
+/// ```
+/// # use biblatex::{Bibliography, Patch};
+/// let old = Bibliography::parse("@book{a, title = {Old}}").unwrap();
+/// let new = Bibliography::parse("@book{a, title = {New}, date = {2001}}").unwrap();
+///
+/// let patch = old.patch(&new);
+/// assert_eq!(patch.to_string(), "= a date {2001}\n= a title {New}\n");
+///
+/// let mut copy = old.clone();
+/// copy.apply_patch(&Patch::parse(&patch.to_string()).unwrap()).unwrap();
+/// assert!(copy.diff(&new).is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Patch {
+    /// The operations in the order they are applied.
+    pub operations: Vec<PatchOperation>,
+}
+
+/// Errors that can occur when reading or applying a [`Patch`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PatchError {
+    /// The line with this (zero-based) index is malformed.
+    Malformed(usize),
+    /// There is no entry with this cite key.
+    UnknownEntry(String),
+    /// An entry with this cite key already exists.
+    DuplicateEntry(String),
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Malformed(line) => write!(f, "malformed patch line {}", line + 1),
+            Self::UnknownEntry(key) => write!(f, "unknown entry {:?}", key),
+            Self::DuplicateEntry(key) => write!(f, "duplicate entry {:?}", key),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl Patch {
+    /// Read a patch from its serialized form.
+    pub fn parse(src: &str) -> Result<Self, PatchError> {
+        let mut operations = vec![];
+
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (op, rest) = line.split_at(line.chars().next().unwrap().len_utf8());
+            let rest = rest.trim_start();
+            let mut words = rest.splitn(3, char::is_whitespace);
+            let malformed = || PatchError::Malformed(i);
+
+            operations.push(match op {
+                "+" => {
+                    let bib = Bibliography::parse(rest).map_err(|_| malformed())?;
+                    let entry = bib.into_iter().next().ok_or_else(malformed)?;
+                    PatchOperation::AddEntry(entry)
+                }
+                "-" => PatchOperation::RemoveEntry(rest.to_string()),
+                "@" => PatchOperation::SetType {
+                    key: words.next().ok_or_else(malformed)?.to_string(),
+                    entry_type: EntryType::new(words.next().ok_or_else(malformed)?),
+                },
+                "=" => {
+                    let key = words.next().ok_or_else(malformed)?.to_string();
+                    let field = words.next().ok_or_else(malformed)?.to_string();
+                    let value = words.next().ok_or_else(malformed)?;
+                    let value = parse_value(&field, value).ok_or_else(malformed)?;
+                    PatchOperation::SetField { key, field, value }
+                }
+                "!" => PatchOperation::RemoveField {
+                    key: words.next().ok_or_else(malformed)?.to_string(),
+                    field: words.next().ok_or_else(malformed)?.to_string(),
+                },
+                _ => return Err(malformed()),
+            });
+        }
+
+        Ok(Self { operations })
+    }
+}
+
+impl Display for Patch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for operation in &self.operations {
+            match operation {
+                PatchOperation::AddEntry(entry) => {
+                    // Remove line breaks from verbatim fields first, so that
+                    // the remaining blank lines can be written as `\par`.
+                    let mut entry = entry.clone();
+                    for (key, chunks) in entry.fields.iter_mut() {
+                        if is_verbatim_field(key) {
+                            for chunk in chunks {
+                                let value = chunk.v.get_mut();
+                                *value = single_line(value, true);
+                            }
+                        }
+                    }
+
+                    let biblatex = entry.to_biblatex_string().replace(",\n", ", ");
+                    writeln!(f, "+ {}", single_line(&biblatex, false))?;
+                }
+                PatchOperation::RemoveEntry(key) => writeln!(f, "- {}", key)?,
+                PatchOperation::SetType { key, entry_type } => {
+                    writeln!(f, "@ {} {}", key, entry_type)?
+                }
+                PatchOperation::SetField { key, field, value } => {
+                    let verbatim = is_verbatim_field(field);
+                    let value = value.to_biblatex_string(verbatim);
+                    writeln!(f, "= {} {} {}", key, field, single_line(&value, verbatim))?;
+                }
+                PatchOperation::RemoveField { key, field } => {
+                    writeln!(f, "! {} {}", key, field)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Bibliography {
+    /// Compute the patch that turns this bibliography into `new`, based on
+    /// [`Bibliography::diff`].
+    pub fn patch(&self, new: &Bibliography) -> Patch {
+        let diff = self.diff(new);
+        let mut operations = vec![];
+
+        for key in diff.removed {
+            operations.push(PatchOperation::RemoveEntry(key));
+        }
+
+        for entry_diff in diff.changed {
+            let key = entry_diff.key;
+            if let Some((_, entry_type)) = entry_diff.entry_type {
+                operations.push(PatchOperation::SetType { key: key.clone(), entry_type });
+            }
+
+            for field_diff in entry_diff.fields {
+                let key = key.clone();
+                let field = field_diff.field;
+                operations.push(match field_diff.new {
+                    Some(value) => {
+                        PatchOperation::SetField { key, field, value: value.to_chunks() }
+                    }
+                    None => PatchOperation::RemoveField { key, field },
+                });
+            }
+        }
+
+        for key in diff.added {
+            operations.push(PatchOperation::AddEntry(new.get(&key).unwrap().clone()));
+        }
+
+        Patch { operations }
+    }
+
+    /// Apply a patch to this bibliography.
+    ///
+    /// The operations are applied in order. If an operation fails, the
+    /// preceding ones remain applied.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), PatchError> {
+        for operation in &patch.operations {
+            match operation {
+                PatchOperation::AddEntry(entry) => {
+                    self.try_insert(entry.clone())
+                        .map_err(|entry| PatchError::DuplicateEntry(entry.key))?;
+                }
+                PatchOperation::RemoveEntry(key) => {
+                    self.remove(key)
+                        .ok_or_else(|| PatchError::UnknownEntry(key.clone()))?;
+                }
+                PatchOperation::SetType { key, entry_type } => {
+                    self.entry_mut(key)?.entry_type = entry_type.clone();
+                }
+                PatchOperation::SetField { key, field, value } => {
+                    let entry = self.entry_mut(key)?;
+                    remove_with_aliases(entry, field);
+                    entry.set(field, value.clone());
+                }
+                PatchOperation::RemoveField { key, field } => {
+                    remove_with_aliases(self.entry_mut(key)?, field);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The entry with the given cite key or an error.
+    fn entry_mut(&mut self, key: &str) -> Result<&mut Entry, PatchError> {
+        self.get_mut(key)
+            .ok_or_else(|| PatchError::UnknownEntry(key.to_string()))
+    }
+}
+
+/// Remove a field together with its legacy aliases.
+fn remove_with_aliases(entry: &mut Entry, field: &str) {
+    let field = canonical_field_name(field);
+    entry.remove(field);
+    for (alias, canonical) in FIELD_ALIASES {
+        if canonical == field {
+            entry.remove(alias);
+        }
+    }
+}
+
+/// Replace line breaks so that a BibLaTeX string fits on a single line.
+///
+/// Blank lines become `\par`, except in verbatim fields like `url`, where
+/// commands are not interpreted.
+fn single_line(s: &str, verbatim: bool) -> String {
+    match verbatim {
+        true => s.replace('\n', " "),
+        false => s.replace("\n\n", "\\par ").replace('\n', " "),
+    }
+}
+
+/// Parse a field value in BibLaTeX syntax.
+fn parse_value(field: &str, value: &str) -> Option<Chunks> {
+    let src = format!("@patch{{patch, {} = {}}}", field, value);
+    let raw = RawBibliography::parse(&src).ok()?;
+    let pair = raw.entries.first()?.v.fields.first()?;
+    let mut chunks = parse_field(field, &pair.value.v, &raw.abbreviations).ok()?;
+    for chunk in &mut chunks {
+        chunk.span = usize::MAX..usize::MAX;
+    }
+    Some(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chunk, Spanned};
+
+    #[test]
+    fn test_patch() {
+        let old = r#"@article{a, author = {Doe, Jane}, journal = {Journal},
+              title = {A Title}, pages = {1--5}, note = {Old}}
+            @book{b, title = {B}}
+            @book{c, title = {C}}"#;
+        let new = r#"@article{a, author = {Doe, Jane and Roe, Richard},
+              journaltitle = {Journal of Things}, title = {A Title}, pages = {1--5}}
+            @misc{b, title = {B}}
+            @book{d, title = {D: 50\% {Off}}, abstract = {One.
+
+              Two.}}"#;
+        let old = Bibliography::parse(old).unwrap();
+        let new = Bibliography::parse(new).unwrap();
+
+        let patch = old.patch(&new);
+        let serialized = patch.to_string();
+        assert_eq!(serialized.lines().count(), 6);
+        assert!(serialized.starts_with("- c\n"));
+        assert!(serialized.contains("! a note\n"));
+        assert!(serialized.contains("@ b misc\n"));
+
+        let parsed = Patch::parse(&serialized).unwrap();
+        let mut copy = old.clone();
+        copy.apply_patch(&parsed).unwrap();
+        assert!(copy.diff(&new).is_empty());
+        assert_eq!(copy.get("a").unwrap().get("journal"), None);
+        assert_eq!(
+            copy.get("d").unwrap().abstract_().unwrap().format_paragraphs().len(),
+            2
+        );
+
+        assert_eq!(copy.apply_patch(&parsed), Err(PatchError::UnknownEntry("c".into())));
+        assert_eq!(Patch::parse("? a"), Err(PatchError::Malformed(0)));
+        assert_eq!(Patch::parse("ä a"), Err(PatchError::Malformed(0)));
+        assert_eq!(Patch::parse("\n= a title"), Err(PatchError::Malformed(1)));
+        // Verbatim fields set by the user may contain blank lines.
+        let old = Bibliography::parse("@misc{a, title = {A}}").unwrap();
+        let mut new = old.clone();
+        let value = |s: &str| vec![Spanned::detached(Chunk::Verbatim(s.into()))];
+        new.get_mut("a").unwrap().set("url", value("http://a.org/\n\nb"));
+        let mut b = Entry::new("b".into(), EntryType::Misc);
+        b.set("verba", value("x\n\ny"));
+        b.set("note", value("x\n\ny"));
+        new.insert(b);
+
+        let serialized = old.patch(&new).to_string();
+        assert_eq!(serialized.lines().count(), 2);
+        assert_eq!(serialized.matches("\\par").count(), 1);
+        assert!(serialized.contains("http://a.org/  b"));
+        assert!(serialized.contains("{x  y}"));
+    }
+}
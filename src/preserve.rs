@@ -0,0 +1,126 @@
+//! Writing a bibliography while keeping the source text of unchanged entries.
+
+use std::collections::HashSet;
+
+use crate::raw::entry_span;
+use crate::{Bibliography, FormatOptions, ParseError, RawBibliography};
+
+impl Bibliography {
+    /// Serialize this bibliography into a BibLaTeX string based on the source
+    /// it was parsed from, so that diffs only show actual changes.
+    ///
+    /// Entries that are unchanged since parsing `src` are copied byte for
+    /// byte, including the text around them like comments, the preamble, and
+    /// `@string` definitions. Modified entries are reformatted with the
+    /// `options` in place, removed entries are dropped together with the
+    /// whitespace after them, and added entries are appended at the end. The
+    /// source order is kept, so the sorting options do not apply.
+    ///
+    /// Fails if the source can not be parsed.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, Chunk, FormatOptions, Spanned};
+    /// let src = "% My sources\n@book{a,  title={A}}\n\n@book{b, title = {B}}\n";
+    /// let mut bibliography = Bibliography::parse(src).unwrap();
+    /// let title = vec![Spanned::zero(Chunk::Normal("C".into()))];
+    /// bibliography.get_mut("b").unwrap().set_title(title);
+    ///
+    /// let written = bibliography
+    ///     .to_biblatex_string_preserving(src, &FormatOptions::default())
+    ///     .unwrap();
+    /// assert_eq!(written, "% My sources\n@book{a,  title={A}}\n\n@book{b,\ntitle = {C},\n}\n");
+    /// ```
+    pub fn to_biblatex_string_preserving(
+        &self,
+        src: &str,
+        options: &FormatOptions,
+    ) -> Result<String, ParseError> {
+        let raw = RawBibliography::parse(src)?;
+        let spans: Vec<_> = raw
+            .entries
+            .iter()
+            .map(|entry| (&src[entry.v.key.span.clone()], entry_span(src, &entry.span)))
+            .collect();
+        let original = Bibliography::from_raw(raw)?;
+
+        let mut res = String::new();
+        let mut cursor = 0;
+        let mut written = HashSet::new();
+        for (key, span) in spans {
+            let current = self
+                .get(key)
+                .filter(|entry| entry.key == key)
+                .or_else(|| self.xdata(key));
+            let before = original.get(key).or_else(|| original.xdata(key));
+
+            match current {
+                Some(entry) if Some(entry) == before => {
+                    res.push_str(&src[cursor..span.end]);
+                }
+                Some(entry) => {
+                    res.push_str(&src[cursor..span.start]);
+                    res.push_str(&entry.to_biblatex_string_with(options));
+                }
+                None => {
+                    res.push_str(&src[cursor..span.start]);
+                    let rest = &src[span.end..];
+                    cursor = src.len() - rest.trim_start().len();
+                    continue;
+                }
+            }
+
+            written.insert(key);
+            cursor = span.end;
+        }
+        res.push_str(&src[cursor..]);
+
+        let mut appended = false;
+        for entry in self.xdata.values().chain(&self.entries) {
+            if written.contains(entry.key.as_str()) {
+                continue;
+            }
+
+            res.truncate(res.trim_end().len());
+            if !res.is_empty() {
+                options.write_separator(&mut res).unwrap();
+            }
+            res.push_str(&entry.to_biblatex_string_with(options));
+            appended = true;
+        }
+
+        options.write_end(&mut res, !appended).unwrap();
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{Chunk, Entry, EntryType, Spanned};
+
+    #[test]
+    fn test_preserving() {
+        let src = fs::read_to_string("tests/gral.bib").unwrap();
+        let options = FormatOptions::canonical();
+        let mut bibliography = Bibliography::parse(&src).unwrap();
+        let written = bibliography.to_biblatex_string_preserving(&src, &options).unwrap();
+        assert_eq!(written, src);
+
+        let note = vec![Spanned::zero(Chunk::Normal("Edited".into()))];
+        bibliography.get_mut("kim2009").unwrap().set("note", note);
+        bibliography.remove("ishihara2012");
+        bibliography.insert(Entry::new("new".into(), EntryType::Misc));
+
+        let written = bibliography.to_biblatex_string_preserving(&src, &options).unwrap();
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+        assert!(written.contains("@inproceedings{kim2009,\n  author = "));
+        assert!(written.ends_with("}\n\n@misc{new,\n}\n"));
+        assert!(!written.contains("ishihara2012"));
+
+        let start = src.find("@article{ishihara2012").unwrap();
+        assert_eq!(written[..start], src[..start]);
+    }
+}
@@ -0,0 +1,511 @@
+//! Filtering of entries by predicates over their fields.
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Bound, RangeBounds};
+
+use unscanny::Scanner;
+
+use crate::mechanics::canonical_field_name;
+use crate::scanner::ScannerExt;
+use crate::{
+    Bibliography, ChunksExt, DateValue, Entry, EntryType, PermissiveType, Person,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A predicate over entries.
+type Predicate<'a> = Box<dyn Fn(&Entry) -> bool + 'a>;
+
+/// A filter over the entries of a bibliography, created through
+/// [`Bibliography::query`]. The conditions added with the builder methods
+/// must all hold for an entry to match.
+///
+/// Text comparisons ignore case.
+///
+/// ```
+/// # use biblatex::{Bibliography, EntryType};
+/// let src = "@article{a, author = {Knuth, Donald}, date = {2016}}
+///            @book{b, author = {Knuth, Donald}, date = {2016}}
+///            @article{c, author = {Lamport, Leslie}, date = {2018}}";
+/// let bibliography = Bibliography::parse(src).unwrap();
+///
+/// let keys: Vec<_> = bibliography
+///     .query()
+///     .entry_type(EntryType::Article)
+///     .year_range(2015..=2020)
+///     .author_contains("knuth")
+///     .iter()
+///     .map(|entry| entry.key.as_str())
+///     .collect();
+/// assert_eq!(keys, ["a"]);
+/// ```
+pub struct Query<'a> {
+    bibliography: &'a Bibliography,
+    predicates: Vec<Predicate<'a>>,
+}
+
+/// Errors that can occur when reading a query string with
+/// [`Query::search`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum QueryError {
+    /// A search term was expected at this byte offset.
+    Expected(usize),
+    /// The quote or parenthesis at this byte offset is not closed.
+    Unclosed(usize),
+    /// The closing parenthesis at this byte offset has no opening one.
+    Unopened(usize),
+    /// A year or year range is malformed.
+    InvalidYear(String),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Expected(pos) => write!(f, "expected search term at {}", pos),
+            Self::Unclosed(pos) => write!(f, "unclosed delimiter at {}", pos),
+            Self::Unopened(pos) => write!(f, "unopened parenthesis at {}", pos),
+            Self::InvalidYear(year) => write!(f, "invalid year {:?}", year),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl<'a> Query<'a> {
+    /// Require a specific entry type.
+    pub fn entry_type(self, entry_type: EntryType) -> Self {
+        self.with(entry_type_is(entry_type))
+    }
+
+    /// Require the (start) year of the `date` or `year` field to be in a
+    /// range.
+    pub fn year_range(self, range: impl RangeBounds<i64>) -> Self {
+        self.with(year_in((range.start_bound().cloned(), range.end_bound().cloned())))
+    }
+
+    /// Require an author whose name contains the text.
+    pub fn author_contains(self, text: &str) -> Self {
+        self.with(author_contains(text))
+    }
+
+    /// Require an editor whose name contains the text.
+    pub fn editor_contains(self, text: &str) -> Self {
+        self.with(editor_contains(text))
+    }
+
+    /// Require the title to contain the text.
+    pub fn title_contains(self, text: &str) -> Self {
+        self.field_contains("title", text)
+    }
+
+    /// Require a field to contain the text. Legacy BibTeX field names like
+    /// `journal` are treated like their BibLaTeX counterparts.
+    pub fn field_contains(self, field: &str, text: &str) -> Self {
+        self.with(field_contains(field, text))
+    }
+
+    /// Require a field to be present.
+    pub fn has_field(self, field: &str) -> Self {
+        self.with(has_field(field))
+    }
+
+    /// Require a keyword in the comma-separated `keywords` field.
+    pub fn keyword(self, keyword: &str) -> Self {
+        self.with(has_keyword(keyword))
+    }
+
+    /// Require the conditions of a query string to hold.
+    ///
+    /// A query consists of search terms, which are combined with `AND`
+    /// (also implied between adjacent terms), `OR`, and `NOT` (or a leading
+    /// `-`) and can be grouped with parentheses. `AND` binds stronger than
+    /// `OR`. A term is either a word that some field must contain or a field
+    /// name and a value separated by a colon. Values with spaces can be
+    /// quoted. The following fields have special meaning:
+    ///
+    /// - `author:` and `editor:` match names like
+    ///   [`author_contains`](Self::author_contains),
+    /// - `year:` takes a year like `1984` or a range like `1990..2000`,
+    ///   `1990..`, or `..2000`, with inclusive bounds,
+    /// - `keyword:` matches a single keyword,
+    /// - `type:` matches the entry type,
+    /// - `key:` matches the cite key exactly, and
+    /// - `has:` requires the field with the given name.
+    ///
+    /// Any other field name matches like
+    /// [`field_contains`](Self::field_contains).
+    ///
+    /// ```
+    /// # use biblatex::Bibliography;
+    /// let src = "@book{a, author = {Knuth, Donald}, date = {1997},
+    ///              keywords = {typesetting}, title = {The TeXbook}}
+    ///            @book{b, author = {Knuth, Donald}, date = {1968}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let query = "author:knuth AND year:1990..2000 AND keyword:typesetting";
+    /// let matches: Vec<_> = bibliography.query().search(query).unwrap().iter().collect();
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].key, "a");
+    /// ```
+    pub fn search(self, query: &str) -> Result<Self, QueryError> {
+        let mut s = Scanner::new(query);
+        let predicate = parse_or(&mut s)?;
+        s.eat_whitespace();
+        if s.peek() == Some(')') {
+            return Err(QueryError::Unopened(s.cursor()));
+        }
+
+        Ok(self.with(predicate))
+    }
+
+    /// Require an arbitrary predicate to hold.
+    pub fn filter(mut self, predicate: impl Fn(&Entry) -> bool + 'a) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Add a boxed predicate.
+    fn with(mut self, predicate: Predicate<'a>) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Whether an entry fulfills all conditions.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.predicates.iter().all(|predicate| predicate(entry))
+    }
+
+    /// The matching entries in bibliography order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a Entry> + '_ {
+        self.bibliography.iter().filter(|entry| self.matches(entry))
+    }
+}
+
+impl<'a> IntoIterator for Query<'a> {
+    type Item = &'a Entry;
+    type IntoIter = Box<dyn Iterator<Item = &'a Entry> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let bibliography = self.bibliography;
+        Box::new(bibliography.iter().filter(move |entry| self.matches(entry)))
+    }
+}
+
+impl Bibliography {
+    /// Start a query over the entries. Without further conditions, all
+    /// entries match.
+    pub fn query(&self) -> Query<'_> {
+        Query { bibliography: self, predicates: vec![] }
+    }
+}
+
+fn entry_type_is(entry_type: EntryType) -> Predicate<'static> {
+    Box::new(move |entry| entry.entry_type == entry_type)
+}
+
+fn year_in(bounds: (Bound<i64>, Bound<i64>)) -> Predicate<'static> {
+    Box::new(move |entry| year(entry).is_some_and(|year| bounds.contains(&year)))
+}
+
+fn author_contains(text: &str) -> Predicate<'static> {
+    let text = text.to_lowercase();
+    Box::new(move |entry| {
+        entry
+            .author()
+            .unwrap_or_default()
+            .iter()
+            .any(|p| full_name(p).contains(&text))
+    })
+}
+
+fn editor_contains(text: &str) -> Predicate<'static> {
+    let text = text.to_lowercase();
+    Box::new(move |entry| {
+        entry
+            .editors()
+            .unwrap_or_default()
+            .iter()
+            .any(|(persons, _)| persons.iter().any(|p| full_name(p).contains(&text)))
+    })
+}
+
+fn field_contains(field: &str, text: &str) -> Predicate<'static> {
+    let (field, text) = (field.to_lowercase(), text.to_lowercase());
+    Box::new(move |entry| {
+        field_value(entry, &field)
+            .is_some_and(|value| value.to_lowercase().contains(&text))
+    })
+}
+
+fn has_field(field: &str) -> Predicate<'static> {
+    let field = field.to_lowercase();
+    Box::new(move |entry| field_value(entry, &field).is_some())
+}
+
+fn has_keyword(keyword: &str) -> Predicate<'static> {
+    let keyword = keyword.trim().to_lowercase();
+    Box::new(move |entry| {
+        entry.keywords().is_ok_and(|keywords| {
+            keywords
+                .format_verbatim()
+                .split(',')
+                .any(|k| k.trim().to_lowercase() == keyword)
+        })
+    })
+}
+
+fn key_is(key: &str) -> Predicate<'static> {
+    let key = key.to_string();
+    Box::new(move |entry| entry.key == key)
+}
+
+fn any_field_contains(text: &str) -> Predicate<'static> {
+    let text = text.to_lowercase();
+    Box::new(move |entry| {
+        entry
+            .fields
+            .values()
+            .any(|chunks| chunks.format_verbatim().to_lowercase().contains(&text))
+    })
+}
+
+/// Parse alternatives separated by `OR`.
+fn parse_or(s: &mut Scanner) -> Result<Predicate<'static>, QueryError> {
+    let mut alternatives = vec![parse_and(s)?];
+    while eat_operator(s, "OR") {
+        alternatives.push(parse_and(s)?);
+    }
+
+    Ok(match alternatives.len() {
+        1 => alternatives.pop().unwrap(),
+        _ => Box::new(move |entry| alternatives.iter().any(|p| p(entry))),
+    })
+}
+
+/// Parse terms that are separated by `AND` or juxtaposed.
+fn parse_and(s: &mut Scanner) -> Result<Predicate<'static>, QueryError> {
+    let mut terms = vec![parse_unary(s)?];
+    loop {
+        let checkpoint = s.checkpoint();
+        s.eat_whitespace();
+        let end = s.done() || s.peek() == Some(')');
+        s.restore(checkpoint);
+        if end || is_operator(s, "OR") {
+            break;
+        }
+
+        eat_operator(s, "AND");
+        terms.push(parse_unary(s)?);
+    }
+
+    Ok(match terms.len() {
+        1 => terms.pop().unwrap(),
+        _ => Box::new(move |entry| terms.iter().all(|p| p(entry))),
+    })
+}
+
+/// Parse a negated, parenthesized, or plain term.
+fn parse_unary(s: &mut Scanner) -> Result<Predicate<'static>, QueryError> {
+    s.eat_whitespace();
+    if eat_operator(s, "NOT") || s.eat_if('-') {
+        let inner = parse_unary(s)?;
+        return Ok(Box::new(move |entry| !inner(entry)));
+    }
+
+    let start = s.cursor();
+    if s.eat_if('(') {
+        let inner = parse_or(s)?;
+        s.eat_whitespace();
+        if !s.eat_if(')') {
+            return Err(QueryError::Unclosed(start));
+        }
+        return Ok(inner);
+    }
+
+    let word =
+        s.eat_while(|c: char| !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | ':'));
+    if !word.is_empty() && s.eat_if(':') {
+        let value = parse_value(s)?;
+        return term(&word.to_lowercase(), &value);
+    }
+
+    if word.is_empty() {
+        let value = parse_value(s)?;
+        return Ok(any_field_contains(&value));
+    }
+
+    Ok(any_field_contains(word))
+}
+
+/// Parse a quoted or bare value.
+fn parse_value(s: &mut Scanner) -> Result<String, QueryError> {
+    let start = s.cursor();
+    if s.eat_if('"') {
+        let value = s.eat_until('"');
+        if !s.eat_if('"') {
+            return Err(QueryError::Unclosed(start));
+        }
+        return Ok(value.to_string());
+    }
+
+    let value = s.eat_while(|c: char| !c.is_whitespace() && !matches!(c, '(' | ')'));
+    if value.is_empty() {
+        return Err(QueryError::Expected(start));
+    }
+    Ok(value.to_string())
+}
+
+/// The predicate for a term with a field name.
+fn term(field: &str, value: &str) -> Result<Predicate<'static>, QueryError> {
+    Ok(match field {
+        "author" => author_contains(value),
+        "editor" => editor_contains(value),
+        "keyword" | "keywords" => has_keyword(value),
+        "type" => entry_type_is(EntryType::new(value)),
+        "key" => key_is(value),
+        "has" => has_field(value),
+        "year" => {
+            let invalid = || QueryError::InvalidYear(value.to_string());
+            let bound = |year: &str| -> Result<Bound<i64>, QueryError> {
+                match year.trim() {
+                    "" => Ok(Bound::Unbounded),
+                    year => year.parse().map(Bound::Included).map_err(|_| invalid()),
+                }
+            };
+
+            let bounds = match value.split_once("..") {
+                Some((start, end)) => (bound(start)?, bound(end)?),
+                None => (bound(value)?, bound(value)?),
+            };
+            if bounds == (Bound::Unbounded, Bound::Unbounded) {
+                return Err(invalid());
+            }
+            year_in(bounds)
+        }
+        field => field_contains(field, value),
+    })
+}
+
+/// Whether an operator keyword follows, possibly after whitespace.
+fn is_operator(s: &mut Scanner, operator: &str) -> bool {
+    let checkpoint = s.checkpoint();
+    let found = eat_operator(s, operator);
+    s.restore(checkpoint);
+    found
+}
+
+/// Eat an operator keyword, possibly preceded by whitespace, if it is not
+/// just the start of a longer word.
+fn eat_operator(s: &mut Scanner, operator: &str) -> bool {
+    let checkpoint = s.checkpoint();
+    s.eat_whitespace();
+    let next = s.peek_nth(operator.chars().count());
+    let boundary = !next.is_some_and(|c| !c.is_whitespace() && c != '(');
+    if boundary && s.eat_if(operator) {
+        return true;
+    }
+    s.restore(checkpoint);
+    false
+}
+
+/// The (start) year of the date of an entry.
+fn year(entry: &Entry) -> Option<i64> {
+    match entry.date().ok()? {
+        PermissiveType::Typed(date) => Some(match date.value {
+            DateValue::At(start)
+            | DateValue::After(start)
+            | DateValue::Between(start, _) => start.year as i64,
+            DateValue::Before(end) => end.year as i64,
+        }),
+        PermissiveType::Chunks(_) => None,
+    }
+}
+
+/// The lowercase name of a person with all name parts.
+fn full_name(person: &Person) -> String {
+    [&person.given_name, &person.prefix, &person.name, &person.suffix]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The verbatim value of a field, also looking up its legacy BibTeX name.
+fn field_value(entry: &Entry, field: &str) -> Option<String> {
+    let canonical = canonical_field_name(field);
+    entry
+        .fields
+        .iter()
+        .find(|(name, _)| canonical_field_name(name) == canonical)
+        .map(|(_, chunks)| chunks.format_verbatim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let src = r#"@article{a, author = {Knuth, Donald E.}, title = {Literate Programming},
+              journal = {The Computer Journal}, date = {1984}, keywords = {tex, programming}}
+            @book{b, editor = {van der Berg, Anna}, title = {Typesetting}, year = {2001/2003}}
+            @article{c, author = {Lamport, Leslie}, title = {Time, Clocks},
+              journaltitle = {CACM}, date = {1978}, keywords = {distributed}}
+            @misc{d, title = {Undated}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let keys =
+            |query: Query| query.into_iter().map(|e| e.key.clone()).collect::<Vec<_>>();
+
+        assert_eq!(keys(bibliography.query()), ["a", "b", "c", "d"]);
+        assert_eq!(keys(bibliography.query().year_range(..1990)), ["a", "c"]);
+        assert_eq!(keys(bibliography.query().year_range(2001..)), ["b"]);
+        assert_eq!(keys(bibliography.query().author_contains("donald e")), ["a"]);
+        assert_eq!(keys(bibliography.query().editor_contains("VAN DER")), ["b"]);
+        assert_eq!(keys(bibliography.query().title_contains("clock")), ["c"]);
+        assert_eq!(keys(bibliography.query().field_contains("journaltitle", "j")), ["a"]);
+        assert_eq!(keys(bibliography.query().has_field("journal")), ["a", "c"]);
+        assert_eq!(keys(bibliography.query().keyword("TeX")), ["a"]);
+        assert_eq!(keys(bibliography.query().filter(|e| e.fields.len() == 1)), ["d"]);
+
+        let query = bibliography.query().entry_type(EntryType::Article).keyword("tex");
+        assert_eq!(query.iter().count(), 1);
+        assert!(!query.matches(bibliography.get("c").unwrap()));
+    }
+
+    #[test]
+    fn test_search() {
+        let src = r#"@article{a, author = {Knuth, Donald}, title = {Literate Programming},
+              date = {1984}, keywords = {tex, programming}}
+            @book{b, author = {Knuth, Donald}, title = {The TeXbook}, date = {1986},
+              keywords = {tex}}
+            @article{c, author = {Lamport, Leslie}, title = {Time, Clocks}, date = {1978}}
+            @online{d, title = {ORCID Search}, url = {https://orcid.org}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let search = |query: &str| -> Result<Vec<String>, QueryError> {
+            let query = bibliography.query().search(query)?;
+            Ok(query.iter().map(|e| e.key.clone()).collect())
+        };
+
+        assert_eq!(search("knuth").unwrap(), ["a", "b"]);
+        assert_eq!(search("author:knuth year:..1985").unwrap(), ["a"]);
+        assert_eq!(search("year:1986").unwrap(), ["b"]);
+        assert_eq!(search("type:article OR type:online").unwrap(), ["a", "c", "d"]);
+        assert_eq!(search("keyword:tex AND NOT type:book").unwrap(), ["a"]);
+        assert_eq!(search("-keyword:tex -has:url").unwrap(), ["c"]);
+        assert_eq!(search(r#"title:"time, clocks""#).unwrap(), ["c"]);
+        assert_eq!(search("(key:a OR key:c) AND year:1980..").unwrap(), ["a"]);
+        assert_eq!(search("orcid OR lamport").unwrap(), ["c", "d"]);
+        assert_eq!(search("").unwrap_err(), QueryError::Expected(0));
+        assert_eq!(search("author:").unwrap_err(), QueryError::Expected(7));
+        assert_eq!(search("(tex").unwrap_err(), QueryError::Unclosed(0));
+        assert_eq!(search("tex)").unwrap_err(), QueryError::Unopened(3));
+        assert_eq!(search("title:\"tex").unwrap_err(), QueryError::Unclosed(6));
+        assert_eq!(
+            search("year:19x").unwrap_err(),
+            QueryError::InvalidYear("19x".into())
+        );
+    }
+}
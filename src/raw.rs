@@ -1,44 +1,125 @@
 //! Low-level representation of a bibliography file.
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
-use crate::{Span, Spanned, TypeErrorKind};
+use crate::mechanics::TYPE_ALIASES;
+use crate::{Bibliography, EntryType, FormatOptions, Span, Spanned, TypeErrorKind};
 
+use memchr::{memchr, memchr2, memchr3};
 use unscanny::Scanner;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// The content of a field or abbreviation.
 pub type Field<'s> = Vec<Spanned<RawChunk<'s>>>;
 
 /// A literal representation of a bibliography file, with abbreviations not yet
 /// resolved.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RawBibliography<'s> {
     /// TeX commands to be prepended to the document, only supported by BibTeX.
     pub preamble: String,
     /// The collection of citation keys and bibliography entries.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub entries: Vec<Spanned<RawEntry<'s>>>,
     /// A map of reusable abbreviations, only supported by BibTeX.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub abbreviations: Vec<Pair<'s>>,
 }
 
 /// A raw extracted entry, with abbreviations not yet resolved.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RawEntry<'s> {
     /// The citation key.
-    pub key: Spanned<&'s str>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub key: Spanned<Cow<'s, str>>,
     /// Denotes the type of bibliographic item (e.g., `article`).
-    pub kind: Spanned<&'s str>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub kind: Spanned<Cow<'s, str>>,
     /// Maps from field names to their values.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub fields: Vec<Pair<'s>>,
 }
 
 /// A literal representation of a bibliography entry field.
+///
+/// Parsed chunks borrow from the source. Edited chunks can own their text
+/// instead, so that changing a few values does not require copying the rest
+/// of the bibliography.
+///
+/// ```
+/// # use std::borrow::Cow;
+/// # use biblatex::{RawBibliography, RawChunk};
+/// let src = "@book{a, title = {Title}, note = {Note}}";
+/// let mut raw = RawBibliography::parse(src).unwrap();
+///
+/// let title = &mut raw.entries[0].v.fields[0].value.v[0].v;
+/// if let RawChunk::Normal(text) = title {
+///     *text = Cow::Owned(text.to_uppercase());
+/// }
+///
+/// let fields = &raw.entries[0].v.fields;
+/// assert_eq!(fields[0].value.v[0].v, RawChunk::Normal("TITLE".into()));
+/// assert!(matches!(&fields[1].value.v[0].v, RawChunk::Normal(Cow::Borrowed("Note"))));
+/// ```
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RawChunk<'s> {
     /// A normal field value.
-    Normal(&'s str),
+    Normal(#[cfg_attr(feature = "serde", serde(borrow))] Cow<'s, str>),
     /// A field with strings and abbreviations.
-    Abbreviation(&'s str),
+    Abbreviation(#[cfg_attr(feature = "serde", serde(borrow))] Cow<'s, str>),
+}
+
+/// An iterator over the regular entries of a source, parsed one at a time.
+///
+/// Created by [`RawBibliography::iter_entries`]. The abbreviations and the
+/// preamble are collected as they are encountered.
+#[derive(Debug, Clone)]
+pub struct RawEntries<'s> {
+    parser: BiblatexParser<'s>,
+    done: bool,
+}
+
+/// The abbreviations and `@xdata` entries removed by
+/// [`RawBibliography::collect_garbage`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Garbage<'s> {
+    /// The names of the removed abbreviations.
+    pub abbreviations: Vec<Cow<'s, str>>,
+    /// The keys of the removed `@xdata` entries.
+    pub xdata: Vec<Cow<'s, str>>,
+}
+
+impl RawEntry<'_> {
+    /// Detach the entry from the source by copying all borrowed text.
+    pub fn into_owned(self) -> RawEntry<'static> {
+        RawEntry {
+            key: self.key.map(owned),
+            kind: self.kind.map(owned),
+            fields: self.fields.into_iter().map(Pair::into_owned).collect(),
+        }
+    }
+}
+
+impl RawChunk<'_> {
+    /// Detach the chunk from the source by copying its text if borrowed.
+    pub fn into_owned(self) -> RawChunk<'static> {
+        match self {
+            Self::Normal(text) => RawChunk::Normal(owned(text)),
+            Self::Abbreviation(name) => RawChunk::Abbreviation(owned(name)),
+        }
+    }
+}
+
+/// Copy borrowed text.
+fn owned(text: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
 }
 
 impl<'s> RawBibliography<'s> {
@@ -46,9 +127,357 @@ impl<'s> RawBibliography<'s> {
     pub fn parse(src: &'s str) -> Result<Self, ParseError> {
         BiblatexParser::new(src).parse()
     }
+
+    /// Parse the regular entries of a source string one at a time.
+    ///
+    /// Unlike [`RawBibliography::parse`], this does not collect the entries,
+    /// so they can be processed or filtered as they are parsed. The iterator
+    /// stops after the first error.
+    ///
+    /// ```
+    /// # use biblatex::RawBibliography;
+    /// let src = "@string{acm = \"ACM\"} @book{a, publisher = acm} @misc{b,}";
+    /// let mut entries = RawBibliography::iter_entries(src);
+    /// assert_eq!(entries.next().unwrap().unwrap().v.key.v, "a");
+    /// assert_eq!(entries.abbreviations()[0].key.v, "acm");
+    /// assert_eq!(entries.next().unwrap().unwrap().v.key.v, "b");
+    /// assert!(entries.next().is_none());
+    /// ```
+    pub fn iter_entries(src: &'s str) -> RawEntries<'s> {
+        RawEntries { parser: BiblatexParser::new(src), done: false }
+    }
+
+    /// Parse the entries that start in a range of the source. Spans are
+    /// relative to the full source.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn parse_range(
+        src: &'s str,
+        range: std::ops::Range<usize>,
+    ) -> Result<Self, ParseError> {
+        let mut parser = BiblatexParser::new(&src[..range.end]);
+        parser.s.jump(range.start);
+        parser.parse()
+    }
+
+    /// Detach the raw bibliography from the source it was parsed from by
+    /// copying all borrowed text. The result can outlive the source, e.g. to
+    /// be stored in a struct or sent to another thread.
+    pub fn into_owned(self) -> RawBibliography<'static> {
+        RawBibliography {
+            preamble: self.preamble,
+            entries: self
+                .entries
+                .into_iter()
+                .map(|entry| entry.map(RawEntry::into_owned))
+                .collect(),
+            abbreviations: self.abbreviations.into_iter().map(Pair::into_owned).collect(),
+        }
+    }
+
+    /// Remove the abbreviations and `@xdata` entries that are not referenced,
+    /// directly or transitively, by any regular entry.
+    ///
+    /// This is useful after removing entries from the bibliography. Returns
+    /// what was removed.
+    pub fn collect_garbage(&mut self) -> Garbage<'s> {
+        let is_xdata = |entry: &RawEntry| entry.kind.v.eq_ignore_ascii_case("xdata");
+
+        let mut abbreviations: Vec<&str> = vec![];
+        let mut xdata: Vec<&str> = vec![];
+        let mut queue: Vec<(&str, &Field)> = self
+            .entries
+            .iter()
+            .filter(|entry| !is_xdata(&entry.v))
+            .flat_map(|entry| entry.v.fields.iter().map(|p| (&*p.key.v, &p.value.v)))
+            .collect();
+
+        while let Some((key, field)) = queue.pop() {
+            for chunk in field {
+                match &chunk.v {
+                    RawChunk::Abbreviation(name) => {
+                        let name = &**name;
+                        if abbreviations.contains(&name) {
+                            continue;
+                        }
+
+                        abbreviations.push(name);
+                        queue.extend(
+                            self.abbreviations
+                                .iter()
+                                .filter(|pair| pair.key.v == name)
+                                .map(|pair| (key, &pair.value.v)),
+                        );
+                    }
+                    RawChunk::Normal(text) => {
+                        let is_list = key.eq_ignore_ascii_case("xdata");
+                        for target in xdata_references(text, is_list) {
+                            if xdata.contains(&target) {
+                                continue;
+                            }
+
+                            xdata.push(target);
+                            queue.extend(
+                                self.entries
+                                    .iter()
+                                    .filter(|e| is_xdata(&e.v) && e.v.key.v == target)
+                                    .flat_map(|e| {
+                                        e.v.fields.iter().map(|p| (&*p.key.v, &p.value.v))
+                                    }),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let abbreviations: Vec<String> =
+            abbreviations.into_iter().map(Into::into).collect();
+        let xdata: Vec<String> = xdata.into_iter().map(Into::into).collect();
+
+        let mut garbage = Garbage::default();
+        self.abbreviations.retain(|pair| {
+            let used = abbreviations.iter().any(|name| *name == pair.key.v);
+            if !used {
+                garbage.abbreviations.push(pair.key.v.clone());
+            }
+            used
+        });
+        self.entries.retain(|entry| {
+            let used =
+                !is_xdata(&entry.v) || xdata.iter().any(|key| *key == entry.v.key.v);
+            if !used {
+                garbage.xdata.push(entry.v.key.v.clone());
+            }
+            used
+        });
+
+        garbage
+    }
+
+    /// The entries whose type is a legacy alias like `@www`, `@webpage`,
+    /// `@electronic`, or `@conference`, paired with the type it is mapped to
+    /// when the bibliography is resolved. This allows pointing users to the
+    /// entries that should be updated.
+    ///
+    /// ```
+    /// # use biblatex::{EntryType, RawBibliography};
+    /// let src = "@webpage{a, url = {https://example.com}} @online{b,}";
+    /// let raw = RawBibliography::parse(src).unwrap();
+    ///
+    /// let aliases = raw.type_aliases();
+    /// assert_eq!(aliases.len(), 1);
+    /// assert_eq!(aliases[0].0.kind.v, "webpage");
+    /// assert_eq!(&src[aliases[0].0.kind.span.clone()], "webpage");
+    /// assert_eq!(aliases[0].1, EntryType::Online);
+    /// ```
+    pub fn type_aliases(&self) -> Vec<(&RawEntry<'s>, EntryType)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let name = entry.v.kind.v.to_lowercase();
+                let (_, entry_type) =
+                    TYPE_ALIASES.iter().find(|(alias, _)| *alias == name)?;
+                Some((&entry.v, entry_type.clone()))
+            })
+            .collect()
+    }
+
+    /// Extract the entries with the given cite keys together with everything
+    /// they depend on: the entries they refer to through `crossref`, `xref`,
+    /// `xdata`, `related`, and `entryset` (transitively), and the
+    /// abbreviations used by any of them.
+    ///
+    /// Entries keep their order. Unknown keys are ignored.
+    pub fn subset<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Self {
+        let index: HashMap<&str, &RawEntry> =
+            self.entries.iter().map(|entry| (&*entry.v.key.v, &entry.v)).collect();
+        let mut included: HashSet<&str> = HashSet::new();
+        let mut queue: Vec<&str> = keys.into_iter().collect();
+
+        while let Some(key) = queue.pop() {
+            let Some(&entry) = index.get(key) else { continue };
+            if !included.insert(&entry.key.v) {
+                continue;
+            }
+
+            for pair in &entry.fields {
+                let name = pair.key.v.to_ascii_lowercase();
+                let is_reference = ["crossref", "xref", "xdata", "related", "entryset"]
+                    .contains(&name.as_str());
+                for chunk in &pair.value.v {
+                    let RawChunk::Normal(text) = &chunk.v else { continue };
+                    if is_reference {
+                        queue.extend(text.split([',', ' ', '\t', '\n']));
+                    }
+                    queue.extend(xdata_references(text, false));
+                }
+            }
+        }
+
+        let mut subset = self.clone();
+        subset.entries.retain(|entry| included.contains(&*entry.v.key.v));
+        subset.collect_garbage();
+        subset
+    }
+
+    /// Write the raw bibliography into a writer in the Bib(La)TeX format.
+    ///
+    /// The abbreviations are written as `@string` entries ahead of the
+    /// preamble and the entries, unless disabled through
+    /// [`FormatOptions::set_write_strings`]. Field values keep their references to
+    /// abbreviations and are otherwise written verbatim, so that parsing the
+    /// output yields the same raw bibliography up to spans and field order.
+    pub fn write_biblatex(&self, sink: impl fmt::Write) -> fmt::Result {
+        self.write_biblatex_with(sink, &FormatOptions::default())
+    }
+
+    /// Write the raw bibliography into a writer in the Bib(La)TeX format with
+    /// a custom layout.
+    ///
+    /// Sorting the entries with [`EntryOrder::Sorting`](crate::EntryOrder)
+    /// requires resolving the bibliography. If it cannot be resolved, e.g.
+    /// because a field refers to an undefined abbreviation, the entries are
+    /// written in source order instead. Call
+    /// [`Bibliography::from_raw`] beforehand to detect this case.
+    pub fn write_biblatex_with(
+        &self,
+        mut sink: impl fmt::Write,
+        options: &FormatOptions,
+    ) -> fmt::Result {
+        let mut first = !options.write_abbreviations(&mut sink, &self.abbreviations)?;
+
+        if !self.preamble.is_empty() {
+            if !first {
+                options.write_separator(&mut sink)?;
+            }
+            write!(sink, "@preamble{{{}}}", self.preamble)?;
+            first = false;
+        }
+
+        let mut entries: Vec<&Spanned<RawEntry>> = self.entries.iter().collect();
+        options.order_entries(
+            &mut entries,
+            |entry| &entry.v.key.v,
+            |template| match Bibliography::from_raw(self.clone()) {
+                Ok(bibliography) => bibliography.sorted_keys(template),
+                Err(_) => vec![],
+            },
+        );
+        for entry in entries {
+            if !first {
+                options.write_separator(&mut sink)?;
+            }
+            let mut fields: Vec<(&str, String, usize)> = entry
+                .v
+                .fields
+                .iter()
+                .map(|pair| {
+                    let value = options.raw_value(&pair.key.v, &pair.value.v);
+                    (&*pair.key.v, value, pair.key.span.start)
+                })
+                .collect();
+            options.write_entry(
+                &mut sink,
+                &entry.v.kind.v,
+                &entry.v.key.v,
+                &mut fields,
+            )?;
+            first = false;
+        }
+        options.write_end(&mut sink, first)
+    }
+
+    /// Serialize the raw bibliography into a Bib(La)TeX string.
+    pub fn to_biblatex_string(&self) -> String {
+        self.to_biblatex_string_with(&FormatOptions::default())
+    }
+
+    /// Serialize the raw bibliography into a Bib(La)TeX string with a custom
+    /// layout.
+    pub fn to_biblatex_string_with(&self, options: &FormatOptions) -> String {
+        let mut biblatex = String::new();
+        self.write_biblatex_with(&mut biblatex, options).unwrap();
+        biblatex
+    }
+}
+
+/// The keys of `@xdata` entries a field value could refer to, either as an
+/// item of an `xdata` field (if `is_list` is set) or through a granular
+/// `xdata=key-field` reference.
+///
+/// Since keys may contain dashes, every prefix of a granular reference ending
+/// before a dash is a candidate. This may keep unused containers but never
+/// removes used ones.
+fn xdata_references(text: &str, is_list: bool) -> impl Iterator<Item = &str> {
+    text.split([',', ' ', '\t', '\n'])
+        .filter(|s| !s.is_empty())
+        .flat_map(move |item| {
+            let plain = Some(item).filter(|_| is_list);
+            let granular =
+                item.strip_prefix("xdata=").into_iter().flat_map(|reference| {
+                    reference.match_indices('-').map(move |(i, _)| &reference[..i])
+                });
+            plain.into_iter().chain(granular)
+        })
+}
+
+/// Extend the span of a raw entry to its closing delimiter.
+pub(crate) fn entry_span(src: &str, span: &Span) -> Span {
+    let rest = &src[span.end..];
+    let trimmed = rest.trim_start();
+    match trimmed.chars().next() {
+        Some(c @ ('}' | ')')) => span.start..src.len() - trimmed.len() + c.len_utf8(),
+        _ => span.clone(),
+    }
+}
+
+impl<'s> RawEntries<'s> {
+    /// The abbreviations defined before the current position.
+    pub fn abbreviations(&self) -> &[Pair<'s>] {
+        &self.parser.res.abbreviations
+    }
+
+    /// The preamble up to the current position.
+    pub fn preamble(&self) -> &str {
+        &self.parser.res.preamble
+    }
+
+    /// Parse the next top-level item and return it if it is a regular entry.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn next_item(
+        &mut self,
+    ) -> Option<Result<Option<Spanned<RawEntry<'s>>>, ParseError>> {
+        let parser = &mut self.parser;
+        parser
+            .skip_to(memchr(b'@', parser.s.after().as_bytes()))
+            .then(|| parser.entry())
+    }
+
+    /// The byte offset up to which the source was parsed.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn cursor(&self) -> usize {
+        self.parser.s.cursor()
+    }
 }
 
+impl<'s> Iterator for RawEntries<'s> {
+    type Item = Result<Spanned<RawEntry<'s>>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = self.parser.next_entry().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+impl std::iter::FusedIterator for RawEntries<'_> {}
+
 /// Backing struct for parsing a Bib(La)TeX file into a [`RawBibliography`].
+#[derive(Debug, Clone)]
 struct BiblatexParser<'s> {
     s: Scanner<'s>,
     res: RawBibliography<'s>,
@@ -56,6 +485,7 @@ struct BiblatexParser<'s> {
 
 /// An error that might occur during initial parsing of the bibliography.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ParseError {
     /// Where in the source the error occurred.
     pub span: std::ops::Range<usize>,
@@ -88,6 +518,7 @@ impl std::error::Error for ParseError {
 ///
 /// Also see [`ParseError`].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[non_exhaustive]
 pub enum ParseErrorKind {
     /// The file ended prematurely.
@@ -108,6 +539,7 @@ pub enum ParseErrorKind {
 
 /// A token that can be encountered during parsing.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Token {
     /// An identifier for a field key, citation type, abbreviation, or citation
     /// key.
@@ -171,20 +603,25 @@ impl<'s> BiblatexParser<'s> {
 
     /// Parses the file, consuming the parser in the process.
     pub fn parse(mut self) -> Result<RawBibliography<'s>, ParseError> {
-        while !self.s.done() {
-            self.s.eat_whitespace();
-            match self.s.peek() {
-                Some('@') => self.entry()?,
-                Some(_) => {
-                    self.s.eat();
-                }
-                None => break,
-            }
+        while let Some(entry) = self.next_entry()? {
+            self.res.entries.push(entry);
         }
 
         Ok(self.res)
     }
 
+    /// Parses up to and including the next regular entry and returns it.
+    /// Abbreviations and preambles on the way are added to the result.
+    fn next_entry(&mut self) -> Result<Option<Spanned<RawEntry<'s>>>, ParseError> {
+        while self.skip_to(memchr(b'@', self.s.after().as_bytes())) {
+            if let Some(entry) = self.entry()? {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Eat a comma.
     fn comma(&mut self) -> Result<(), ParseError> {
         if !self.s.eat_if(',') {
@@ -238,22 +675,17 @@ impl<'s> BiblatexParser<'s> {
         self.quote()?;
         let idx = self.s.cursor();
 
-        while let Some(c) = self.s.peek() {
-            match c {
-                '"' => {
-                    let res = self.s.from(idx);
-                    let span = idx..self.s.cursor();
-                    self.quote()?;
-                    return Ok(Spanned::new(res, span));
-                }
-                '\\' => {
-                    self.s.eat();
-                    self.s.eat();
-                }
-                _ => {
-                    self.s.eat();
-                }
+        while self.skip_to(memchr2(b'"', b'\\', self.s.after().as_bytes())) {
+            if self.s.at('"') {
+                let res = self.s.from(idx);
+                let span = idx..self.s.cursor();
+                self.quote()?;
+                return Ok(Spanned::new(res, span));
             }
+
+            // Skip the backslash and the escaped character.
+            self.s.eat();
+            self.s.eat();
         }
 
         Err(ParseError::new(self.here(), ParseErrorKind::UnexpectedEof))
@@ -296,8 +728,8 @@ impl<'s> BiblatexParser<'s> {
         let idx = self.s.cursor();
         let mut braces = 0;
 
-        while let Some(c) = self.s.peek() {
-            match c {
+        while self.skip_to(memchr3(b'{', b'}', b'\\', self.s.after().as_bytes())) {
+            match self.s.peek().unwrap_or_default() {
                 '{' => {
                     self.brace(true)?;
                     braces += 1;
@@ -307,15 +739,13 @@ impl<'s> BiblatexParser<'s> {
                     let span = idx..self.s.cursor();
                     self.brace(false)?;
                     if braces == 0 {
-                        return Ok(Spanned::new(RawChunk::Normal(res), span));
+                        return Ok(Spanned::new(RawChunk::Normal(res.into()), span));
                     }
                     braces -= 1;
                 }
-                '\\' => {
-                    self.s.eat();
-                    self.s.eat();
-                }
                 _ => {
+                    // Skip the backslash and the escaped character.
+                    self.s.eat();
                     self.s.eat();
                 }
             }
@@ -328,9 +758,11 @@ impl<'s> BiblatexParser<'s> {
     fn abbr_element(&mut self) -> Result<Spanned<RawChunk<'s>>, ParseError> {
         let start = self.s.cursor();
         let res = match self.s.peek() {
-            Some(c) if c.is_ascii_digit() => self.number().map(RawChunk::Normal),
+            Some(c) if c.is_ascii_digit() => {
+                self.number().map(|s| RawChunk::Normal(s.into()))
+            }
             Some(c) if is_id_start(c) => {
-                self.ident().map(|s| RawChunk::Abbreviation(s.v))
+                self.ident().map(|s| RawChunk::Abbreviation(s.v.into()))
             }
             _ => {
                 return self.single_field();
@@ -358,8 +790,10 @@ impl<'s> BiblatexParser<'s> {
     }
 
     /// Eat a field.
-    fn field(&mut self) -> Result<(Spanned<&'s str>, Spanned<Field<'s>>), ParseError> {
-        let key = self.ident()?;
+    fn field(
+        &mut self,
+    ) -> Result<(Spanned<Cow<'s, str>>, Spanned<Field<'s>>), ParseError> {
+        let key = self.ident()?.map(Cow::Borrowed);
         self.s.eat_whitespace();
         self.equals()?;
         self.s.eat_whitespace();
@@ -374,9 +808,7 @@ impl<'s> BiblatexParser<'s> {
     fn single_field(&mut self) -> Result<Spanned<RawChunk<'s>>, ParseError> {
         match self.s.peek() {
             Some('{') => self.braced(),
-            Some('"') => {
-                self.string().map(|s| Spanned::new(RawChunk::Normal(s.v), s.span))
-            }
+            Some('"') => self.string().map(|s| s.map(|v| RawChunk::Normal(v.into()))),
             _ => Err(ParseError::new(self.here(), ParseErrorKind::UnexpectedEof)),
         }
     }
@@ -416,11 +848,11 @@ impl<'s> BiblatexParser<'s> {
     }
 
     /// Eat an entry key.
-    fn key(&mut self) -> Result<Spanned<&'s str>, ParseError> {
+    fn key(&mut self) -> Result<Spanned<Cow<'s, str>>, ParseError> {
         let idx = self.s.cursor();
         self.s.eat_while(is_key);
 
-        Ok(Spanned::new(self.s.from(idx), idx..self.s.cursor()))
+        Ok(Spanned::new(self.s.from(idx).into(), idx..self.s.cursor()))
     }
 
     /// Eat an identifier.
@@ -437,8 +869,8 @@ impl<'s> BiblatexParser<'s> {
         }
     }
 
-    /// Eat an entry.
-    fn entry(&mut self) -> Result<(), ParseError> {
+    /// Eat an entry. Returns it if it is a regular entry.
+    fn entry(&mut self) -> Result<Option<Spanned<RawEntry<'s>>>, ParseError> {
         let start = self.s.cursor();
         if self.s.eat() != Some('@') {
             panic!("must not call entry when not at an '@'");
@@ -449,19 +881,26 @@ impl<'s> BiblatexParser<'s> {
         self.brace(true)?;
         self.s.eat_whitespace();
 
-        match entry_type.v.to_ascii_lowercase().as_str() {
-            "string" => self.strings()?,
-            "preamble" => self.preamble()?,
+        let entry = match entry_type.v.to_ascii_lowercase().as_str() {
+            "string" => {
+                self.strings()?;
+                None
+            }
+            "preamble" => {
+                self.preamble()?;
+                None
+            }
             "comment" => {
                 self.s.eat_until('}');
+                None
             }
-            _ => self.body(entry_type, start)?,
-        }
+            _ => Some(self.body(entry_type.map(Cow::Borrowed), start)?),
+        };
 
         self.s.eat_whitespace();
         self.brace(false)?;
 
-        Ok(())
+        Ok(entry)
     }
 
     /// Eat the body of a strings entry.
@@ -475,7 +914,13 @@ impl<'s> BiblatexParser<'s> {
     fn preamble(&mut self) -> Result<(), ParseError> {
         let idx = self.s.cursor();
         self.string()?;
-        let string = self.s.from(idx);
+        self.s.eat_whitespace();
+        while self.s.eat_if('#') {
+            self.s.eat_whitespace();
+            self.string()?;
+            self.s.eat_whitespace();
+        }
+        let string = self.s.from(idx).trim_end();
 
         if !self.res.preamble.is_empty() {
             self.res.preamble.push_str(" # ");
@@ -487,7 +932,11 @@ impl<'s> BiblatexParser<'s> {
     }
 
     /// Eat the body of an entry.
-    fn body(&mut self, kind: Spanned<&'s str>, start: usize) -> Result<(), ParseError> {
+    fn body(
+        &mut self,
+        kind: Spanned<Cow<'s, str>>,
+        start: usize,
+    ) -> Result<Spanned<RawEntry<'s>>, ParseError> {
         let key = self.key()?;
         self.s.eat_whitespace();
         self.comma()?;
@@ -495,10 +944,26 @@ impl<'s> BiblatexParser<'s> {
         self.s.eat_whitespace();
         let fields = self.fields()?;
 
-        self.res
-            .entries
-            .push(Spanned::new(RawEntry { key, kind, fields }, start..self.s.cursor()));
-        Ok(())
+        Ok(Spanned::new(RawEntry { key, kind, fields }, start..self.s.cursor()))
+    }
+
+    /// Move to the next delimiter, given its offset in the rest of the
+    /// source, or to the end if there is none. Returns whether a delimiter
+    /// was found.
+    ///
+    /// Delimiters are ASCII, so their byte offsets are always at character
+    /// boundaries.
+    fn skip_to(&mut self, offset: Option<usize>) -> bool {
+        match offset {
+            Some(offset) => {
+                self.s.jump(self.s.cursor() + offset);
+                true
+            }
+            None => {
+                self.s.jump(self.s.string().len());
+                false
+            }
+        }
     }
 
     fn here(&self) -> Span {
@@ -508,18 +973,34 @@ impl<'s> BiblatexParser<'s> {
 
 /// The keys for fields and their values.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pair<'s> {
     /// The key.
-    pub key: Spanned<&'s str>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub key: Spanned<Cow<'s, str>>,
     /// The value.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub value: Spanned<Field<'s>>,
 }
 
 impl<'s> Pair<'s> {
     /// Constructs a new key-value pair.
-    pub fn new(key: Spanned<&'s str>, value: Spanned<Field<'s>>) -> Self {
+    pub fn new(key: Spanned<Cow<'s, str>>, value: Spanned<Field<'s>>) -> Self {
         Self { key, value }
     }
+
+    /// Detach the pair from the source by copying all borrowed text.
+    pub fn into_owned(self) -> Pair<'static> {
+        Pair {
+            key: self.key.map(owned),
+            value: self.value.map(|field| {
+                field
+                    .into_iter()
+                    .map(|chunk| chunk.map(RawChunk::into_owned))
+                    .collect()
+            }),
+        }
+    }
 }
 
 /// Whether a character is allowed in an entry key
@@ -566,7 +1047,7 @@ mod tests {
                 first = false;
             }
 
-            match field.v {
+            match &field.v {
                 RawChunk::Normal(s) => {
                     res.push('"');
                     res.push_str(s);
@@ -627,7 +1108,7 @@ mod tests {
     fn test_resolve_string() {
         let bt = RawBibliography::parse("@string{BT = \"bibtex\"}").unwrap();
         assert_eq!(bt.abbreviations[0].key.v, "BT");
-        assert_eq!(&bt.abbreviations[0].value.v, &vec![Spanned::new(RawChunk::Normal("bibtex"), 14..20)]);
+        assert_eq!(&bt.abbreviations[0].value.v, &vec![Spanned::new(RawChunk::Normal("bibtex".into()), 14..20)]);
     }
 
     #[test]
@@ -639,4 +1120,145 @@ mod tests {
     fn test_abbr() {
         assert_eq!(test_prop("author", "dec # {~12}"), "dec # \"~12\"");
     }
+
+    #[test]
+    fn test_collect_garbage() {
+        let file = r#"@string{acm = "ACM"}
+            @string{acmpress = acm # " Press"}
+            @string{ieee = "IEEE"}
+            @xdata{pub, publisher = acmpress}
+            @xdata{loc, location = {New York}}
+            @xdata{my-names, author = {Doe, Jane}}
+            @xdata{unused, note = ieee}
+            @book{a, title = {A}, xdata = {pub}, author = {xdata=my-names-author-1}}
+            @book{b, title = {B}, note = {Not loc}}"#;
+        let mut bt = RawBibliography::parse(file).unwrap();
+
+        let garbage = bt.collect_garbage();
+        assert_eq!(garbage.abbreviations, ["ieee"]);
+        assert_eq!(garbage.xdata, ["loc", "unused"]);
+        assert_eq!(bt.abbreviations.len(), 2);
+        assert_eq!(bt.entries.len(), 4);
+
+        bt.entries.retain(|entry| entry.v.key.v != "a");
+        let garbage = bt.collect_garbage();
+        assert_eq!(garbage.abbreviations, ["acm", "acmpress"]);
+        assert_eq!(garbage.xdata, ["pub", "my-names"]);
+        assert_eq!(bt.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_subset() {
+        let file = r#"@string{acm = "ACM"}
+            @string{ieee = "IEEE"}
+            @xdata{pub, publisher = acm}
+            @xdata{other, publisher = ieee}
+            @proceedings{conf, title = {Conference}, xdata = {pub}}
+            @inproceedings{paper, crossref = {conf}, related = {note}}
+            @misc{note, author = {xdata=names-author}}
+            @xdata{names, author = {Doe, Jane}}
+            @article{unrelated, journal = ieee}"#;
+        let bt = RawBibliography::parse(file).unwrap();
+
+        let subset = bt.subset(["paper", "missing"]);
+        let keys: Vec<_> = subset.entries.iter().map(|e| &e.v.key.v).collect();
+        assert_eq!(keys, ["pub", "conf", "paper", "note", "names"]);
+        assert_eq!(subset.abbreviations.len(), 1);
+        assert_eq!(subset.abbreviations[0].key.v, "acm");
+
+        let subset = bt.subset(["unrelated"]);
+        assert_eq!(subset.entries.len(), 1);
+        assert_eq!(subset.abbreviations[0].key.v, "ieee");
+        assert!(bt.subset([]).entries.is_empty());
+    }
+
+    #[test]
+    fn test_delimiters() {
+        assert_eq!(test_prop("title", "{Ünïcödé {ß} \\{ \\é}"), "{Ünïcödé {ß} \\{ \\é}");
+        assert_eq!(test_prop("title", "\"Straße \\\" {Ä}\""), "{Straße \\\" {Ä}}");
+        assert_eq!(test_prop("title", "{ä} # \"ö\""), "\"ä\" # \"ö\"");
+
+        let bt = RawBibliography::parse("é % junk @misc{a,} ü @misc{b,}").unwrap();
+        assert_eq!(bt.entries.len(), 2);
+        assert_eq!(bt.entries[1].span, 23..31);
+
+        let err = RawBibliography::parse("@misc{a, title = {ä {ö}").unwrap_err();
+        assert_eq!(err.span, 25..25);
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEof);
+        let err = RawBibliography::parse("@misc{a, title = \"ä\\\"").unwrap_err();
+        assert_eq!(err.span, 22..22);
+    }
+
+    #[test]
+    fn test_iter_entries() {
+        let src = r#"@preamble{"a"} @comment{b} @book{c,} @string{d = "D"}
+            @misc{e, note = d} @misc{f, note = {"#;
+        let mut entries = RawBibliography::iter_entries(src);
+        assert_eq!(entries.preamble(), "");
+        assert_eq!(entries.next().unwrap().unwrap().v.key.v, "c");
+        assert_eq!(entries.preamble(), "\"a\"");
+        assert!(entries.abbreviations().is_empty());
+
+        let e = entries.next().unwrap().unwrap();
+        assert_eq!(e.v.key.v, "e");
+        assert_eq!(e.span, 66..83);
+        assert_eq!(entries.abbreviations().len(), 1);
+        assert_eq!(entries.next().unwrap().unwrap_err(), RawBibliography::parse(src).unwrap_err());
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn test_into_owned() {
+        fn parse(src: String) -> RawBibliography<'static> {
+            RawBibliography::parse(&src).unwrap().into_owned()
+        }
+
+        let src = "@string{acm = \"ACM\"} @book{a, publisher = acm # { Press}}";
+        let bt = std::thread::spawn(move || parse(src.to_string())).join().unwrap();
+        assert_eq!(bt.abbreviations[0].key.v, "acm");
+        assert_eq!(bt.entries[0].v.key.v, "a");
+        assert_eq!(bt.entries[0].span, 21..56);
+        assert_eq!(format(&bt.entries[0].v.fields[0].value.v), "acm # \" Press\"");
+        assert!(matches!(bt.entries[0].v.kind.v, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_write_raw() {
+        let file = r#"@string{acm = "ACM"}
+            @string{acmpress = acm # " Press"}
+            @preamble{"\newcommand{\noop}[1]{}"}
+            @book{a, title = "Curly \{ Brace", year = 2020, publisher = acmpress}
+            @article{b, title = {Nested {Braces}}, month = dec # { 12}}"#;
+        let bt = RawBibliography::parse(file).unwrap();
+        let written = bt.to_biblatex_string();
+        assert_eq!(written, "@string{acm = {ACM}}
+@string{acmpress = acm # { Press}}
+
+@preamble{\"\\newcommand{\\noop}[1]{}\"}
+
+@book{a,
+publisher = acmpress,
+title = {Curly \\{ Brace},
+year = 2020,
+}
+
+@article{b,
+month = dec # { 12},
+title = {Nested {Braces}},
+}
+");
+
+        let reparsed = RawBibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), written);
+        assert_eq!(reparsed.preamble, bt.preamble);
+        for (a, b) in reparsed.entries.iter().zip(&bt.entries) {
+            let fields = |e: &RawEntry| -> Vec<(String, String)> {
+                let mut fields: Vec<_> =
+                    e.fields.iter().map(|p| (p.key.v.to_string(), format(&p.value.v))).collect();
+                fields.sort();
+                fields
+            };
+            assert_eq!(fields(&a.v), fields(&b.v));
+        }
+    }
 }
@@ -1,10 +1,11 @@
 use unicode_normalization::char;
 
 use crate::chunk::{Chunk, Chunks};
-use crate::mechanics::is_verbatim_field;
+use crate::mechanics::{is_paragraph_field, is_verbatim_field};
 use crate::raw::{
     is_id_continue, Field, Pair, ParseError, ParseErrorKind, RawChunk, Token,
 };
+use crate::swar::{zeros, HIGH, ONES};
 use crate::types::get_month_for_abbr;
 use crate::{ChunksExt, Span, Spanned};
 use unscanny::Scanner;
@@ -13,84 +14,146 @@ use unscanny::Scanner;
 pub fn parse_field(
     key: &str,
     field: &Field,
-    abbreviations: &Vec<Pair<'_>>,
+    abbreviations: &[Pair<'_>],
 ) -> Result<Chunks, ParseError> {
     let mut chunks = vec![];
+    parse_field_into(key, field, abbreviations, &mut chunks)?;
+    Ok(chunks)
+}
+
+/// Fully parse a field into a sink.
+pub(crate) fn parse_field_into(
+    key: &str,
+    field: &Field,
+    abbreviations: &[Pair<'_>],
+    sink: &mut impl ChunkSink,
+) -> Result<(), ParseError> {
     for e in field {
-        match e.v {
+        match &e.v {
             RawChunk::Abbreviation(s) => {
-                chunks.extend(resolve_abbreviation(
-                    key,
-                    s,
-                    e.span.clone(),
-                    abbreviations,
-                )?);
+                resolve_abbreviation(key, s, e.span.clone(), abbreviations, sink)?;
             }
             RawChunk::Normal(s) => {
-                chunks.extend(ContentParser::new(key, s, e.span.start).parse()?);
+                ContentParser::new(key, s, e.span.start, sink).parse()?;
             }
         }
     }
 
-    flatten(&mut chunks);
-    Ok(chunks)
+    Ok(())
+}
+
+/// The kind of a chunk.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum ChunkKind {
+    Normal,
+    Verbatim,
+    Math,
+}
+
+impl ChunkKind {
+    /// The kind of a chunk.
+    fn of(chunk: &Chunk) -> Self {
+        match chunk {
+            Chunk::Normal(_) => Self::Normal,
+            Chunk::Verbatim(_) => Self::Verbatim,
+            Chunk::Math(_) => Self::Math,
+        }
+    }
+
+    /// The kind of text at the given brace depth.
+    fn at_depth(depth: usize) -> Self {
+        if depth > 0 {
+            Self::Verbatim
+        } else {
+            Self::Normal
+        }
+    }
+
+    /// Whether a chunk of this kind is merged into a preceding chunk of the
+    /// other kind.
+    pub(crate) fn merges_with(self, other: Self) -> bool {
+        self == other && self != Self::Math
+    }
+}
+
+/// Receives the chunks of a field as they are decoded.
+pub(crate) trait ChunkSink {
+    /// Append a chunk. Normal and verbatim chunks are merged into a
+    /// preceding chunk of the same kind.
+    fn push(&mut self, kind: ChunkKind, text: &str, span: Span);
+}
+
+impl ChunkSink for Chunks {
+    fn push(&mut self, kind: ChunkKind, text: &str, span: Span) {
+        match self.last_mut() {
+            Some(last) if kind.merges_with(ChunkKind::of(&last.v)) => {
+                last.v.get_mut().push_str(text);
+                last.span.end = span.end;
+            }
+            _ => {
+                let text = text.to_string();
+                let chunk = match kind {
+                    ChunkKind::Normal => Chunk::Normal(text),
+                    ChunkKind::Verbatim => Chunk::Verbatim(text),
+                    ChunkKind::Math => Chunk::Math(text),
+                };
+                Vec::push(self, Spanned::new(chunk, span));
+            }
+        }
+    }
 }
 
-#[derive(Clone)]
-struct ContentParser<'s> {
+struct ContentParser<'s, 'a, S> {
     s: Scanner<'s>,
     verb_field: bool,
-    current_chunk: Chunk,
-    result: Chunks,
+    paragraph_field: bool,
+    /// The kind of the current chunk.
+    kind: ChunkKind,
+    /// The text of the current chunk.
+    text: String,
+    /// Whether a chunk was pushed to the sink.
+    pushed: bool,
+    sink: &'a mut S,
     start: usize,
     offset: usize,
 }
 
-impl<'s> ContentParser<'s> {
-    fn new(key: &'s str, field: &'s str, offset: usize) -> Self {
+impl<'s, 'a, S: ChunkSink> ContentParser<'s, 'a, S> {
+    fn new(key: &'s str, field: &'s str, offset: usize, sink: &'a mut S) -> Self {
         Self {
             s: Scanner::new(field),
             verb_field: is_verbatim_field(key),
-            current_chunk: Self::default_chunk(0),
-            result: vec![],
+            paragraph_field: is_paragraph_field(key),
+            kind: ChunkKind::Normal,
+            text: String::new(),
+            pushed: false,
+            sink,
             start: 0,
             offset,
         }
     }
 
-    fn parse(self) -> Result<Chunks, ParseError> {
+    fn parse(self) -> Result<(), ParseError> {
         let offset = self.offset;
-        self.parse_impl()
-            .map_err(|mut e| {
-                e.span.start += offset;
-                e.span.end += offset;
-                e
-            })
-            .map(|mut chunks| {
-                for chunk in &mut chunks {
-                    chunk.span.start += offset;
-                    chunk.span.end += offset;
-                }
-
-                chunks
-            })
+        self.parse_impl().map_err(|mut e| {
+            e.span.start += offset;
+            e.span.end += offset;
+            e
+        })
     }
 
-    fn parse_impl(mut self) -> Result<Chunks, ParseError> {
+    fn parse_impl(mut self) -> Result<(), ParseError> {
         let mut depth = 0;
 
-        self.current_chunk = Self::default_chunk(depth);
-
         while let Some(c) = self.s.peek() {
             match c {
                 '\\' => {
                     let sequence = self.backslash()?;
-                    self.current_chunk.get_mut().push_str(&sequence)
+                    self.text.push_str(&sequence)
                 }
                 '$' if !self.verb_field => {
                     self.turnaround(depth);
-                    let math = self.math()?;
-                    self.result.push(math);
+                    self.math()?;
                 }
                 '{' => {
                     depth += 1;
@@ -125,32 +188,45 @@ impl<'s> ContentParser<'s> {
                     });
 
                     match count {
-                        1 => self.current_chunk.get_mut().push('-'),
-                        2 => self.current_chunk.get_mut().push('–'),
-                        3 => self.current_chunk.get_mut().push('—'),
-                        _ => self.current_chunk.get_mut().push_str(hyphens),
+                        1 => self.text.push('-'),
+                        2 => self.text.push('–'),
+                        3 => self.text.push('—'),
+                        _ => self.text.push_str(hyphens),
                     }
                 }
                 _ if c.is_whitespace() => {
-                    self.current_chunk.get_mut().push(' ');
-                    self.s.eat_whitespace();
+                    // Blank lines separate paragraphs in long text fields.
+                    let ws = self.s.eat_whitespace();
+                    if self.paragraph_field && ws.matches('\n').count() >= 2 {
+                        self.text.push_str("\n\n");
+                    } else {
+                        self.text.push(' ');
+                    }
                 }
-                _ => self.current_chunk.get_mut().push(self.s.eat().unwrap()),
+                _ => match plain_len(self.s.after()) {
+                    0 => self.text.push(self.s.eat().unwrap()),
+                    len => {
+                        let cursor = self.s.cursor();
+                        self.text.push_str(&self.s.after()[..len]);
+                        self.s.jump(cursor + len);
+                    }
+                },
             }
         }
 
-        if !self.current_chunk.get().is_empty() || self.result.is_empty() {
+        if !self.text.is_empty() || !self.pushed {
             self.turnaround(depth);
         }
 
-        Ok(self.result)
+        Ok(())
     }
 
     fn turnaround(&mut self, depth: usize) {
-        self.result.push(Spanned::new(
-            std::mem::replace(&mut self.current_chunk, Self::default_chunk(depth)),
-            self.start..self.s.cursor(),
-        ));
+        let span = self.start + self.offset..self.s.cursor() + self.offset;
+        self.sink.push(self.kind, &self.text, span);
+        self.text.clear();
+        self.kind = ChunkKind::at_depth(depth);
+        self.pushed = true;
         self.start = self.s.cursor();
     }
 
@@ -228,19 +304,23 @@ impl<'s> ContentParser<'s> {
             let brace = '}'.len_utf8();
             let arg = self.s.from(idx);
 
-            let arg = ContentParser::new("", &arg[..arg.len() - brace], idx)
-                .parse()?
-                .format_verbatim();
+            let mut chunks = vec![];
+            ContentParser::new("", &arg[..arg.len() - brace], idx, &mut chunks)
+                .parse()?;
 
-            Some(arg)
+            Some(chunks.format_verbatim())
         } else {
             None
         };
 
+        if command == "par" && arg.is_none() {
+            return Ok(if self.paragraph_field { "\n\n" } else { " " }.into());
+        }
+
         Ok(execute_command(command, arg.as_deref()))
     }
 
-    fn math(&mut self) -> Result<Spanned<Chunk>, ParseError> {
+    fn math(&mut self) -> Result<(), ParseError> {
         self.eat_assert('$');
         let idx = self.s.cursor();
         let res = self.s.eat_until(|c| c == '$');
@@ -252,7 +332,13 @@ impl<'s> ContentParser<'s> {
 
         self.s.eat();
         self.start = self.s.cursor();
-        Ok(Spanned::new(Chunk::Math(res.into()), span))
+        self.sink.push(
+            ChunkKind::Math,
+            res,
+            span.start + self.offset..span.end + self.offset,
+        );
+        self.pushed = true;
+        Ok(())
     }
 
     #[track_caller]
@@ -265,14 +351,6 @@ impl<'s> ContentParser<'s> {
     fn here(&self) -> Span {
         self.s.cursor()..self.s.cursor()
     }
-
-    fn default_chunk(depth: usize) -> Chunk {
-        if depth > 0 {
-            Chunk::Verbatim(String::new())
-        } else {
-            Chunk::Normal(String::new())
-        }
-    }
 }
 
 /// Resolves `Chunk::Abbreviation` items to their respective string values.
@@ -280,8 +358,9 @@ fn resolve_abbreviation(
     key: &str,
     abbr: &str,
     span: Span,
-    map: &Vec<Pair<'_>>,
-) -> Result<Chunks, ParseError> {
+    map: &[Pair<'_>],
+    sink: &mut impl ChunkSink,
+) -> Result<(), ParseError> {
     let fields =
         map.iter()
             .find(|e| e.key.v == abbr)
@@ -293,11 +372,12 @@ fn resolve_abbreviation(
 
     if fields.is_err() {
         if let Some(month) = get_month_for_abbr(abbr) {
-            return Ok(vec![Spanned::new(Chunk::Normal(month.0.to_string()), span)]);
+            sink.push(ChunkKind::Normal, month.0, span);
+            return Ok(());
         }
     }
 
-    parse_field(key, fields?, map)
+    parse_field_into(key, fields?, map, sink)
 }
 
 /// Best-effort evaluation of LaTeX commands with a focus on diacritics.
@@ -390,7 +470,7 @@ fn execute_command(command: &str, arg: Option<&str>) -> String {
         "i" => "ı".to_string(),
         "oe" => "œ".to_string(),
         "OE" => "Œ".to_string(),
-        "o" if arg.is_none() => "ø".to_string(),
+        "o" if matches!(arg, None | Some("")) => "ø".to_string(),
         "O" => "Ø".to_string(),
         "ss" => "ß".to_string(),
         "SS" => "ẞ".to_string(),
@@ -436,35 +516,6 @@ fn execute_command(command: &str, arg: Option<&str>) -> String {
     }
 }
 
-/// Simplifies a chunk vector by collapsing neighboring Normal or Verbatim chunks.
-fn flatten(chunks: &mut Chunks) {
-    let mut i = 1;
-
-    loop {
-        if i >= chunks.len() {
-            break;
-        }
-
-        let merge = matches!(
-            (&chunks[i - 1].v, &chunks[i].v),
-            (Chunk::Normal(_), Chunk::Normal(_))
-                | (Chunk::Verbatim(_), Chunk::Verbatim(_))
-        );
-
-        if merge {
-            let redundant = std::mem::replace(
-                &mut chunks[i],
-                Spanned::new(Chunk::Normal("".to_string()), 0..0),
-            );
-            chunks[i - 1].v.get_mut().push_str(redundant.v.get());
-            chunks[i - 1].span.end = redundant.span.end;
-            chunks.remove(i);
-        } else {
-            i += 1;
-        }
-    }
-}
-
 /// Characters that can be escaped.
 ///
 /// In read mode (`read_char = true`), colons are also converted to an unescaped
@@ -482,6 +533,40 @@ pub fn is_escapable(c: char, verb: bool, read_char: bool) -> bool {
     }
 }
 
+/// The length of the run of ASCII text at the start of a field value that
+/// can be copied as is, i.e. without commands, math, braces, hyphens, or
+/// whitespace.
+///
+/// Long values like abstracts mostly consist of such text, so it is scanned
+/// eight bytes at a time. The scan stops at the first non-ASCII byte, after
+/// which the caller handles a character on its own.
+fn plain_len(text: &str) -> usize {
+    /// The high bits of the bytes that stop a run.
+    fn stops(word: u64) -> u64 {
+        let controls = word.wrapping_sub(ONES * 0x21) & !word & HIGH;
+        let specials = [b'\\', b'$', b'{', b'}', b'-']
+            .iter()
+            .fold(0, |acc, &b| acc | zeros(word ^ (ONES * u64::from(b))));
+        (word & HIGH) | controls | specials
+    }
+
+    let is_plain = |b: u8| b > b' ' && b.is_ascii() && !b"\\${}-".contains(&b);
+
+    let bytes = text.as_bytes();
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        let stops = stops(word);
+        if stops != 0 {
+            return len + stops.trailing_zeros() as usize / 8;
+        }
+        len += 8;
+    }
+
+    len + chunks.remainder().iter().take_while(|&&b| is_plain(b)).count()
+}
+
 /// Characters that are the name of a single-char command
 /// that automatically terminates.
 fn is_single_char_func(c: char) -> bool {
@@ -509,24 +594,51 @@ mod tests {
         Spanned::new(c, 0..0)
     }
 
+    #[test]
+    fn test_plain_len() {
+        use super::plain_len;
+        assert_eq!(plain_len(""), 0);
+        assert_eq!(plain_len("abc"), 3);
+        assert_eq!(plain_len("Lorem ipsum"), 5);
+        assert_eq!(plain_len("Loremipsumdolor{sit}"), 15);
+        assert_eq!(plain_len("abcdefgh\\emph"), 8);
+        assert_eq!(plain_len("abcdefghij$x$"), 10);
+        assert_eq!(plain_len("abcdefghijk-l"), 11);
+        assert_eq!(plain_len("abcdefg\tab"), 7);
+        assert_eq!(plain_len("Schrödinger"), 4);
+        assert_eq!(plain_len("~!#%&*+,./:;<=>?@[]^_`|"), 23);
+        assert_eq!(plain_len("\u{1}abc"), 0);
+        assert_eq!(plain_len("€uro"), 0);
+
+        for b in 0..=0x7f {
+            let plain = b > b' ' && !b"\\${}-".contains(&b);
+            for i in 0..20 {
+                let mut text = vec![b'a'; 20];
+                text[i] = b;
+                let text = std::str::from_utf8(&text).unwrap();
+                assert_eq!(plain_len(text), if plain { 20 } else { i });
+            }
+        }
+    }
+
     #[test]
     fn test_process() {
         let map: Vec<_> = [("abc", "ABC"), ("hi", "hello"), ("you", "person")]
             .into_iter()
             .map(|(k, v)| {
                 Pair::new(
-                    Spanned::detached(k),
-                    Spanned::detached(vec![z(RawChunk::Normal(v))]),
+                    Spanned::detached(k.into()),
+                    Spanned::detached(vec![z(RawChunk::Normal(v.into()))]),
                 )
             })
             .collect();
 
         let field = vec![
-            z(RawChunk::Abbreviation("abc")),
-            z(RawChunk::Normal("good {TIMES}")),
-            z(RawChunk::Abbreviation("hi")),
-            z(RawChunk::Abbreviation("you")),
-            z(RawChunk::Normal("last")),
+            z(RawChunk::Abbreviation("abc".into())),
+            z(RawChunk::Normal("good {TIMES}".into())),
+            z(RawChunk::Abbreviation("hi".into())),
+            z(RawChunk::Abbreviation("you".into())),
+            z(RawChunk::Normal("last".into())),
         ];
 
         let res = parse_field("", &field, &map).unwrap();
@@ -536,10 +648,23 @@ mod tests {
         assert_eq!(res.len(), 3);
     }
 
+    #[test]
+    fn test_paragraphs() {
+        let field = vec![z(RawChunk::Normal(
+            "First\n  paragraph.\n\n Second.\\par Third.".into(),
+        ))];
+
+        let res = parse_field("abstract", &field, &Vec::new()).unwrap();
+        assert_eq!(res[0].v, N("First paragraph.\n\nSecond.\n\nThird."));
+
+        let res = parse_field("title", &field, &Vec::new()).unwrap();
+        assert_eq!(res[0].v, N("First paragraph. Second. Third."));
+    }
+
     #[test]
     fn test_resolve_commands_and_escape() {
         let field = vec![z(RawChunk::Normal(
-            "\\\"{A}ther und {\"\\LaTeX \"} {\\relax for you\\}}",
+            "\\\"{A}ther und {\"\\LaTeX \"} {\\relax for you\\}}".into(),
         ))];
 
         let res = parse_field("", &field, &Vec::new()).unwrap();
@@ -549,12 +674,12 @@ mod tests {
         assert_eq!(res[3].v, V("\\relax for you}"));
         assert_eq!(res.len(), 4);
 
-        let field = vec![z(RawChunk::Normal("M\\\"etal S\\= ound"))];
+        let field = vec![z(RawChunk::Normal("M\\\"etal S\\= ound".into()))];
 
         let res = parse_field("", &field, &Vec::new()).unwrap();
         assert_eq!(res[0].v, N("Mëtal Sōund"));
 
-        let field = vec![z(RawChunk::Normal(r"L\^{e} D\~{u}ng Tr\'{a}ng"))];
+        let field = vec![z(RawChunk::Normal(r"L\^{e} D\~{u}ng Tr\'{a}ng".into()))];
 
         let res = parse_field("", &field, &Vec::new()).unwrap();
         assert_eq!(res[0].v, N("Lê Dũng Tráng"));
@@ -563,7 +688,7 @@ mod tests {
     #[test]
     fn test_math() {
         let field = vec![z(RawChunk::Normal(
-            "The $11^{th}$ International Conference on How To Make \\$\\$",
+            "The $11^{th}$ International Conference on How To Make \\$\\$".into(),
         ))];
 
         let res = parse_field("", &field, &Vec::new()).unwrap();
@@ -575,8 +700,9 @@ mod tests {
 
     #[test]
     fn test_commands() {
-        let field =
-            vec![z(RawChunk::Normal("Bose\\textendash{}Einstein uses Win\\-dows"))];
+        let field = vec![z(RawChunk::Normal(
+            "Bose\\textendash{}Einstein uses Win\\-dows".into(),
+        ))];
 
         let res = parse_field("", &field, &Vec::new()).unwrap();
         assert_eq!(res[0].v, N("Bose–Einstein uses Windows"));
@@ -584,8 +710,9 @@ mod tests {
 
     #[test]
     fn test_hyphens() {
-        let field =
-            vec![z(RawChunk::Normal("- Knitting A--Z --- A practical guide -----"))];
+        let field = vec![z(RawChunk::Normal(
+            "- Knitting A--Z --- A practical guide -----".into(),
+        ))];
 
         let res = parse_field("", &field, &Vec::new()).unwrap();
         assert_eq!(res[0].v, N("- Knitting A–Z — A practical guide —–"));
@@ -0,0 +1,61 @@
+//! Lookahead and backtracking for the scanners of the parsers.
+
+use unscanny::Scanner;
+
+/// A saved scanner position.
+///
+/// Obtained from [`ScannerExt::checkpoint`] and passed to
+/// [`ScannerExt::restore`] to backtrack.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Checkpoint(usize);
+
+/// Extensions to [`Scanner`]. Eating a string if it follows is already
+/// covered by [`Scanner::eat_if`].
+pub(crate) trait ScannerExt {
+    /// The character `n` characters after the cursor. `peek_nth(0)` is the
+    /// same as `peek()`.
+    fn peek_nth(&self, n: usize) -> Option<char>;
+
+    /// Save the current position.
+    fn checkpoint(&self) -> Checkpoint;
+
+    /// Return to a saved position.
+    fn restore(&mut self, checkpoint: Checkpoint);
+}
+
+impl ScannerExt for Scanner<'_> {
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.after().chars().nth(n)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.cursor())
+    }
+
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        self.jump(checkpoint.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scanner_ext() {
+        let mut s = Scanner::new("äb cd");
+        assert_eq!(s.peek_nth(0), Some('ä'));
+        assert_eq!(s.peek_nth(1), Some('b'));
+        assert_eq!(s.peek_nth(5), None);
+
+        let checkpoint = s.checkpoint();
+        assert!(!s.eat_if("äc"));
+        assert!(s.eat_if("äb"));
+        assert!(s.eat_if(" c"));
+        assert_eq!(s.after(), "d");
+
+        s.restore(checkpoint);
+        assert_eq!(s.cursor(), 0);
+        assert_eq!(s.peek(), Some('ä'));
+    }
+}
@@ -0,0 +1,281 @@
+//! Fuzzy full-text search over titles and abstracts.
+
+use std::collections::HashMap;
+
+use crate::keygen::transliterate;
+use crate::{Bibliography, ChunksExt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The indexed fields with the weight of a match in them.
+const SEARCH_FIELDS: [(&str, f32); 7] = [
+    ("title", 2.0),
+    ("subtitle", 2.0),
+    ("titleaddon", 1.5),
+    ("maintitle", 1.5),
+    ("booktitle", 1.5),
+    ("journaltitle", 1.0),
+    ("abstract", 1.0),
+];
+
+/// An in-memory inverted index for fuzzy search over the titles and
+/// abstracts of a bibliography. Can be obtained through
+/// [`Bibliography::search_index`].
+///
+/// Words are matched by the similarity of their character trigrams, so that
+/// typos and inflections still find an entry. The last word of a query also
+/// matches all words it is a prefix of, which allows as-you-type search.
+///
+/// The index does not borrow the bibliography and thus does not observe
+/// changes made to it after construction.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchIndex {
+    /// The cite keys of the indexed entries.
+    keys: Vec<String>,
+    /// The distinct words in the indexed fields.
+    words: Vec<String>,
+    /// The indices of the words in lexicographic order.
+    sorted: Vec<usize>,
+    /// The number of distinct trigrams of each word.
+    trigram_counts: Vec<usize>,
+    /// The words containing each trigram.
+    #[cfg_attr(feature = "serde", serde(with = "trigram_map"))]
+    trigrams: HashMap<[char; 3], Vec<usize>>,
+    /// The entries containing each word with the highest field weight.
+    postings: Vec<Vec<(usize, f32)>>,
+    /// The minimum similarity for a fuzzy match.
+    threshold: f32,
+}
+
+impl SearchIndex {
+    /// Index the titles and abstracts of all entries of a bibliography.
+    pub fn new(bibliography: &Bibliography) -> Self {
+        let mut index = Self {
+            keys: vec![],
+            words: vec![],
+            sorted: vec![],
+            trigram_counts: vec![],
+            trigrams: HashMap::new(),
+            postings: vec![],
+            threshold: 0.5,
+        };
+
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        for (doc, entry) in bibliography.iter().enumerate() {
+            index.keys.push(entry.key.clone());
+            for (field, weight) in SEARCH_FIELDS {
+                let Some(chunks) = entry.get(field) else { continue };
+                for word in tokenize(&chunks.format_verbatim()) {
+                    let id = *ids.entry(word).or_insert_with_key(|word| {
+                        let id = index.words.len();
+                        let trigrams = trigrams(word);
+                        for &trigram in &trigrams {
+                            index.trigrams.entry(trigram).or_default().push(id);
+                        }
+                        index.trigram_counts.push(trigrams.len());
+                        index.words.push(word.clone());
+                        index.postings.push(vec![]);
+                        id
+                    });
+
+                    let postings = &mut index.postings[id];
+                    match postings.last_mut() {
+                        Some((last, best)) if *last == doc => *best = best.max(weight),
+                        _ => postings.push((doc, weight)),
+                    }
+                }
+            }
+        }
+
+        index.sorted = (0..index.words.len()).collect();
+        index.sorted.sort_by(|&a, &b| index.words[a].cmp(&index.words[b]));
+        index
+    }
+
+    /// Set the minimum trigram similarity between 0 and 1 for a word to
+    /// match a query word. Defaults to 0.5.
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    /// The number of indexed entries.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no entries are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Find the entries matching all words of the query, best matches first.
+    ///
+    /// Returns the cite keys with a score that grows with the similarity of
+    /// the matched words and with the weight of the fields they appear in,
+    /// where title matches count more than abstract matches. Entries with
+    /// the same score stay in bibliography order.
+    ///
+    /// ```
+    /// # use biblatex::Bibliography;
+    /// let src = "@article{a, title = {Typesetting Mathematics}}
+    ///            @article{b, title = {Literate Programming}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    /// let index = bibliography.search_index();
+    ///
+    /// let keys = |query| index.search(query).into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+    /// assert_eq!(keys("mathematcs"), ["a"]);
+    /// assert_eq!(keys("literate prog"), ["b"]);
+    /// ```
+    pub fn search(&self, query: &str) -> Vec<(&str, f32)> {
+        let words = tokenize(query);
+        let Some(last) = words.len().checked_sub(1) else { return vec![] };
+        let complete = query.ends_with(|c: char| !c.is_alphanumeric());
+
+        let mut scores: Vec<Option<f32>> = vec![Some(0.0); self.keys.len()];
+        for (i, word) in words.iter().enumerate() {
+            let mut best: HashMap<usize, f32> = HashMap::new();
+            for (id, similarity) in self.matches(word, i == last && !complete) {
+                for &(doc, weight) in &self.postings[id] {
+                    let score = best.entry(doc).or_insert(0.0);
+                    *score = score.max(similarity * weight);
+                }
+            }
+
+            for (doc, score) in scores.iter_mut().enumerate() {
+                *score = match (*score, best.get(&doc)) {
+                    (Some(total), Some(add)) => Some(total + add),
+                    _ => None,
+                };
+            }
+        }
+
+        let mut hits: Vec<(&str, f32)> = scores
+            .into_iter()
+            .enumerate()
+            .filter_map(|(doc, score)| Some((self.keys[doc].as_str(), score?)))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hits
+    }
+
+    /// The indexed words similar to a query word with their similarity.
+    fn matches(&self, word: &str, prefix: bool) -> HashMap<usize, f32> {
+        let query = trigrams(word);
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for trigram in &query {
+            for &id in self.trigrams.get(trigram).into_iter().flatten() {
+                *shared.entry(id).or_insert(0) += 1;
+            }
+        }
+
+        let mut matches: HashMap<usize, f32> = shared
+            .into_iter()
+            .map(|(id, shared)| {
+                let total = query.len() + self.trigram_counts[id];
+                (id, 2.0 * shared as f32 / total as f32)
+            })
+            .filter(|&(_, similarity)| similarity >= self.threshold)
+            .collect();
+
+        if prefix {
+            let start = self.sorted.partition_point(|&id| self.words[id].as_str() < word);
+            for &id in &self.sorted[start..] {
+                if !self.words[id].starts_with(word) {
+                    break;
+                }
+                matches.insert(id, 1.0);
+            }
+        }
+
+        matches
+    }
+}
+
+impl Bibliography {
+    /// Build a fuzzy [`SearchIndex`] over the titles and abstracts of all
+    /// entries.
+    pub fn search_index(&self) -> SearchIndex {
+        SearchIndex::new(self)
+    }
+}
+
+/// Split text into lowercase ASCII words without diacritics.
+fn tokenize(text: &str) -> Vec<String> {
+    transliterate(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The distinct trigrams of a word padded with spaces.
+fn trigrams(word: &str) -> Vec<[char; 3]> {
+    let chars: Vec<char> = std::iter::once(' ')
+        .chain(word.chars())
+        .chain(std::iter::once(' '))
+        .collect();
+
+    let mut trigrams: Vec<[char; 3]> =
+        chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+/// Serializes the trigram map as a list of pairs, since formats like JSON
+/// only support strings as map keys.
+#[cfg(feature = "serde")]
+mod trigram_map {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<[char; 3], Vec<usize>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<[char; 3], Vec<usize>>, D::Error> {
+        let pairs = Vec::<([char; 3], Vec<usize>)>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_index() {
+        let src = r#"@article{a, title = {Über die Schrödinger-Gleichung},
+              abstract = {We study wave functions.}}
+            @article{b, title = {Wave Mechanics}, journaltitle = {Physical Review}}
+            @book{c, title = {The Art of Computer Programming}}
+            @article{d, title = {Functional Programming}, abstract = {Wave-like lists}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let mut index = bibliography.search_index();
+        assert_eq!(index.len(), 4);
+
+        let keys = |index: &SearchIndex, query: &str| -> Vec<String> {
+            index.search(query).into_iter().map(|(k, _)| k.to_string()).collect()
+        };
+
+        assert_eq!(keys(&index, "schrodinger"), ["a"]);
+        assert_eq!(keys(&index, "wave"), ["b", "a", "d"]);
+        assert_eq!(keys(&index, "progamming"), ["c", "d"]);
+        assert_eq!(keys(&index, "functional prog"), ["d"]);
+        assert_eq!(keys(&index, "com"), ["c"]);
+        assert!(keys(&index, "com ").is_empty());
+        assert!(keys(&index, "physics review quantum").is_empty());
+        assert!(keys(&index, "").is_empty());
+
+        index.set_threshold(1.0);
+        assert!(keys(&index, "progamming").is_empty());
+    }
+}
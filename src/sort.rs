@@ -0,0 +1,509 @@
+//! Sorting of entries according to the BibLaTeX sorting schemes.
+
+use std::cmp::Ordering;
+
+use strum::{Display, EnumString};
+
+use crate::label::alpha_label;
+use crate::names::NAME_FIELDS;
+use crate::{Bibliography, ChunksExt, Entry, PermissiveType, Person};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A predefined sorting scheme of BibLaTeX, selected with its `sorting`
+/// option.
+///
+/// All schemes start with the `presort` field (defaulting to `mm`). If an
+/// entry has a `sortkey`, it replaces all following criteria. Names are taken
+/// from `sortname`, falling back on `author`, `editor`, `translator`, and
+/// finally the title. Titles are taken from `sorttitle`, falling back on
+/// `title`. Years are taken from `sortyear`, falling back on `date` and
+/// `year`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[strum(serialize_all = "lowercase")]
+pub enum SortingScheme {
+    /// Sort by name, title, year, and volume.
+    Nty,
+    /// Sort by name, year, title, and volume.
+    Nyt,
+    /// Sort by name, year, volume, and title.
+    Nyvt,
+    /// Sort by alphabetic label, name, year, title, and volume.
+    Anyt,
+    /// Sort by alphabetic label, name, year, volume, and title.
+    Anyvt,
+    /// Sort by year, name, and title.
+    Ynt,
+    /// Sort by year (descending), name, and title.
+    Ydnt,
+    /// Do not sort, i.e., keep the bibliography order.
+    None,
+}
+
+/// A sorting specification made of criteria in order of precedence, like
+/// `\DeclareSortingTemplate` in BibLaTeX.
+///
+/// Templates for the predefined schemes can be obtained through
+/// [`SortingScheme::template`].
+///
+/// ```
+/// # use biblatex::{Bibliography, SortElement, SortingTemplate};
+/// let src = "@report{a, author = {Knuth, Donald}, number = {10}}
+///            @report{b, author = {Knuth, Donald}, number = {9}}
+///            @report{c, author = {Lamport, Leslie}, number = {2}}";
+/// let bibliography = Bibliography::parse(src).unwrap();
+///
+/// let mut template = SortingTemplate::new();
+/// template.add_element(SortElement::field("number").pad(3, '0').descending());
+/// template.add_element(SortElement::fields(&["sortname", "author"]));
+///
+/// let sorted = bibliography.sorted(&template);
+/// assert_eq!(sorted[0].key, "a");
+/// assert_eq!(sorted[2].key, "c");
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SortingTemplate {
+    elements: Vec<SortElement>,
+}
+
+impl SortingTemplate {
+    /// Create a template without criteria, which keeps the bibliography
+    /// order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a criterion with lower precedence than the existing ones, like
+    /// `\sort` in BibLaTeX.
+    pub fn add_element(&mut self, element: SortElement) {
+        self.elements.push(element);
+    }
+
+    /// The criteria in order of precedence.
+    pub fn elements(&self) -> &[SortElement] {
+        &self.elements
+    }
+}
+
+impl From<SortingScheme> for SortingTemplate {
+    fn from(scheme: SortingScheme) -> Self {
+        scheme.template()
+    }
+}
+
+impl From<&SortingTemplate> for SortingTemplate {
+    fn from(template: &SortingTemplate) -> Self {
+        template.clone()
+    }
+}
+
+/// One criterion of a [`SortingTemplate`]: The value of the first present
+/// field out of a list or a literal.
+///
+/// Besides regular fields, the criterion can use `labelalpha` for the
+/// alphabetic label. The `year` field falls back on the year of the `date`,
+/// the `presort` field defaults to `mm`, and name lists are compared by
+/// family name, given name, prefix, and suffix.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SortElement {
+    fields: Vec<String>,
+    literal: Option<String>,
+    descending: bool,
+    case_sensitive: bool,
+    padding: Option<(usize, char)>,
+    is_final: bool,
+}
+
+impl SortElement {
+    /// Create an ascending criterion for a field, like `\field` in BibLaTeX.
+    pub fn field(field: &str) -> Self {
+        Self::fields(&[field])
+    }
+
+    /// Create an ascending criterion for the first present field out of a
+    /// list.
+    pub fn fields(fields: &[&str]) -> Self {
+        Self {
+            fields: fields.iter().map(|field| field.to_lowercase()).collect(),
+            literal: None,
+            descending: false,
+            case_sensitive: true,
+            padding: None,
+            is_final: false,
+        }
+    }
+
+    /// Create a criterion with the same value for all entries, like
+    /// `\literal` in BibLaTeX.
+    pub fn literal(text: &str) -> Self {
+        Self {
+            literal: Some(text.to_string()),
+            ..Self::fields(&[])
+        }
+    }
+
+    /// Compare the values in descending order, like `direction=descending`.
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+
+    /// Ignore the case of text values, like `sortcase=false`. By default,
+    /// case only breaks ties between otherwise equal texts.
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_sensitive = false;
+        self
+    }
+
+    /// Pad text values on the left with a character up to a width, like
+    /// `padside=left`, `padwidth`, and `padchar`. Numbers are always
+    /// compared numerically.
+    pub fn pad(mut self, width: usize, c: char) -> Self {
+        self.padding = Some((width, c));
+        self
+    }
+
+    /// Replace the following criteria if the value is present and skip this
+    /// criterion otherwise, like `\sort[final]`.
+    pub fn final_(mut self) -> Self {
+        self.is_final = true;
+        self
+    }
+}
+
+impl SortingScheme {
+    /// The template with the criteria of the scheme.
+    pub fn template(self) -> SortingTemplate {
+        let name = || {
+            SortElement::fields(&[
+                "sortname",
+                "author",
+                "editor",
+                "translator",
+                "sorttitle",
+                "title",
+            ])
+        };
+        let title = || SortElement::fields(&["sorttitle", "title"]);
+        let year = || SortElement::fields(&["sortyear", "year"]);
+        let volume = || SortElement::field("volume");
+        let label = || SortElement::field("labelalpha");
+
+        let elements = match self {
+            Self::Nty => vec![name(), title(), year(), volume()],
+            Self::Nyt => vec![name(), year(), title(), volume()],
+            Self::Nyvt => vec![name(), year(), volume(), title()],
+            Self::Anyt => vec![label(), name(), year(), title(), volume()],
+            Self::Anyvt => vec![label(), name(), year(), volume(), title()],
+            Self::Ynt => vec![year(), name(), title()],
+            Self::Ydnt => vec![year().descending(), name(), title()],
+            Self::None => return SortingTemplate::new(),
+        };
+
+        let mut template = SortingTemplate::new();
+        template.add_element(SortElement::field("presort"));
+        template.add_element(SortElement::field("sortkey").final_());
+        for element in elements {
+            template.add_element(element);
+        }
+        template
+    }
+}
+
+/// A value an entry is sorted by.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum SortValue {
+    /// A number like a year or a volume.
+    Number(i64),
+    /// A normalized text.
+    Text(String),
+}
+
+/// The values an entry is sorted by, each with whether it is compared in
+/// descending order.
+type SortKey = Vec<(Option<SortValue>, bool)>;
+
+impl Bibliography {
+    /// The entries ordered by a BibLaTeX sorting scheme or a custom
+    /// [`SortingTemplate`].
+    ///
+    /// Missing criteria sort after present ones. Entries that compare equal
+    /// keep their bibliography order. Text is compared case-insensitively
+    /// by code point with punctuation removed.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, SortingScheme};
+    /// let src = "@book{a, author = {Lamport, Leslie}, year = {1986}}
+    ///            @book{b, author = {Knuth, Donald}, year = {1984}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    /// let sorted = bibliography.sorted(SortingScheme::Nyt);
+    /// assert_eq!(sorted[0].key, "b");
+    /// ```
+    pub fn sorted(&self, template: impl Into<SortingTemplate>) -> Vec<&Entry> {
+        sort_by_template(self, &template.into(), &compare_text)
+    }
+
+    /// The cite keys of the entries ordered by a sorting template.
+    pub(crate) fn sorted_keys(&self, template: &SortingTemplate) -> Vec<String> {
+        self.sorted(template)
+            .into_iter()
+            .map(|entry| entry.key.clone())
+            .collect()
+    }
+
+    /// The entries ordered by a BibLaTeX sorting scheme or a custom
+    /// [`SortingTemplate`], comparing text with
+    /// the Unicode collation rules of a locale like `de` or `sv-SE`.
+    ///
+    /// Unlike [`Bibliography::sorted`], this sorts accented letters like `ä`,
+    /// `å`, and `ø` where the locale expects them. Returns `None` if the locale
+    /// is malformed or not supported.
+    #[cfg(feature = "icu")]
+    pub fn sorted_localized(
+        &self,
+        template: impl Into<SortingTemplate>,
+        locale: &str,
+    ) -> Option<Vec<&Entry>> {
+        use icu_collator::{Collator, CollatorOptions};
+
+        let locale: icu_locid::Locale = locale.parse().ok()?;
+        let collator = Collator::try_new(&locale.into(), CollatorOptions::new()).ok()?;
+        Some(sort_by_template(self, &template.into(), &|a, b| collator.compare(a, b)))
+    }
+}
+
+/// Sort the entries of a bibliography by the criteria of a template,
+/// comparing text with the given function.
+pub(crate) fn sort_by_template<'a>(
+    bibliography: &'a Bibliography,
+    template: &SortingTemplate,
+    text: &dyn Fn(&str, &str) -> Ordering,
+) -> Vec<&'a Entry> {
+    let mut keyed: Vec<(SortKey, &Entry)> = bibliography
+        .iter()
+        .map(|entry| (sort_values(entry, template), entry))
+        .collect();
+
+    keyed.sort_by(|(a, _), (b, _)| compare(a, b, text));
+    keyed.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Compute the values an entry is sorted by.
+fn sort_values(entry: &Entry, template: &SortingTemplate) -> SortKey {
+    let mut values = vec![];
+    for element in &template.elements {
+        let value = match &element.literal {
+            Some(literal) => Some(SortValue::Text(literal.clone())),
+            None => element.fields.iter().find_map(|field| sort_value(entry, field)),
+        };
+
+        let value = value.map(|value| match value {
+            SortValue::Text(mut text) => {
+                if !element.case_sensitive {
+                    text = text.to_lowercase();
+                }
+                if let Some((width, c)) = element.padding {
+                    let missing = width.saturating_sub(text.chars().count());
+                    text.insert_str(0, &c.to_string().repeat(missing));
+                }
+                SortValue::Text(text)
+            }
+            number => number,
+        });
+
+        match value {
+            Some(value) if element.is_final => {
+                values.push((Some(value), element.descending));
+                break;
+            }
+            None if element.is_final => {}
+            value => values.push((value, element.descending)),
+        }
+    }
+    values
+}
+
+/// Compare two lists of sort values element by element. Values are compared
+/// in descending order only if both of them ask for it.
+fn compare(
+    a: &[(Option<SortValue>, bool)],
+    b: &[(Option<SortValue>, bool)],
+    text: &dyn Fn(&str, &str) -> Ordering,
+) -> Ordering {
+    let values = |a: &SortValue, b: &SortValue| match (a, b) {
+        (SortValue::Number(a), SortValue::Number(b)) => a.cmp(b),
+        (SortValue::Text(a), SortValue::Text(b)) => text(a, b),
+        (SortValue::Number(_), SortValue::Text(_)) => Ordering::Less,
+        (SortValue::Text(_), SortValue::Number(_)) => Ordering::Greater,
+    };
+
+    for ((a, a_descending), (b, b_descending)) in a.iter().zip(b) {
+        let ordering = match (a, b) {
+            (Some(a), Some(b)) if *a_descending && *b_descending => values(b, a),
+            (Some(a), Some(b)) => values(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    a.len().cmp(&b.len())
+}
+
+/// The value of a field for sorting, if it is present.
+fn sort_value(entry: &Entry, field: &str) -> Option<SortValue> {
+    match field {
+        "presort" => {
+            Some(SortValue::Text(entry.presort().unwrap_or_else(|_| "mm".into())))
+        }
+        "labelalpha" => {
+            let label = normalize(&alpha_label(entry));
+            (!label.is_empty()).then_some(SortValue::Text(label))
+        }
+        "year" => entry.sorting_year().ok().map(SortValue::Number),
+        "sortyear" => entry.sort_year().ok().map(SortValue::Number),
+        "volume" => match entry.volume().ok()? {
+            PermissiveType::Typed(volume) => Some(SortValue::Number(volume)),
+            PermissiveType::Chunks(chunks) => {
+                Some(SortValue::Text(normalize(&chunks.format_verbatim())))
+            }
+        },
+        _ if NAME_FIELDS.contains(&field) => {
+            let persons = entry.get_as::<Vec<Person>>(field).ok()?;
+            let names: Vec<String> = persons.iter().map(name_key).collect();
+            (!names.is_empty()).then(|| SortValue::Text(names.join("  ")))
+        }
+        _ => {
+            let text = entry.get(field)?.format_verbatim();
+            Some(SortValue::Text(normalize(&text)))
+        }
+    }
+}
+
+/// The sort string of a name: Family name, given name, prefix, and suffix.
+fn name_key(person: &Person) -> String {
+    let parts = [&person.name, &person.given_name, &person.prefix, &person.suffix];
+    normalize(&parts.map(|part| part.as_str()).join(" "))
+}
+
+/// Compare two texts case-insensitively, with case only breaking ties.
+fn compare_text(a: &str, b: &str) -> Ordering {
+    a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b))
+}
+
+/// Remove punctuation and superfluous whitespace from a text.
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(entries: Vec<&Entry>) -> Vec<&str> {
+        entries.into_iter().map(|entry| entry.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sorting_schemes() {
+        let src = r#"@book{knuth68, author = {Knuth, Donald}, title = {Volume One},
+              year = {1968}, volume = {1}}
+            @book{knuth69, author = {Knuth, Donald}, title = {Another Volume},
+              year = {1969}, volume = {2}}
+            @book{lamport, author = {Lamport, Leslie}, title = {LaTeX}, year = {1986}}
+            @book{anon, title = {Beowulf}}
+            @book{gaulle, author = {de Gaulle, Charles}, sortname = {Gaulle, Charles de},
+              title = {Memoirs}, year = {1954}}
+            @book{first, author = {Zeta, Zed}, title = {Z}, presort = {aa}}
+            @book{keyed, author = {Able, Abe}, title = {A}, sortkey = {zzz}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        assert_eq!(
+            keys(bibliography.sorted(SortingScheme::Nty)),
+            ["first", "anon", "gaulle", "knuth69", "knuth68", "lamport", "keyed"]
+        );
+        assert_eq!(
+            keys(bibliography.sorted(SortingScheme::Nyt)),
+            ["first", "anon", "gaulle", "knuth68", "knuth69", "lamport", "keyed"]
+        );
+        assert_eq!(
+            keys(bibliography.sorted(SortingScheme::Ydnt)),
+            ["first", "lamport", "knuth69", "knuth68", "gaulle", "keyed", "anon"]
+        );
+        assert_eq!(
+            keys(bibliography.sorted(SortingScheme::Anyt)),
+            ["first", "gaulle", "knuth68", "knuth69", "lamport", "keyed", "anon"]
+        );
+        assert_eq!(
+            keys(bibliography.sorted(SortingScheme::None)),
+            ["knuth68", "knuth69", "lamport", "anon", "gaulle", "first", "keyed"]
+        );
+        assert_eq!("ydnt".parse(), Ok(SortingScheme::Ydnt));
+    }
+
+    #[test]
+    fn test_sorting_template() {
+        let src = r#"@misc{a, title = {b}, note = {x}}
+            @misc{b, title = {B}, note = {x}, usera = {7}}
+            @misc{c, title = {a}, note = {y}}
+            @misc{d, title = {A}, note = {x}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let mut template = SortingTemplate::new();
+        template.add_element(SortElement::literal("same"));
+        template.add_element(SortElement::field("usera").pad(2, '0').final_());
+        template.add_element(SortElement::field("title"));
+        assert_eq!(keys(bibliography.sorted(&template)), ["b", "d", "c", "a"]);
+
+        let mut template = SortingTemplate::new();
+        template.add_element(SortElement::field("note").descending());
+        template.add_element(SortElement::field("title").case_insensitive());
+        assert_eq!(template.elements().len(), 2);
+        assert_eq!(keys(bibliography.sorted(template)), ["c", "d", "a", "b"]);
+
+        assert_eq!(SortingScheme::None.template(), SortingTemplate::new());
+
+        let src = r#"@misc{x, editora = {Anna Zorn}}
+            @misc{y, editora = {Zoe Berg}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let mut template = SortingTemplate::new();
+        template.add_element(SortElement::field("editora"));
+        assert_eq!(keys(bibliography.sorted(template)), ["y", "x"]);
+    }
+
+    #[test]
+    #[cfg(feature = "icu")]
+    fn test_sorted_localized() {
+        let src = r#"@book{zander, author = {Zander, Zoe}}
+            @book{aberg, author = {Åberg, Anna}}
+            @book{arger, author = {Ärger, Paul}}
+            @book{adams, author = {adams, Ada}}
+            @book{ayer, author = {Ayer, Alfred}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        assert_eq!(
+            keys(bibliography.sorted(SortingScheme::Nty)),
+            ["adams", "ayer", "zander", "arger", "aberg"]
+        );
+        assert_eq!(
+            keys(bibliography.sorted_localized(SortingScheme::Nty, "de").unwrap()),
+            ["aberg", "adams", "arger", "ayer", "zander"]
+        );
+        assert_eq!(
+            keys(bibliography.sorted_localized(SortingScheme::Nty, "sv").unwrap()),
+            ["adams", "ayer", "zander", "aberg", "arger"]
+        );
+        assert!(bibliography.sorted_localized(SortingScheme::Nty, "??").is_none());
+    }
+}
@@ -0,0 +1,159 @@
+//! Aggregate reports about the contents of a bibliography.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::mechanics::canonical_field_name;
+use crate::{Bibliography, ChunksExt, EntryType, Person};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Counts that summarize a bibliography. Can be obtained through
+/// [`Bibliography::statistics`].
+///
+/// Lists of counts are ordered by descending count, ties are broken by first
+/// appearance for entry types and by value otherwise.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Statistics {
+    /// The number of entries.
+    pub entries: usize,
+    /// The number of entries per entry type.
+    pub entry_types: Vec<(EntryType, usize)>,
+    /// The number of entries per year, as determined by
+    /// [`Entry::sorting_year`](crate::Entry::sorting_year).
+    pub years: BTreeMap<i64, usize>,
+    /// The number of entries without a valid year.
+    pub undated: usize,
+    /// The number of entries per venue, which is the `journaltitle` or, if
+    /// there is none, the `booktitle`.
+    pub venues: Vec<(String, usize)>,
+    /// The number of entries per author.
+    pub authors: Vec<(Person, usize)>,
+    /// The number of entries that have a field, by BibLaTeX field name.
+    pub fields: BTreeMap<String, usize>,
+}
+
+impl Statistics {
+    /// The percentage of entries that lack a field, from 0 to 100. Legacy
+    /// field names like `journal` are mapped to their BibLaTeX counterparts.
+    pub fn missing_percentage(&self, field: &str) -> f64 {
+        if self.entries == 0 {
+            return 0.0;
+        }
+
+        let present = self.fields.get(canonical_field_name(field)).copied().unwrap_or(0);
+        100.0 * (self.entries - present) as f64 / self.entries as f64
+    }
+
+    /// The `n` most frequent authors.
+    pub fn top_authors(&self, n: usize) -> &[(Person, usize)] {
+        &self.authors[..n.min(self.authors.len())]
+    }
+}
+
+impl Bibliography {
+    /// Compute aggregate counts over all entries, e.g. to sanity-check a
+    /// large imported database.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, EntryType};
+    /// let src = "@book{a, author = {Doe, Jane}, year = {2001}}
+    ///            @article{b, author = {Doe, Jane}, journal = {J}}";
+    /// let stats = Bibliography::parse(src).unwrap().statistics();
+    /// assert_eq!(stats.entry_types[0], (EntryType::Book, 1));
+    /// assert_eq!(stats.authors[0].1, 2);
+    /// assert_eq!(stats.missing_percentage("date"), 50.0);
+    /// ```
+    pub fn statistics(&self) -> Statistics {
+        let mut stats = Statistics { entries: self.entries.len(), ..Default::default() };
+        let mut venues = BTreeMap::<String, usize>::new();
+        let mut authors = BTreeMap::<Person, usize>::new();
+
+        for entry in &self.entries {
+            match stats.entry_types.iter_mut().find(|(t, _)| *t == entry.entry_type) {
+                Some((_, count)) => *count += 1,
+                None => stats.entry_types.push((entry.entry_type.clone(), 1)),
+            }
+
+            match entry.sorting_year() {
+                Ok(year) => *stats.years.entry(year).or_default() += 1,
+                Err(_) => stats.undated += 1,
+            }
+
+            let venue = entry.journal_title().or_else(|_| entry.book_title());
+            if let Ok(venue) = venue {
+                *venues.entry(venue.format_verbatim()).or_default() += 1;
+            }
+
+            let mut persons = entry.author().unwrap_or_default();
+            persons.sort();
+            persons.dedup();
+            for person in persons {
+                *authors.entry(person).or_default() += 1;
+            }
+
+            let mut fields: Vec<&str> =
+                entry.fields.keys().map(|key| canonical_field_name(key)).collect();
+            fields.sort_unstable();
+            fields.dedup();
+            if fields.contains(&"year") && !fields.contains(&"date") {
+                fields.push("date");
+            }
+            for field in fields {
+                *stats.fields.entry(field.to_string()).or_default() += 1;
+            }
+        }
+
+        stats.entry_types.sort_by_key(|(_, count)| Reverse(*count));
+        stats.venues = by_count(venues);
+        stats.authors = by_count(authors);
+        stats
+    }
+}
+
+/// Order counts descendingly, keeping the map order for ties.
+fn by_count<T>(counts: BTreeMap<T, usize>) -> Vec<(T, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics() {
+        let src = r#"@article{a, author = {Doe, Jane and Roe, Richard},
+              journal = {Nature}, date = {2001-02}}
+            @article{b, author = {Doe, Jane}, journaltitle = {Nature}, year = {2001}}
+            @inproceedings{c, author = {Roe, R.}, booktitle = {Proc.}, year = {1999}}
+            @article{d, author = {Jane Doe}, journal = {Science}}"#;
+        let stats = Bibliography::parse(src).unwrap().statistics();
+
+        assert_eq!(stats.entries, 4);
+        assert_eq!(
+            stats.entry_types,
+            [(EntryType::Article, 3), (EntryType::InProceedings, 1)]
+        );
+        assert_eq!(stats.years, BTreeMap::from([(1999, 1), (2001, 2)]));
+        assert_eq!(stats.undated, 1);
+        assert_eq!(
+            stats.venues,
+            [("Nature".into(), 2), ("Proc.".into(), 1), ("Science".into(), 1)]
+        );
+
+        let top = stats.top_authors(2);
+        assert_eq!(top.len(), 2);
+        assert_eq!((top[0].0.name.as_str(), top[0].1), ("Doe", 3));
+        assert_eq!(top[1].1, 1);
+        assert_eq!(stats.top_authors(10).len(), 3);
+
+        assert_eq!(stats.missing_percentage("journal"), 25.0);
+        assert_eq!(stats.missing_percentage("date"), 25.0);
+        assert_eq!(stats.missing_percentage("doi"), 100.0);
+        assert_eq!(Statistics::default().missing_percentage("doi"), 0.0);
+    }
+}
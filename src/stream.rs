@@ -0,0 +1,365 @@
+//! Parsing entries incrementally from an asynchronous reader.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    decode_fields, Entry, EntryType, Pair, ParseError, RawBibliography, RawEntry, Spanned,
+};
+
+/// How many bytes to read at once.
+const READ_SIZE: usize = 16 * 1024;
+
+/// A stream of the entries of a bibliography that is read incrementally from
+/// an [`AsyncRead`] source, e.g. a network connection or a file.
+///
+/// Only the text of entries that are not yet complete is kept in memory.
+/// Abbreviations are resolved with the `@string` definitions read so far, so
+/// they must be defined before they are used. `crossref` and `xdata` links
+/// are not followed. Spans refer to
+/// byte offsets in the complete source.
+///
+/// ```
+/// # use biblatex::EntryStream;
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let src = "@string{acm = {ACM}} @book{a, publisher = acm} @misc{b,}";
+/// let mut stream = EntryStream::new(src.as_bytes());
+/// while let Some(entry) = stream.next_entry().await.unwrap() {
+///     println!("{}", entry.key);
+/// }
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct EntryStream<R> {
+    /// The source.
+    reader: R,
+    /// Bytes that were read but are not yet valid UTF-8.
+    bytes: Vec<u8>,
+    /// The source text that was not yet parsed.
+    buf: String,
+    /// The offset of `buf` in the complete source.
+    offset: usize,
+    /// The length of the incomplete item at the start of `buf` when it was
+    /// last parsed.
+    pending: usize,
+    /// The abbreviations read so far.
+    abbreviations: Vec<Pair<'static>>,
+    /// The preamble read so far.
+    preamble: String,
+    /// Entries that were parsed but not yet returned.
+    queue: VecDeque<Entry>,
+    /// Whether the reader is exhausted.
+    eof: bool,
+    /// An error to return after the queued entries.
+    error: Option<StreamError>,
+    /// Whether an error was returned.
+    failed: bool,
+}
+
+/// An error that occurred while reading an [`EntryStream`].
+#[derive(Debug)]
+pub enum StreamError {
+    /// The source could not be read or is not valid UTF-8.
+    Io(io::Error),
+    /// The source could not be parsed.
+    Parse(ParseError),
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Parse(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ParseError> for StreamError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl<R: AsyncRead + Unpin> EntryStream<R> {
+    /// Create a stream that reads from the given source.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            bytes: vec![],
+            buf: String::new(),
+            offset: 0,
+            pending: 0,
+            abbreviations: vec![],
+            preamble: String::new(),
+            queue: VecDeque::new(),
+            eof: false,
+            error: None,
+            failed: false,
+        }
+    }
+
+    /// Read the next entry. Returns `None` at the end of the source. After
+    /// an error, no more entries are returned.
+    pub async fn next_entry(&mut self) -> Result<Option<Entry>, StreamError> {
+        loop {
+            if let Some(entry) = self.queue.pop_front() {
+                return Ok(Some(entry));
+            } else if let Some(err) = self.error.take() {
+                self.failed = true;
+                return Err(err);
+            } else if self.failed || (self.eof && self.buf.is_empty()) {
+                return Ok(None);
+            }
+
+            // Parsing an incomplete item again after each read would take
+            // quadratic time for large items, so wait until its text doubled.
+            match self.fill().await {
+                Ok(()) if !self.eof && self.buf.len() < 2 * self.pending => {}
+                Ok(()) => self.parse_complete(),
+                Err(err) => self.error = Some(err),
+            }
+        }
+    }
+
+    /// The abbreviations defined in the source read so far.
+    pub fn abbreviations(&self) -> &[Pair<'static>] {
+        &self.abbreviations
+    }
+
+    /// The preamble of the source read so far.
+    pub fn preamble(&self) -> &str {
+        &self.preamble
+    }
+
+    /// Read more text into the buffer.
+    async fn fill(&mut self) -> Result<(), StreamError> {
+        self.bytes.reserve(READ_SIZE);
+        if self.reader.read_buf(&mut self.bytes).await? == 0 {
+            self.eof = true;
+        }
+
+        // A character may be split between two reads.
+        let valid = match std::str::from_utf8(&self.bytes) {
+            Ok(text) => text.len(),
+            Err(err) if err.error_len().is_none() && !self.eof => err.valid_up_to(),
+            Err(err) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err).into())
+            }
+        };
+
+        let text = std::str::from_utf8(&self.bytes[..valid]).expect("valid UTF-8");
+        self.buf.push_str(text);
+        self.bytes.drain(..valid);
+        Ok(())
+    }
+
+    /// Parse the complete items at the start of the buffer, queue their
+    /// entries, and remove them from the buffer.
+    fn parse_complete(&mut self) {
+        let mut items = RawBibliography::iter_entries(&self.buf);
+        let mut entries = vec![];
+
+        // The cursor, the number of abbreviations, and the length of the
+        // preamble after the last complete item.
+        let mut parsed = (0, 0, 0);
+        loop {
+            match items.next_item() {
+                // Entries may only use the abbreviations defined before them,
+                // no matter how the source was split into reads.
+                Some(Ok(entry)) => entries.extend(entry.map(|entry| {
+                    (entry, self.abbreviations.len() + items.abbreviations().len())
+                })),
+                Some(Err(err)) => {
+                    // The rest of the item may not have been read yet.
+                    if self.eof || err.span.start < self.buf.len() {
+                        self.error = Some(shift_error(err, self.offset).into());
+                    }
+                    break;
+                }
+                None => {
+                    parsed.0 = self.buf.len();
+                    break;
+                }
+            }
+
+            let preamble = items.preamble().len();
+            parsed = (items.cursor(), items.abbreviations().len(), preamble);
+        }
+
+        let (cursor, abbreviations, preamble) = parsed;
+        let offset = self.offset;
+        self.abbreviations
+            .extend(items.abbreviations()[..abbreviations].iter().map(|pair| {
+                let mut pair = pair.clone().into_owned();
+                shift_pair(&mut pair, offset);
+                pair
+            }));
+
+        let preamble = &items.preamble()[..preamble];
+        if !preamble.is_empty() {
+            if !self.preamble.is_empty() {
+                self.preamble.push_str(" # ");
+            }
+            self.preamble.push_str(preamble);
+        }
+
+        for (entry, defined) in entries {
+            let mut entry = entry.map(RawEntry::into_owned);
+            shift_entry(&mut entry, offset);
+            match decode_fields(&entry.v, &self.abbreviations[..defined]) {
+                Ok(fields) => self.queue.push_back(Entry {
+                    key: entry.v.key.v.into_owned(),
+                    entry_type: EntryType::new(&entry.v.kind.v),
+                    fields,
+                }),
+                Err(err) => {
+                    self.error = Some(err.into());
+                    break;
+                }
+            }
+        }
+
+        self.buf.drain(..cursor);
+        self.offset += cursor;
+        self.pending = self.buf.len();
+    }
+}
+
+/// Make the span of an error relative to the complete source.
+fn shift_error(mut err: ParseError, offset: usize) -> ParseError {
+    shift(&mut err.span, offset);
+    err
+}
+
+/// Make the spans of a raw entry relative to the complete source.
+fn shift_entry(entry: &mut Spanned<RawEntry>, offset: usize) {
+    shift(&mut entry.span, offset);
+    shift(&mut entry.v.key.span, offset);
+    shift(&mut entry.v.kind.span, offset);
+    for pair in &mut entry.v.fields {
+        shift_pair(pair, offset);
+    }
+}
+
+/// Make the spans of a field or abbreviation relative to the complete
+/// source.
+fn shift_pair(pair: &mut Pair, offset: usize) {
+    shift(&mut pair.key.span, offset);
+    shift(&mut pair.value.span, offset);
+    for chunk in &mut pair.value.v {
+        shift(&mut chunk.span, offset);
+    }
+}
+
+fn shift(span: &mut std::ops::Range<usize>, offset: usize) {
+    *span = span.start + offset..span.end + offset;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use tokio::io::ReadBuf;
+
+    use super::*;
+    use crate::{Bibliography, ParseErrorKind};
+
+    /// A reader that returns at most the given number of bytes at once.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &mut ReadBuf,
+        ) -> Poll<io::Result<()>> {
+            let n = self.1.min(self.0.len()).min(buf.remaining());
+            buf.put_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn collect(src: &str, size: usize) -> Result<(Vec<Entry>, String), StreamError> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let mut stream = EntryStream::new(Trickle(src.as_bytes(), size));
+            let mut entries = vec![];
+            while let Some(entry) = stream.next_entry().await? {
+                entries.push(entry);
+            }
+            Ok((entries, stream.preamble().into()))
+        })
+    }
+
+    #[test]
+    fn test_entry_stream() {
+        let src = r#"@preamble{"a"} @string{jörg = "Jörg"}
+            @book{a, author = jörg # { Müller}, title = {Über {TeX}}}
+            % A comment
+            @preamble{"b"} @misc{b, note = {x}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        for size in [1, 2, 7, 4096] {
+            let (entries, preamble) = collect(src, size).unwrap();
+            assert_eq!(
+                entries.iter().collect::<Vec<_>>(),
+                bibliography.iter().collect::<Vec<_>>()
+            );
+            assert_eq!(preamble, "\"a\" # \"b\"");
+        }
+
+        let src = "@misc{a,} @misc{b, note = undefined} @misc{c,}";
+        let err = RawBibliography::parse(src)
+            .and_then(Bibliography::from_raw)
+            .unwrap_err();
+        let mut stream = EntryStream::new(src.as_bytes());
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            assert_eq!(stream.next_entry().await.unwrap().unwrap().key, "a");
+            assert!(matches!(stream.next_entry().await, Err(StreamError::Parse(e)) if e == err));
+            assert!(stream.next_entry().await.unwrap().is_none());
+        });
+
+        let src = "@book{a, publisher = acm} @string{acm = {ACM}} @misc{b,}";
+        for size in [1, 2, 7, 4096] {
+            let res = collect(src, size).map(|(entries, _)| entries.len());
+            assert!(
+                matches!(res, Err(StreamError::Parse(ref e)) if e.kind
+                    == ParseErrorKind::UnknownAbbreviation("acm".into())),
+                "{res:?}"
+            );
+        }
+
+        assert!(matches!(collect("@misc{a, note = {}", 3), Err(StreamError::Parse(_))));
+        assert_eq!(collect("@misc{a, note = {ÿ€}}\n", 16).unwrap().0.len(), 1);
+        let large = format!("@misc{{a, note = {{{}}}}} @misc{{b,}}", "x".repeat(1 << 20));
+        let (entries, _) = collect(&large, 64).unwrap();
+        assert_eq!(entries[0].get("note").unwrap()[0].v.get().len(), 1 << 20);
+        assert_eq!(entries[1].key, "b");
+
+        let invalid = Trickle(b"@misc{a, note = {\xff}}", 4);
+        let mut stream = EntryStream::new(invalid);
+        runtime.block_on(async {
+            assert!(matches!(stream.next_entry().await, Err(StreamError::Io(_))));
+        });
+    }
+}
@@ -0,0 +1,14 @@
+//! Scanning text eight bytes at a time.
+
+/// A word with each byte set to one.
+pub(crate) const ONES: u64 = u64::from_ne_bytes([0x01; 8]);
+
+/// A word with the high bit of each byte set.
+pub(crate) const HIGH: u64 = u64::from_ne_bytes([0x80; 8]);
+
+/// The high bits of the bytes of a word that are zero. Only the lowest one is
+/// guaranteed to be exact.
+#[inline]
+pub(crate) fn zeros(word: u64) -> u64 {
+    word.wrapping_sub(ONES) & !word & HIGH
+}
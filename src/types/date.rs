@@ -3,11 +3,16 @@ use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
 use crate::chunk::*;
+use crate::scanner::ScannerExt;
 use crate::{Span, Spanned, Type, TypeError, TypeErrorKind};
 use unscanny::Scanner;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// A date or a range of dates and their certainty and exactness.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Date {
     /// The date or the date range.
     pub value: DateValue,
@@ -19,6 +24,7 @@ pub struct Date {
 
 /// A single date or a range of dates.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DateValue {
     /// A single date.
     At(Datetime),
@@ -34,6 +40,7 @@ pub enum DateValue {
 ///
 /// Must specify a year and may specify month, day, and time.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Datetime {
     /// The year.
     ///
@@ -51,6 +58,7 @@ pub struct Datetime {
 
 /// A potentially timezone aware time.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Time {
     /// The hour (0-23).
     pub hour: u8,
@@ -112,6 +120,7 @@ impl PartialOrd for Time {
 
 /// A timezone offset.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TimeOffset {
     /// Time is UTC. Do not assume an origin timezone.
     Utc,
@@ -239,13 +248,31 @@ impl Date {
         let mut date_atom = Datetime { year, month: None, day: None, time: None };
 
         if let Some(month) = month {
+            let month_span = month.span();
             let month = month.format_verbatim();
             let mut s = Scanner::new(&month);
             s.eat_whitespace();
-            let month = s.eat_while(char::is_ascii_alphabetic);
+            let name = s.eat_while(char::is_alphabetic);
+
+            if name.is_empty() {
+                let idx = s.cursor();
+                let number = s.eat_while(char::is_ascii_digit);
+                if !number.is_empty() {
+                    let number: u8 = number.parse().unwrap_or(u8::MAX);
+                    if !(1..=12).contains(&number) {
+                        return Err(TypeError::new(
+                            month_span.start + idx..month_span.start + s.cursor(),
+                            TypeErrorKind::MonthOutOfRange,
+                        ));
+                    }
 
-            date_atom.month = get_month_for_name(month)
-                .or_else(|| get_month_for_abbr(month).map(|x| x.1));
+                    date_atom.month = Some(number - 1);
+                }
+            } else {
+                // Abbreviated month names may end with a period (`Sept.`).
+                s.eat_if('.');
+                date_atom.month = get_month_for_name(name);
+            }
 
             if let Some(day) = day {
                 let span = day.span();
@@ -446,7 +473,7 @@ fn parse_era_marker(s: &mut Scanner) -> Result<Option<bool>, TypeError> {
     s.eat_whitespace();
     let era_idx = s.cursor();
     if s.eat_if("AD") || s.eat_if("CE") {
-        if s.peek().map_or(false, |c| c.is_alphanumeric()) {
+        if s.peek().is_some_and(|c| c.is_alphanumeric()) {
             return Err(TypeError::new(
                 era_idx..s.cursor(),
                 TypeErrorKind::InvalidFormat,
@@ -458,7 +485,7 @@ fn parse_era_marker(s: &mut Scanner) -> Result<Option<bool>, TypeError> {
 
     if s.eat_if("BC") {
         s.eat_if("E");
-        if s.peek().map_or(false, |c| c.is_alphanumeric()) {
+        if s.peek().is_some_and(|c| c.is_alphanumeric()) {
             return Err(TypeError::new(
                 era_idx..s.cursor(),
                 TypeErrorKind::InvalidFormat,
@@ -531,8 +558,9 @@ impl Datetime {
     fn parse(src: &str) -> Result<Self, TypeError> {
         let mut s = Scanner::new(src);
         let pos = s.cursor();
+        let checkpoint = s.checkpoint();
         let year = parse_short_year(&mut s).or_else(|_| {
-            s.jump(pos);
+            s.restore(checkpoint);
             parse_year(&mut s)
         })?;
 
@@ -874,23 +902,46 @@ pub(crate) fn get_month_for_abbr(month: &str) -> Option<(&'static str, u8)> {
     }
 }
 
-/// Used to resolve month names to their respective values.
+/// Spelled-out month names in English, German, French, Spanish, Italian,
+/// Portuguese, and Dutch, indexed by month.
+const MONTH_NAMES: [&[&str]; 12] = [
+    &["january", "januar", "jänner", "janvier", "enero", "gennaio", "janeiro", "januari"],
+    &["february", "februar", "février", "febrero", "febbraio", "fevereiro", "februari"],
+    &["march", "märz", "mars", "marzo", "março", "maart"],
+    &["april", "avril", "abril", "aprile"],
+    &["may", "mai", "mayo", "maggio", "maio", "mei"],
+    &["june", "juni", "juin", "junio", "giugno", "junho"],
+    &["july", "juli", "juillet", "julio", "luglio", "julho"],
+    &["august", "août", "agosto", "augustus"],
+    &["september", "septembre", "septiembre", "settembre", "setembro"],
+    &["october", "oktober", "octobre", "octubre", "ottobre", "outubro"],
+    &["november", "novembre", "noviembre", "novembro"],
+    &["december", "dezember", "décembre", "diciembre", "dicembre", "dezembro"],
+];
+
+/// Used to resolve spelled-out or abbreviated month names to their respective
+/// values.
+///
+/// Abbreviations are accepted if they are at least three letters long and
+/// unambiguously identify a month (e.g., `Sept`, `Dez`, or `févr`).
 fn get_month_for_name(month: &str) -> Option<u8> {
-    match month.to_lowercase().as_str() {
-        "january" => Some(0),
-        "february" => Some(1),
-        "march" => Some(2),
-        "april" => Some(3),
-        "may" => Some(4),
-        "june" => Some(5),
-        "july" => Some(6),
-        "august" => Some(7),
-        "september" => Some(8),
-        "october" => Some(9),
-        "november" => Some(10),
-        "december" => Some(11),
-        _ => None,
+    let month = month.trim().trim_end_matches('.').to_lowercase();
+    if let Some(idx) = MONTH_NAMES.iter().position(|names| names.contains(&&*month)) {
+        return Some(idx as u8);
+    }
+
+    if month.chars().count() < 3 {
+        return None;
     }
+
+    let mut candidates = MONTH_NAMES
+        .iter()
+        .enumerate()
+        .filter(|(_, names)| names.iter().any(|name| name.starts_with(&month)))
+        .map(|(idx, _)| idx as u8);
+
+    let first = candidates.next()?;
+    candidates.next().is_none().then_some(first)
 }
 
 #[cfg(test)]
@@ -1018,6 +1069,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_month_names() {
+        let year = &[s(N("2021"), 0..4)];
+        let month_of = |src: &str| {
+            let month = &[s(N(src), 10..10 + src.len())];
+            match Date::parse_three_fields(year, Some(month), None).unwrap().value {
+                DateValue::At(Datetime { month, .. }) => month,
+                _ => panic!("expected a single date"),
+            }
+        };
+
+        assert_eq!(month_of("June"), Some(5));
+        assert_eq!(month_of("Sept."), Some(8));
+        assert_eq!(month_of("sep"), Some(8));
+        assert_eq!(month_of("März"), Some(2));
+        assert_eq!(month_of("févr."), Some(1));
+        assert_eq!(month_of("Dezember"), Some(11));
+        assert_eq!(month_of("giugno"), Some(5));
+        assert_eq!(month_of("6"), Some(5));
+        assert_eq!(month_of("Ju"), None);
+        assert_eq!(month_of("Smarch"), None);
+
+        let month = &[s(N("Okt. 3"), 10..16)];
+        let date = Date::parse_three_fields(year, Some(month), None).unwrap();
+        assert_eq!(
+            date.value,
+            DateValue::At(Datetime {
+                year: 2021,
+                month: Some(9),
+                day: Some(2),
+                time: None,
+            })
+        );
+
+        let month = &[s(N("13"), 10..12)];
+        assert_eq!(
+            Date::parse_three_fields(year, Some(month), None),
+            Err(TypeError::new(10..12, TypeErrorKind::MonthOutOfRange))
+        );
+    }
+
     #[test]
     fn test_parse_bce_year() {
         let year = &[s(N("3 AD"), 0..4)];
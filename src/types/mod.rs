@@ -16,9 +16,13 @@ use strum::{AsRefStr, Display, EnumString};
 use crate::{chunk::*, Span, Spanned};
 use unscanny::Scanner;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// An error that may occur while parsing the chunks in a field into a specific
 /// [`Type`].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TypeError {
     /// Where in the source the error occurred.
     pub span: Span,
@@ -48,6 +52,7 @@ impl fmt::Display for TypeError {
 ///
 /// Also see [`TypeError`].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[non_exhaustive]
 pub enum TypeErrorKind {
     /// The date range was open on both sides.
@@ -68,12 +73,19 @@ pub enum TypeErrorKind {
     UnknownGender,
     /// There was no integer range.
     InvalidIntegerRange,
-    /// There is no [`Pagination`] variant for this input.
+    /// The input was not a valid [`Pagination`] value.
     UnknownPagination,
     /// There is no [`EditorType`] variant for this input.
     UnknownEditorType,
     /// The year 0 CE or BCE does not exist.
     YearZeroCE,
+    /// There is no entry with this key in the bibliography.
+    UnknownEntryKey,
+    /// An entry inherits from itself through `crossref` or `xdata`.
+    CyclicReference,
+    /// A granular `xdata` reference selects an item of a field that is not a
+    /// name or literal list.
+    NotAList,
 }
 
 impl fmt::Display for TypeErrorKind {
@@ -91,6 +103,9 @@ impl fmt::Display for TypeErrorKind {
             Self::UnknownPagination => "unknown pagination",
             Self::UnknownEditorType => "unknown editor type",
             Self::YearZeroCE => "year 0 CE or BCE does not exist",
+            Self::UnknownEntryKey => "unknown entry key",
+            Self::CyclicReference => "cyclic crossref or xdata reference",
+            Self::NotAList => "field is not a list",
         })
     }
 }
@@ -130,6 +145,35 @@ impl Type for i64 {
     }
 }
 
+/// Parse an ordinal number like `2nd`, `3e`, or `4.` into an integer.
+pub(crate) fn parse_ordinal(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if digits == 0 {
+        return None;
+    }
+
+    let (number, suffix) = s.split_at(digits);
+    let valid = matches!(
+        suffix.trim_start().to_lowercase().as_str(),
+        "" | "." | "st" | "nd" | "rd" | "th" | "e" | "er" | "re" | "ème" | "º" | "ª"
+    );
+
+    valid.then(|| number.parse().ok()).flatten()
+}
+
+/// Parse an `edition` field, keeping values that are neither integers nor
+/// ordinal numbers as chunks.
+pub(crate) fn parse_edition(chunks: ChunksRef) -> PermissiveType<i64> {
+    match chunks.parse::<i64>() {
+        Ok(edition) => PermissiveType::Typed(edition),
+        Err(_) => match parse_ordinal(&chunks.format_verbatim()) {
+            Some(edition) => PermissiveType::Typed(edition),
+            None => PermissiveType::Chunks(chunks.to_vec()),
+        },
+    }
+}
+
 impl Type for String {
     fn from_chunks(chunks: ChunksRef) -> Result<Self, TypeError> {
         Ok(chunks.format_verbatim())
@@ -151,8 +195,7 @@ impl Type for Range<u32> {
     }
 
     fn to_chunks(&self) -> Chunks {
-        let str = format!("{}-{}", self.start, self.end);
-        vec![Spanned::detached(Chunk::Normal(str))]
+        vec![Spanned::detached(Chunk::Normal(format_range(self)))]
     }
 }
 
@@ -251,18 +294,25 @@ impl Type for Vec<Range<u32>> {
     fn to_chunks(&self) -> Chunks {
         let chunks = self
             .iter()
-            .map(|range| {
-                let str = format!("{}-{}", range.start, range.end);
-                Spanned::detached(Chunk::Normal(str))
-            })
+            .map(|range| Spanned::detached(Chunk::Normal(format_range(range))))
             .collect::<Chunks>();
 
         join_chunk_list(&chunks, ",")
     }
 }
 
+/// Format a range like `10-20`, collapsing single pages like `5..5` to `5`.
+fn format_range(range: &Range<u32>) -> String {
+    if range.start == range.end {
+        range.start.to_string()
+    } else {
+        format!("{}-{}", range.start, range.end)
+    }
+}
+
 /// A value that could be either a typed value or a literal string.
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PermissiveType<T: Type> {
     /// A typed version of the value.
     Typed(T),
@@ -290,9 +340,48 @@ where
     }
 }
 
+impl<T> PartialOrd for PermissiveType<T>
+where
+    T: Type + Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Typed values are ordered before literal strings, which are ordered by
+/// their verbatim text. This allows numeric sorting of fields like `volume`
+/// even if some values are not numbers (e.g., `Suppl. 2`).
+impl<T> Ord for PermissiveType<T>
+where
+    T: Type + Ord,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+
+        match (self, other) {
+            (Self::Typed(a), Self::Typed(b)) => a.cmp(b),
+            (Self::Typed(_), Self::Chunks(_)) => Ordering::Less,
+            (Self::Chunks(_), Self::Typed(_)) => Ordering::Greater,
+            (Self::Chunks(a), Self::Chunks(b)) => {
+                a.format_verbatim().cmp(&b.format_verbatim()).then_with(|| {
+                    // Break ties consistently with `Eq`.
+                    let key =
+                        |c: &Spanned<Chunk>| (c.v.clone(), c.span.start, c.span.end);
+                    a.iter().map(key).cmp(b.iter().map(key))
+                })
+            }
+        }
+    }
+}
+
 /// Defines the pagination scheme to use for formatting purposes.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Display, EnumString, AsRefStr)]
-#[strum(serialize_all = "snake_case")]
+///
+/// The value of the `pagination` and `bookpagination` fields. Styles may
+/// define their own schemes which are preserved in the `Other` variant.
+#[derive(Debug, Clone, Eq, PartialEq, Display, EnumString, AsRefStr)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
 #[allow(missing_docs)]
 pub enum Pagination {
     Page,
@@ -301,12 +390,21 @@ pub enum Pagination {
     Verse,
     Section,
     Paragraph,
+
+    #[strum(default)]
+    Other(String),
 }
 
 impl Type for Pagination {
     fn from_chunks(chunks: ChunksRef) -> Result<Self, TypeError> {
         let span = chunks.span();
-        Pagination::from_str(&chunks.format_verbatim().to_lowercase())
+        let value = chunks.format_verbatim();
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(TypeError::new(span, TypeErrorKind::UnknownPagination));
+        }
+
+        Pagination::from_str(value)
             .map_err(|_| TypeError::new(span, TypeErrorKind::UnknownPagination))
     }
 
@@ -318,8 +416,10 @@ impl Type for Pagination {
 
 /// Which role the according editor had.
 ///
-/// The value of the `editor` through `editorc` fields.
+/// The value of the `editortype` through `editorctype` fields. Roles not
+/// defined by BibLaTeX are preserved in the `Unknown` variant.
 #[derive(Debug, Clone, Eq, PartialEq, Display, EnumString, AsRefStr)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[strum(serialize_all = "snake_case")]
 #[allow(missing_docs)]
 pub enum EditorType {
@@ -338,9 +438,16 @@ pub enum EditorType {
 }
 
 impl Type for EditorType {
+    /// Parses the role, assuming `EditorType::Editor` for empty values.
     fn from_chunks(chunks: ChunksRef) -> Result<Self, TypeError> {
         let span = chunks.span();
-        EditorType::from_str(&chunks.format_verbatim().to_lowercase())
+        let value = chunks.format_verbatim();
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(EditorType::Editor);
+        }
+
+        EditorType::from_str(&value.to_lowercase())
             .map_err(|_| TypeError::new(span, TypeErrorKind::UnknownEditorType))
     }
 
@@ -350,15 +457,113 @@ impl Type for EditorType {
     }
 }
 
+/// A more specific kind of entry within its [`EntryType`](crate::EntryType).
+///
+/// The value of the `entrysubtype` field. BibLaTeX does not define a fixed set
+/// of subtypes, but styles commonly recognize the ones listed here. Other
+/// values are preserved verbatim in the `Unknown` variant.
+#[derive(Debug, Clone, Eq, PartialEq, Display, EnumString, AsRefStr)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[strum(serialize_all = "snake_case")]
+#[allow(missing_docs)]
+pub enum EntrySubtype {
+    /// An article in a magazine rather than an academic journal.
+    Magazine,
+    /// An article in a newspaper.
+    Newspaper,
+    /// A classical or ancient work, cited by its traditional division.
+    Classical,
+
+    #[strum(default)]
+    Unknown(String),
+}
+
+impl Type for EntrySubtype {
+    /// Recognizes known subtypes case-insensitively and keeps any other value
+    /// as it is.
+    fn from_chunks(chunks: ChunksRef) -> Result<Self, TypeError> {
+        let value = chunks.format_verbatim();
+        let value = value.trim();
+
+        Ok(match EntrySubtype::from_str(&value.to_lowercase()) {
+            Ok(EntrySubtype::Unknown(_)) | Err(_) => {
+                EntrySubtype::Unknown(value.to_string())
+            }
+            Ok(subtype) => subtype,
+        })
+    }
+
+    fn to_chunks(&self) -> Chunks {
+        let res = self.to_string();
+        vec![Spanned::detached(Chunk::Normal(res))]
+    }
+}
+
+/// The kind of a thesis.
+///
+/// The value of the `type` field of `@thesis` entries. Besides the BibLaTeX
+/// localization keys like `phdthesis`, common spelled-out descriptions like
+/// `Ph.D. thesis` or `Master's thesis` are recognized. Other values are
+/// preserved in the `Unknown` variant.
+#[derive(Debug, Clone, Eq, PartialEq, Display, EnumString, AsRefStr)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[strum(serialize_all = "lowercase")]
+pub enum ThesisType {
+    /// A doctoral thesis (`phdthesis`).
+    PhdThesis,
+    /// A master's thesis (`mathesis`).
+    MaThesis,
+    /// A candidate thesis (`candthesis`).
+    CandThesis,
+
+    /// Any other kind of thesis.
+    #[strum(default)]
+    Unknown(String),
+}
+
+impl Type for ThesisType {
+    fn from_chunks(chunks: ChunksRef) -> Result<Self, TypeError> {
+        let value = chunks.format_normalized();
+        let key: String =
+            value.to_lowercase().chars().filter(|c| c.is_alphanumeric()).collect();
+
+        Ok(match key.as_str() {
+            "phdthesis" | "phddissertation" | "doctoralthesis" | "dissertation" => {
+                Self::PhdThesis
+            }
+            "mathesis" | "masterthesis" | "mastersthesis" | "msthesis" | "mscthesis" => {
+                Self::MaThesis
+            }
+            "candthesis" | "candidatethesis" => Self::CandThesis,
+            _ => Self::Unknown(value),
+        })
+    }
+
+    fn to_chunks(&self) -> Chunks {
+        let res = self.to_string();
+        vec![Spanned::detached(Chunk::Normal(res))]
+    }
+}
+
 /// Gender of the author or editor (if no author was specified).
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Display, AsRefStr)]
+///
+/// The value of the `gender` field, serialized as the two-letter codes
+/// defined by BibLaTeX (e.g., `sf` for a single female person).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Display, EnumString, AsRefStr)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[allow(missing_docs)]
 pub enum Gender {
+    #[strum(serialize = "sf")]
     SingularFemale,
+    #[strum(serialize = "sm")]
     SingularMale,
+    #[strum(serialize = "sn")]
     SingularNeuter,
+    #[strum(serialize = "pf")]
     PluralFemale,
+    #[strum(serialize = "pm")]
     PluralMale,
+    #[strum(serialize = "pn")]
     PluralNeuter,
 }
 
@@ -419,15 +624,8 @@ impl Type for Gender {
     fn from_chunks(chunks: ChunksRef) -> Result<Self, TypeError> {
         // Two-letter gender serialization in accordance with the BibLaTeX standard.
         let span = chunks.span();
-        match chunks.format_verbatim().to_lowercase().as_ref() {
-            "sf" => Ok(Gender::SingularFemale),
-            "sm" => Ok(Gender::SingularMale),
-            "sn" => Ok(Gender::SingularNeuter),
-            "pf" => Ok(Gender::PluralFemale),
-            "pm" => Ok(Gender::PluralMale),
-            "pn" => Ok(Gender::PluralNeuter),
-            _ => Err(TypeError::new(span, TypeErrorKind::UnknownGender)),
-        }
+        Gender::from_str(chunks.format_verbatim().trim().to_lowercase().as_ref())
+            .map_err(|_| TypeError::new(span, TypeErrorKind::UnknownGender))
     }
 
     fn to_chunks(&self) -> Chunks {
@@ -450,6 +648,75 @@ mod tests {
         assert_eq!(res[2], 194..245);
     }
 
+    #[test]
+    fn test_pagination() {
+        let column = &[Spanned::zero(N("Column"))];
+        assert_eq!(column.parse::<Pagination>(), Ok(Pagination::Column));
+
+        let custom = &[Spanned::zero(N("folio"))];
+        let pagination = custom.parse::<Pagination>().unwrap();
+        assert_eq!(pagination, Pagination::Other("folio".into()));
+        assert_eq!(pagination.to_chunks(), vec![Spanned::detached(N("folio"))]);
+
+        let custom = &[Spanned::zero(N(" Folio "))];
+        assert_eq!(custom.parse::<Pagination>(), Ok(Pagination::Other("Folio".into())));
+    }
+
+    #[test]
+    fn test_gender() {
+        let gender = &[Spanned::zero(N(" PF"))];
+        let gender = gender.parse::<Gender>().unwrap();
+        assert_eq!(gender, Gender::PluralFemale);
+        assert_eq!(gender.to_chunks(), vec![Spanned::detached(N("pf"))]);
+        assert_eq!(gender.singular().to_string(), "sf");
+
+        let unknown = &[s(N("xy"), 3..5)];
+        assert_eq!(
+            unknown.parse::<Gender>(),
+            Err(TypeError::new(3..5, TypeErrorKind::UnknownGender))
+        );
+    }
+
+    #[test]
+    fn test_entry_subtype() {
+        let subtype = &[Spanned::zero(N(" Newspaper "))];
+        let subtype = subtype.parse::<EntrySubtype>().unwrap();
+        assert_eq!(subtype, EntrySubtype::Newspaper);
+        assert_eq!(subtype.to_chunks(), vec![Spanned::detached(N("newspaper"))]);
+
+        let unknown = &[Spanned::zero(N("Blog Post"))];
+        let unknown = unknown.parse::<EntrySubtype>().unwrap();
+        assert_eq!(unknown, EntrySubtype::Unknown("Blog Post".into()));
+        assert_eq!(unknown.to_string(), "Blog Post");
+    }
+
+    #[test]
+    fn test_thesis_type() {
+        let parse = |s: &str| [Spanned::zero(N(s))].parse::<ThesisType>().unwrap();
+        assert_eq!(parse("phdthesis"), ThesisType::PhdThesis);
+        assert_eq!(parse("Ph.D.  Thesis"), ThesisType::PhdThesis);
+        assert_eq!(parse("Master's thesis"), ThesisType::MaThesis);
+        assert_eq!(parse("Habilitation"), ThesisType::Unknown("Habilitation".into()));
+        assert_eq!(ThesisType::MaThesis.to_string(), "mathesis");
+    }
+
+    #[test]
+    fn test_permissive_ordering() {
+        let mut volumes: Vec<PermissiveType<i64>> = ["Suppl. 2", "10", "2", "iv"]
+            .into_iter()
+            .map(|v| [Spanned::zero(N(v))].parse().unwrap())
+            .collect();
+
+        volumes.sort();
+        assert_eq!(volumes[0], PermissiveType::Typed(2));
+        assert_eq!(volumes[1], PermissiveType::Typed(4));
+        assert_eq!(volumes[2], PermissiveType::Typed(10));
+        assert_eq!(
+            volumes[3],
+            PermissiveType::Chunks(vec![Spanned::zero(N("Suppl. 2"))])
+        );
+    }
+
     #[test]
     fn test_ranges_2228() {
         let ranges = &[Spanned::zero(N("34,37--39"))];
@@ -4,11 +4,15 @@ use std::fmt::{self, Display, Formatter};
 use crate::{chunk::*, Spanned};
 use crate::{Type, TypeError};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// An author, editor, or some other person affiliated with a cited work.
 ///
 /// When parsed through [`Person::parse`], the whitespace is trimmed from the
 /// fields.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Person {
     /// The surname / family name / last name.
     pub name: String,
@@ -24,12 +28,12 @@ pub struct Person {
 impl Person {
     /// Constructs a new person from a chunk vector:
     /// 1. according to the specs of
-    ///     [Nicolas Markey in "Tame the BeaST"][taming], pp. 23-24.
+    ///    [Nicolas Markey in "Tame the BeaST"][taming], pp. 23-24.
     /// 2. biblatex extended name format according to the
-    ///     [documentation of biblatex][biblatex], section 3.4 pp. 80-81,
-    ///     and section §4.2.3 pp. 164-165.
-    ///     Support is limited to default `nameparts`: prefix, family,
-    ///     suffix, given.
+    ///    [documentation of biblatex][biblatex], section 3.4 pp. 80-81,
+    ///    and section §4.2.3 pp. 164-165.
+    ///    Support is limited to default `nameparts`: prefix, family,
+    ///    suffix, given.
     ///
     /// [taming]: https://ftp.rrze.uni-erlangen.de/ctan/info/bibtex/tamethebeast/ttb_en.pdf
     /// [biblatex]: https://ctan.gutenberg-asso.fr/macros/latex/contrib/biblatex/doc/biblatex.pdf
@@ -264,15 +268,33 @@ impl Type for Vec<Person> {
                     (None, String::new())
                 };
 
-                let name_str = if !p.suffix.is_empty() {
-                    format!("{}{}, {}, {}", prefix.1, p.name, p.suffix, p.given_name)
+                let rest = if !p.suffix.is_empty() {
+                    format!(", {}, {}", p.suffix, p.given_name)
                 } else {
-                    format!("{}{}, {}", prefix.1, p.name, p.given_name)
+                    format!(", {}", p.given_name)
                 };
 
-                let mut res = vec![Spanned::detached(Chunk::Normal(name_str))];
+                // Protect names that would otherwise be split into multiple
+                // persons or name parts, e.g. `{Barnes and Noble}`.
+                let protect =
+                    p.name.contains(',') || p.name.split_whitespace().any(|w| w == "and");
+
+                let mut res = vec![];
                 if let Some(pre_chunk) = prefix.0 {
-                    res.insert(0, pre_chunk);
+                    res.push(pre_chunk);
+                }
+
+                if protect {
+                    if !prefix.1.is_empty() {
+                        res.push(Spanned::detached(Chunk::Normal(prefix.1)));
+                    }
+                    res.push(Spanned::detached(Chunk::Verbatim(p.name.clone())));
+                    if !p.given_name.is_empty() || !p.suffix.is_empty() {
+                        res.push(Spanned::detached(Chunk::Normal(rest)));
+                    }
+                } else {
+                    let name_str = format!("{}{}{}", prefix.1, p.name, rest);
+                    res.push(Spanned::detached(Chunk::Normal(name_str)));
                 }
 
                 res
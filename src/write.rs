@@ -0,0 +1,1298 @@
+//! Configurable layout of the Bib(La)TeX output.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::io;
+
+use crate::encode::encode_latex;
+use crate::mechanics::is_verbatim_field;
+use crate::raw::Field;
+use crate::types::get_month_for_abbr;
+use crate::{
+    Bibliography, Chunk, ChunksExt, ChunksRef, Entry, Pair, ParseError, RawBibliography,
+    RawChunk, SortingTemplate, Spanned,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The predefined macros for the months.
+const MONTH_MACROS: [&str; 12] =
+    ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+/// How field values are delimited in the output.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Delimiter {
+    /// Values are enclosed in braces: `title = {Title}`.
+    Braces,
+    /// Values are enclosed in quotes: `title = "Title"`. Values containing a
+    /// quote outside of a brace group are enclosed in braces instead.
+    Quotes,
+}
+
+/// The order in which the fields of an entry are written.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FieldOrder {
+    /// Fields are sorted by name.
+    Alphabetical,
+    /// Names and titles come first, followed by publication details, dates,
+    /// identifiers, and notes, as is common in style guides. Other fields
+    /// follow in alphabetical order.
+    Canonical,
+    /// Fields keep their order in the parsed source. Fields that were added
+    /// later follow in alphabetical order.
+    Source,
+}
+
+/// The order in which the entries of a bibliography are written. The
+/// `@string` definitions always keep their order because they may refer to
+/// each other.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EntryOrder {
+    /// Entries keep their order in the bibliography.
+    Bibliography,
+    /// Entries are sorted by cite key, compared case-insensitively first.
+    Key,
+    /// Entries are sorted like in [`Bibliography::sorted`], so that the file
+    /// matches the order of the printed bibliography. A raw bibliography that
+    /// cannot be resolved is written in source order.
+    Sorting(SortingTemplate),
+    /// Entries with the given cite keys come first in the given order, e.g.
+    /// one obtained from a localized sort. Other entries follow in
+    /// bibliography order.
+    Keys(Vec<String>),
+}
+
+/// The field order of [`FieldOrder::Canonical`].
+const CANONICAL_ORDER: &[&str] = &[
+    "author",
+    "editor",
+    "translator",
+    "bookauthor",
+    "title",
+    "subtitle",
+    "titleaddon",
+    "booktitle",
+    "booksubtitle",
+    "maintitle",
+    "mainsubtitle",
+    "journaltitle",
+    "journal",
+    "issuetitle",
+    "eventtitle",
+    "series",
+    "volume",
+    "number",
+    "issue",
+    "edition",
+    "chapter",
+    "pages",
+    "pagetotal",
+    "type",
+    "publisher",
+    "organization",
+    "institution",
+    "school",
+    "location",
+    "address",
+    "venue",
+    "date",
+    "year",
+    "month",
+    "day",
+    "eventdate",
+    "isbn",
+    "issn",
+    "doi",
+    "eprint",
+    "eprinttype",
+    "eprintclass",
+    "archiveprefix",
+    "primaryclass",
+    "url",
+    "urldate",
+    "language",
+    "keywords",
+    "abstract",
+    "note",
+    "addendum",
+];
+
+/// Options for the layout of written Bib(La)TeX files.
+///
+/// The default options yield the same output as
+/// [`Bibliography::to_biblatex_string`](crate::Bibliography::to_biblatex_string).
+///
+/// ```
+/// # use biblatex::{Bibliography, Delimiter, FormatOptions};
+/// let src = "@book{knuth, title = {The {TeX}book}, date = {1984}}";
+/// let bibliography = Bibliography::parse(src).unwrap();
+///
+/// let mut options = FormatOptions::default();
+/// options.set_indent(2);
+/// options.set_delimiter(Delimiter::Quotes);
+/// options.set_align_equals(true);
+/// assert_eq!(
+///     bibliography.to_biblatex_string_with(&options),
+///     "@book{knuth,\n  date  = \"1984\",\n  title = \"The {TeX}book\",\n}\n",
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FormatOptions {
+    indent: usize,
+    delimiter: Delimiter,
+    align_equals: bool,
+    blank_lines: usize,
+    field_order: FieldOrder,
+    max_width: Option<usize>,
+    /// The user-defined abbreviations with their value in the output format.
+    abbreviations: Vec<(String, String)>,
+    month_macros: bool,
+    write_strings: bool,
+    encode_unicode: bool,
+    entry_order: EntryOrder,
+    lowercase: bool,
+    delimit_numbers: bool,
+    trailing_comma: bool,
+    final_newline: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 0,
+            delimiter: Delimiter::Braces,
+            align_equals: false,
+            blank_lines: 1,
+            field_order: FieldOrder::Alphabetical,
+            max_width: None,
+            abbreviations: vec![],
+            month_macros: false,
+            write_strings: true,
+            encode_unicode: false,
+            entry_order: EntryOrder::Bibliography,
+            lowercase: false,
+            delimit_numbers: false,
+            trailing_comma: true,
+            final_newline: true,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Options for a deterministic layout that keeps diffs of regenerated
+    /// files minimal.
+    ///
+    /// Entries are sorted by cite key and fields by name, and entry types and
+    /// field names are written in lowercase. Each field is written on its own
+    /// line with an indent of two spaces, a braced value (also for numbers),
+    /// and a trailing comma. Values are neither wrapped nor aligned, so that
+    /// changing one field never touches the lines of another.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, FormatOptions};
+    /// let src = r#"@book{b, title = "B", author = {Doe, Jane}}
+    ///              @article{a, year = 2020}"#;
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    /// assert_eq!(
+    ///     bibliography.to_biblatex_string_with(&FormatOptions::canonical()),
+    ///     "@article{a,\n  year = {2020},\n}\n\n@book{b,\n  author = {Doe, Jane},\n  title = {B},\n}\n",
+    /// );
+    /// ```
+    pub fn canonical() -> Self {
+        Self {
+            indent: 2,
+            entry_order: EntryOrder::Key,
+            lowercase: true,
+            delimit_numbers: true,
+            ..Self::default()
+        }
+    }
+
+    /// Set the number of spaces before each field. Defaults to 0.
+    pub fn set_indent(&mut self, indent: usize) {
+        self.indent = indent;
+    }
+
+    /// Set how field values are delimited. Defaults to
+    /// [`Delimiter::Braces`].
+    pub fn set_delimiter(&mut self, delimiter: Delimiter) {
+        self.delimiter = delimiter;
+    }
+
+    /// Set whether the equals signs of an entry are aligned by padding the
+    /// field names. Defaults to `false`.
+    pub fn set_align_equals(&mut self, align_equals: bool) {
+        self.align_equals = align_equals;
+    }
+
+    /// Set the number of blank lines between entries. Defaults to 1.
+    pub fn set_blank_lines(&mut self, blank_lines: usize) {
+        self.blank_lines = blank_lines;
+    }
+
+    /// Set the order in which the fields of an entry are written. Defaults
+    /// to [`FieldOrder::Alphabetical`].
+    pub fn set_field_order(&mut self, field_order: FieldOrder) {
+        self.field_order = field_order;
+    }
+
+    /// Set the column after which long field values are wrapped, or `None`
+    /// to never wrap. Defaults to `None`.
+    ///
+    /// Values are only broken at spaces, which are equivalent to line breaks
+    /// in Bib(La)TeX. Continuation lines are aligned with the start of the
+    /// value. Spaces in math, escaped spaces, and values of verbatim fields
+    /// like `url` are kept, so a line may still exceed the width.
+    pub fn set_max_width(&mut self, max_width: Option<usize>) {
+        self.max_width = max_width;
+    }
+
+    /// Define an abbreviation that replaces field values equal to its value.
+    ///
+    /// Its definition is written as an `@string` entry ahead of the entries,
+    /// unless a raw bibliography already defines an abbreviation with this
+    /// name.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, FormatOptions};
+    /// let src = "@book{a, publisher = {ACM Press}, month = {3}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let mut options = FormatOptions::default();
+    /// options.add_abbreviation("acmpress", "ACM Press");
+    /// options.set_month_macros(true);
+    /// assert_eq!(
+    ///     bibliography.to_biblatex_string_with(&options),
+    ///     "@string{acmpress = {ACM Press}}\n\n@book{a,\nmonth = mar,\npublisher = acmpress,\n}\n",
+    /// );
+    /// ```
+    pub fn add_abbreviation(&mut self, name: &str, value: &str) {
+        let value =
+            [Spanned::detached(Chunk::Normal(value.into()))].to_biblatex_string(false);
+        self.abbreviations.push((name.into(), value));
+    }
+
+    /// Set whether `month` fields holding a month number or English month
+    /// name are written as the predefined macros like `jan`. Defaults to
+    /// `false`.
+    pub fn set_month_macros(&mut self, month_macros: bool) {
+        self.month_macros = month_macros;
+    }
+
+    /// Set whether the `@string` definitions of abbreviations are written.
+    /// Disabling this is useful if they are defined in another file.
+    /// Defaults to `true`.
+    pub fn set_write_strings(&mut self, write_strings: bool) {
+        self.write_strings = write_strings;
+    }
+
+    /// Set whether non-ASCII characters are written as LaTeX commands, like
+    /// `\"{a}` for `ä`, for toolchains without Unicode support. Math,
+    /// verbatim fields, and characters without a LaTeX equivalent are kept.
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, FormatOptions};
+    /// let src = "@book{a, author = {Gödel, Kurt}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let mut options = FormatOptions::default();
+    /// options.set_encode_unicode(true);
+    /// let written = bibliography.to_biblatex_string_with(&options);
+    /// assert_eq!(written, "@book{a,\nauthor = {G\\\"{o}del, Kurt},\n}\n");
+    /// ```
+    pub fn set_encode_unicode(&mut self, encode_unicode: bool) {
+        self.encode_unicode = encode_unicode;
+    }
+
+    /// Set the order in which entries are written. Defaults to
+    /// [`EntryOrder::Bibliography`]. The `@xdata` containers of a
+    /// [`Bibliography`] are always written first.
+    ///
+    /// ```
+    /// # use biblatex::{Bibliography, EntryOrder, FormatOptions, SortingScheme};
+    /// let src = "@book{a, author = {Lamport, Leslie}, year = {1986}}
+    ///            @book{b, author = {Knuth, Donald}, year = {1984}}";
+    /// let bibliography = Bibliography::parse(src).unwrap();
+    ///
+    /// let mut options = FormatOptions::default();
+    /// options.set_entry_order(EntryOrder::Sorting(SortingScheme::Nyt.template()));
+    /// let written = bibliography.to_biblatex_string_with(&options);
+    /// assert!(written.starts_with("@book{b,"));
+    /// ```
+    pub fn set_entry_order(&mut self, entry_order: EntryOrder) {
+        self.entry_order = entry_order;
+    }
+
+    /// Set whether entry types and field names are written in lowercase,
+    /// like `@article` and `title` for `@Article` and `Title`. Otherwise,
+    /// they are written as they appear in the bibliography. Defaults to
+    /// `false`.
+    pub fn set_lowercase(&mut self, lowercase: bool) {
+        self.lowercase = lowercase;
+    }
+
+    /// Set whether numbers that a raw bibliography holds without delimiters,
+    /// like `year = 2020`, are delimited like other values. Defaults to
+    /// `false`.
+    pub fn set_delimit_numbers(&mut self, delimit_numbers: bool) {
+        self.delimit_numbers = delimit_numbers;
+    }
+
+    /// Set whether the last field of an entry is followed by a comma, which
+    /// keeps diffs minimal when fields are appended. Defaults to `true`.
+    pub fn set_trailing_comma(&mut self, trailing_comma: bool) {
+        self.trailing_comma = trailing_comma;
+    }
+
+    /// Set whether written files end with a newline. Defaults to `true`.
+    pub fn set_final_newline(&mut self, final_newline: bool) {
+        self.final_newline = final_newline;
+    }
+
+    /// Write the `@string` definitions of the abbreviations of a raw
+    /// bibliography and the user-defined ones. Returns whether anything was
+    /// written.
+    pub(crate) fn write_abbreviations(
+        &self,
+        sink: &mut impl Write,
+        defined: &[Pair],
+    ) -> Result<bool, fmt::Error> {
+        if !self.write_strings {
+            return Ok(false);
+        }
+
+        let mut strings = vec![];
+        for pair in defined {
+            let value = self.encode(self.raw_chunks(&pair.value.v));
+            strings.push((&*pair.key.v, value));
+        }
+
+        for (name, value) in &self.abbreviations {
+            if !defined.iter().any(|pair| pair.key.v.eq_ignore_ascii_case(name)) {
+                strings.push((name.as_str(), self.delimit(self.encode(value.clone()))));
+            }
+        }
+
+        for (i, (name, value)) in strings.iter().enumerate() {
+            if i > 0 {
+                writeln!(sink)?;
+            }
+            write!(sink, "@string{{{} = {}}}", name, value)?;
+        }
+
+        Ok(!strings.is_empty())
+    }
+
+    /// Order entries with the given cite keys. The keys of the entries in the
+    /// order of a sorting template are only computed if needed.
+    pub(crate) fn order_entries<T>(
+        &self,
+        entries: &mut [T],
+        key: impl Fn(&T) -> &str,
+        sorted: impl FnOnce(&SortingTemplate) -> Vec<String>,
+    ) {
+        let keys = match &self.entry_order {
+            EntryOrder::Bibliography => return,
+            EntryOrder::Key => {
+                entries.sort_by(|a, b| {
+                    let (a, b) = (key(a), key(b));
+                    a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b))
+                });
+                return;
+            }
+            EntryOrder::Sorting(template) => &sorted(template),
+            EntryOrder::Keys(keys) => keys,
+        };
+
+        let ranks: HashMap<&str, usize> = keys
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, key)| (key.as_str(), i))
+            .collect();
+        entries.sort_by_key(|entry| ranks.get(key(entry)).copied().unwrap_or(keys.len()));
+    }
+
+    /// Write a field value, replacing it with a macro if possible.
+    pub(crate) fn value(&self, field: &str, chunks: ChunksRef) -> String {
+        let verbatim = is_verbatim_field(field);
+        let braced = chunks.to_biblatex_string(verbatim);
+        match self.macro_for(field, &braced) {
+            Some(name) => name.to_string(),
+            None if verbatim => self.delimit(braced),
+            None => self.delimit(self.encode(braced)),
+        }
+    }
+
+    /// Encode non-ASCII characters if enabled.
+    fn encode(&self, text: String) -> String {
+        match self.encode_unicode {
+            true => encode_latex(&text),
+            false => text,
+        }
+    }
+
+    /// The macro that a field value, enclosed in braces, can be replaced with.
+    fn macro_for(&self, field: &str, braced: &str) -> Option<&str> {
+        if self.month_macros && field.eq_ignore_ascii_case("month") {
+            let month = braced.strip_prefix('{')?.strip_suffix('}')?.trim();
+            let index = match month.parse::<usize>() {
+                Ok(number) => number.checked_sub(1),
+                Err(_) => MONTH_MACROS.iter().position(|abbr| {
+                    abbr.eq_ignore_ascii_case(month)
+                        || get_month_for_abbr(abbr)
+                            .is_some_and(|(name, _)| name.eq_ignore_ascii_case(month))
+                }),
+            };
+            if let Some(&name) = index.and_then(|i| MONTH_MACROS.get(i)) {
+                return Some(name);
+            }
+        }
+
+        self.abbreviations
+            .iter()
+            .find(|(_, value)| value == braced)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Write an entry without a trailing newline. The fields consist of their
+    /// name, their already delimited value, and their position in the source.
+    pub(crate) fn write_entry<K: AsRef<str>>(
+        &self,
+        sink: &mut impl Write,
+        kind: &str,
+        key: &str,
+        fields: &mut [(K, String, usize)],
+    ) -> fmt::Result {
+        match self.lowercase {
+            true => writeln!(sink, "@{}{{{},", kind.to_lowercase(), key)?,
+            false => writeln!(sink, "@{}{{{},", kind, key)?,
+        }
+
+        let name = |field: &(K, String, usize)| field.0.as_ref().to_ascii_lowercase();
+        match self.field_order {
+            FieldOrder::Alphabetical => fields.sort_by_key(name),
+            FieldOrder::Canonical => fields.sort_by_cached_key(|field| {
+                let name = name(field);
+                let rank = CANONICAL_ORDER.iter().position(|&f| f == name);
+                (rank.unwrap_or(CANONICAL_ORDER.len()), name)
+            }),
+            FieldOrder::Source => {
+                fields.sort_by_cached_key(|field| (field.2, name(field)))
+            }
+        }
+
+        let width = match self.align_equals {
+            true => fields.iter().map(|(name, ..)| name.as_ref().chars().count()).max(),
+            false => None,
+        };
+
+        let count = fields.len();
+        for (i, (name, value, _)) in fields.iter().enumerate() {
+            let comma = if i + 1 < count || self.trailing_comma { "," } else { "" };
+            let name = match self.lowercase {
+                true => name.as_ref().to_lowercase(),
+                false => name.as_ref().to_string(),
+            };
+            let padding = width.map_or(0, |width| width - name.chars().count());
+            let prefix = format!(
+                "{:indent$}{}{:padding$} = ",
+                "",
+                name,
+                "",
+                indent = self.indent,
+                padding = padding,
+            );
+
+            match self.max_width {
+                Some(max_width) if !is_verbatim_field(&name.to_lowercase()) => {
+                    let column = prefix.chars().count();
+                    let wrapped = wrap(value, column, max_width);
+                    writeln!(sink, "{}{}{}", prefix, wrapped, comma)?;
+                }
+                _ => writeln!(sink, "{}{}{}", prefix, value, comma)?,
+            }
+        }
+
+        sink.write_char('}')
+    }
+
+    /// Write the end of an item followed by the blank lines before the next
+    /// one.
+    pub(crate) fn write_separator(&self, sink: &mut impl Write) -> fmt::Result {
+        for _ in 0..=self.blank_lines {
+            writeln!(sink)?;
+        }
+        Ok(())
+    }
+
+    /// Write the end of the last item if any item was written.
+    pub(crate) fn write_end(&self, sink: &mut impl Write, empty: bool) -> fmt::Result {
+        match !empty && self.final_newline {
+            true => writeln!(sink),
+            false => Ok(()),
+        }
+    }
+
+    /// Apply the delimiter to a value that is enclosed in braces.
+    pub(crate) fn delimit(&self, braced: String) -> String {
+        let inner = braced
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .filter(|inner| is_balanced(inner));
+
+        match inner {
+            Some(inner) if self.delimiter == Delimiter::Quotes && !has_quote(inner) => {
+                format!("\"{}\"", inner)
+            }
+            _ => braced,
+        }
+    }
+
+    /// Write a raw field value, replacing it with a macro if possible.
+    pub(crate) fn raw_value(&self, name: &str, field: &Field) -> String {
+        if let [Spanned { v: RawChunk::Normal(text), .. }] = field.as_slice() {
+            if let Some(name) = self.macro_for(name, &format!("{{{}}}", text)) {
+                return name.to_string();
+            }
+        }
+
+        let value = self.raw_chunks(field);
+        match is_verbatim_field(&name.to_ascii_lowercase()) {
+            true => value,
+            false => self.encode(value),
+        }
+    }
+
+    /// Write raw field chunks, keeping references to abbreviations. Numbers
+    /// are written without delimiters and text that would leave braces
+    /// unbalanced is always quoted.
+    fn raw_chunks(&self, field: &Field) -> String {
+        let mut res = String::new();
+        for (i, chunk) in field.iter().enumerate() {
+            if i > 0 {
+                res.push_str(" # ");
+            }
+
+            match &chunk.v {
+                RawChunk::Abbreviation(name) => res.push_str(name),
+                RawChunk::Normal(text)
+                    if !self.delimit_numbers
+                        && !text.is_empty()
+                        && text.chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    res.push_str(text)
+                }
+                RawChunk::Normal(text) if is_balanced(text) => {
+                    res.push_str(&self.delimit(format!("{{{}}}", text)));
+                }
+                RawChunk::Normal(text) => {
+                    res.push('"');
+                    res.push_str(text);
+                    res.push('"');
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Writes entries one by one to an [`io::Write`] sink, without first
+/// serializing the whole bibliography into a string. Useful for very large
+/// databases that are written to files, sockets, or compressors.
+///
+/// The `@string` definitions of the [`FormatOptions`] are written ahead of the
+/// first item. Entries are written in the order they are given, so the
+/// [`FormatOptions::set_entry_order`] option does not apply. The sink is
+/// written to for every item, so it should usually be buffered.
+///
+/// ```
+/// # use biblatex::{BibWriter, Bibliography, FormatOptions};
+/// let src = "@book{a, title = {A}} @book{b, title = {B}}";
+/// let bibliography = Bibliography::parse(src).unwrap();
+///
+/// let mut writer = BibWriter::new(vec![], FormatOptions::default());
+/// for entry in bibliography.iter().filter(|entry| entry.key != "a") {
+///     writer.write_entry(entry).unwrap();
+/// }
+/// let written = writer.finish().unwrap();
+/// assert_eq!(written, b"@book{b,\ntitle = {B},\n}\n");
+/// ```
+#[derive(Debug)]
+pub struct BibWriter<W: io::Write> {
+    sink: W,
+    options: FormatOptions,
+    /// Whether the `@string` definitions were already written.
+    started: bool,
+    /// Whether nothing was written yet.
+    first: bool,
+}
+
+impl<W: io::Write> BibWriter<W> {
+    /// Create a writer that writes to the sink with the given layout.
+    pub fn new(sink: W, options: FormatOptions) -> Self {
+        Self { sink, options, started: false, first: true }
+    }
+
+    /// Write a `@preamble` entry. The value is written as-is, like in
+    /// [`Bibliography::set_preamble`].
+    pub fn write_preamble(&mut self, preamble: &str) -> io::Result<()> {
+        self.start()?;
+        write!(self.sink, "@preamble{{{}}}", preamble)
+    }
+
+    /// Write an entry in the BibLaTeX format.
+    pub fn write_entry(&mut self, entry: &Entry) -> io::Result<()> {
+        self.start()?;
+        write!(self.sink, "{}", entry.to_biblatex_string_with(&self.options))
+    }
+
+    /// Write an entry in the BibTeX format.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the entry has a malformed
+    /// date field, in which case nothing is written.
+    pub fn write_bibtex_entry(&mut self, entry: &Entry) -> io::Result<()> {
+        let bibtex = entry
+            .to_bibtex_string_with(&self.options)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        self.start()?;
+        write!(self.sink, "{}", bibtex)
+    }
+
+    /// The underlying sink.
+    pub fn get_ref(&self) -> &W {
+        &self.sink
+    }
+
+    /// Write the `@string` definitions if no item was written and the final
+    /// newline, flush the sink, and return it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_strings()?;
+        if !self.first && self.options.final_newline {
+            writeln!(self.sink)?;
+        }
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+
+    /// Write the `@string` definitions unless they were already written.
+    fn write_strings(&mut self) -> io::Result<()> {
+        if !self.started {
+            let mut strings = String::new();
+            let written = self.options.write_abbreviations(&mut strings, &[]).unwrap();
+            self.sink.write_all(strings.as_bytes())?;
+            self.first = !written;
+            self.started = true;
+        }
+        Ok(())
+    }
+
+    /// Write what precedes the next item.
+    fn start(&mut self) -> io::Result<()> {
+        self.write_strings()?;
+        if !self.first {
+            let mut separator = String::new();
+            self.options.write_separator(&mut separator).unwrap();
+            self.sink.write_all(separator.as_bytes())?;
+        }
+
+        self.first = false;
+        Ok(())
+    }
+}
+
+impl Bibliography {
+    /// Write the bibliography into an [`io::Write`] sink in the BibLaTeX
+    /// format, one entry at a time. Yields the same output as
+    /// [`Bibliography::write_biblatex_with`].
+    pub fn write_biblatex_io(
+        &self,
+        sink: impl io::Write,
+        options: &FormatOptions,
+    ) -> io::Result<()> {
+        let mut writer = BibWriter::new(sink, options.clone());
+        if !self.preamble.is_empty() {
+            writer.write_preamble(&self.preamble)?;
+        }
+
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        options.order_entries(&mut entries, |entry| &entry.key, |t| self.sorted_keys(t));
+        for entry in self.xdata.values().chain(entries) {
+            writer.write_entry(entry)?;
+        }
+
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Rewrite Bib(La)TeX source text so that all field values use the same
+/// delimiter. The rest of the text, including comments and whitespace, is
+/// kept as-is.
+///
+/// Numbers like `2020` are enclosed in the delimiter, while references to
+/// abbreviations stay bare. Values that can not be represented with the
+/// delimiter keep theirs: quoted values with unbalanced braces cannot be
+/// braced and braced values with a quote outside of a nested brace group
+/// would end a quoted value early. The same rules apply to the values
+/// written with [`FormatOptions::set_delimiter`].
+///
+/// Fails if the source can not be parsed.
+///
+/// ```
+/// # use biblatex::{normalize_delimiters, Delimiter};
+/// let src = "% Note\n@book{a, title = \"The {TeX}book\", year = 1984, note = {\"Hi\"}}";
+/// assert_eq!(
+///     normalize_delimiters(src, Delimiter::Braces).unwrap(),
+///     "% Note\n@book{a, title = {The {TeX}book}, year = {1984}, note = {\"Hi\"}}",
+/// );
+/// assert_eq!(
+///     normalize_delimiters(src, Delimiter::Quotes).unwrap(),
+///     "% Note\n@book{a, title = \"The {TeX}book\", year = \"1984\", note = {\"Hi\"}}",
+/// );
+/// ```
+pub fn normalize_delimiters(
+    src: &str,
+    delimiter: Delimiter,
+) -> Result<String, ParseError> {
+    let raw = RawBibliography::parse(src)?;
+    let options = FormatOptions { delimiter, ..FormatOptions::default() };
+
+    let pairs = raw.entries.iter().flat_map(|entry| &entry.v.fields);
+    let mut replacements = vec![];
+    for pair in raw.abbreviations.iter().chain(pairs) {
+        for chunk in &pair.value.v {
+            let RawChunk::Normal(text) = &chunk.v else { continue };
+            let span = &chunk.span;
+            let (range, value) = match src[..span.start].chars().next_back() {
+                Some('{') => (span.start - 1..span.end + 1, format!("{{{}}}", text)),
+                Some('"') if is_balanced(text) => {
+                    (span.start - 1..span.end + 1, format!("{{{}}}", text))
+                }
+                Some('"') => continue,
+                _ => (span.clone(), format!("{{{}}}", text)),
+            };
+            replacements.push((range, options.delimit(value)));
+        }
+    }
+
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut res = String::new();
+    let mut cursor = 0;
+    for (range, value) in replacements {
+        res.push_str(&src[cursor..range.start]);
+        res.push_str(&value);
+        cursor = range.end;
+    }
+    res.push_str(&src[cursor..]);
+    Ok(res)
+}
+
+/// Break a value that starts at the given column at spaces so that its lines
+/// fit into the width where possible. The trailing comma counts towards the
+/// last line.
+fn wrap(value: &str, column: usize, max_width: usize) -> String {
+    // Find the spaces at which the value may be broken.
+    let chars: Vec<char> = value.chars().collect();
+    let mut breaks = vec![];
+    let mut math = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '$' => math = !math,
+            ' ' if !math
+                && i > 0
+                && !chars[i - 1].is_whitespace()
+                && chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) =>
+            {
+                breaks.push(i);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    breaks.push(chars.len());
+
+    let indent = column + 1;
+    let mut res = String::new();
+    let mut start = 0;
+    let mut col = column;
+    for (n, &end) in breaks.iter().enumerate() {
+        let word = &chars[start..end];
+        let last = n + 1 == breaks.len();
+        let len = word.len() + usize::from(last);
+
+        // Text after an existing line break starts at the first column.
+        let fits = match word.iter().position(|&c| c == '\n') {
+            Some(_) => col + word.iter().take_while(|&&c| c != '\n').count() <= max_width,
+            None => col + len <= max_width,
+        };
+
+        if start > 0 {
+            if fits || col <= indent {
+                res.push(' ');
+                col += 1;
+            } else {
+                res.push('\n');
+                res.push_str(&" ".repeat(indent));
+                col = indent;
+            }
+        }
+
+        res.extend(word);
+        col = match word.iter().rposition(|&c| c == '\n') {
+            Some(p) => word.len() - p - 1,
+            None => col + word.len(),
+        };
+        start = end + 1;
+    }
+
+    res
+}
+
+/// Whether the unescaped braces in the text are balanced.
+fn is_balanced(text: &str) -> bool {
+    let mut depth = 0usize;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+/// Whether the text contains a quote outside of a brace group, which would
+/// end a quoted value.
+fn has_quote(text: &str) -> bool {
+    let mut depth = 0usize;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if depth > 0 => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '"' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bibliography, RawBibliography, SortingScheme};
+
+    #[test]
+    fn test_format_options() {
+        let src = r#"@string{pub = "Publisher"}
+            @book{a, title = {Say "Hi"}, publisher = pub # { Inc}, year = 2020}
+            @article{b, journal = {J}}"#;
+
+        let mut options = FormatOptions::default();
+        let bibliography = Bibliography::parse(src).unwrap();
+        assert_eq!(
+            bibliography.to_biblatex_string_with(&options),
+            bibliography.to_biblatex_string()
+        );
+
+        options.set_indent(4);
+        options.set_delimiter(Delimiter::Quotes);
+        options.set_align_equals(true);
+        options.set_blank_lines(2);
+        assert_eq!(
+            bibliography.to_biblatex_string_with(&options),
+            r#"@book{a,
+    publisher = "Publisher Inc",
+    title     = {Say "Hi"},
+    year      = "2020",
+}
+
+
+@article{b,
+    journaltitle = "J",
+}
+"#
+        );
+
+        let raw = RawBibliography::parse(src).unwrap();
+        options.set_blank_lines(0);
+        assert_eq!(
+            raw.to_biblatex_string_with(&options),
+            r#"@string{pub = "Publisher"}
+@book{a,
+    publisher = pub # " Inc",
+    title     = {Say "Hi"},
+    year      = 2020,
+}
+@article{b,
+    journal = "J",
+}
+"#
+        );
+
+        let written = raw.to_biblatex_string_with(&options);
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+    }
+
+    #[test]
+    fn test_field_order() {
+        let src = "@article{a, year = 2020, Note = {N}, author = {A}, title = {T}, \
+            foo = {F}, journaltitle = {J}}";
+        let mut bibliography = Bibliography::parse(src).unwrap();
+        bibliography.get_mut("a").unwrap().set("bar", vec![]);
+        let raw = RawBibliography::parse(src).unwrap();
+
+        let names = |written: String| -> Vec<String> {
+            written
+                .lines()
+                .filter_map(|line| Some(line.split_once(" = ")?.0.to_string()))
+                .collect()
+        };
+
+        let mut options = FormatOptions::default();
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert_eq!(
+            names(written),
+            ["author", "bar", "foo", "journaltitle", "note", "title", "year"]
+        );
+        let written = raw.to_biblatex_string_with(&options);
+        assert_eq!(
+            names(written),
+            ["author", "foo", "journaltitle", "Note", "title", "year"]
+        );
+
+        options.set_field_order(FieldOrder::Canonical);
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert_eq!(
+            names(written),
+            ["author", "title", "journaltitle", "year", "note", "bar", "foo"]
+        );
+
+        options.set_field_order(FieldOrder::Source);
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert_eq!(
+            names(written),
+            ["year", "note", "author", "title", "foo", "journaltitle", "bar"]
+        );
+        let written = raw.to_biblatex_string_with(&options);
+        assert_eq!(
+            names(written),
+            ["year", "Note", "author", "title", "foo", "journaltitle"]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let src = r#"@article{a, title = {A Rather Long Title with {Protected Words} in It},
+            abstract = {One two three.
+
+                Four five $a + b$ six.},
+            url = {https://example.com/a very long url that stays on one line},
+            author = {Averyveryverylongname, Anna}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+
+        let mut options = FormatOptions::default();
+        options.set_indent(2);
+        options.set_max_width(Some(30));
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert_eq!(
+            written,
+            "@article{a,
+  abstract = {One two three.
+
+Four five $a + b$ six.},
+  author = {Averyveryverylongname,
+            Anna},
+  title = {A Rather Long Title
+           with {Protected
+           Words} in It},
+  url = {https://example.com/a very long url that stays on one line},
+}
+"
+        );
+
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let src = r#"@string{acm = "ACM"}
+            @book{a, publisher = {ACM Press}, month = {October}, date = {2020-05}}
+            @book{b, publisher = acm, month = oct, note = {ACM Press}}"#;
+        let bibliography = Bibliography::parse(src).unwrap();
+        let raw = RawBibliography::parse(src).unwrap();
+
+        let mut options = FormatOptions::default();
+        options.set_month_macros(true);
+        options.add_abbreviation("acmpress", "ACM Press");
+        options.add_abbreviation("acm", "ACM");
+
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert!(written.starts_with(
+            "@string{acmpress = {ACM Press}}\n@string{acm = {ACM}}\n\n@book{a,\n"
+        ));
+        assert!(written.contains("month = oct,\npublisher = acmpress,\n}"));
+        assert!(written.contains("note = acmpress,\npublisher = acm,\n}"));
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+
+        let bibtex = bibliography.to_bibtex_string_with(&options);
+        assert!(bibtex.contains("month = may,\n"));
+
+        let written = raw.to_biblatex_string_with(&options);
+        assert!(written
+            .starts_with("@string{acm = {ACM}}\n@string{acmpress = {ACM Press}}\n\n"));
+        assert!(written.contains("publisher = acmpress,\n"));
+        assert!(written.contains("month = oct,\n"));
+
+        options.set_write_strings(false);
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert!(written.starts_with("@book{a,\n"));
+        let written = raw.to_biblatex_string_with(&options);
+        assert!(written.starts_with("@book{a,\n"));
+    }
+
+    #[test]
+    fn test_canonical() {
+        let src = r#"@book{Knuth84, title = "The {TeX}book", Author = {Knuth, Donald}}
+            @article{b, journal = {J},
+                     year = 2020}
+            @misc{alpha, note = "N"}"#;
+        let shuffled = r#"@misc{alpha, note = {N}}
+            @article{b, year = {2020}, journal = {J}}
+            @book{Knuth84, author = {Knuth, Donald}, title = {The {TeX}book}}"#;
+
+        let options = FormatOptions::canonical();
+        let written = Bibliography::parse(src).unwrap().to_biblatex_string_with(&options);
+        assert_eq!(
+            written,
+            "@misc{alpha,
+  note = {N},
+}
+
+@article{b,
+  journaltitle = {J},
+  year = {2020},
+}
+
+@book{Knuth84,
+  author = {Knuth, Donald},
+  title = {The {TeX}book},
+}
+"
+        );
+
+        let other = Bibliography::parse(shuffled).unwrap();
+        assert_eq!(other.to_biblatex_string_with(&options), written);
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string_with(&options), written);
+
+        let raw = RawBibliography::parse(src).unwrap().to_biblatex_string_with(&options);
+        assert!(raw.starts_with("@misc{alpha,\n  note = {N},\n}\n\n@article{b,\n"));
+        assert!(raw.contains("@book{Knuth84,\n  author = {Knuth, Donald},\n"));
+    }
+
+    #[test]
+    fn test_entry_order() {
+        let src = "@book{c, author = {Lamport, Leslie}, year = {1986}}
+            @book{B, author = {Knuth, Donald}, year = {1984}}
+            @book{a, author = {Knuth, Donald}, year = {1968}}";
+        let bibliography = Bibliography::parse(src).unwrap();
+        let raw = RawBibliography::parse(src).unwrap();
+
+        let keys = |written: String| -> Vec<String> {
+            written
+                .lines()
+                .filter_map(|line| {
+                    Some(line.strip_prefix("@book{")?.trim_end_matches(','))
+                })
+                .map(String::from)
+                .collect()
+        };
+
+        let mut options = FormatOptions::default();
+        assert_eq!(keys(bibliography.to_biblatex_string_with(&options)), ["c", "B", "a"]);
+
+        options.set_entry_order(EntryOrder::Key);
+        assert_eq!(keys(bibliography.to_biblatex_string_with(&options)), ["a", "B", "c"]);
+
+        options.set_entry_order(EntryOrder::Sorting(SortingScheme::Ydnt.template()));
+        assert_eq!(keys(bibliography.to_biblatex_string_with(&options)), ["c", "B", "a"]);
+        options.set_entry_order(EntryOrder::Sorting(SortingScheme::Nyt.template()));
+        assert_eq!(keys(bibliography.to_biblatex_string_with(&options)), ["a", "B", "c"]);
+        assert_eq!(keys(raw.to_biblatex_string_with(&options)), ["a", "B", "c"]);
+
+        // The raw bibliography cannot be resolved, so its order is kept.
+        let unresolvable = RawBibliography::parse(
+            "@book{c, note = undefined} @book{B, date = 1990} @book{a, date = 1980}",
+        )
+        .unwrap();
+        assert!(Bibliography::from_raw(unresolvable.clone()).is_err());
+        assert_eq!(keys(unresolvable.to_biblatex_string_with(&options)), ["c", "B", "a"]);
+
+        options.set_entry_order(EntryOrder::Keys(vec!["a".into(), "x".into()]));
+        assert_eq!(keys(bibliography.to_biblatex_string_with(&options)), ["a", "c", "B"]);
+        assert_eq!(keys(raw.to_biblatex_string_with(&options)), ["a", "c", "B"]);
+    }
+
+    #[test]
+    fn test_normalize_delimiters() {
+        let src = r#"@string{me = "Jane {Doe}"}
+            % A comment with {braces} and "quotes".
+            @book{a, author = me # " and " # {Max}, title = "Open { brace",
+                note = {Say "Hi"}, volume = 12, pages = "3--4"}"#;
+
+        let braced = normalize_delimiters(src, Delimiter::Braces).unwrap();
+        assert_eq!(
+            braced,
+            r#"@string{me = {Jane {Doe}}}
+            % A comment with {braces} and "quotes".
+            @book{a, author = me # { and } # {Max}, title = "Open { brace",
+                note = {Say "Hi"}, volume = {12}, pages = {3--4}}"#
+        );
+
+        let quoted = normalize_delimiters(&braced, Delimiter::Quotes).unwrap();
+        assert_eq!(
+            quoted,
+            r#"@string{me = "Jane {Doe}"}
+            % A comment with {braces} and "quotes".
+            @book{a, author = me # " and " # "Max", title = "Open { brace",
+                note = {Say "Hi"}, volume = "12", pages = "3--4"}"#
+        );
+
+        let original = RawBibliography::parse(src).unwrap().to_biblatex_string();
+        for normalized in [braced, quoted] {
+            let raw = RawBibliography::parse(&normalized).unwrap();
+            assert_eq!(raw.to_biblatex_string(), original);
+        }
+
+        let mut options = FormatOptions::default();
+        options.set_delimit_numbers(true);
+        let raw = RawBibliography::parse(src).unwrap();
+        assert!(raw.to_biblatex_string_with(&options).contains("volume = {12},"));
+    }
+
+    #[test]
+    fn test_trailing_comma_and_newline() {
+        let src = "@string{p = {P}} @book{a, title = {A}, publisher = p} @misc{b,}";
+        let bibliography = Bibliography::parse(src).unwrap();
+        let raw = RawBibliography::parse(src).unwrap();
+
+        let mut options = FormatOptions::default();
+        options.set_trailing_comma(false);
+        options.set_final_newline(false);
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert_eq!(written, "@book{a,\npublisher = {P},\ntitle = {A}\n}\n\n@misc{b,\n}");
+        let written = raw.to_biblatex_string_with(&options);
+        assert_eq!(
+            written,
+            "@string{p = {P}}\n\n@book{a,\npublisher = p,\ntitle = {A}\n}\n\n@misc{b,\n}"
+        );
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+
+        let mut writer = BibWriter::new(vec![], options.clone());
+        writer.write_entry(bibliography.get("b").unwrap()).unwrap();
+        assert_eq!(writer.finish().unwrap(), b"@misc{b,\n}");
+
+        options.set_final_newline(true);
+        assert!(raw.to_biblatex_string_with(&options).ends_with("}\n\n@misc{b,\n}\n"));
+        assert_eq!(Bibliography::new().to_biblatex_string_with(&options), "");
+    }
+
+    #[test]
+    fn test_lowercase() {
+        let src = "@Article{Key, Title = {T}, journal = {J}, YEAR = 2020}";
+        let raw = RawBibliography::parse(src).unwrap();
+
+        let mut options = FormatOptions::default();
+        options.set_align_equals(true);
+        assert_eq!(
+            raw.to_biblatex_string_with(&options),
+            "@Article{Key,\njournal = {J},\nTitle   = {T},\nYEAR    = 2020,\n}\n"
+        );
+
+        options.set_lowercase(true);
+        assert_eq!(
+            raw.to_biblatex_string_with(&options),
+            "@article{Key,\njournal = {J},\ntitle   = {T},\nyear    = 2020,\n}\n"
+        );
+
+        let mut bibliography = Bibliography::parse(src).unwrap();
+        bibliography.get_mut("Key").unwrap().set("Note", vec![]);
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert!(written.starts_with("@article{Key,\n"));
+        assert!(written.contains("\nnote         = {},\n"));
+    }
+
+    #[test]
+    fn test_bib_writer() {
+        let contents = std::fs::read_to_string("tests/gral.bib").unwrap();
+        let mut bibliography = Bibliography::parse(&contents).unwrap();
+        bibliography.set_preamble("\"\\newcommand{\\noop}[1]{}\"");
+
+        let mut options = FormatOptions::canonical();
+        options.add_abbreviation("acm", "ACM");
+        let mut written = vec![];
+        bibliography.write_biblatex_io(&mut written, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            bibliography.to_biblatex_string_with(&options)
+        );
+
+        let writer = BibWriter::new(vec![], options.clone());
+        assert_eq!(writer.finish().unwrap(), b"@string{acm = {ACM}}\n");
+
+        let entry = bibliography.get("kim2009").unwrap();
+        let mut writer = BibWriter::new(vec![], FormatOptions::default());
+        writer.write_bibtex_entry(entry).unwrap();
+        writer.write_entry(entry).unwrap();
+        let expected = format!("{}\n\n{}\n", entry.to_bibtex_string().unwrap(), entry);
+        assert_eq!(writer.get_ref(), expected.trim_end().as_bytes());
+        assert_eq!(writer.finish().unwrap(), expected.as_bytes());
+    }
+
+    #[test]
+    fn test_encode_unicode() {
+        let src = r#"@string{pub = "Verlag für Ökologie"}
+            @book{a, title = {Über Straßen}, url = {https://example.com/ü}, publisher = pub}"#;
+        let mut options = FormatOptions::default();
+        options.set_encode_unicode(true);
+
+        let bibliography = Bibliography::parse(src).unwrap();
+        let written = bibliography.to_biblatex_string_with(&options);
+        assert!(written.contains("publisher = {Verlag f\\\"{u}r \\\"{O}kologie},"));
+        assert!(written.contains("title = {\\\"{U}ber Stra\\ss{}en},"));
+        assert!(written.contains("url = {https://example.com/ü},"));
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+
+        let raw = RawBibliography::parse(src).unwrap();
+        let written = raw.to_biblatex_string_with(&options);
+        assert!(written.starts_with("@string{pub = {Verlag f\\\"{u}r \\\"{O}kologie}}"));
+        assert!(written.contains("url = {https://example.com/ü},"));
+        let reparsed = Bibliography::parse(&written).unwrap();
+        assert_eq!(reparsed.to_biblatex_string(), bibliography.to_biblatex_string());
+    }
+}
@@ -0,0 +1,272 @@
+//! Reading and writing XML documents.
+
+use unscanny::Scanner;
+
+/// How deeply elements may be nested before parsing fails, so that malicious
+/// input cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// An XML element with its attributes and content.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Element {
+    /// The qualified name, e.g. `bltx:entry`.
+    pub name: String,
+    /// The attributes in order with their unescaped values.
+    pub attributes: Vec<(String, String)>,
+    /// The child elements and text.
+    pub children: Vec<Node>,
+}
+
+/// A piece of element content.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    /// Parse the root element of a document. The prolog, comments, and
+    /// processing instructions are skipped. Returns the byte offset of the
+    /// first error. Elements nested deeper than [`MAX_DEPTH`] are an error.
+    pub(crate) fn parse(src: &str) -> Result<Self, usize> {
+        let mut s = Scanner::new(src);
+        skip_misc(&mut s)?;
+        let root = parse_element(&mut s, 0)?;
+        skip_misc(&mut s)?;
+        if s.done() {
+            Ok(root)
+        } else {
+            Err(s.cursor())
+        }
+    }
+
+    /// The name without its namespace prefix.
+    pub(crate) fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or_default()
+    }
+
+    /// The value of the attribute with the given name.
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The child elements.
+    pub(crate) fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// The first child element with the given local name.
+    pub(crate) fn element(&self, local_name: &str) -> Option<&Element> {
+        self.elements().find(|element| element.local_name() == local_name)
+    }
+
+    /// The text of the element and its descendants.
+    pub(crate) fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            match child {
+                Node::Element(element) => text.push_str(&element.text()),
+                Node::Text(t) => text.push_str(t),
+            }
+        }
+        text
+    }
+}
+
+/// Escape text for use in content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '&' => res.push_str("&amp;"),
+            '"' => res.push_str("&quot;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Skip whitespace, comments, processing instructions, and document type
+/// declarations.
+fn skip_misc(s: &mut Scanner) -> Result<(), usize> {
+    loop {
+        s.eat_whitespace();
+        if s.eat_if("<?") {
+            skip_past(s, "?>")?;
+        } else if s.eat_if("<!--") {
+            skip_past(s, "-->")?;
+        } else if s.eat_if("<!DOCTYPE") {
+            s.eat_until(['[', '>']);
+            if s.eat_if('[') {
+                skip_past(s, "]")?;
+            }
+            s.eat_until('>');
+            if !s.eat_if('>') {
+                return Err(s.cursor());
+            }
+        } else {
+            return Ok(());
+        }
+    }
+}
+
+/// Skip past the next occurrence of a delimiter.
+fn skip_past(s: &mut Scanner, delimiter: &str) -> Result<(), usize> {
+    s.eat_until(delimiter);
+    if s.eat_if(delimiter) {
+        Ok(())
+    } else {
+        Err(s.cursor())
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, ':' | '_' | '-' | '.')
+}
+
+/// Parse an element starting at its opening tag, nested in `depth` other
+/// elements.
+fn parse_element(s: &mut Scanner, depth: usize) -> Result<Element, usize> {
+    if depth == MAX_DEPTH || !s.eat_if('<') {
+        return Err(s.cursor());
+    }
+
+    let mut element = Element {
+        name: s.eat_while(is_name_char).into(),
+        ..Element::default()
+    };
+    if element.name.is_empty() {
+        return Err(s.cursor());
+    }
+
+    loop {
+        s.eat_whitespace();
+        if s.eat_if("/>") {
+            return Ok(element);
+        } else if s.eat_if('>') {
+            break;
+        }
+
+        let name = s.eat_while(is_name_char);
+        s.eat_whitespace();
+        if name.is_empty() || !s.eat_if('=') {
+            return Err(s.cursor());
+        }
+        s.eat_whitespace();
+        let Some(quote) = s.eat().filter(|&c| c == '"' || c == '\'') else {
+            return Err(s.cursor());
+        };
+        let start = s.cursor();
+        let value = s.eat_until(quote);
+        if !s.eat_if(quote) {
+            return Err(s.cursor());
+        }
+        element.attributes.push((name.into(), unescape(value, start)?));
+    }
+
+    loop {
+        let start = s.cursor();
+        if s.eat_if("</") {
+            let name = s.eat_while(is_name_char);
+            s.eat_whitespace();
+            if name != element.name || !s.eat_if('>') {
+                return Err(start);
+            }
+            return Ok(element);
+        } else if s.eat_if("<!--") {
+            skip_past(s, "-->")?;
+        } else if s.eat_if("<?") {
+            skip_past(s, "?>")?;
+        } else if s.eat_if("<![CDATA[") {
+            let text = s.eat_until("]]>");
+            skip_past(s, "]]>")?;
+            push_text(&mut element, text.into());
+        } else if s.at('<') {
+            let child = parse_element(s, depth + 1)?;
+            element.children.push(Node::Element(child));
+        } else if s.done() {
+            return Err(start);
+        } else {
+            let text = s.eat_until('<');
+            push_text(&mut element, unescape(text, start)?);
+        }
+    }
+}
+
+/// Append text to an element, merging it with preceding text.
+fn push_text(element: &mut Element, text: String) {
+    match element.children.last_mut() {
+        Some(Node::Text(prev)) => prev.push_str(&text),
+        _ => element.children.push(Node::Text(text)),
+    }
+}
+
+/// Resolve the entity and character references in text that starts at the
+/// given offset.
+fn unescape(text: &str, offset: usize) -> Result<String, usize> {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        res.push_str(&rest[..i]);
+        let error = offset + (text.len() - rest.len()) + i;
+        let end = rest[i..].find(';').ok_or(error)?;
+        let reference = &rest[i + 1..i + end];
+        let c = match reference {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => reference.strip_prefix('#').ok_or(error)?.parse(),
+                };
+                code.ok().and_then(char::from_u32).ok_or(error)?
+            }
+        };
+        res.push(c);
+        rest = &rest[i + end + 1..];
+    }
+    res.push_str(rest);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml() {
+        let src = r#"<?xml version="1.0"?>
+            <!DOCTYPE a [<!ENTITY x "y">]>
+            <!-- comment -->
+            <p:a k="1 &amp; 2" l='&#x3c;'>x &lt; y<![CDATA[ & <z>]]><b/><!-- c --><c>t</c></p:a>
+        "#;
+        let root = Element::parse(src).unwrap();
+        assert_eq!(root.local_name(), "a");
+        assert_eq!(root.attribute("k"), Some("1 & 2"));
+        assert_eq!(root.attribute("l"), Some("<"));
+        assert_eq!(root.elements().count(), 2);
+        assert_eq!(root.element("c").unwrap().text(), "t");
+        assert_eq!(root.text(), "x < y & <z>t");
+        assert_eq!(escape(&root.text()), "x &lt; y &amp; &lt;z&gt;t");
+
+        assert_eq!(Element::parse("<a><b></a>"), Err(6));
+        assert_eq!(Element::parse("<a>&nbsp;</a>"), Err(3));
+        assert_eq!(Element::parse("<a/><b/>"), Err(4));
+
+        let nested = |n| format!("{}{}", "<a>".repeat(n), "</a>".repeat(n));
+        assert!(Element::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(Element::parse(&nested(MAX_DEPTH + 1)), Err(3 * MAX_DEPTH));
+        assert_eq!(Element::parse(&nested(100_000)), Err(3 * MAX_DEPTH));
+    }
+}